@@ -0,0 +1,94 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Condvar, Mutex, PoisonError};
+
+use log::debug;
+
+/// Limits the total size of files mapped in memory concurrently.
+///
+/// Analyses of files that would exceed the limit wait until enough mapped bytes are released.
+/// A file larger than the limit is analyzed alone.
+pub(crate) struct MappedBytesLimiter {
+    max_bytes: Option<u64>,
+    mapped_bytes: Mutex<u64>,
+    released: Condvar,
+}
+
+impl MappedBytesLimiter {
+    pub(crate) fn new(max_bytes: Option<u64>) -> Self {
+        Self {
+            max_bytes,
+            mapped_bytes: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Waits until the file at `path` can be mapped without exceeding the limit.
+    ///
+    /// The mapped bytes are accounted for until the returned permit is dropped.
+    pub(crate) fn acquire(&self, path: &Path) -> MappedBytesPermit<'_> {
+        let Some(max_bytes) = self.max_bytes else {
+            return MappedBytesPermit {
+                limiter: self,
+                bytes: 0,
+            };
+        };
+
+        // If the size is unknown, then opening the file will most likely fail anyway.
+        let bytes = fs::metadata(path).map_or(0, |metadata| metadata.len());
+
+        let mut mapped_bytes = self
+            .mapped_bytes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        while *mapped_bytes > 0 && mapped_bytes.saturating_add(bytes) > max_bytes {
+            debug!(
+                "Waiting for {} mapped bytes to be released before analyzing '{}'.",
+                bytes,
+                path.display()
+            );
+
+            mapped_bytes = self
+                .released
+                .wait(mapped_bytes)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+
+        *mapped_bytes = mapped_bytes.saturating_add(bytes);
+
+        MappedBytesPermit {
+            limiter: self,
+            bytes,
+        }
+    }
+}
+
+/// Accounts for mapped bytes until dropped.
+pub(crate) struct MappedBytesPermit<'t> {
+    limiter: &'t MappedBytesLimiter,
+    bytes: u64,
+}
+
+impl Drop for MappedBytesPermit<'_> {
+    fn drop(&mut self) {
+        if self.bytes == 0 {
+            return;
+        }
+
+        let mut mapped_bytes = self
+            .limiter
+            .mapped_bytes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        *mapped_bytes = mapped_bytes.saturating_sub(self.bytes);
+        self.limiter.released.notify_all();
+    }
+}
@@ -5,40 +5,245 @@
 // or distributed except according to those terms.
 
 use log::{debug, warn};
+use rayon::prelude::*;
+use scroll::Pread;
 
+use crate::cmdline::ArchiveMembers;
+use crate::context::AnalysisContext;
 use crate::errors::{Error, Result};
-use crate::options::status::DisplayInColorTerm;
-use crate::options::{BinarySecurityOption, ELFStackProtectionOption};
+use crate::extract::{ExtractionBudget, ExtractionLimits, Refusal};
+use crate::options::status::{CheckResult, DetailedStatus, SupportLevel, YesNoUnknownStatus};
+use crate::options::{ArchiveDeterminismOption, BinarySecurityOption, ELFStackProtectionOption};
 use crate::parser::BinaryParser;
 
+/// Keyword of the result reporting whether an archive was created in deterministic mode.
+pub(crate) const DETERMINISTIC_AR: &str = "DETERMINISTIC-AR";
+
 pub(crate) fn analyze_binary(
     parser: &BinaryParser,
-    options: &crate::cmdline::Options,
-) -> Result<Vec<Box<dyn DisplayInColorTerm>>> {
-    let has_stack_protection = ELFStackProtectionOption.check(parser, options)?;
-    Ok(vec![has_stack_protection])
+    context: &AnalysisContext,
+) -> Result<Vec<CheckResult>> {
+    let deterministic = ArchiveDeterminismOption.timed_check(parser, context)?;
+
+    // The symbol tables of all members would need to be walked.
+    if context.options.fast {
+        return Ok(vec![
+            DetailedStatus::skipped("STACK-PROT").into(),
+            deterministic,
+        ]);
+    }
+
+    let has_stack_protection = ELFStackProtectionOption.timed_check(parser, context)?;
+    Ok(vec![has_stack_protection, deterministic])
+}
+
+/// Name of the member holding the 64-bits symbol index of GNU archives.
+const GNU_SYM64_NAME: &str = "/SYM64/";
+
+/// Names of the members holding the symbol index of BSD archives, and their 64-bits variants.
+const BSD_SYMDEF_NAMES: &[&str] = &["__.SYMDEF", "__.SYMDEF SORTED"];
+const BSD_SYMDEF_64_NAMES: &[&str] = &["__.SYMDEF_64", "__.SYMDEF_64 SORTED"];
+
+/// Positions of the modification timestamp, the user identifier and the group
+/// identifier in member headers.
+const HEADER_TIMESTAMP: core::ops::Range<usize> = 16..28;
+const HEADER_UID: core::ops::Range<usize> = 28..34;
+const HEADER_GID: core::ops::Range<usize> = 34..40;
+
+/// Returns the members of the archive holding files, in archive order.
+///
+/// Members sharing the same name are all returned, as static libraries often hold objects
+/// compiled from files of the same name in different directories. The 64-bits symbol indexes of
+/// GNU and BSD archives are not returned, as `goblin` reports them as regular members.
+fn file_members<'t>(
+    archive: &'t goblin::archive::Archive,
+) -> impl Iterator<Item = &'t goblin::archive::Member<'t>> {
+    (0..archive.len())
+        .filter_map(|index| archive.get_at(index))
+        .filter(|member| {
+            !member.raw_name().starts_with(GNU_SYM64_NAME)
+                && !BSD_SYMDEF_64_NAMES.contains(&member.extended_name())
+        })
+}
+
+/// Returns the kind of the symbol index of the archive, which is its first member, if any.
+fn symbol_index_kind(bytes: &[u8]) -> &'static str {
+    use goblin::archive::{SIZEOF_HEADER, SIZEOF_MAGIC};
+
+    let Some(name) = bytes.get(SIZEOF_MAGIC..SIZEOF_MAGIC + 16) else {
+        return "none";
+    };
+
+    if name == b"/               " {
+        "sysv"
+    } else if name.starts_with(GNU_SYM64_NAME.as_bytes()) {
+        "sysv64"
+    } else if let Some(length) = name.strip_prefix(b"#1/") {
+        // BSD archives store long names right after the member header.
+        let length = core::str::from_utf8(length)
+            .ok()
+            .and_then(|length| length.trim_end().parse::<usize>().ok())
+            .unwrap_or_default();
+        let start = SIZEOF_MAGIC + SIZEOF_HEADER;
+        let name = bytes
+            .get(start..start.saturating_add(length))
+            .and_then(|name| core::str::from_utf8(name).ok())
+            .map(|name| name.trim_end_matches('\0'));
+
+        match name {
+            Some(name) if BSD_SYMDEF_NAMES.contains(&name) => "bsd",
+            Some(name) if BSD_SYMDEF_64_NAMES.contains(&name) => "bsd64",
+            _ => "none",
+        }
+    } else {
+        "none"
+    }
 }
 
-pub(crate) fn has_stack_protection(
+/// Returns the numeric field of a member header, or zero if it is blank or invalid.
+fn header_number(
+    bytes: &[u8],
+    member: &goblin::archive::Member,
+    field: core::ops::Range<usize>,
+) -> u64 {
+    let start = usize::try_from(member.header_offset).unwrap_or(usize::MAX);
+    bytes
+        .get(start.saturating_add(field.start)..start.saturating_add(field.end))
+        .and_then(|field| core::str::from_utf8(field).ok())
+        .and_then(|field| field.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+/// Returns whether the archive was created in deterministic mode, as needed by reproducible
+/// builds.
+///
+/// In deterministic mode (e.g., `ar rcsD`), the modification timestamps, user identifiers and
+/// group identifiers of members are recorded as zero. The details list the number of members
+/// and the kind of symbol index (`sysv`, `sysv64`, `bsd`, `bsd64` or `none`), followed by
+/// `timestamps` and/or `owners` when these are recorded.
+pub(crate) fn determinism(
     parser: &BinaryParser,
     archive: &goblin::archive::Archive,
-) -> Result<bool> {
+    context: &AnalysisContext,
+) -> DetailedStatus {
     let bytes = parser.bytes();
-    for member_name in archive.members() {
-        let buffer =
-            archive
-                .extract(member_name, bytes)
-                .map_err(|source| Error::ExtractArchiveMember {
-                    member: member_name.into(),
-                    source,
-                })?;
-
-        let r = member_has_stack_protection(member_name, buffer)?;
-        if r {
-            return Ok(true);
+    let mut members = 0_u64;
+    let mut has_timestamps = false;
+    let mut has_owners = false;
+
+    for member in file_members(archive) {
+        members += 1;
+        has_timestamps |= header_number(bytes, member, HEADER_TIMESTAMP) != 0;
+        has_owners |= header_number(bytes, member, HEADER_UID) != 0
+            || header_number(bytes, member, HEADER_GID) != 0;
+    }
+
+    let mut details = vec![
+        format!("members={}", context.number_format.count(members)),
+        format!("index={}", symbol_index_kind(bytes)),
+    ];
+    if has_timestamps {
+        details.push("timestamps".into());
+    }
+    if has_owners {
+        details.push("owners".into());
+    }
+
+    let level = if has_timestamps || has_owners {
+        SupportLevel::Unsupported
+    } else {
+        SupportLevel::Supported
+    };
+    DetailedStatus::new(DETERMINISTIC_AR, level, details)
+}
+
+/// Returns the members of the archive that are analyzed, in archive order, within the
+/// extraction limits.
+fn admitted_members<'t>(
+    parser: &BinaryParser,
+    archive: &'t goblin::archive::Archive,
+    context: &AnalysisContext,
+) -> Vec<&'t goblin::archive::Member<'t>> {
+    let mut budget = ExtractionBudget::new(parser.path(), ExtractionLimits::new(context.options));
+    let mut members = Vec::default();
+
+    for member in file_members(archive) {
+        match budget.admit(member.extended_name(), member.size() as u64) {
+            Ok(()) => members.push(member),
+            Err(Refusal::SkipMember) => {}
+            Err(Refusal::StopContainer) => break,
+        }
+    }
+    members
+}
+
+/// Returns whether the members of the archive are stack-protected.
+///
+/// Members are extracted and parsed in parallel. With `--archive-members first-hit`, the archive
+/// is protected if any member is, and the analysis stops at the first protected member. The
+/// first error, or the first protected member, in archive order, decides the result, as if
+/// members were analyzed in order. With `--archive-members exhaustive`, all members are analyzed,
+/// and the number of protected members is reported.
+pub(crate) fn stack_protection(
+    parser: &BinaryParser,
+    archive: &goblin::archive::Archive,
+    context: &AnalysisContext,
+) -> Result<CheckResult> {
+    let members = admitted_members(parser, archive, context);
+    let bytes = parser.bytes();
+
+    let member_is_protected = |member: &&goblin::archive::Member| {
+        let member_name = member.extended_name();
+        let buffer = usize::try_from(member.offset)
+            .map_err(|_r| goblin::error::Error::Malformed("member offset is too large".into()))
+            .and_then(|offset| Ok(bytes.pread_with::<&[u8]>(offset, member.size())?))
+            .map_err(|source| Error::ExtractArchiveMember {
+                member: member_name.into(),
+                source,
+            })?;
+
+        member_has_stack_protection(member_name, buffer)
+    };
+
+    match context.options.archive_members {
+        ArchiveMembers::FirstHit => {
+            let r = members
+                .par_iter()
+                .map(member_is_protected)
+                .find_first(|r| !matches!(r, Ok(false)))
+                .transpose()?
+                .is_some();
+            Ok(YesNoUnknownStatus::new("STACK-PROT", r).into())
+        }
+
+        ArchiveMembers::Exhaustive => {
+            let protected = members
+                .par_iter()
+                .map(member_is_protected)
+                .collect::<Result<Vec<_>>>()?;
+
+            for (member, _protected) in members.iter().zip(&protected).filter(|(_, &p)| !p) {
+                debug!(
+                    "Archive member '{}' is not stack-protected.",
+                    member.extended_name()
+                );
+            }
+
+            let protected_count = protected.iter().filter(|&&p| p).count();
+            let level = if protected_count > 0 {
+                SupportLevel::Supported
+            } else {
+                SupportLevel::Unsupported
+            };
+
+            let details = vec![format!(
+                "members={}/{}",
+                context.number_format.count(protected_count as u64),
+                context.number_format.count(members.len() as u64)
+            )];
+            Ok(DetailedStatus::new("STACK-PROT", level, details).into())
         }
     }
-    Ok(false)
 }
 
 /// - [`__stack_chk_fail`](http://refspecs.linux-foundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/baselib---stack-chk-fail-1.html).
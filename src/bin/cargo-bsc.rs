@@ -0,0 +1,290 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Cargo subcommand running `binary-security-check` on the artifacts of the current crate, as
+//! `cargo bsc`.
+//!
+//! The executables, dynamic libraries and static libraries built by Cargo are located through
+//! `cargo metadata`, then analyzed once per profile. The command fails when the analysis of any
+//! profile fails, e.g., when artifacts do not meet the `--policy` given to the analysis.
+
+#![warn(unsafe_op_in_unsafe_fn)]
+#![warn(clippy::all, clippy::pedantic)]
+#![allow(
+    clippy::upper_case_acronyms,
+    clippy::missing_docs_in_private_items,
+    clippy::print_stderr,
+    clippy::print_stdout,
+    clippy::module_name_repetitions
+)]
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+
+use clap::Parser;
+use serde::Deserialize;
+
+type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("failed to run '{program}'")]
+    Run {
+        program: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("'cargo metadata' failed: {0}")]
+    Metadata(String),
+
+    #[error("failed to parse the output of 'cargo metadata'")]
+    ParseMetadata(#[from] serde_json::Error),
+
+    #[error("package '{0}' is not a member of the workspace")]
+    UnknownPackage(String),
+
+    #[error("no artifacts were found in '{0}'. Build the crate first, e.g., by 'cargo build'")]
+    NoArtifacts(PathBuf),
+}
+
+#[derive(Debug, Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    Bsc(Options),
+}
+
+/// Analyze the security features of the artifacts built for the current crate.
+///
+/// Arguments following `--` are given to `binary-security-check`, e.g.,
+/// `cargo bsc --profile release -- --policy debian12`.
+#[derive(Debug, clap::Args)]
+#[command(version)]
+struct Options {
+    /// Path of the `Cargo.toml` file of the crate or of the workspace.
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+
+    /// Package whose artifacts are analyzed. By default, the artifacts of all packages of the
+    /// workspace are analyzed.
+    #[arg(short, long, value_name = "SPEC")]
+    package: Vec<String>,
+
+    /// Profile whose artifacts are analyzed. By default, the artifacts of the `dev` and `release`
+    /// profiles are analyzed, when they are built.
+    #[arg(long, value_name = "PROFILE-NAME")]
+    profile: Vec<String>,
+
+    /// Target triple for which artifacts were built, e.g., `x86_64-pc-windows-gnu`.
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Arguments of `binary-security-check`.
+    #[arg(last = true, value_name = "ARGS")]
+    checker_args: Vec<OsString>,
+}
+
+/// Subset of the output of `cargo metadata`.
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    target_directory: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    targets: Vec<Target>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let Cargo::Bsc(options) = Cargo::parse();
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+
+        Err(error) => {
+            eprintln!("error: {}", format_error(&error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn format_error(mut r: &dyn std::error::Error) -> String {
+    use core::fmt::Write;
+
+    let mut text = format!("{r}.");
+    while let Some(source) = r.source() {
+        let _ignored = write!(&mut text, " {source}.");
+        r = source;
+    }
+    text
+}
+
+/// Analyzes the artifacts of each profile, and returns `true` if all analyses succeed.
+fn run(options: &Options) -> Result<bool> {
+    let metadata = cargo_metadata(options)?;
+    let artifact_names = artifact_names(&metadata, &options.package)?;
+
+    let mut profiles_dir = metadata.target_directory.clone();
+    if let Some(target) = &options.target {
+        profiles_dir.push(target);
+    }
+
+    let profiles = if options.profile.is_empty() {
+        vec!["dev".to_owned(), "release".to_owned()]
+    } else {
+        options.profile.clone()
+    };
+
+    let checker = checker_path();
+    let mut analyzed = false;
+    let mut success = true;
+
+    for profile in &profiles {
+        let dir = profiles_dir.join(profile_dir_name(profile));
+        let artifacts: Vec<_> = artifact_names
+            .iter()
+            .map(|name| dir.join(name))
+            .filter(|path| path.is_file())
+            .collect();
+
+        if artifacts.is_empty() {
+            continue;
+        }
+        analyzed = true;
+
+        eprintln!(
+            "Analyzing {} artifacts of profile '{profile}' in '{}'.",
+            artifacts.len(),
+            dir.display()
+        );
+
+        let status = Command::new(&checker)
+            .args(&options.checker_args)
+            .args(&artifacts)
+            .stdin(Stdio::null())
+            .status()
+            .map_err(|source| Error::Run {
+                program: checker.clone(),
+                source,
+            })?;
+        success &= status.success();
+    }
+
+    if analyzed {
+        Ok(success)
+    } else {
+        Err(Error::NoArtifacts(profiles_dir))
+    }
+}
+
+/// Runs `cargo metadata` on the workspace of the current crate.
+fn cargo_metadata(options: &Options) -> Result<Metadata> {
+    // Cargo names itself when it runs subcommands.
+    let cargo = std::env::var_os("CARGO").map_or_else(|| PathBuf::from("cargo"), PathBuf::from);
+
+    let mut command = Command::new(&cargo);
+    command.args(["metadata", "--format-version", "1", "--no-deps"]);
+    if let Some(manifest_path) = &options.manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|source| Error::Run {
+            program: cargo,
+            source,
+        })?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Metadata(message.trim().to_owned()));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Returns the file names of the artifacts of the packages named `selected`, or of all packages
+/// of the workspace if none are selected.
+fn artifact_names(metadata: &Metadata, selected: &[String]) -> Result<Vec<String>> {
+    if let Some(name) = selected.iter().find(|&name| {
+        !metadata
+            .packages
+            .iter()
+            .any(|package| &package.name == name)
+    }) {
+        return Err(Error::UnknownPackage(name.clone()));
+    }
+
+    let mut names: Vec<_> = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .filter(|package| selected.is_empty() || selected.contains(&package.name))
+        .flat_map(|package| &package.targets)
+        .flat_map(|target| {
+            target
+                .kind
+                .iter()
+                .flat_map(|kind| target_file_names(&target.name, kind))
+        })
+        .collect();
+
+    names.sort_unstable();
+    names.dedup();
+    Ok(names)
+}
+
+/// Returns the possible file names of the artifact of a target named `name`, of the given kind,
+/// on all platforms.
+fn target_file_names(name: &str, kind: &str) -> Vec<String> {
+    // Libraries are named after their crate name.
+    let lib = name.replace('-', "_");
+
+    match kind {
+        "bin" => vec![name.to_owned(), format!("{name}.exe")],
+        "cdylib" | "dylib" => vec![
+            format!("lib{lib}.so"),
+            format!("{lib}.dll"),
+            format!("lib{lib}.dylib"),
+        ],
+        "staticlib" => vec![format!("lib{lib}.a"), format!("{lib}.lib")],
+        // Rust libraries (`rlib`) are only consumed by the Rust compiler.
+        _ => Vec::default(),
+    }
+}
+
+/// Returns the name of the directory of the artifacts of `profile`.
+fn profile_dir_name(profile: &str) -> &str {
+    match profile {
+        "dev" | "test" => "debug",
+        "bench" => "release",
+        profile => profile,
+    }
+}
+
+/// Returns the path of `binary-security-check`, which is installed next to this program, or is
+/// otherwise searched in `PATH`.
+fn checker_path() -> PathBuf {
+    let name = format!("binary-security-check{}", std::env::consts::EXE_SUFFIX);
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.parent()?.join(&name)))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| Path::new(&name).into())
+}
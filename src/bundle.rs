@@ -0,0 +1,309 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Application bundles, i.e., `AppImage` files and Flatpak and Snap trees, whose binaries depend on
+//! libraries bundled with them.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{debug, warn};
+use rayon::prelude::*;
+
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+use crate::extract::{ExtractionBudget, ExtractionLimits, Refusal};
+use crate::options::status::{CheckResult, InformationalStatus};
+use crate::report::FileResults;
+
+/// Keyword of the result reporting that a file is an application bundle.
+pub(crate) const BUNDLE: &str = "BUNDLE";
+
+/// Magic bytes of type 2 `AppImage` files, located in the padding of the ELF identification.
+const APPIMAGE_TYPE_2_MAGIC: &[u8] = b"AI\x02";
+
+/// Offset of the magic bytes of `AppImage` files.
+const APPIMAGE_MAGIC_OFFSET: usize = 8;
+
+/// Magic bytes of little endian squashfs file systems.
+const SQUASHFS_MAGIC: &[u8; 4] = b"hsqs";
+
+/// Kind of an application bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BundleKind {
+    AppImage,
+    Flatpak,
+    Snap,
+}
+
+impl core::fmt::Display for BundleKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match *self {
+            Self::AppImage => "appimage",
+            Self::Flatpak => "flatpak",
+            Self::Snap => "snap",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Returns `true` if the ELF executable is a type 2 `AppImage`, i.e., a runtime followed by a
+/// squashfs file system.
+pub(crate) fn is_appimage(bytes: &[u8]) -> bool {
+    bytes.get(APPIMAGE_MAGIC_OFFSET..APPIMAGE_MAGIC_OFFSET + APPIMAGE_TYPE_2_MAGIC.len())
+        == Some(APPIMAGE_TYPE_2_MAGIC)
+}
+
+/// Returns the result reporting that a file is an `AppImage`.
+pub(crate) fn appimage_status() -> CheckResult {
+    InformationalStatus::new(BUNDLE, vec![BundleKind::AppImage.to_string()]).into()
+}
+
+/// Returns the kind of the bundle whose tree is the directory at `path`, if it is one.
+///
+/// Flatpak applications and runtimes are deployed with a `metadata` file next to their `files`
+/// directory. Snaps are mounted with their `meta/snap.yaml` description.
+pub(crate) fn tree_kind(path: &Path) -> Option<BundleKind> {
+    if path.join("metadata").is_file() && path.join("files").is_dir() {
+        Some(BundleKind::Flatpak)
+    } else if path.join("meta").join("snap.yaml").is_file() {
+        Some(BundleKind::Snap)
+    } else {
+        None
+    }
+}
+
+/// Returns the path under which `path`, found inside the bundle `bundle`, is reported, e.g.,
+/// `app.AppImage!usr/bin/app`.
+fn member_path(bundle: &Path, path: &Path) -> PathBuf {
+    let mut member_path = OsString::from(bundle.as_os_str());
+    member_path.push("!");
+    member_path.push(path.as_os_str());
+    member_path.into()
+}
+
+/// Extracts the squashfs file system of the `AppImage` at `path` by running `unsquashfs`, then
+/// analyzes the binaries it contains.
+///
+/// The file system is extracted to a temporary directory, which is removed once its binaries are
+/// analyzed. File systems declaring more files than allowed by `--extract-max-members` are not
+/// extracted.
+pub(crate) fn analyze_appimage(
+    path: &Path,
+    context: &AnalysisContext,
+) -> Result<Vec<(PathBuf, FileResults)>> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let (offset, inode_count) = squashfs_offset(path)?;
+    let budget = ExtractionBudget::new(path, ExtractionLimits::new(context.options));
+    if !budget.admit_declared_members(u64::from(inode_count)) {
+        return Ok(Vec::default());
+    }
+
+    let extracted_path = env::temp_dir().join(format!(
+        "binary-security-check-appimage-{}-{}",
+        process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let r = extract_appimage(path, offset, &extracted_path)
+        .map(|()| analyze_tree(path, &extracted_path, context.options));
+
+    let _ignored = fs::remove_dir_all(&extracted_path);
+    r
+}
+
+fn extract_appimage(path: &Path, offset: u64, extracted_path: &Path) -> Result<()> {
+    debug!(
+        "Extracting squashfs file system of '{}' at offset {offset} to '{}'.",
+        path.display(),
+        extracted_path.display()
+    );
+
+    let output = Command::new("unsquashfs")
+        .args(["-q", "-n", "-no-xattrs", "-o"])
+        .arg(offset.to_string())
+        .arg("-d")
+        .arg(extracted_path)
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|r| Error::from_io1(r, "run 'unsquashfs' on", path))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::ExtractBundle {
+            path: path.into(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        })
+    }
+}
+
+/// Returns the offset of the squashfs file system of the `AppImage` at `path`, which follows the
+/// section headers of its runtime, and the number of files it declares.
+fn squashfs_offset(path: &Path) -> Result<(u64, u32)> {
+    let mut file = fs::File::open(path).map_err(|r| Error::from_io1(r, "open file", path))?;
+
+    let mut header = [0_u8; goblin::elf::header::header64::SIZEOF_EHDR];
+    file.read_exact(&mut header)
+        .map_err(|r| Error::from_io1(r, "read file", path))?;
+    let elf =
+        goblin::elf::Elf::parse_header(&header).map_err(|source| Error::ParseFile { source })?;
+
+    let offset = elf.e_shoff + u64::from(elf.e_shnum) * u64::from(elf.e_shentsize);
+
+    // The magic bytes are followed by the number of inodes of the file system.
+    let mut super_block = [0_u8; 8];
+    let found = file
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_exact(&mut super_block))
+        .is_ok_and(|()| super_block[..4] == *SQUASHFS_MAGIC);
+
+    if found {
+        let inode_count = u32::from_le_bytes([
+            super_block[4],
+            super_block[5],
+            super_block[6],
+            super_block[7],
+        ]);
+        Ok((offset, inode_count))
+    } else {
+        Err(Error::ExtractBundle {
+            path: path.into(),
+            message: format!("no squashfs file system found at offset {offset}"),
+        })
+    }
+}
+
+/// Analyzes the ELF files of the bundle tree rooted at `root`, and reports them as members of
+/// `bundle`.
+///
+/// The C runtime library of each file is looked up among the libraries of the bundle first.
+/// Files that cannot be analyzed are reported as warnings, as they are part of the bundle. Files
+/// exceeding the extraction limits are skipped.
+pub(crate) fn analyze_tree(
+    bundle: &Path,
+    root: &Path,
+    options: &crate::cmdline::Options,
+) -> Vec<(PathBuf, FileResults)> {
+    let context = AnalysisContext::for_bundle(options, library_dirs(root));
+
+    let mut budget = ExtractionBudget::new(bundle, ExtractionLimits::new(options));
+    let mut files = Vec::default();
+    let _stopped = collect_elf_files(root, root, 0, &mut budget, &mut files);
+
+    files
+        .into_par_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let member_path = member_path(bundle, relative);
+
+            match crate::analyze_file(&path, &context) {
+                Ok(results) => Some((member_path, results)),
+
+                Err(error) => {
+                    warn!(
+                        "{}: Bundled file is not analyzed. {}",
+                        member_path.display(),
+                        crate::format_error(&error)
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Returns the directories of the libraries of the bundle tree rooted at `root`, including
+/// multiarch directories, e.g., `usr/lib/x86_64-linux-gnu`.
+fn library_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::default();
+
+    for prefix in ["", "usr", "files"] {
+        for lib in ["lib", "lib64", "lib32"] {
+            let dir = root.join(prefix).join(lib);
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let multiarch_dirs = fs::read_dir(&dir)
+                .into_iter()
+                .flatten()
+                .filter_map(std::result::Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_dir()
+                        && path
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(|name| name.ends_with("-linux-gnu"))
+                })
+                .collect::<Vec<_>>();
+
+            dirs.push(dir);
+            dirs.extend(multiarch_dirs);
+        }
+    }
+
+    debug!("Library directories of bundle: {dirs:?}.");
+    dirs
+}
+
+/// Appends the ELF files found in the directory at `dir` to `files`, recursively, as long as
+/// they are within the extraction limits. Returns `Err` once no more files can be appended.
+///
+/// Symbolic links are not followed, as they might point outside the bundle.
+fn collect_elf_files(
+    root: &Path,
+    dir: &Path,
+    depth: u32,
+    budget: &mut ExtractionBudget,
+    files: &mut Vec<PathBuf>,
+) -> core::result::Result<(), Refusal> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+
+        Err(r) => {
+            warn!("Failed to read directory '{}': {r}", dir.display());
+            return Ok(());
+        }
+    };
+
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let path = entry.path();
+        let name = path.strip_prefix(root).unwrap_or(&path).to_string_lossy();
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            if budget.admit_depth(&name, depth + 1) {
+                collect_elf_files(root, &path, depth + 1, budget, files)?;
+            }
+        } else if metadata.is_file() && is_elf_file(&path) {
+            match budget.admit(&name, metadata.len()) {
+                Ok(()) => files.push(path),
+                Err(Refusal::SkipMember) => {}
+                Err(Refusal::StopContainer) => return Err(Refusal::StopContainer),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_elf_file(path: &Path) -> bool {
+    let mut magic = [0_u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|()| magic == *b"\x7fELF")
+}
@@ -0,0 +1,63 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Paths inside the root directory of a foreign system given by `--chroot`, e.g., a mounted
+//! image, a chroot or a container volume.
+//!
+//! Symbolic links of foreign systems are meant to be resolved inside them, e.g., `/lib64` often
+//! links to `/usr/lib64`, which must not be looked up in the analyzing system.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Maximum number of symbolic links followed while resolving a path, as done by Linux.
+const MAX_SYMLINKS: usize = 40;
+
+/// Pushes the components of `path` to `pending`, so that they are popped in order. Parent
+/// directories are pushed as `None`.
+fn push_components(pending: &mut Vec<Option<OsString>>, path: &Path) {
+    let start = pending.len();
+    pending.extend(path.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(Some(name.to_os_string())),
+        Component::ParentDir => Some(None),
+        Component::Prefix(_) | Component::RootDir | Component::CurDir => None,
+    }));
+    pending[start..].reverse();
+}
+
+/// Returns the path of the file at `path` inside `root`, as if `root` were the root directory.
+///
+/// Symbolic links are resolved inside `root`: absolute targets are relative to `root`, and
+/// parent directories do not escape it. Links that cannot be read are kept as they are.
+pub(crate) fn resolve(root: &Path, path: &Path) -> PathBuf {
+    let mut pending = Vec::default();
+    push_components(&mut pending, path);
+
+    let mut resolved = PathBuf::default();
+    let mut links = 0_usize;
+
+    while let Some(component) = pending.pop() {
+        let Some(name) = component else {
+            resolved.pop();
+            continue;
+        };
+
+        let candidate = resolved.join(name);
+        match fs::read_link(root.join(&candidate)) {
+            Ok(target) if links < MAX_SYMLINKS => {
+                links += 1;
+                if target.has_root() {
+                    resolved = PathBuf::default();
+                }
+                push_components(&mut pending, &target);
+            }
+
+            _ => resolved = candidate,
+        }
+    }
+    root.join(resolved)
+}
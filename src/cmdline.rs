@@ -5,9 +5,12 @@
 // or distributed except according to those terms.
 
 use core::fmt;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
-use crate::elf;
+use regex::Regex;
+
+use crate::{fortify, walk};
 
 const HELP_TEMPLATE: &str = "{before-help}{about-with-newline}
 {usage-heading} {usage}
@@ -16,15 +19,23 @@ const HELP_TEMPLATE: &str = "{before-help}{about-with-newline}
 \u{1b}[1m\u{1b}[4mAuthors:\u{1b}[24m\u{1b}[22m
 {tab}{author-with-newline}";
 
-#[derive(Debug, clap::Parser)]
+#[derive(Debug, Clone, clap::Parser)]
 #[command(
     author,
     version,
     about,
     next_line_help = true,
+    subcommand_negates_reqs = true,
+    disable_help_subcommand = true,
+    override_usage = "binary-security-check [OPTIONS] <INPUT_FILES>...\n       \
+                      binary-security-check [OPTIONS] doctor\n       \
+                      binary-security-check [OPTIONS] serve [--listen <ADDRESS>]\n       \
+                      binary-security-check [OPTIONS] daemon --socket <SOCKET>\n       \
+                      binary-security-check client --socket <SOCKET> <FILES>...",
     help_template = HELP_TEMPLATE,
     after_help = include_str!("command-line-after-help.txt"),
 )]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct Options {
     /// Verbose logging.
     #[arg(short = 'v', long, global = true, default_value_t = false)]
@@ -34,27 +45,512 @@ pub(crate) struct Options {
     #[arg(short = 'c', long, global = true, value_enum, default_value_t = UseColor::Auto)]
     pub(crate) color: UseColor,
 
-    /// Path of the C runtime library file.
+    /// Path of the C runtime library file. This can be specified multiple times, e.g., once per
+    /// architecture of a firmware, in which case the first library targeting the architecture of
+    /// each analyzed file is used.
     #[arg(short = 'l', long, conflicts_with_all = ["sysroot", "libc_spec", "no_libc"])]
-    pub(crate) libc: Option<PathBuf>,
+    pub(crate) libc: Vec<PathBuf>,
 
-    /// Path of the system root for finding the corresponding C runtime library.
+    /// Path of the system root for finding the corresponding C runtime library. This can be
+    /// specified multiple times, e.g., once per architecture of a firmware, in which case system
+    /// roots are searched in order for a library targeting the architecture of each analyzed file.
     #[arg(short = 's', long, conflicts_with_all = ["libc", "libc_spec", "no_libc"])]
-    pub(crate) sysroot: Option<PathBuf>,
+    pub(crate) sysroot: Vec<PathBuf>,
+
+    /// Root directory of a foreign system, e.g., a mounted image, a chroot or a container volume.
+    /// Input files are looked up inside it, as if it were the root directory, and it is the
+    /// system root for finding the corresponding C runtime library. Symbolic links are resolved
+    /// inside it.
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath, conflicts_with_all = ["libc", "sysroot"])]
+    pub(crate) chroot: Option<PathBuf>,
+
+    /// Directories where libraries are looked up before the dynamic loader cache, as
+    /// `LD_LIBRARY_PATH` does for the dynamic loader, i.e., after `DT_RPATH` and before
+    /// `DT_RUNPATH`. Directories are separated by `:`. This can be specified multiple times.
+    #[arg(long, value_name = "DIRS", value_delimiter = ':')]
+    pub(crate) library_path: Vec<PathBuf>,
 
     /// Use an internal list of checked functions as specified by a specification.
     #[arg(short = 'i', long, value_enum, conflicts_with_all = ["libc", "sysroot", "no_libc"])]
     pub(crate) libc_spec: Option<LibCSpec>,
 
+    /// C runtime library assumed for the files whose C runtime library cannot be found, instead of
+    /// reporting their `FORTIFY-SOURCE` as unknown, e.g., `spec:lsb5`. Specifications are named
+    /// as by `--libc-spec`. A warning is logged for each file falling back to it.
+    #[arg(long, value_name = "spec:SPEC", value_parser = parse_libc_fallback, conflicts_with_all = ["libc_spec", "no_libc"])]
+    pub(crate) libc_fallback: Option<LibCSpec>,
+
     /// Assume that input files do not use any C runtime libraries.
     #[arg(short = 'n', long, default_value_t = false, conflicts_with_all = ["libc", "sysroot", "libc_spec"])]
     pub(crate) no_libc: bool,
 
+    /// Enable an optional check. This can be specified multiple times.
+    #[arg(short = 'e', long = "enable", value_enum)]
+    pub(crate) enabled_checks: Vec<OptionalCheck>,
+
+    /// Analyze files inside input directories, recursively.
+    #[arg(short = 'r', long, default_value_t = false)]
+    pub(crate) recursive: bool,
+
+    /// Skip the files and directories matching a glob pattern while analyzing input directories
+    /// recursively, e.g., `*.bin` or `vendor/`. Patterns containing `/` match paths relative to
+    /// input directories, and other patterns match names. This can be specified multiple times.
+    /// Patterns are also read from `.bscignore` files found in walked directories.
+    #[arg(long, value_name = "GLOB", value_parser = walk::IgnorePattern::parse)]
+    pub(crate) exclude: Vec<walk::IgnorePattern>,
+
+    /// Maximum depth of the files analyzed inside input directories, where the files directly
+    /// inside input directories are at depth 1.
+    #[arg(long, value_name = "N")]
+    pub(crate) max_depth: Option<u32>,
+
+    /// Skip the directories that are on other file systems than input directories, e.g., `/proc`,
+    /// `/sys`, network mounts or container overlays, while analyzing input directories
+    /// recursively. This is only supported on Unix-like systems.
+    #[arg(long, default_value_t = false)]
+    pub(crate) one_file_system: bool,
+
+    /// Skip input files that are not regular files (e.g., named pipes, sockets, devices), or that
+    /// are empty, instead of reporting errors. Such files are always skipped inside directories.
+    #[arg(long, default_value_t = false)]
+    pub(crate) skip_special_files: bool,
+
+    /// Maximum number of discovered files waiting to be analyzed.
+    #[arg(long, default_value_t = 1024, value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) max_pending: u32,
+
+    /// Maximum total size of files mapped in memory concurrently, in bytes.
+    /// Suffixes K, M, G and T multiply by powers of 1024.
+    #[arg(long, value_name = "BYTES", value_parser = parse_byte_size)]
+    pub(crate) max_mapped_bytes: Option<u64>,
+
+    /// Analyze each file in a separate worker process, so that files crashing or exhausting the
+    /// analysis are reported as errors, instead of ending it.
+    #[arg(long, default_value_t = false)]
+    pub(crate) isolate: bool,
+
+    /// Maximum size of the address space of each worker process of `--isolate`, in bytes.
+    /// Suffixes K, M, G and T multiply by powers of 1024.
+    #[arg(long, value_name = "BYTES", value_parser = parse_byte_size, default_value = "8G", requires = "isolate")]
+    pub(crate) isolate_max_memory: u64,
+
+    /// Maximum processor time spent by each worker process of `--isolate`, in seconds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..), requires = "isolate")]
+    pub(crate) isolate_max_cpu: u64,
+
+    /// Analyze only this file, and write its results for the process that started this worker
+    /// process.
+    #[arg(long, value_name = "FILE", hide = true)]
+    pub(crate) isolated_worker: Option<PathBuf>,
+
+    /// Only perform checks derivable from headers and dynamic tables, for faster triage.
+    /// Skipped checks are reported as unknown, with the `skipped` detail.
+    #[arg(long, default_value_t = false)]
+    pub(crate) fast: bool,
+
+    /// Directory holding the separate debug files of stripped binaries, which are looked up by
+    /// build identifier (`.build-id/XX/YYYY.debug`) or by `.gnu_debuglink` name, to recover their
+    /// symbols. By default, this is `/usr/lib/debug` in each system root.
+    #[arg(long, value_name = "DIR")]
+    pub(crate) debug_dir: Option<PathBuf>,
+
+    /// Read the compiler options recorded in the debugging information of `ELF` files, and report
+    /// the hardening options of their compilation units as `DWARF-HARDENING`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) use_dwarf: bool,
+
+    /// Decompress executables packed by UPX, by running `upx -d`, and analyze their payload as
+    /// well. The payload of `FILE` is reported as `FILE#upx`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unpack_upx: bool,
+
+    /// Decompress the kernel of `bzImage` files, by running the decompressor matching its
+    /// compression, e.g., `zstd` or `xz`, and analyze it as well. The kernel of `FILE` is
+    /// reported as `FILE#vmlinux`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unpack_kernel: bool,
+
+    /// Analyze the vDSO that the running kernel maps into processes, which is reported as
+    /// `[vdso]`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) vdso: bool,
+
+    /// Scan the overlay of PE and ELF files for embedded PE and ELF images, e.g., the payloads of
+    /// installers, and analyze them as well. The image found at offset `OFFSET` of `FILE` is
+    /// reported as `FILE@OFFSET`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) embedded: bool,
+
+    /// Analyze the binaries bundled in `AppImage` files, by extracting their payload with
+    /// `unsquashfs`, and in input directories that are Flatpak or Snap trees. The bundled file
+    /// `PATH` of `BUNDLE` is reported as `BUNDLE!PATH`, and its C runtime library is looked up
+    /// in the libraries of the bundle first.
+    #[arg(long, default_value_t = false)]
+    pub(crate) bundles: bool,
+
+    /// Maximum number of members extracted from each archive or bundle. Further members are
+    /// skipped, with a warning.
+    #[arg(long, value_name = "COUNT", default_value_t = 100_000)]
+    pub(crate) extract_max_members: u64,
+
+    /// Maximum size of each member extracted from an archive, a bundle or a packed executable, in
+    /// bytes. Larger members are skipped, with a warning.
+    /// Suffixes K, M, G and T multiply by powers of 1024.
+    #[arg(long, value_name = "BYTES", default_value = "1G", value_parser = parse_byte_size)]
+    pub(crate) extract_max_member_size: u64,
+
+    /// Maximum total size of the members extracted from each archive or bundle, in bytes.
+    /// Further members are skipped, with a warning.
+    /// Suffixes K, M, G and T multiply by powers of 1024.
+    #[arg(long, value_name = "BYTES", default_value = "16G", value_parser = parse_byte_size)]
+    pub(crate) extract_max_total_size: u64,
+
+    /// Maximum depth of the directories nested in each extracted bundle. Deeper directories are
+    /// skipped, with a warning.
+    #[arg(long, value_name = "DEPTH", default_value_t = 64)]
+    pub(crate) extract_max_depth: u32,
+
+    /// Whether the analysis of the stack protection of archives stops at the first protected
+    /// member, or analyzes all members and reports the number of protected members.
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = ArchiveMembers::FirstHit)]
+    pub(crate) archive_members: ArchiveMembers,
+
+    /// Print the schema describing the structure of reports of the given format, e.g., to
+    /// validate reports or to generate code reading them, then exit.
+    #[arg(long, value_enum, value_name = "FORMAT", exclusive = true)]
+    pub(crate) print_schema: Option<ReportSchema>,
+
+    /// Only classify input files by kind (e.g., ELF executable, ELF shared library, PE), and
+    /// report the number of files of each kind, without checking their security features.
+    #[arg(long, default_value_t = false, conflicts_with_all = ["compare_with", "export_weg", "webhook"])]
+    pub(crate) list_only: bool,
+
+    /// Only report security features whose keyword or check identifier matches this regular
+    /// expression.
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    pub(crate) only: Option<Regex>,
+
+    /// Only report security features that are not supported.
+    #[arg(long, default_value_t = false)]
+    pub(crate) failing: bool,
+
+    /// Exit with a failure status if the status of every security feature of a file is unknown,
+    /// which usually reveals a configuration problem, e.g., a missing `--libc-spec`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) strict_unknown: bool,
+
+    /// Verify that ELF files meet the hardening requirements of the packaging guidelines of a
+    /// distribution. Exit with a failure status if a file does not meet them.
+    #[arg(long, value_enum, value_name = "PRESET")]
+    pub(crate) policy: Option<Policy>,
+
+    /// Report the security label of ELF files, and flag files that are not labeled.
+    #[arg(long, default_value_t = false)]
+    pub(crate) with_mac_context: bool,
+
+    /// Exported function allowed by the `suspicious-exports` check. Can be repeated.
+    #[arg(long = "allow-export", value_name = "SYMBOL")]
+    pub(crate) allowed_exports: Vec<String>,
+
+    /// Report hints about how to enable security features that are not supported.
+    #[arg(long, default_value_t = false)]
+    pub(crate) hints: bool,
+
+    /// Report the architecture and binary format of each file after its path.
+    #[arg(long, default_value_t = false)]
+    pub(crate) show_architecture: bool,
+
+    /// Measure the time spent analyzing each file, and checking each security feature.
+    #[arg(long, default_value_t = false)]
+    pub(crate) timings: bool,
+
+    /// Format numbers using the digit grouping and decimal separators of the locale
+    /// (`LC_ALL`, `LC_NUMERIC` or `LANG`), instead of plain digits.
+    #[arg(long, default_value_t = false)]
+    pub(crate) locale_numbers: bool,
+
+    /// Format of the report.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) format: OutputFormat,
+
+    /// Do not print the numbers of files that pass, warn, fail, or could not be analyzed, once
+    /// all files are reported.
+    #[arg(long, default_value_t = false)]
+    pub(crate) no_summary: bool,
+
+    /// Print nothing if the analysis succeeds, i.e., if the exit status is zero, e.g., when run by
+    /// build rules. Otherwise, the report is printed as usual.
+    #[arg(long, default_value_t = false)]
+    pub(crate) quiet_success: bool,
+
+    /// Replace user directories in reported paths by `~`, so that reports can be shared.
+    #[arg(long, default_value_t = false)]
+    pub(crate) redact_paths: bool,
+
+    /// Report files in the order their analysis completed, instead of the order of the input files.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unordered: bool,
+
+    /// Order in which files are reported.
+    #[arg(long, value_enum, default_value_t = SortOrder::Input)]
+    pub(crate) sort: SortOrder,
+
+    /// Report files in groups.
+    #[arg(long, value_enum)]
+    pub(crate) group_by: Option<GroupBy>,
+
+    /// Export a Windows Defender Exploit Guard process mitigation policy to a file.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) export_weg: Option<PathBuf>,
+
+    /// Update the modification time of a file if the analysis succeeds, or remove it otherwise, so
+    /// that build systems track when binaries were last verified.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) stamp_file: Option<PathBuf>,
+
+    /// Write a dependency file in Makefile syntax, declaring that the `--stamp-file` depends on
+    /// the analyzed files, so that build systems rerun the analysis only when they change.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath, requires = "stamp_file")]
+    pub(crate) depfile: Option<PathBuf>,
+
+    /// Write an SVG badge showing the hardening score of the analyzed files, e.g.,
+    /// `hardening: 92%`. The score is the percentage of the requirements of the `--policy` met by
+    /// the files if a policy is given, or of their supported security features otherwise.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) badge: Option<PathBuf>,
+
+    /// Write a copy of the analyzed ELF file to a file, with a `.note.bsc` section embedding its
+    /// JSON report. Exactly one ELF file must be analyzed.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) annotate_elf: Option<PathBuf>,
+
+    /// URL of an HTTP endpoint to which the JSON report is posted, in addition to printing the
+    /// report in the requested format.
+    #[arg(long, value_name = "URL", value_parser = parse_webhook_url)]
+    pub(crate) webhook: Option<String>,
+
+    /// HTTP header sent along with the reports posted to `--webhook`, e.g.,
+    /// `"Authorization: Bearer TOKEN"`. This can be specified multiple times.
+    #[arg(long, value_name = "NAME: VALUE", value_parser = parse_http_header, requires = "webhook")]
+    pub(crate) webhook_header: Vec<HttpHeader>,
+
+    /// Post a JSON report for each file to `--webhook`, instead of a single report of all files.
+    #[arg(long, default_value_t = false, requires = "webhook")]
+    pub(crate) webhook_per_file: bool,
+
+    /// Number of times posting a report to `--webhook` is retried after failures that might be
+    /// transient, e.g., connection failures and server errors.
+    #[arg(long, value_name = "COUNT", default_value_t = 3, requires = "webhook")]
+    pub(crate) webhook_retries: u32,
+
+    /// Analyze again the files of a JSON report that could not be analyzed, or that do not
+    /// support all security features. Other files are reported as they were.
+    #[arg(long, value_name = "REPORT", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) retry_from: Option<PathBuf>,
+
+    /// Compare the results with those of a previous JSON report, and only report the security
+    /// features whose status changed, followed by the numbers of regressions and improvements.
+    #[arg(long, value_name = "REPORT", value_hint = clap::ValueHint::FilePath, conflicts_with = "retry_from")]
+    pub(crate) compare_with: Option<PathBuf>,
+
+    /// File listing libraries that the input files load at runtime (e.g., by `dlopen`), one per
+    /// line. Listed libraries are analyzed along with the input files.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub(crate) dlopen_list: Option<PathBuf>,
+
+    /// Sandbox the analysis, before any file is parsed, so that crafted files exploiting the
+    /// parsers cannot write files, access the network, or run programs.
+    #[arg(long, value_enum, default_value_t = Sandbox::Off)]
+    pub(crate) sandbox: Sandbox,
+
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
     /// Binary files to analyze.
-    #[arg(required = true, value_hint = clap::ValueHint::FilePath)]
+    #[arg(required_unless_present_any = ["retry_from", "dlopen_list", "print_schema", "vdso"], value_hint = clap::ValueHint::FilePath)]
     pub(crate) input_files: Vec<PathBuf>,
 }
 
+#[derive(Debug, Clone, clap::Subcommand)]
+pub(crate) enum Command {
+    /// Verify the environment (e.g., the dynamic loader cache, the system root, the resolution of
+    /// the C runtime library), and verify the checks against synthesized binaries, to help
+    /// understanding unknown results.
+    Doctor,
+
+    /// Serve an HTTP endpoint analyzing uploaded binaries: posting a binary to `/analyze`
+    /// returns its JSON report.
+    Serve {
+        /// Address and port on which the server listens.
+        #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+
+        /// Maximum size of uploaded binaries, in bytes, optionally followed by a binary
+        /// multiple suffix (e.g., `512M`).
+        #[arg(long, value_name = "SIZE", value_parser = parse_byte_size, default_value = "256M")]
+        max_upload_size: u64,
+    },
+
+    /// Run a daemon analyzing the files whose paths are sent to a Unix domain socket, one per
+    /// line, and answering each of them by a JSON report on one line.
+    #[cfg(unix)]
+    Daemon {
+        /// Path of the Unix domain socket on which the daemon listens.
+        #[arg(long, value_name = "SOCKET", value_hint = clap::ValueHint::FilePath)]
+        socket: PathBuf,
+    },
+
+    /// Analyze files with a daemon, and print the JSON report of each file on one line.
+    #[cfg(unix)]
+    Client {
+        /// Path of the Unix domain socket on which the daemon listens.
+        #[arg(long, value_name = "SOCKET", value_hint = clap::ValueHint::FilePath)]
+        socket: PathBuf,
+
+        /// Binary files to analyze.
+        #[arg(required = true, value_hint = clap::ValueHint::FilePath)]
+        files: Vec<PathBuf>,
+    },
+}
+
+/// Parses a size in bytes, optionally followed by a binary multiple suffix (e.g., `512M`).
+fn parse_byte_size(text: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match text.char_indices().last() {
+        Some((index, 'K' | 'k')) => (&text[..index], 1_u64 << 10),
+        Some((index, 'M' | 'm')) => (&text[..index], 1_u64 << 20),
+        Some((index, 'G' | 'g')) => (&text[..index], 1_u64 << 30),
+        Some((index, 'T' | 't')) => (&text[..index], 1_u64 << 40),
+        _ => (text, 1_u64),
+    };
+
+    digits
+        .parse::<u64>()
+        .map_err(|r| r.to_string())?
+        .checked_mul(multiplier)
+        .filter(|&bytes| bytes > 0)
+        .ok_or_else(|| String::from("size must be between 1 and 2^64 - 1 bytes"))
+}
+
+/// HTTP header sent to `--webhook`, whose value is not logged, as it usually holds credentials.
+#[derive(Clone)]
+pub(crate) struct HttpHeader {
+    pub(crate) name: String,
+    pub(crate) value: String,
+}
+
+impl core::fmt::Debug for HttpHeader {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}: <redacted>", self.name)
+    }
+}
+
+/// Parses an HTTP header specified as `NAME: VALUE`.
+fn parse_http_header(text: &str) -> Result<HttpHeader, String> {
+    let (name, value) = text
+        .split_once(':')
+        .ok_or_else(|| String::from("header must be specified as 'NAME: VALUE'"))?;
+
+    let name = name.trim();
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(format!("invalid header name '{name}'"));
+    }
+
+    Ok(HttpHeader {
+        name: name.into(),
+        value: value.trim().into(),
+    })
+}
+
+/// Parses the C runtime library given by `--libc-fallback`, specified as `spec:SPEC`.
+fn parse_libc_fallback(text: &str) -> Result<LibCSpec, String> {
+    use clap::ValueEnum;
+
+    let name = text
+        .strip_prefix("spec:")
+        .ok_or_else(|| String::from("fallback must be specified as 'spec:SPEC'"))?;
+
+    LibCSpec::from_str(name, true).map_err(|_r| {
+        let names: Vec<_> = LibCSpec::value_variants()
+            .iter()
+            .filter_map(ValueEnum::to_possible_value)
+            .map(|value| value.get_name().to_owned())
+            .collect();
+        format!(
+            "unknown specification '{name}', expected one of: {}",
+            names.join(", ")
+        )
+    })
+}
+
+/// Parses the URL of a webhook, which must use HTTP or HTTPS.
+fn parse_webhook_url(text: &str) -> Result<String, String> {
+    if text.starts_with("http://") || text.starts_with("https://") {
+        Ok(text.into())
+    } else {
+        Err(String::from("URL must start with 'http://' or 'https://'"))
+    }
+}
+
+impl Options {
+    pub(crate) fn check_is_enabled(&self, check: OptionalCheck) -> bool {
+        self.enabled_checks.contains(&check)
+    }
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable text.
+    Text,
+    /// JSON document, which also describes the tool and its invocation.
+    Json,
+    /// Prometheus text exposition format, for the textfile collector of `node_exporter`.
+    #[value(name = "prom")]
+    Prometheus,
+    /// Workflow commands of GitHub Actions, which annotate files whose security features are not
+    /// fully supported.
+    #[value(name = "gh-annotations")]
+    GitHubAnnotations,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+pub(crate) enum ReportSchema {
+    /// JSON Schema of the reports printed by `--format json`.
+    Json,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum ArchiveMembers {
+    /// Stop at the first member that is stack-protected.
+    FirstHit,
+    /// Analyze all members, and report the number of stack-protected members.
+    Exhaustive,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+pub(crate) enum Policy {
+    /// Fedora 40: PIE, full RELRO, stack protector, and fortified functions.
+    #[value(name = "fedora40")]
+    Fedora40,
+    /// Debian 12: PIE, partial RELRO, stack protector, and fortified functions.
+    #[value(name = "debian12")]
+    Debian12,
+    /// Ubuntu 24.04: PIE, full RELRO, stack protector, and fortified functions.
+    #[value(name = "ubuntu24.04")]
+    Ubuntu2404,
+    /// Alpine 3.20: PIE, full RELRO, and stack protector.
+    #[value(name = "alpine3.20")]
+    Alpine320,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Sandbox {
+    /// Only allow reading files, mapping them in memory, and writing to the standard output and
+    /// error streams. This uses seccomp on Linux, and job objects on Windows.
+    Strict,
+    /// Do not sandbox the analysis.
+    Off,
+}
+
 #[derive(Debug, Copy, Clone, clap::ValueEnum)]
 pub(crate) enum UseColor {
     Auto,
@@ -72,6 +568,63 @@ impl From<UseColor> for termcolor::ColorChoice {
     }
 }
 
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+pub(crate) enum SortOrder {
+    /// Order in which files are given or discovered.
+    Input,
+    /// Path of the file.
+    Name,
+    /// Most problematic status of the file, most problematic first.
+    Status,
+    /// Percentage of supported security features, lowest first.
+    Score,
+}
+
+#[derive(Debug, Copy, Clone, clap::ValueEnum)]
+pub(crate) enum GroupBy {
+    /// Directory containing the file.
+    Directory,
+    /// Most problematic status of the file.
+    Status,
+}
+
+// If this changes, then update the command line reference.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum OptionalCheck {
+    /// Heap hardening of the memory allocator used by the binary.
+    HeapHardening,
+    /// Automatic variables initialization, as enabled by `-ftrivial-auto-var-init`.
+    AutoVarInit,
+    /// Anomalies suggesting that ELF files were modified after they were linked.
+    Tampering,
+    /// Exports of ELF shared libraries that interpose C runtime library functions or hook into
+    /// loading.
+    SuspiciousExports,
+    /// Number of dynamic symbols exported by ELF files, and their hash tables.
+    SymbolVisibility,
+    /// Operating system and minimum kernel version targeted by ELF files.
+    AbiTag,
+    /// Indirect functions defined by ELF files, whose resolvers run early.
+    #[value(name = "ifunc")]
+    IFunc,
+    /// Numbers of `COPY` and `IRELATIVE` relocations of ELF files.
+    RelocTypes,
+    /// Resources of PE files that embed executable images, scripts, or large high-entropy data.
+    ResourceAnomalies,
+    /// Memory tagging requested by `aarch64` ELF files, for their heap and stack.
+    Mte,
+    /// Forwarded exports, data exports and COM entry points of PE DLLs.
+    ExportAudit,
+    /// Restriction of the directories searched for the DLLs imported by PE files.
+    SafeDllSearch,
+    /// Imports of PE files by ordinal only from DLLs that are not part of the system, and import
+    /// address tables in writable sections.
+    IatAnomalies,
+    /// Versions of OpenSSL, zlib and curl embedded in binaries, and their known critical
+    /// vulnerabilities.
+    VulnerableLibs,
+}
+
 // If this changes, then update the command line reference.
 #[derive(Debug, Copy, Clone, clap::ValueEnum)]
 pub(crate) enum LibCSpec {
@@ -143,8 +696,39 @@ impl LibCSpec {
             | LibCSpec::LSB3dot2 => &[],
 
             LibCSpec::LSB4 | LibCSpec::LSB4dot1 | LibCSpec::LSB5 => {
-                elf::checked_functions::LSB_4_0_0_FUNCTIONS_WITH_CHECKED_VERSIONS
+                fortify::LSB_4_0_0_FUNCTIONS_WITH_CHECKED_VERSIONS
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_libc_fallback, LibCSpec};
+
+    #[test]
+    fn libc_fallback_accepts_specifications() {
+        assert!(matches!(
+            parse_libc_fallback("spec:lsb5"),
+            Ok(LibCSpec::LSB5)
+        ));
+        assert!(matches!(
+            parse_libc_fallback("spec:LSB2dot0dot1"),
+            Ok(LibCSpec::LSB2dot0dot1)
+        ));
+    }
+
+    #[test]
+    fn libc_fallback_requires_spec_prefix() {
+        let error = parse_libc_fallback("lsb5").unwrap_err();
+        assert_eq!(error, "fallback must be specified as 'spec:SPEC'");
+        assert!(parse_libc_fallback("/lib/libc.so.6").is_err());
+    }
+
+    #[test]
+    fn libc_fallback_rejects_unknown_specifications() {
+        let error = parse_libc_fallback("spec:glibc").unwrap_err();
+        assert!(error.starts_with("unknown specification 'glibc', expected one of: lsb1,"));
+        assert!(parse_libc_fallback("spec:").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! State shared by the analyses of files performed with the same configuration.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::elf::needed_libc::LibCResolver;
+use crate::errors::{Error, Result};
+use crate::ui::numbers::NumberFormat;
+
+/// Configuration of an analysis, and the state it caches across files.
+///
+/// Every check receives the context explicitly, so that analyses with different configurations
+/// can run concurrently in the same process.
+pub(crate) struct AnalysisContext<'t> {
+    pub(crate) options: &'t crate::cmdline::Options,
+    pub(crate) number_format: NumberFormat,
+    /// Resolver of C runtime libraries, created when it is first needed.
+    libc_resolver: OnceLock<Option<LibCResolver>>,
+    /// Directories of the libraries of the bundle containing the analyzed files, if any.
+    bundle_library_dirs: Vec<PathBuf>,
+    /// Whether the analyzed files depend on a C runtime library, which is not the case of the
+    /// vDSO.
+    pub(crate) needs_libc: bool,
+}
+
+impl<'t> AnalysisContext<'t> {
+    pub(crate) fn new(options: &'t crate::cmdline::Options) -> Self {
+        Self {
+            options,
+            number_format: NumberFormat::new(options),
+            libc_resolver: OnceLock::new(),
+            bundle_library_dirs: Vec::default(),
+            needs_libc: true,
+        }
+    }
+
+    /// Returns a context for analyzing the files of a bundle, whose C runtime library is looked up
+    /// in the given library directories of the bundle first.
+    pub(crate) fn for_bundle(
+        options: &'t crate::cmdline::Options,
+        bundle_library_dirs: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            bundle_library_dirs,
+            ..Self::new(options)
+        }
+    }
+
+    /// Returns a context for analyzing the vDSO, which depends on no C runtime library.
+    pub(crate) fn for_vdso(options: &'t crate::cmdline::Options) -> Self {
+        Self {
+            needs_libc: false,
+            ..Self::new(options)
+        }
+    }
+
+    /// Returns the resolver of C runtime libraries needed by the analyzed files.
+    ///
+    /// The resolver is created once. If that fails, then the first call returns the reason, and
+    /// later calls fail without retrying.
+    pub(crate) fn libc_resolver(&self) -> Result<&LibCResolver> {
+        let mut first_err = None;
+
+        let r = self
+            .libc_resolver
+            .get_or_init(|| match LibCResolver::new(self.options) {
+                Ok(r) => Some(r.with_bundle_dirs(self.bundle_library_dirs.clone())),
+
+                Err(err) => {
+                    first_err = Some(err);
+                    None
+                }
+            });
+
+        if let Some(err) = first_err {
+            Err(err)
+        } else {
+            r.as_ref().ok_or_else(|| {
+                let err = std::io::ErrorKind::InvalidData.into();
+                Error::from_io1(err, "load linker cache", "")
+            })
+        }
+    }
+}
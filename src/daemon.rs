@@ -0,0 +1,159 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Daemon analyzing files one at a time for other processes, run by the `daemon` command, and its
+//! client, run by the `client` command.
+//!
+//! The daemon amortizes the start of the tool and the resolution of C runtime libraries over all
+//! the files it analyzes. Clients connect to its Unix domain socket, then send the paths of files
+//! to analyze, one per line. The daemon answers each path by the JSON report of the file on one
+//! line, as printed by `--format json`, followed by the reports of its payloads, if any.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use log::{debug, warn};
+
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+
+/// Listens on the Unix domain socket at `socket`, and analyzes the files whose paths are sent to
+/// it, until the process is terminated.
+///
+/// Each client is served by its own thread. Relative paths are resolved from the working
+/// directory of the daemon. A socket left by a daemon that is not running anymore is replaced.
+pub(crate) fn run(socket: &Path, options: &crate::cmdline::Options) -> Result<()> {
+    remove_stale_socket(socket)?;
+
+    let listener =
+        UnixListener::bind(socket).map_err(|r| Error::from_io1(r, "bind socket", socket))?;
+
+    eprintln!("Listening on '{}'", socket.display());
+
+    let context = AnalysisContext::new(options);
+
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let context = &context;
+                    scope.spawn(move || {
+                        if let Err(r) = serve_client(stream, context) {
+                            warn!("Failed to serve client: {r}");
+                        }
+                    });
+                }
+
+                Err(r) => warn!("Failed to accept client: {r}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Removes the socket at `path` if no daemon listens on it anymore.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+
+    if metadata.file_type().is_socket()
+        && UnixStream::connect(path).is_err_and(|r| r.kind() == io::ErrorKind::ConnectionRefused)
+    {
+        debug!("Removing stale socket '{}'.", path.display());
+        fs::remove_file(path).map_err(|r| Error::from_io1(r, "remove socket", path))?;
+    }
+    Ok(())
+}
+
+/// Answers each path sent by a client by the JSON reports of the file and of its payloads.
+fn serve_client(stream: UnixStream, context: &AnalysisContext) -> io::Result<()> {
+    let mut writer = io::BufWriter::new(stream.try_clone()?);
+
+    for line in BufReader::new(stream).split(b'\n') {
+        let line = line?;
+        let path = Path::new(OsStr::from_bytes(line.strip_suffix(b"\r").unwrap_or(&line)));
+        if path.as_os_str().is_empty() {
+            continue;
+        }
+
+        debug!("Analyzing '{}' for client.", path.display());
+
+        let document = match crate::analyze_file_and_payload(path, context) {
+            Ok(results) => crate::report::json_document(&results, &[], context.options),
+
+            Err(error) => {
+                crate::report::json_document(&[], &[(path.into(), error)], context.options)
+            }
+        };
+
+        let document = document.map_err(|r| io::Error::other(crate::format_error(&r)))?;
+        writer.write_all(&document)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Sends the paths of `files` to the daemon listening on `socket`, and prints the JSON reports it
+/// answers, one per line.
+///
+/// Paths are made absolute before they are sent, as the daemon might run in another directory.
+/// Returns `false` if any file could not be analyzed.
+pub(crate) fn run_client(socket: &Path, files: &[PathBuf]) -> Result<bool> {
+    let stream =
+        UnixStream::connect(socket).map_err(|r| Error::from_io1(r, "connect to socket", socket))?;
+
+    let mut reader = BufReader::new(
+        stream
+            .try_clone()
+            .map_err(|r| Error::from_io1(r, "clone socket", socket))?,
+    );
+    let mut writer = stream;
+    let mut out = io::stdout().lock();
+    let mut all_analyzed = true;
+
+    for path in files {
+        let path = std::path::absolute(path).map_err(|r| Error::from_io1(r, "resolve", path))?;
+
+        let mut request = path.into_os_string().into_vec();
+        request.push(b'\n');
+        writer
+            .write_all(&request)
+            .map_err(|r| Error::from_io1(r, "write to socket", socket))?;
+
+        let mut document = String::default();
+        let n = reader
+            .read_line(&mut document)
+            .map_err(|r| Error::from_io1(r, "read from socket", socket))?;
+        if n == 0 {
+            let r = io::ErrorKind::UnexpectedEof.into();
+            return Err(Error::from_io1(r, "read from socket", socket));
+        }
+
+        all_analyzed &= !has_errors(&document);
+
+        out.write_all(document.as_bytes())
+            .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+    }
+    Ok(all_analyzed)
+}
+
+/// Returns `true` if a JSON report lists files that could not be analyzed.
+fn has_errors(document: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(document).map_or(true, |document| {
+        document
+            .get("errors")
+            .and_then(serde_json::Value::as_array)
+            .is_some_and(|errors| !errors.is_empty())
+    })
+}
@@ -0,0 +1,411 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Diagnosis of the environment and of the checks, performed by the `doctor` command to help
+//! understanding unknown results.
+
+use std::io::Write;
+use std::path::Path;
+use std::{env, fs, process};
+
+use termcolor::WriteColor;
+
+use crate::context::AnalysisContext;
+use crate::elf::needed_libc::LibCResolver;
+use crate::errors::{Error, Result};
+use crate::options::status::{CheckResult, SupportLevel};
+use crate::options::{BinarySecurityOption, ELFFortifySourceOption};
+use crate::parser::BinaryFile;
+use crate::ui::ColorBuffer;
+
+/// Outcome of a diagnostic.
+struct Diagnostic {
+    /// `Supported` if the diagnostic passed, `Unsupported` if it failed, and `Unknown` if it is
+    /// not applicable.
+    level: SupportLevel,
+    subject: &'static str,
+    text: String,
+}
+
+impl Diagnostic {
+    fn new(level: SupportLevel, subject: &'static str, text: impl Into<String>) -> Self {
+        Self {
+            level,
+            subject,
+            text: text.into(),
+        }
+    }
+
+    fn failed(subject: &'static str, error: &Error) -> Self {
+        Self::new(
+            SupportLevel::Unsupported,
+            subject,
+            crate::format_error(error),
+        )
+    }
+}
+
+/// Diagnoses the environment and the checks, then prints the outcome of each diagnostic.
+///
+/// Returns `false` if any diagnostic failed.
+pub(crate) fn run(options: &crate::cmdline::Options) -> Result<bool> {
+    let mut diagnostics = vec![
+        dynamic_loader_cache(options),
+        system_root(options),
+        libc_resolution(options),
+    ];
+    diagnostics.extend(self_test(options));
+
+    let mut out = ColorBuffer::for_stdout(options.color);
+    for diagnostic in &diagnostics {
+        print_diagnostic(&mut out, diagnostic)?;
+    }
+
+    Ok(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.level != SupportLevel::Unsupported))
+}
+
+fn print_diagnostic(out: &mut ColorBuffer, diagnostic: &Diagnostic) -> Result<()> {
+    let (marker, color) = diagnostic.level.marker_and_color();
+
+    let color_buffer = &mut out.color_buffer;
+    color_buffer.clear();
+
+    color_buffer
+        .set_color(termcolor::ColorSpec::new().set_fg(Some(color)))
+        .map_err(|r| Error::from_io1(r, "set color", "standard output stream"))?;
+
+    write!(color_buffer, "{marker}{}", diagnostic.subject)
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+    color_buffer
+        .reset()
+        .map_err(|r| Error::from_io1(r, "reset", "standard output stream"))?;
+
+    writeln!(color_buffer, ": {}", diagnostic.text)
+        .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+
+    out.print()
+}
+
+/// Verifies that the dynamic loader cache is readable, unless a system root is specified.
+fn dynamic_loader_cache(options: &crate::cmdline::Options) -> Diagnostic {
+    const SUBJECT: &str = "dynamic loader cache";
+
+    if !options.sysroot.is_empty() {
+        return Diagnostic::new(
+            SupportLevel::Unknown,
+            SUBJECT,
+            "not used, as a system root is specified",
+        );
+    }
+
+    let count = dynamic_loader_cache::Cache::load()
+        .map_err(Error::from)
+        .and_then(|cache| Ok(cache.iter()?.flatten().count()));
+
+    match count {
+        Ok(0) => Diagnostic::new(
+            SupportLevel::Unsupported,
+            SUBJECT,
+            "readable, but lists no libraries",
+        ),
+
+        Ok(count) => Diagnostic::new(
+            SupportLevel::Supported,
+            SUBJECT,
+            format!("readable, and lists {count} libraries"),
+        ),
+
+        Err(error) => Diagnostic::failed(SUBJECT, &error),
+    }
+}
+
+/// Verifies that the system roots, if specified, contain library directories.
+fn system_root(options: &crate::cmdline::Options) -> Diagnostic {
+    const SUBJECT: &str = "system root";
+
+    if options.sysroot.is_empty() {
+        return Diagnostic::new(SupportLevel::Unknown, SUBJECT, "not specified");
+    }
+
+    if let Some(sys_root) = options.sysroot.iter().find(|path| !path.is_dir()) {
+        return Diagnostic::new(
+            SupportLevel::Unsupported,
+            SUBJECT,
+            format!("'{}' is not a directory", sys_root.display()),
+        );
+    }
+
+    let directories = match LibCResolver::new(options) {
+        Ok(resolver) => resolver.library_directories(),
+        Err(error) => return Diagnostic::failed(SUBJECT, &error),
+    };
+
+    let empty_sys_root = options
+        .sysroot
+        .iter()
+        .find(|&sys_root| !directories.iter().any(|dir| dir.starts_with(sys_root)));
+
+    if let Some(sys_root) = empty_sys_root {
+        return Diagnostic::new(
+            SupportLevel::Unsupported,
+            SUBJECT,
+            format!("'{}' contains no library directories", sys_root.display()),
+        );
+    }
+
+    let directories: Vec<_> = directories
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+
+    Diagnostic::new(
+        SupportLevel::Supported,
+        SUBJECT,
+        format!("library directories: {}", directories.join(", ")),
+    )
+}
+
+/// Verifies that the C runtime library needed by this tool is resolved, as it would be for
+/// analyzed files, when this tool is an ELF executable.
+fn libc_resolution(options: &crate::cmdline::Options) -> Diagnostic {
+    const SUBJECT: &str = "C runtime library";
+
+    if options.no_libc {
+        return Diagnostic::new(SupportLevel::Unknown, SUBJECT, "not used, by --no-libc");
+    }
+
+    let path = match env::current_exe() {
+        Ok(path) => path,
+        Err(r) => {
+            let error = Error::from_io1(r, "get path of", "current executable");
+            return Diagnostic::failed(SUBJECT, &error);
+        }
+    };
+
+    let file = match BinaryFile::open(&path) {
+        Ok(file) => file,
+        Err(error) => return Diagnostic::failed(SUBJECT, &error),
+    };
+
+    let parser = match file.parse() {
+        Ok(parser) => parser,
+        Err(error) => return Diagnostic::failed(SUBJECT, &error),
+    };
+
+    if !matches!(parser.object(), goblin::Object::Elf(_)) {
+        return Diagnostic::new(
+            SupportLevel::Unknown,
+            SUBJECT,
+            "not verified, as this tool is not an ELF executable",
+        );
+    }
+
+    let context = AnalysisContext::new(options);
+    match ELFFortifySourceOption::new(options.libc_spec).check(&parser, &context) {
+        Ok(_status) => Diagnostic::new(
+            SupportLevel::Supported,
+            SUBJECT,
+            format!("resolved for '{}'", path.display()),
+        ),
+
+        Err(error) => Diagnostic::failed(SUBJECT, &error),
+    }
+}
+
+/// Expected statuses of the checks of a synthesized binary.
+struct SelfTest {
+    subject: &'static str,
+    hardened: bool,
+    expected: &'static [(&'static str, SupportLevel)],
+}
+
+const SELF_TESTS: [SelfTest; 2] = [
+    SelfTest {
+        subject: "self-test of a hardened ELF executable",
+        hardened: true,
+        expected: &[
+            ("ASLR", SupportLevel::Supported),
+            ("READ-ONLY-RELOC", SupportLevel::Supported),
+            ("IMMEDIATE-BIND", SupportLevel::Supported),
+        ],
+    },
+    SelfTest {
+        subject: "self-test of a non-hardened ELF executable",
+        hardened: false,
+        expected: &[
+            ("ASLR", SupportLevel::Unsupported),
+            ("READ-ONLY-RELOC", SupportLevel::Unsupported),
+            ("IMMEDIATE-BIND", SupportLevel::Unsupported),
+        ],
+    },
+];
+
+/// Analyzes synthesized binaries whose security features are known, and verifies the results.
+///
+/// The binaries do not need a C runtime library, so they are analyzed as with `--no-libc`.
+fn self_test(options: &crate::cmdline::Options) -> Vec<Diagnostic> {
+    let mut self_test_options = options.clone();
+    self_test_options.no_libc = true;
+    self_test_options.libc.clear();
+    self_test_options.libc_spec = None;
+    self_test_options.enabled_checks.clear();
+    self_test_options.fast = false;
+    self_test_options.timings = false;
+    let context = AnalysisContext::new(&self_test_options);
+
+    SELF_TESTS
+        .iter()
+        .map(|self_test| {
+            let path = env::temp_dir().join(format!(
+                "binary-security-check-doctor-{}-{}",
+                process::id(),
+                if self_test.hardened { "good" } else { "bad" }
+            ));
+
+            let r = run_self_test(&path, self_test, &context);
+            let _ignored = fs::remove_file(&path);
+
+            match r {
+                Ok(mismatches) if mismatches.is_empty() => Diagnostic::new(
+                    SupportLevel::Supported,
+                    self_test.subject,
+                    "results are as expected",
+                ),
+
+                Ok(mismatches) => Diagnostic::new(
+                    SupportLevel::Unsupported,
+                    self_test.subject,
+                    format!("unexpected results: {}", mismatches.join(", ")),
+                ),
+
+                Err(error) => Diagnostic::failed(self_test.subject, &error),
+            }
+        })
+        .collect()
+}
+
+/// Returns the checks whose statuses differ from the expected ones, as `CHECK=STATUS`.
+fn run_self_test(
+    path: &Path,
+    self_test: &SelfTest,
+    context: &AnalysisContext,
+) -> Result<Vec<String>> {
+    fs::write(path, synthesize_elf_executable(self_test.hardened))
+        .map_err(|r| Error::from_io1(r, "write file", path))?;
+
+    let results = crate::analyze_file(&path, context)?;
+
+    let mismatches = self_test
+        .expected
+        .iter()
+        .filter_map(|&(name, expected)| {
+            let level = results
+                .checks
+                .iter()
+                .find(|check| check.name() == name)
+                .and_then(CheckResult::level);
+
+            (level != Some(expected)).then(|| {
+                let status = level.map_or("missing", SupportLevel::description);
+                format!("{name}={status}")
+            })
+        })
+        .collect();
+    Ok(mismatches)
+}
+
+/// Returns a minimal 64-bits little-endian x86-64 ELF executable, made of program headers only.
+///
+/// The hardened executable is position-independent, and its dynamic linking information requests
+/// immediate binding and read-only relocated data. The other one is position-dependent, and has no
+/// dynamic linking information. Both start with a `hlt` instruction.
+fn synthesize_elf_executable(hardened: bool) -> Vec<u8> {
+    use goblin::elf::dynamic::{DF_1_NOW, DF_1_PIE, DF_BIND_NOW, DT_FLAGS, DT_FLAGS_1, DT_NULL};
+    use goblin::elf::header::{EM_X86_64, ET_DYN, ET_EXEC};
+    use goblin::elf::program_header::{
+        PF_R, PF_W, PF_X, PT_DYNAMIC, PT_GNU_RELRO, PT_GNU_STACK, PT_LOAD, PT_PHDR,
+    };
+
+    const SIZEOF_EHDR: u16 = 64;
+    const SIZEOF_PHDR: u16 = 56;
+    const SIZEOF_DYN: u64 = 16;
+
+    let (e_type, base_address) = if hardened {
+        (ET_DYN, 0_u64)
+    } else {
+        (ET_EXEC, 0x0040_0000_u64)
+    };
+
+    let dynamic: &[(u64, u64)] = if hardened {
+        &[
+            (DT_FLAGS, DF_BIND_NOW),
+            (DT_FLAGS_1, DF_1_NOW | DF_1_PIE),
+            (DT_NULL, 0),
+        ]
+    } else {
+        &[]
+    };
+
+    let phdr_count: u16 = if hardened { 5 } else { 2 };
+    let phdrs_size = u64::from(phdr_count) * u64::from(SIZEOF_PHDR);
+    let dynamic_offset = u64::from(SIZEOF_EHDR) + phdrs_size;
+    let dynamic_size = dynamic.len() as u64 * SIZEOF_DYN;
+    let entry_offset = dynamic_offset + dynamic_size;
+    let file_size = entry_offset + 8;
+
+    // (p_type, p_flags, p_offset, p_filesz, p_align)
+    let mut phdrs: Vec<(u32, u32, u64, u64, u64)> =
+        vec![(PT_LOAD, PF_R | PF_X, 0, file_size, 0x1000)];
+    if hardened {
+        phdrs.insert(0, (PT_PHDR, PF_R, u64::from(SIZEOF_EHDR), phdrs_size, 8));
+        phdrs.push((PT_DYNAMIC, PF_R | PF_W, dynamic_offset, dynamic_size, 8));
+        phdrs.push((PT_GNU_RELRO, PF_R, dynamic_offset, dynamic_size, 1));
+    }
+    phdrs.push((PT_GNU_STACK, PF_R | PF_W, 0, 0, 16));
+
+    let mut bytes = Vec::with_capacity(usize::try_from(file_size).unwrap_or_default());
+
+    // ELF header.
+    bytes.extend_from_slice(b"\x7fELF\x02\x01\x01\x00");
+    bytes.extend_from_slice(&[0; 8]);
+    bytes.extend_from_slice(&e_type.to_le_bytes());
+    bytes.extend_from_slice(&EM_X86_64.to_le_bytes());
+    bytes.extend_from_slice(&1_u32.to_le_bytes()); // e_version
+    bytes.extend_from_slice(&(base_address + entry_offset).to_le_bytes()); // e_entry
+    bytes.extend_from_slice(&u64::from(SIZEOF_EHDR).to_le_bytes()); // e_phoff
+    bytes.extend_from_slice(&0_u64.to_le_bytes()); // e_shoff
+    bytes.extend_from_slice(&0_u32.to_le_bytes()); // e_flags
+    bytes.extend_from_slice(&SIZEOF_EHDR.to_le_bytes()); // e_ehsize
+    bytes.extend_from_slice(&SIZEOF_PHDR.to_le_bytes()); // e_phentsize
+    bytes.extend_from_slice(&phdr_count.to_le_bytes()); // e_phnum
+    bytes.extend_from_slice(&[0; 6]); // e_shentsize, e_shnum, e_shstrndx
+
+    // Program headers.
+    for (p_type, p_flags, p_offset, p_filesz, p_align) in phdrs {
+        bytes.extend_from_slice(&p_type.to_le_bytes());
+        bytes.extend_from_slice(&p_flags.to_le_bytes());
+        bytes.extend_from_slice(&p_offset.to_le_bytes());
+        bytes.extend_from_slice(&(base_address + p_offset).to_le_bytes()); // p_vaddr
+        bytes.extend_from_slice(&(base_address + p_offset).to_le_bytes()); // p_paddr
+        bytes.extend_from_slice(&p_filesz.to_le_bytes());
+        bytes.extend_from_slice(&p_filesz.to_le_bytes()); // p_memsz
+        bytes.extend_from_slice(&p_align.to_le_bytes());
+    }
+
+    // Dynamic linking information.
+    for &(d_tag, d_val) in dynamic {
+        bytes.extend_from_slice(&d_tag.to_le_bytes());
+        bytes.extend_from_slice(&d_val.to_le_bytes());
+    }
+
+    // Code: `hlt`, padded with `int3`.
+    bytes.push(0xF4);
+    bytes.resize(usize::try_from(file_size).unwrap_or_default(), 0xCC);
+    bytes
+}
@@ -0,0 +1,505 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! UEFI firmware: Terse Executable (TE) images of PEI modules, and the capsules and firmware
+//! volumes holding the images of firmware modules.
+//!
+//! TE images are PE images whose headers are stripped down to the fields needed to load them.
+//! Fields such as `DllCharacteristics` are not kept, so the checks of PE images are mapped to
+//! what the remaining headers and the section table tell.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use goblin::pe::section_table::{
+    SectionTable, IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_WRITE,
+};
+use log::{debug, warn};
+use scroll::{Pread, LE};
+
+use crate::context::AnalysisContext;
+use crate::embedded::image_path;
+use crate::errors::{Error, Result};
+use crate::extract::{ExtractionBudget, ExtractionLimits, Refusal};
+use crate::options::status::{
+    CheckResult, DetailedStatus, InformationalStatus, SupportLevel, YesNoUnknownStatus,
+};
+use crate::parser::{BinaryFile, BinaryParser};
+use crate::report::{is_failing, Architecture, FileResults};
+
+/// Keyword of the result describing a UEFI capsule or firmware volume.
+pub(crate) const EFI_CONTAINER: &str = "EFI-CONTAINER";
+
+/// Signature starting TE headers.
+const TE_SIGNATURE: &[u8] = b"VZ";
+const SIZEOF_TE_HEADER: usize = 40;
+
+/// Size of memory pages, to which sections must be aligned for firmware to apply their
+/// permissions.
+const PAGE_SIZE: u32 = 4096;
+
+/// Identifiers of the capsules defined by the UEFI specification: `EFI_CAPSULE_GUID` and
+/// `EFI_FIRMWARE_MANAGEMENT_CAPSULE_ID_GUID`, as stored in files.
+static CAPSULE_GUIDS: &[[u8; 16]] = &[
+    [
+        0xbd, 0x86, 0x66, 0x3b, 0x76, 0x0d, 0x30, 0x40, 0xb7, 0x0e, 0xb5, 0x51, 0x9e, 0x2f, 0xc5,
+        0xa0,
+    ],
+    [
+        0xed, 0xd5, 0xcb, 0x6d, 0x2d, 0xe8, 0x44, 0x4c, 0xbd, 0xa1, 0x71, 0x94, 0x19, 0x9a, 0xd9,
+        0x2a,
+    ],
+];
+
+/// Identifiers of the file systems of firmware volumes: `EFI_FIRMWARE_FILE_SYSTEM2_GUID` and
+/// `EFI_FIRMWARE_FILE_SYSTEM3_GUID`, as stored in files.
+static FILE_SYSTEM_GUIDS: &[[u8; 16]] = &[
+    [
+        0x78, 0xe5, 0x8c, 0x8c, 0x3d, 0x8a, 0x1c, 0x4f, 0x99, 0x35, 0x89, 0x61, 0x85, 0xc3, 0x2d,
+        0xd3,
+    ],
+    [
+        0x7a, 0xc0, 0x73, 0x54, 0xcb, 0x3d, 0xca, 0x4d, 0xbd, 0x6f, 0x1e, 0x96, 0x89, 0xe7, 0x34,
+        0x9a,
+    ],
+];
+
+/// Signature of firmware volume headers, and its offset in these headers.
+const FV_SIGNATURE: &[u8] = b"_FVH";
+const FV_SIGNATURE_OFFSET: usize = 40;
+const SIZEOF_FV_HEADER: usize = 56;
+const EFI_FVB2_ERASE_POLARITY: u32 = 0x0800;
+
+const SIZEOF_FFS_FILE_HEADER: usize = 24;
+const SIZEOF_FFS_FILE_HEADER2: usize = 32;
+const FFS_ATTRIB_LARGE_FILE: u8 = 0x01;
+const EFI_FV_FILETYPE_FFS_PAD: u8 = 0xf0;
+const EFI_FILE_DELETED: u8 = 0x10;
+
+const EFI_SECTION_COMPRESSION: u8 = 0x01;
+const EFI_SECTION_GUID_DEFINED: u8 = 0x02;
+const EFI_SECTION_PE32: u8 = 0x10;
+const EFI_SECTION_TE: u8 = 0x12;
+const EFI_SECTION_FIRMWARE_VOLUME_IMAGE: u8 = 0x17;
+const EFI_NOT_COMPRESSED: u8 = 0x00;
+const EFI_GUIDED_SECTION_PROCESSING_REQUIRED: u16 = 0x01;
+
+/// Maximum nesting of firmware volumes and encapsulation sections, beyond which contents are
+/// ignored.
+const MAX_NESTING: usize = 8;
+
+/// Fields of TE headers.
+struct TeHeader {
+    machine: u16,
+    number_of_sections: u8,
+    stripped_size: u16,
+    address_of_entry_point: u32,
+    base_relocation_table_size: u32,
+}
+
+fn te_header(bytes: &[u8]) -> Option<TeHeader> {
+    if !bytes.starts_with(TE_SIGNATURE) || bytes.len() < SIZEOF_TE_HEADER {
+        return None;
+    }
+
+    Some(TeHeader {
+        machine: bytes.pread_with(2, LE).ok()?,
+        number_of_sections: bytes.pread_with(4, LE).ok()?,
+        stripped_size: bytes.pread_with(6, LE).ok()?,
+        address_of_entry_point: bytes.pread_with(8, LE).ok()?,
+        base_relocation_table_size: bytes.pread_with(28, LE).ok()?,
+    })
+}
+
+/// Returns `true` if `bytes` start with the headers of a TE image.
+pub(crate) fn is_te_image(bytes: &[u8]) -> bool {
+    te_header(bytes).is_some_and(|header| {
+        // TE headers replace at least the DOS, PE and COFF headers.
+        usize::from(header.stripped_size) >= SIZEOF_TE_HEADER
+    })
+}
+
+/// Returns the architecture targeted by the TE image, and the results of the checks of PE images
+/// that apply to it.
+///
+/// - `ASLR` is supported if base relocations are kept, so that the image can be loaded at another
+///   address.
+/// - `DATA-EXEC-PREVENT` is supported if no section is both writable and executable, and if all
+///   sections are aligned to memory pages, so that firmware can apply their permissions.
+/// - `ENTRY-POINT` is only reported when the entry point is unexpectedly located.
+pub(crate) fn analyze_te_image(bytes: &[u8]) -> Result<(Architecture, Vec<CheckResult>)> {
+    let header = te_header(bytes).ok_or_else(|| Error::ParseFile {
+        source: goblin::error::Error::Malformed("TE header is truncated".into()),
+    })?;
+
+    let mut offset = SIZEOF_TE_HEADER;
+    let sections = (0..header.number_of_sections)
+        .map(|_index| SectionTable::parse(bytes, &mut offset, 0))
+        .collect::<goblin::error::Result<Vec<_>>>()
+        .map_err(|source| Error::ParseFile { source })?;
+
+    let relocatable =
+        YesNoUnknownStatus::new("ASLR", header.base_relocation_table_size != 0).into();
+    let mut checks = vec![relocatable, data_execution_prevention(&sections).into()];
+
+    let entry_point = crate::pe::entry_point_anomalies_in_sections(
+        header.address_of_entry_point.into(),
+        &sections,
+    )
+    .into();
+    if is_failing(&entry_point) {
+        checks.push(entry_point);
+    }
+
+    let is_64 = matches!(
+        header.machine,
+        goblin::pe::header::COFF_MACHINE_X86_64
+            | goblin::pe::header::COFF_MACHINE_ARM64
+            | goblin::pe::header::COFF_MACHINE_RISCV64
+            | goblin::pe::header::COFF_MACHINE_IA64
+    );
+    Ok((
+        crate::pe::machine_architecture(header.machine, is_64),
+        checks,
+    ))
+}
+
+/// Reports the sections of a TE image that prevent firmware from mapping its data as
+/// non-executable: `writable-executable=...` names sections that are both writable and
+/// executable, and `unaligned-sections` is reported if sections are not aligned to memory pages.
+fn data_execution_prevention(sections: &[SectionTable]) -> DetailedStatus {
+    let writable_executable: Vec<_> = sections
+        .iter()
+        .filter(|section| {
+            (section.characteristics & IMAGE_SCN_MEM_WRITE) != 0
+                && (section.characteristics & (IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_CNT_CODE)) != 0
+        })
+        .map(|section| section.name().unwrap_or_default())
+        .collect();
+
+    let mut details = Vec::default();
+    if !writable_executable.is_empty() {
+        details.push(format!(
+            "writable-executable={}",
+            writable_executable.join("+")
+        ));
+    }
+    if sections
+        .iter()
+        .any(|section| section.virtual_address % PAGE_SIZE != 0)
+    {
+        details.push("unaligned-sections".into());
+    }
+
+    let level = if details.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("DATA-EXEC-PREVENT", level, details)
+}
+
+/// Kind of a container of firmware images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContainerKind {
+    /// UEFI capsule, starting with a capsule header.
+    Capsule,
+    /// Firmware volume, starting with a firmware volume header.
+    FirmwareVolume,
+}
+
+fn container_kind(bytes: &[u8]) -> Option<ContainerKind> {
+    if bytes
+        .get(..16)
+        .is_some_and(|guid| CAPSULE_GUIDS.iter().any(|capsule| capsule == guid))
+    {
+        let header_size: u32 = bytes.pread_with(16, LE).ok()?;
+        (usize::try_from(header_size).ok()? <= bytes.len()).then_some(ContainerKind::Capsule)
+    } else {
+        firmware_volume_size(bytes).map(|_size| ContainerKind::FirmwareVolume)
+    }
+}
+
+/// Returns `true` if `bytes` start with a UEFI capsule or a firmware volume.
+pub(crate) fn is_container(bytes: &[u8]) -> bool {
+    container_kind(bytes).is_some()
+}
+
+/// Returns the size of the firmware volume starting `bytes`, if it is valid.
+fn firmware_volume_size(bytes: &[u8]) -> Option<usize> {
+    let file_system = bytes.get(16..32)?;
+    let length: u64 = bytes.pread_with(32, LE).ok()?;
+    let header_length: u16 = bytes.pread_with(48, LE).ok()?;
+    let length = usize::try_from(length).ok()?;
+
+    (bytes.get(FV_SIGNATURE_OFFSET..FV_SIGNATURE_OFFSET + 4)? == FV_SIGNATURE
+        && FILE_SYSTEM_GUIDS.iter().any(|guid| guid == file_system)
+        && usize::from(header_length) >= SIZEOF_FV_HEADER
+        && usize::from(header_length) <= length
+        && length <= bytes.len())
+    .then_some(length)
+}
+
+/// Images and firmware volumes found in a container.
+#[derive(Default)]
+struct Contents {
+    volumes: usize,
+    /// Offsets and sizes of PE and TE images, relative to the container.
+    images: Vec<(usize, usize)>,
+    /// Number of sections that are compressed or encoded, whose images are not found.
+    encoded: usize,
+}
+
+impl Contents {
+    /// Finds the firmware volumes of `bytes`, located at `base` in the container.
+    fn scan_volumes(&mut self, bytes: &[u8], base: usize, nesting: usize) {
+        let mut offset = 0;
+        while let Some(position) = bytes
+            .get(offset + FV_SIGNATURE_OFFSET..)
+            .and_then(|rest| rest.windows(4).position(|window| window == FV_SIGNATURE))
+        {
+            let start = offset + position;
+            match firmware_volume_size(&bytes[start..]) {
+                Some(size) => {
+                    self.walk_volume(&bytes[start..start + size], base + start, nesting);
+                    offset = start + size;
+                }
+
+                None => offset = start + 1,
+            }
+        }
+    }
+
+    /// Walks the files of the firmware volume `volume`, located at `base` in the container.
+    fn walk_volume(&mut self, volume: &[u8], base: usize, nesting: usize) {
+        if nesting >= MAX_NESTING {
+            debug!("Ignoring firmware volume at offset {base:#x}, which is nested too deeply.");
+            return;
+        }
+        self.volumes += 1;
+
+        let attributes: u32 = volume.pread_with(44, LE).unwrap_or_default();
+        let header_length: u16 = volume.pread_with(48, LE).unwrap_or_default();
+        let ext_header_offset: u16 = volume.pread_with(52, LE).unwrap_or_default();
+        let erased = if (attributes & EFI_FVB2_ERASE_POLARITY) == 0 {
+            0x00
+        } else {
+            0xff
+        };
+
+        let mut offset = usize::from(header_length);
+        if ext_header_offset != 0 {
+            let ext_header_size: u32 = volume
+                .pread_with(usize::from(ext_header_offset) + 16, LE)
+                .unwrap_or_default();
+            offset = offset.max(
+                usize::from(ext_header_offset)
+                    .saturating_add(usize::try_from(ext_header_size).unwrap_or(usize::MAX)),
+            );
+        }
+
+        loop {
+            offset = offset.next_multiple_of(8);
+            let Some(header) = volume.get(offset..offset + SIZEOF_FFS_FILE_HEADER) else {
+                break;
+            };
+
+            // Free space is filled with erased bytes.
+            if header.iter().all(|&b| b == erased) {
+                break;
+            }
+
+            let file_type = header[18];
+            let file_attributes = header[19];
+            let state = header[23] ^ erased;
+            let (size, header_size) = if (file_attributes & FFS_ATTRIB_LARGE_FILE) != 0 {
+                let size: u64 = volume.pread_with(offset + 24, LE).unwrap_or_default();
+                (
+                    usize::try_from(size).unwrap_or(usize::MAX),
+                    SIZEOF_FFS_FILE_HEADER2,
+                )
+            } else {
+                (
+                    usize::from(header[20])
+                        | (usize::from(header[21]) << 8)
+                        | (usize::from(header[22]) << 16),
+                    SIZEOF_FFS_FILE_HEADER,
+                )
+            };
+
+            let Some(file) = volume.get(offset..offset.saturating_add(size)) else {
+                debug!(
+                    "Firmware file at offset {:#x} exceeds its volume.",
+                    base + offset
+                );
+                break;
+            };
+            if size < header_size {
+                break;
+            }
+
+            if file_type != EFI_FV_FILETYPE_FFS_PAD && (state & EFI_FILE_DELETED) == 0 {
+                self.walk_sections(&file[header_size..], base + offset + header_size, nesting);
+            }
+            offset += size;
+        }
+    }
+
+    /// Walks the sections of a firmware file, `sections`, located at `base` in the container.
+    fn walk_sections(&mut self, sections: &[u8], base: usize, nesting: usize) {
+        let mut offset = 0;
+
+        while let Some(header) = sections.get(offset..offset + 4) {
+            let mut size = usize::from(header[0])
+                | (usize::from(header[1]) << 8)
+                | (usize::from(header[2]) << 16);
+            let section_type = header[3];
+            let mut header_size = 4;
+
+            if size == 0x00ff_ffff {
+                let extended_size: u32 = sections.pread_with(offset + 4, LE).unwrap_or_default();
+                size = usize::try_from(extended_size).unwrap_or(usize::MAX);
+                header_size = 8;
+            }
+
+            let Some(section) = sections.get(offset..offset.saturating_add(size)) else {
+                break;
+            };
+            if size < header_size {
+                break;
+            }
+            let data = &section[header_size..];
+            let data_base = base + offset + header_size;
+
+            match section_type {
+                EFI_SECTION_PE32 | EFI_SECTION_TE => self.images.push((data_base, data.len())),
+
+                EFI_SECTION_FIRMWARE_VOLUME_IMAGE => {
+                    self.scan_volumes(data, data_base, nesting + 1);
+                }
+
+                // Sections that are not compressed are encapsulated as is.
+                EFI_SECTION_COMPRESSION if nesting + 1 < MAX_NESTING => {
+                    if data.get(4) == Some(&EFI_NOT_COMPRESSED) {
+                        self.walk_sections(&data[5..], data_base + 5, nesting + 1);
+                    } else {
+                        self.encoded += 1;
+                    }
+                }
+
+                // Sections that need no processing, e.g., signed sections, are encapsulated as is.
+                EFI_SECTION_GUID_DEFINED if nesting + 1 < MAX_NESTING => {
+                    let data_offset: u16 = section.pread_with(header_size + 16, LE).unwrap_or(0);
+                    let attributes: u16 = section.pread_with(header_size + 18, LE).unwrap_or(0);
+
+                    match section.get(usize::from(data_offset)..) {
+                        Some(encapsulated)
+                            if (attributes & EFI_GUIDED_SECTION_PROCESSING_REQUIRED) == 0 =>
+                        {
+                            let encapsulated_base = base + offset + usize::from(data_offset);
+                            self.walk_sections(encapsulated, encapsulated_base, nesting + 1);
+                        }
+
+                        _ => self.encoded += 1,
+                    }
+                }
+
+                _ => {}
+            }
+
+            offset = (offset + size).next_multiple_of(4);
+        }
+    }
+}
+
+/// Returns the images and the firmware volumes found in the container `bytes`.
+fn contents(bytes: &[u8]) -> Contents {
+    let mut contents = Contents::default();
+
+    match container_kind(bytes) {
+        Some(ContainerKind::FirmwareVolume) => {
+            let size = firmware_volume_size(bytes).unwrap_or_default();
+            contents.walk_volume(&bytes[..size], 0, 0);
+        }
+
+        // Capsules hold firmware volumes, possibly wrapped into further headers, e.g., those of
+        // firmware management capsules.
+        Some(ContainerKind::Capsule) => {
+            let header_size: u32 = bytes.pread_with(16, LE).unwrap_or_default();
+            let start = usize::try_from(header_size).unwrap_or(usize::MAX);
+            if let Some(payload) = bytes.get(start..) {
+                contents.scan_volumes(payload, start, 0);
+            }
+        }
+
+        None => {}
+    }
+    contents
+}
+
+/// Returns the result describing the capsule or the firmware volume `bytes`.
+///
+/// The details name the kind of container, followed by the numbers of firmware volumes and
+/// images it holds, and the number of compressed or encoded sections whose images are not
+/// analyzed, if any, e.g., `*EFI-CONTAINER(capsule,volumes=2,images=35,encoded=1)`.
+pub(crate) fn container_status(bytes: &[u8]) -> CheckResult {
+    let kind = match container_kind(bytes) {
+        Some(ContainerKind::Capsule) => "capsule",
+        Some(ContainerKind::FirmwareVolume) | None => "firmware-volume",
+    };
+
+    let contents = contents(bytes);
+    let mut details = vec![
+        kind.to_owned(),
+        format!("volumes={}", contents.volumes),
+        format!("images={}", contents.images.len()),
+    ];
+    if contents.encoded > 0 {
+        details.push(format!("encoded={}", contents.encoded));
+    }
+    InformationalStatus::new(EFI_CONTAINER, details).into()
+}
+
+/// Analyzes the PE and TE images held by the capsule or the firmware volume at `path`.
+///
+/// Images are analyzed in memory. Images in compressed or encoded sections are not found.
+/// Images exceeding the extraction limits are skipped, and images that cannot be analyzed are
+/// reported as warnings.
+pub(crate) fn analyze_images(
+    path: &Path,
+    context: &AnalysisContext,
+) -> Result<Vec<(PathBuf, FileResults)>> {
+    let file = BinaryFile::open(path)?;
+    let parser = file.parse()?;
+    let bytes = parser.bytes();
+
+    let mut budget = ExtractionBudget::new(path, ExtractionLimits::new(context.options));
+    let mut results = Vec::default();
+
+    for (offset, size) in contents(bytes).images {
+        match budget.admit(&format!("{offset:#x}"), size as u64) {
+            Ok(()) => {}
+            Err(Refusal::SkipMember) => continue,
+            Err(Refusal::StopContainer) => break,
+        }
+
+        let image_path = image_path(path, offset);
+        let start = context.options.timings.then(Instant::now);
+        let r = BinaryParser::parse(&image_path, &bytes[offset..offset + size])
+            .and_then(|image_parser| crate::analyze_parsed_file(&image_parser, context, start));
+
+        match r {
+            Ok(image_results) => results.push((image_path, image_results)),
+
+            Err(error) => warn!(
+                "{}: Firmware image is not analyzed. {}",
+                image_path.display(),
+                crate::format_error(&error)
+            ),
+        }
+    }
+    Ok(results)
+}
@@ -4,33 +4,57 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
-pub(crate) mod checked_functions;
+pub(crate) mod annotate;
+pub(crate) mod debug_file;
+pub(crate) mod dlopen_list;
+pub(crate) mod dwarf;
 pub(crate) mod needed_libc;
+pub(crate) mod search_path;
 
 use std::collections::HashSet;
+use std::path::Path;
+use std::sync::LazyLock;
 
 use log::{debug, log_enabled, warn};
+use regex::bytes::{Regex, RegexBuilder};
 
+use crate::cmdline::OptionalCheck;
+use crate::context::AnalysisContext;
 use crate::errors::Result;
-use crate::options::status::{ASLRCompatibilityLevel, DisplayInColorTerm};
+use crate::fortify::SymbolSource;
+use crate::options::status::{
+    ASLRCompatibilityLevel, CheckResult, DetailedStatus, InformationalStatus, NotApplicableStatus,
+    SupportLevel,
+};
 use crate::options::{
-    AddressSpaceLayoutRandomizationOption, BinarySecurityOption, ELFFortifySourceOption,
-    ELFImmediateBindingOption, ELFReadOnlyAfterRelocationsOption, ELFStackProtectionOption,
+    AddressSpaceLayoutRandomizationOption, BinarySecurityOption, ELFAbiTagOption, ELFAndroidOption,
+    ELFAutoVarInitOption, ELFBranchProtectionOption, ELFDwarfHardeningOption,
+    ELFFortifySourceOption, ELFHeaderConsistencyOption, ELFImmediateBindingOption,
+    ELFIndirectFunctionsOption, ELFMemoryTaggingOption, ELFReadOnlyAfterRelocationsOption,
+    ELFRelocationTypesOption, ELFStackProtectionOption, ELFSuspiciousExportsOption,
+    ELFSymbolVisibilityOption, ELFTamperingOption, EntryPointOption, HeapHardeningOption,
+    ImageBaseOption, OverlayOption, VulnerableLibrariesOption,
 };
 use crate::parser::BinaryParser;
+use crate::report::{is_failing, Architecture, Endianness};
+use crate::ui::numbers::NumberFormat;
+use crate::vulnerable_libs::VULNERABLE_LIBS;
+use crate::xattrs;
 
-use self::checked_functions::function_is_checked_version;
-use self::needed_libc::NeededLibC;
+use self::needed_libc::{LibCSource, NeededLibC};
 
+/// Returns the results of the checks of an `ELF` binary, along with the C runtime library that
+/// its checked functions were compared against, if it was resolved.
 pub(crate) fn analyze_binary(
     parser: &BinaryParser,
-    options: &crate::cmdline::Options,
-) -> Result<Vec<Box<dyn DisplayInColorTerm>>> {
+    context: &AnalysisContext,
+) -> Result<(Vec<CheckResult>, Option<LibCSource>)> {
+    let options = context.options;
     let supports_address_space_layout_randomization =
-        AddressSpaceLayoutRandomizationOption.check(parser, options)?;
-    let has_stack_protection = ELFStackProtectionOption.check(parser, options)?;
-    let read_only_after_reloc = ELFReadOnlyAfterRelocationsOption.check(parser, options)?;
-    let immediate_bind = ELFImmediateBindingOption.check(parser, options)?;
+        AddressSpaceLayoutRandomizationOption.timed_check(parser, context)?;
+    let has_stack_protection = ELFStackProtectionOption.timed_check(parser, context)?;
+    let read_only_after_reloc = ELFReadOnlyAfterRelocationsOption.timed_check(parser, context)?;
+    let immediate_bind = ELFImmediateBindingOption.timed_check(parser, context)?;
 
     let mut result = vec![
         supports_address_space_layout_randomization,
@@ -39,44 +63,297 @@ pub(crate) fn analyze_binary(
         immediate_bind,
     ];
 
-    if !options.no_libc {
-        let fortify_source =
-            ELFFortifySourceOption::new(options.libc_spec).check(parser, options)?;
-        result.push(fortify_source);
+    // Files granting privileges to the processes executing them deserve more scrutiny.
+    let capabilities = xattrs::file_capabilities(parser.path());
+    let privileged = capabilities.is_some() || !set_id_bits(parser.path()).is_empty();
+    let check_is_enabled = |check| {
+        options.check_is_enabled(check) || (privileged && PRIVILEGED_CHECKS.contains(&check))
+    };
+
+    // Checks that walk full symbol tables or section contents are skipped by `--fast`.
+    let mut libc_source = None;
+    if !options.no_libc && context.needs_libc {
+        if options.fast {
+            result.push(DetailedStatus::skipped("FORTIFY-SOURCE").into());
+        } else {
+            let fortify_source = ELFFortifySourceOption::new(options.libc_spec);
+            match fortify_source.timed_check(parser, context) {
+                Ok(status) => {
+                    result.push(status);
+                    libc_source = fortify_source.libc_source();
+                }
+
+                // The other security features do not depend on the C runtime library.
+                Err(error) if fortify_source.libc_is_unresolved() => {
+                    warn!(
+                        "{}: The C runtime library is not resolved. {}",
+                        parser.path().display(),
+                        crate::format_error(&error)
+                    );
+                    let status = DetailedStatus::new(
+                        "FORTIFY-SOURCE",
+                        SupportLevel::Unknown,
+                        vec!["unresolved libc".into()],
+                    );
+                    result.push(status.into());
+                    libc_source = fortify_source.libc_source();
+                }
+
+                Err(error) => return Err(error),
+            }
+        }
+    } else {
+        libc_source = Some(LibCSource::None);
+
+        // Files assumed to use no C runtime library are not checked, which differs from files
+        // whose checked functions are unknown.
+        if options.no_libc {
+            result.push(NotApplicableStatus::new("FORTIFY-SOURCE", "--no-libc").into());
+        }
+    }
+
+    if let goblin::Object::Elf(elf) = parser.object() {
+        // Position-dependent executables are loaded at addresses known in advance.
+        if elf.header.e_type == goblin::elf::header::ET_EXEC {
+            result.push(ImageBaseOption.timed_check(parser, context)?);
+        }
+
+        // Binaries for Android are checked for properties specific to that platform.
+        if depends_on_bionic(parser, elf) {
+            result.push(ELFAndroidOption.timed_check(parser, context)?);
+
+            if elf.header.e_machine == goblin::elf::header::EM_AARCH64 {
+                result.push(ELFBranchProtectionOption.timed_check(parser, context)?);
+            }
+        }
+
+        let dynamic_flags = attack_surface_dynamic_flags(elf);
+        if !dynamic_flags.is_empty() {
+            result.push(InformationalStatus::new("DYNAMIC-FLAGS", dynamic_flags).into());
+        }
+    }
+
+    if let Some(capabilities) = capabilities {
+        result.push(InformationalStatus::new("CAPABILITIES", capabilities).into());
+    }
+
+    if check_is_enabled(OptionalCheck::HeapHardening) {
+        if options.fast {
+            result.push(DetailedStatus::skipped("HEAP-HARDENING").into());
+        } else {
+            result.push(HeapHardeningOption.timed_check(parser, context)?);
+        }
+    }
+
+    if check_is_enabled(OptionalCheck::AutoVarInit) {
+        if options.fast {
+            result.push(DetailedStatus::skipped("AUTO-VAR-INIT").into());
+        } else {
+            result.push(ELFAutoVarInitOption.timed_check(parser, context)?);
+        }
+    }
+
+    if OverlayOption::overlay(parser).is_some() {
+        result.push(OverlayOption.timed_check(parser, context)?);
+    }
+
+    // Files are only reported when their entry point is unexpectedly located.
+    let entry_point = EntryPointOption.timed_check(parser, context)?;
+    if is_failing(&entry_point) {
+        result.push(entry_point);
+    }
+
+    // Files are only reported when their section headers and program headers disagree.
+    let header_consistency = ELFHeaderConsistencyOption.timed_check(parser, context)?;
+    if is_failing(&header_consistency) {
+        result.push(header_consistency);
+    }
+
+    push_optional_checks(parser, context, &check_is_enabled, &mut result)?;
+    Ok((result, libc_source))
+}
+
+/// Performs the optional checks that are reported after the entry point, when they are enabled.
+fn push_optional_checks(
+    parser: &BinaryParser,
+    context: &AnalysisContext,
+    check_is_enabled: &dyn Fn(OptionalCheck) -> bool,
+    result: &mut Vec<CheckResult>,
+) -> Result<()> {
+    let options = context.options;
+
+    // Debugging information is only read on demand, as it can be large.
+    if options.use_dwarf {
+        if options.fast {
+            result.push(DetailedStatus::skipped(dwarf::DWARF_HARDENING).into());
+        } else {
+            result.push(ELFDwarfHardeningOption.timed_check(parser, context)?);
+        }
+    }
+
+    if check_is_enabled(OptionalCheck::IFunc) {
+        if options.fast {
+            result.push(DetailedStatus::skipped("IFUNC").into());
+        } else {
+            result.push(ELFIndirectFunctionsOption.timed_check(parser, context)?);
+        }
+    }
+
+    if check_is_enabled(OptionalCheck::RelocTypes) {
+        result.push(ELFRelocationTypesOption.timed_check(parser, context)?);
+    }
+
+    if check_is_enabled(OptionalCheck::SuspiciousExports) {
+        if let goblin::Object::Elf(elf) = parser.object() {
+            // Executables are not preloaded.
+            if is_shared_library(elf) {
+                result.push(ELFSuspiciousExportsOption.timed_check(parser, context)?);
+            }
+        }
+    }
+
+    if check_is_enabled(OptionalCheck::SymbolVisibility) {
+        result.push(ELFSymbolVisibilityOption.timed_check(parser, context)?);
+    }
+
+    if check_is_enabled(OptionalCheck::AbiTag) {
+        // Binaries that do not carry the note are not reported.
+        let abi_tag = ELFAbiTagOption.timed_check(parser, context)?;
+        if abi_tag.level() != Some(SupportLevel::Unknown) {
+            result.push(abi_tag);
+        }
+    }
+
+    if check_is_enabled(OptionalCheck::Mte) {
+        if let goblin::Object::Elf(elf) = parser.object() {
+            // Memory tagging is only defined for `aarch64`.
+            if elf.header.e_machine == goblin::elf::header::EM_AARCH64 {
+                result.push(ELFMemoryTaggingOption.timed_check(parser, context)?);
+            }
+        }
+    }
+
+    if check_is_enabled(OptionalCheck::VulnerableLibs) {
+        // Binaries that do not embed recognized library versions are not reported.
+        if options.fast {
+            result.push(DetailedStatus::skipped(VULNERABLE_LIBS).into());
+        } else {
+            let vulnerable_libs = VulnerableLibrariesOption.timed_check(parser, context)?;
+            if vulnerable_libs.level() != Some(SupportLevel::Unknown) {
+                result.push(vulnerable_libs);
+            }
+        }
     }
 
-    Ok(result)
+    if check_is_enabled(OptionalCheck::Tampering) {
+        // Files are only reported when they seem to be tampered with.
+        let tampering = ELFTamperingOption.timed_check(parser, context)?;
+        if is_failing(&tampering) {
+            result.push(tampering);
+        }
+    }
+
+    Ok(())
+}
+
+/// Optional checks performed on files that are `setuid`, `setgid`, or that have capabilities.
+const PRIVILEGED_CHECKS: &[OptionalCheck] = &[
+    OptionalCheck::HeapHardening,
+    OptionalCheck::IFunc,
+    OptionalCheck::Tampering,
+];
+
+/// `EM_LOONGARCH` is not defined by `goblin`.
+const EM_LOONGARCH: u16 = 258;
+
+/// Returns the architecture targeted by the binary.
+pub(crate) fn architecture(elf: &goblin::elf::Elf) -> Architecture {
+    use goblin::elf::header::{
+        machine_to_str, EM_386, EM_AARCH64, EM_ARM, EM_IA_64, EM_MIPS, EM_PPC, EM_PPC64, EM_RISCV,
+        EM_S390, EM_SPARC, EM_SPARCV9, EM_X86_64,
+    };
+
+    let name = match (elf.header.e_machine, elf.is_64) {
+        (EM_386, _) => "x86",
+        (EM_X86_64, _) => "x86_64",
+        (EM_ARM, _) => "arm",
+        (EM_AARCH64, _) => "aarch64",
+        (EM_IA_64, _) => "ia64",
+        (EM_RISCV, false) => "riscv32",
+        (EM_RISCV, true) => "riscv64",
+        (EM_PPC, _) => "powerpc",
+        (EM_PPC64, _) => "powerpc64",
+        (EM_S390, false) => "s390",
+        (EM_S390, true) => "s390x",
+        (EM_MIPS, false) => "mips",
+        (EM_MIPS, true) => "mips64",
+        (EM_SPARC, _) => "sparc",
+        (EM_SPARCV9, _) => "sparc64",
+        (EM_LOONGARCH, false) => "loongarch32",
+        (EM_LOONGARCH, true) => "loongarch64",
+        (machine, _) => machine_to_str(machine),
+    };
+
+    let name = if name == "EM_UNKNOWN" {
+        format!("{:#06x}", elf.header.e_machine)
+    } else {
+        name.to_lowercase()
+    };
+
+    Architecture {
+        name,
+        bits: if elf.is_64 { 64 } else { 32 },
+        endianness: if elf.little_endian {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        },
+    }
 }
 
 pub(crate) fn get_libc_functions_by_protection<'t>(
     elf: &goblin::elf::Elf,
     libc_ref: &'t NeededLibC,
 ) -> (HashSet<&'t str>, HashSet<&'t str>) {
-    let imported_functions = elf
-        .dynsyms
-        .iter()
-        .filter_map(|symbol| dynamic_symbol_is_named_imported_function(elf, &symbol));
-
-    let mut protected_functions = HashSet::<&str>::default();
-    let mut unprotected_functions = HashSet::<&str>::default();
-    for imported_function in imported_functions {
-        if function_is_checked_version(imported_function) {
-            if let Some(unchecked_function) = libc_ref.exports_function(imported_function) {
-                protected_functions.insert(unchecked_function);
-            } else {
-                warn!(
-                    "Checked function '{}' is not exported by the C runtime library. This might indicate a C runtime mismatch.",
-                    imported_function
-                );
-            }
-        } else if let Some(unchecked_function) =
-            libc_ref.exports_checked_version_of_function(imported_function)
-        {
-            unprotected_functions.insert(unchecked_function);
-        }
+    crate::fortify::functions_by_protection(elf, libc_ref.checked_functions())
+}
+
+impl SymbolSource for goblin::elf::Elf<'_> {
+    fn imported_functions(&self) -> Vec<&str> {
+        self.dynsyms
+            .iter()
+            .filter_map(|symbol| dynamic_symbol_is_named_imported_function(self, &symbol))
+            .collect()
     }
 
-    (protected_functions, unprotected_functions)
+    fn exported_functions(&self) -> Vec<&str> {
+        self.dynsyms
+            .iter()
+            .filter_map(|symbol| dynamic_symbol_is_named_exported_function(self, &symbol))
+            .collect()
+    }
+}
+
+/// Default addresses at which linkers place position-dependent executables, e.g., `0x400000` for
+/// `x86_64` and `0x8048000` for `x86`, which attackers assume first.
+pub(crate) static DEFAULT_LOAD_ADDRESSES: &[u64] = &[
+    0x1_0000,
+    0x10_0000,
+    0x40_0000,
+    0x100_0000,
+    0x804_8000,
+    0x1000_0000,
+    0x1_2000_0000,
+];
+
+/// Returns the lowest address of the loadable segments, which is where position-dependent
+/// executables are loaded.
+pub(crate) fn load_address(elf: &goblin::elf::Elf) -> Option<u64> {
+    elf.program_headers
+        .iter()
+        .filter(|ph| ph.p_type == goblin::elf::program_header::PT_LOAD)
+        .map(|ph| ph.p_vaddr)
+        .min()
 }
 
 /// [`ET_EXEC`, `ET_DYN`, `PT_PHDR`](http://refspecs.linux-foundation.org/elf/TIS1.1.pdf).
@@ -142,6 +419,78 @@ pub(crate) fn becomes_read_only_after_relocations(elf: &goblin::elf::Elf) -> boo
     r
 }
 
+/// Returns the names of the sections holding relocated pointers that remain writable after
+/// relocation, i.e., that are writable and outside the `PT_GNU_RELRO` segment.
+///
+/// `.dynamic` and `.got` are expected to become read-only. `.got.plt` is only expected to become
+/// read-only when symbols are bound immediately, as lazy binding writes it while the program
+/// runs. Binaries without section headers have no such sections.
+pub(crate) fn sections_outside_relro(elf: &goblin::elf::Elf) -> Vec<&'static str> {
+    use goblin::elf::program_header::PT_GNU_RELRO;
+    use goblin::elf::section_header::SHF_WRITE;
+
+    let relro_ranges: Vec<_> = elf
+        .program_headers
+        .iter()
+        .filter(|p| p.p_type == PT_GNU_RELRO)
+        .map(|p| p.p_vaddr..p.p_vaddr.saturating_add(p.p_memsz))
+        .collect();
+
+    let mut names = vec![".dynamic", ".got"];
+    if requires_immediate_binding(elf) {
+        names.push(".got.plt");
+    }
+
+    names.retain(|&name| {
+        elf.section_headers.iter().any(|s| {
+            let end = s.sh_addr.saturating_add(s.sh_size);
+            let is_covered = relro_ranges
+                .iter()
+                .any(|range| range.start <= s.sh_addr && end <= range.end);
+
+            s.sh_size > 0
+                && (s.sh_flags & u64::from(SHF_WRITE)) != 0
+                && !is_covered
+                && elf.shdr_strtab.get_at(s.sh_name) == Some(name)
+        })
+    });
+
+    if !names.is_empty() {
+        debug!("Sections {names:?} remain writable after relocation.");
+    }
+    names
+}
+
+/// Returns `true` if a 32-bits PowerPC binary uses the legacy BSS PLT.
+///
+/// The BSS PLT holds code that the dynamic loader writes while resolving symbols, so it remains
+/// writable and executable, even when other relocated data becomes read-only. Binaries linked with
+/// `--secure-plt` (the default of current toolchains) record the `DT_PPC_GOT` dynamic tag instead.
+/// Binaries without PLT relocations are not affected.
+pub(crate) fn uses_powerpc_bss_plt(elf: &goblin::elf::Elf) -> bool {
+    const DT_PPC_GOT: u64 = 0x7000_0000;
+
+    if elf.header.e_machine != goblin::elf::header::EM_PPC {
+        return false;
+    }
+
+    let Some(dynamic) = elf.dynamic.as_ref() else {
+        return false;
+    };
+
+    let has_plt_relocations = dynamic
+        .dyns
+        .iter()
+        .any(|e| e.d_tag == goblin::elf::dynamic::DT_JMPREL);
+    let has_secure_plt = dynamic.dyns.iter().any(|e| e.d_tag == DT_PPC_GOT);
+
+    let r = has_plt_relocations && !has_secure_plt;
+    if r {
+        debug!("Binary uses a BSS PLT, as tag 'DT_PPC_GOT' is not found inside dynamic linking information.");
+    }
+    r
+}
+
 /// [`__stack_chk_fail`](http://refspecs.linux-foundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/baselib---stack-chk-fail-1.html).
 pub(crate) fn has_stack_protection(elf: &goblin::elf::Elf) -> bool {
     let r = elf
@@ -158,13 +507,1324 @@ pub(crate) fn has_stack_protection(elf: &goblin::elf::Elf) -> bool {
     r
 }
 
+/// Returns `true` if the symbol table of the binary, e.g., of its separate debug file, names
+/// `__stack_chk_fail` or `__stack_chk_fail_local`, which are defined in static executables.
+pub(crate) fn symbol_table_has_stack_protection(elf: &goblin::elf::Elf) -> bool {
+    let r = elf
+        .syms
+        .iter()
+        .filter_map(|symbol| symbol_is_named_function_or_unspecified(elf, &symbol))
+        .any(|name| name == "__stack_chk_fail" || name == "__stack_chk_fail_local");
+
+    if r {
+        debug!("Found function symbol '__stack_chk_fail' or '__stack_chk_fail_local' inside symbols section.");
+    }
+    r
+}
+
+/// Message printed by `__stack_chk_fail` of the GNU C library, which remains in stripped static
+/// executables linking it.
+const STACK_SMASHING_MESSAGE: &[u8] = b"stack smashing detected";
+
+/// Matches `mov reg, fs:0x28`, which loads the stack canary from the thread control block in
+/// the prologue of protected functions, on `x86_64`.
+static X86_64_CANARY_LOAD_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(r"\x64[\x48\x4C]\x8B[\x04\x0C\x14\x1C\x24\x2C\x34\x3C]\x25\x28\x00\x00\x00")
+        .unicode(false)
+        .build()
+        .expect("Invalid static regular expression.")
+});
+
+/// Matches `mov reg, gs:0x14`, which loads the stack canary from the thread control block in
+/// the prologue of protected functions, on `x86`.
+static X86_CANARY_LOAD_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(r"\x65(?:\xA1|\x8B[\x05\x0D\x15\x1D\x25\x2D\x35\x3D])\x14\x00\x00\x00")
+        .unicode(false)
+        .build()
+        .expect("Invalid static regular expression.")
+});
+
+/// Returns the contents of the executable sections of the binary, or of its executable segments
+/// if it has no sections.
+fn executable_code<'t>(parser: &'t BinaryParser, elf: &'t goblin::elf::Elf) -> Vec<&'t [u8]> {
+    use goblin::elf::program_header::{PF_X, PT_LOAD};
+    use goblin::elf::section_header::SHF_EXECINSTR;
+
+    if elf.section_headers.is_empty() {
+        elf.program_headers
+            .iter()
+            .filter(|ph| ph.p_type == PT_LOAD && (ph.p_flags & PF_X) != 0)
+            .filter_map(|ph| {
+                let start = usize::try_from(ph.p_offset).ok()?;
+                let end = start.checked_add(usize::try_from(ph.p_filesz).ok()?)?;
+                parser.bytes().get(start..end)
+            })
+            .collect()
+    } else {
+        elf.section_headers
+            .iter()
+            .filter(|section| (section.sh_flags & u64::from(SHF_EXECINSTR)) != 0)
+            .filter_map(|section| section_bytes(parser, section))
+            .collect()
+    }
+}
+
+/// Returns the evidence of stack protection found in the code and data of a binary that imports
+/// no functions, e.g., a stripped static executable, whose symbols cannot reveal it.
+///
+/// - On `x86_64` and `x86`, the number of loads of the stack canary from the thread control block
+///   is reported with a medium confidence, as they might only come from the statically linked C
+///   runtime library.
+/// - Otherwise, the message printed by `__stack_chk_fail` is reported with a low confidence, as
+///   the C runtime library might reference it for its own functions.
+///
+/// This returns `None` if no evidence is found.
+pub(crate) fn stack_protection_heuristic(
+    parser: &BinaryParser,
+    elf: &goblin::elf::Elf,
+) -> Option<DetailedStatus> {
+    use goblin::elf::header::{EM_386, EM_X86_64};
+
+    let pattern = match elf.header.e_machine {
+        EM_X86_64 => Some(&*X86_64_CANARY_LOAD_PATTERN),
+        EM_386 => Some(&*X86_CANARY_LOAD_PATTERN),
+        _ => None,
+    };
+
+    let canary_loads: usize = pattern.map_or(0, |pattern| {
+        executable_code(parser, elf)
+            .into_iter()
+            .map(|bytes| pattern.find_iter(bytes).count())
+            .sum()
+    });
+
+    let details = if canary_loads > 0 {
+        debug!("Found {canary_loads} loads of the stack canary in executable code.");
+        vec![
+            "confidence=medium".into(),
+            format!("canary-loads={canary_loads}"),
+        ]
+    } else if parser
+        .bytes()
+        .windows(STACK_SMASHING_MESSAGE.len())
+        .any(|window| window == STACK_SMASHING_MESSAGE)
+    {
+        debug!("Found message of '__stack_chk_fail' in the binary.");
+        vec!["confidence=low".into(), "failure-message".into()]
+    } else {
+        return None;
+    };
+
+    Some(DetailedStatus::new(
+        "STACK-PROT",
+        SupportLevel::Partial,
+        details,
+    ))
+}
+
+/// Returns `true` if the binary has no symbol table, and its separate debug file shows that it is
+/// stack-protected.
+pub(crate) fn debug_file_has_stack_protection(
+    parser: &BinaryParser,
+    elf: &goblin::elf::Elf,
+    options: &crate::cmdline::Options,
+) -> bool {
+    debug_file::open(parser, elf, options).is_some_and(|debug_file| match debug_file.parse() {
+        Ok(debug_parser) => matches!(debug_parser.object(), goblin::Object::Elf(debug_elf)
+            if symbol_table_has_stack_protection(debug_elf)),
+
+        Err(_r) => {
+            debug!(
+                "Failed to parse debug file '{}'.",
+                debug_file.path().display()
+            );
+            false
+        }
+    })
+}
+
+/// Returns `true` if the binary reads its stack canary from the global variable
+/// `__stack_chk_guard` of the C runtime library, instead of the thread control block.
+///
+/// This is selected by `-mstack-protector-guard=global` on PowerPC, s390x, `aarch64` and RISC-V,
+/// and is the only choice on some other architectures. A global canary lies at a predictable
+/// offset from other writable data, and is shared by all threads.
+///
+/// Executables usually copy the variable by a copy relocation, and thus define it.
+pub(crate) fn imports_global_stack_guard(elf: &goblin::elf::Elf) -> bool {
+    use goblin::elf::section_header::SHN_UNDEF;
+
+    let is_executable = !is_shared_library(elf);
+    let r = elf.dynsyms.iter().any(|symbol| {
+        symbol.st_type() != goblin::elf::sym::STT_FUNC
+            && (is_executable || symbol.st_shndx == SHN_UNDEF as usize)
+            && elf.dynstrtab.get_at(symbol.st_name) == Some("__stack_chk_guard")
+    });
+
+    if r {
+        debug!("Found symbol '__stack_chk_guard' inside dynamic symbols section.");
+    }
+    r
+}
+
+/// Symbols of memory allocators that predate heap hardening techniques such as safe unlinking.
+/// These allocators are compiled into the binary instead of relying on the system allocator.
+static LEGACY_ALLOCATOR_SYMBOLS: &[(&str, &str)] = &[
+    ("dlmalloc", "dlmalloc"),
+    ("dlfree", "dlmalloc"),
+    ("dlrealloc", "dlmalloc"),
+    ("mspace_malloc", "dlmalloc"),
+    ("public_mALLOc", "ptmalloc2"),
+    ("public_fREe", "ptmalloc2"),
+    ("public_rEALLOc", "ptmalloc2"),
+];
+
+/// Returns whether the binary relies on a hardened heap allocator.
+///
+/// A binary that defines the symbols of a legacy allocator (e.g., `dlmalloc` or the original
+/// `ptmalloc2`) has that allocator compiled in, and does not benefit from the hardening of the
+/// system allocator. A binary that imports `malloc` uses the allocator of the system.
+pub(crate) fn heap_hardening(elf: &goblin::elf::Elf) -> DetailedStatus {
+    let defined_symbols = elf
+        .syms
+        .iter()
+        .filter(|symbol| symbol.st_shndx != 0)
+        .filter_map(|symbol| elf.strtab.get_at(symbol.st_name))
+        .chain(
+            elf.dynsyms
+                .iter()
+                .filter(|symbol| symbol.st_shndx != 0)
+                .filter_map(|symbol| elf.dynstrtab.get_at(symbol.st_name)),
+        );
+
+    let mut legacy_allocators: Vec<String> = Vec::default();
+    for name in defined_symbols {
+        if let Some(&(_symbol, allocator)) = LEGACY_ALLOCATOR_SYMBOLS
+            .iter()
+            .find(|&&(symbol, _allocator)| symbol == name)
+        {
+            debug!("Found symbol '{name}' of legacy memory allocator '{allocator}'.");
+            if !legacy_allocators.iter().any(|a| a == allocator) {
+                legacy_allocators.push(allocator.into());
+            }
+        }
+    }
+
+    if !legacy_allocators.is_empty() {
+        return DetailedStatus::new(
+            "HEAP-HARDENING",
+            SupportLevel::Unsupported,
+            legacy_allocators,
+        );
+    }
+
+    let imports_malloc = elf
+        .dynsyms
+        .iter()
+        .filter_map(|symbol| dynamic_symbol_is_named_imported_function(elf, &symbol))
+        .any(|name| name == "malloc");
+
+    if imports_malloc {
+        debug!("Function 'malloc' is imported from the system memory allocator.");
+        DetailedStatus::new("HEAP-HARDENING", SupportLevel::Supported, Vec::default())
+    } else {
+        DetailedStatus::unknown("HEAP-HARDENING")
+    }
+}
+
+/// Returns the bytes of a section, as stored in the file.
+///
+/// This returns `None` for sections that occupy no space in the file, for compressed sections,
+/// and for sections that lie outside the file.
+pub(crate) fn section_bytes<'t>(
+    parser: &'t BinaryParser,
+    section: &goblin::elf::SectionHeader,
+) -> Option<&'t [u8]> {
+    use goblin::elf::section_header::{SHF_COMPRESSED, SHT_NOBITS};
+
+    if section.sh_type == SHT_NOBITS || (section.sh_flags & u64::from(SHF_COMPRESSED)) != 0 {
+        return None;
+    }
+
+    let start = usize::try_from(section.sh_offset).ok()?;
+    let end = start.checked_add(usize::try_from(section.sh_size).ok()?)?;
+    parser.bytes().get(start..end)
+}
+
+/// Sections where compilers and build tools record the command line options used to build the
+/// binary:
+/// - `.GCC.command.line` is filled by `-frecord-gcc-switches`.
+/// - `.debug_str` holds the `DW_AT_producer` strings of the compilation units.
+/// - `.comment` holds the compiler identification, sometimes followed by its options.
+static BUILD_OPTIONS_SECTIONS: &[&str] = &[".GCC.command.line", ".debug_str", ".comment"];
+
+/// Note section where the `annobin` compiler plugin records build attributes.
+const BUILD_ATTRIBUTES_SECTION: &str = ".gnu.build.attributes";
+
+/// Returns the names and contents of the sections where build options are recorded, followed by
+/// the string build attributes recorded by the `annobin` compiler plugin.
+fn build_options_sections<'t>(
+    parser: &'t BinaryParser,
+    elf: &'t goblin::elf::Elf<'t>,
+) -> impl Iterator<Item = (&'t str, &'t [u8])> {
+    sections_named(parser, elf, BUILD_OPTIONS_SECTIONS).chain(
+        build_attribute_strings(parser, elf)
+            .map(|value| (BUILD_ATTRIBUTES_SECTION, value.as_bytes())),
+    )
+}
+
+/// Returns the values of the string build attributes recorded by the `annobin` compiler plugin.
+///
+/// Each attribute is a note of type `NT_GNU_BUILD_ATTRIBUTE_OPEN` or `NT_GNU_BUILD_ATTRIBUTE_FUNC`,
+/// whose name is made of `GA`, the type of the attribute (`$` for strings), the attribute
+/// identifier, and the attribute value. The identifier is either a single byte, or a string
+/// terminated by a null character.
+fn build_attribute_strings<'t>(
+    parser: &'t BinaryParser,
+    elf: &'t goblin::elf::Elf<'t>,
+) -> impl Iterator<Item = &'t str> {
+    /// `NT_GNU_BUILD_ATTRIBUTE_OPEN` note type.
+    const NT_GNU_BUILD_ATTRIBUTE_OPEN: u32 = 0x100;
+    /// `NT_GNU_BUILD_ATTRIBUTE_FUNC` note type.
+    const NT_GNU_BUILD_ATTRIBUTE_FUNC: u32 = 0x101;
+
+    elf.iter_note_sections(parser.bytes(), Some(BUILD_ATTRIBUTES_SECTION))
+        .into_iter()
+        .flatten()
+        // Notes that follow a malformed note cannot be located.
+        .map_while(core::result::Result::ok)
+        .filter(|note| {
+            matches!(
+                note.n_type,
+                NT_GNU_BUILD_ATTRIBUTE_OPEN | NT_GNU_BUILD_ATTRIBUTE_FUNC
+            )
+        })
+        .filter_map(|note| {
+            let attribute = note.name.strip_prefix("GA$")?;
+            let value = if attribute.starts_with(|c: char| c.is_ascii_control()) {
+                attribute.get(1..)?
+            } else {
+                attribute.split_once('\0')?.1
+            };
+            Some(value.trim_end_matches('\0'))
+        })
+}
+
+/// Returns the names and contents of the sections whose name is one of `names`.
+fn sections_named<'t>(
+    parser: &'t BinaryParser,
+    elf: &'t goblin::elf::Elf,
+    names: &'t [&'t str],
+) -> impl Iterator<Item = (&'t str, &'t [u8])> {
+    elf.section_headers.iter().filter_map(move |section| {
+        let name = elf.shdr_strtab.get_at(section.sh_name)?;
+        if names.contains(&name) {
+            Some((name, section_bytes(parser, section)?))
+        } else {
+            None
+        }
+    })
+}
+
+static AUTO_VAR_INIT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(r"-ftrivial-auto-var-init=(zero|pattern|uninitialized)\b")
+        .unicode(false)
+        .build()
+        .expect("Invalid static regular expression.")
+});
+
+/// Returns whether automatic variables are initialized, based on the compiler options recorded
+/// in the binary.
+///
+/// This is a heuristic: compiler options are only recorded when the binary was built with
+/// `-frecord-gcc-switches`, with the `annobin` plugin, or with debugging information.
+pub(crate) fn auto_var_init(parser: &BinaryParser, elf: &goblin::elf::Elf) -> DetailedStatus {
+    let mut modes: Vec<String> = Vec::default();
+
+    for (name, bytes) in build_options_sections(parser, elf) {
+        for captures in AUTO_VAR_INIT_PATTERN.captures_iter(bytes) {
+            let mode = String::from_utf8_lossy(&captures[1]);
+            if !modes.iter().any(|m| *m == mode) {
+                debug!("Found option '-ftrivial-auto-var-init={mode}' in section '{name}'.");
+                modes.push(mode.into_owned());
+            }
+        }
+    }
+
+    let initialized = modes.iter().any(|m| m != "uninitialized");
+    let uninitialized = modes.iter().any(|m| m == "uninitialized");
+
+    let level = match (initialized, uninitialized) {
+        (false, false) => SupportLevel::Unknown,
+        (true, false) => SupportLevel::Supported,
+        (false, true) => SupportLevel::Unsupported,
+        // Some compilation units were built with the option, while others were not.
+        (true, true) => SupportLevel::Partial,
+    };
+    DetailedStatus::new("AUTO-VAR-INIT", level, modes)
+}
+
+/// Matches definitions of `_FORTIFY_SOURCE`, either as compiler options, or as macro definitions
+/// recorded in debugging information by `-g3`.
+static FORTIFY_SOURCE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(r"-D\s*_FORTIFY_SOURCE(?:=([0-9]+))?|\b_FORTIFY_SOURCE ([0-9]+)")
+        .unicode(false)
+        .build()
+        .expect("Invalid static regular expression.")
+});
+
+/// Sections of debugging information that name the directories of the included headers.
+static DEBUG_LINE_SECTIONS: &[&str] = &[".debug_line", ".debug_line_str", ".debug_str"];
+
+/// Directory where the `fortify-headers` project installs its headers, e.g.,
+/// `/usr/include/fortify`.
+const FORTIFY_HEADERS_DIR: &[u8] = b"/include/fortify";
+
+/// Returns `true` if the binary was built with `_FORTIFY_SOURCE` wrappers that leave no trace in
+/// its symbols.
+///
+/// The C runtime library of musl exports no checked functions. Instead, the headers of the
+/// `fortify-headers` project implement `_FORTIFY_SOURCE` by inline wrappers. This is a heuristic:
+/// their use is only visible in debugging information, or in compiler options recorded in the
+/// binary.
+pub(crate) fn uses_fortify_headers(parser: &BinaryParser, elf: &goblin::elf::Elf) -> bool {
+    let includes_fortify_headers =
+        sections_named(parser, elf, DEBUG_LINE_SECTIONS).any(|(name, bytes)| {
+            let found = bytes
+                .windows(FORTIFY_HEADERS_DIR.len())
+                .any(|window| window == FORTIFY_HEADERS_DIR);
+            if found {
+                debug!("Found headers of 'fortify-headers' in section '{name}'.");
+            }
+            found
+        });
+
+    includes_fortify_headers
+        || build_options_sections(parser, elf).any(|(name, bytes)| {
+            FORTIFY_SOURCE_PATTERN.captures_iter(bytes).any(|captures| {
+                // `-D_FORTIFY_SOURCE` alone defines the macro as `1`.
+                let enabled = captures
+                    .get(1)
+                    .or_else(|| captures.get(2))
+                    .is_none_or(|level| level.as_bytes() != b"0");
+                if enabled {
+                    debug!("Found definition of '_FORTIFY_SOURCE' in section '{name}'.");
+                }
+                enabled
+            })
+        })
+}
+
+/// Returns anomalies suggesting that the binary was modified after it was linked.
+///
+/// Process injection tools commonly convert a `PT_NOTE` segment into an executable `PT_LOAD`
+/// segment that maps code appended to the file. Such tools do not update section headers, which
+/// then disagree with program headers.
+pub(crate) fn tampering_anomalies(elf: &goblin::elf::Elf) -> DetailedStatus {
+    use goblin::elf::program_header::{PF_X, PT_LOAD, PT_NOTE};
+    use goblin::elf::section_header::{SHF_ALLOC, SHT_NOBITS, SHT_NOTE};
+
+    let mut anomalies: Vec<String> = Vec::default();
+
+    // Relocatable files have no segments, and stripped files might have no sections.
+    let mapped_sections: Vec<_> = elf
+        .section_headers
+        .iter()
+        .filter(|s| {
+            (s.sh_flags & u64::from(SHF_ALLOC)) != 0 && s.sh_type != SHT_NOBITS && s.sh_size > 0
+        })
+        .collect();
+
+    if elf.program_headers.is_empty() || mapped_sections.is_empty() {
+        return DetailedStatus::new("TAMPERED?", SupportLevel::Supported, anomalies);
+    }
+
+    let loads: Vec<_> = elf
+        .program_headers
+        .iter()
+        .filter(|p| p.p_type == PT_LOAD && p.p_filesz > 0)
+        .collect();
+
+    let sections_end = mapped_sections
+        .iter()
+        .map(|s| s.sh_offset.saturating_add(s.sh_size))
+        .max()
+        .unwrap_or_default();
+
+    // A loaded segment that maps no section is suspicious if it is executable, or if it maps
+    // data located after all sections.
+    for load in &loads {
+        let load_range = load.p_offset..load.p_offset.saturating_add(load.p_filesz);
+        let maps_sections = mapped_sections
+            .iter()
+            .any(|s| load_range.contains(&s.sh_offset));
+
+        if !maps_sections && ((load.p_flags & PF_X) != 0 || load.p_offset >= sections_end) {
+            debug!(
+                "Loaded segment at file offset {:#x} maps no section.",
+                load.p_offset
+            );
+            anomalies.push(format!("load-without-sections@{:#x}", load.p_offset));
+        }
+    }
+
+    // Loaded notes are always mapped by a note segment.
+    let notes: Vec<_> = elf
+        .program_headers
+        .iter()
+        .filter(|p| p.p_type == PT_NOTE)
+        .collect();
+
+    let has_unmapped_note = mapped_sections.iter().any(|s| {
+        s.sh_type == SHT_NOTE
+            && !notes.iter().any(|p| {
+                s.sh_offset >= p.p_offset
+                    && s.sh_offset.saturating_add(s.sh_size)
+                        <= p.p_offset.saturating_add(p.p_filesz)
+            })
+    });
+
+    if has_unmapped_note {
+        anomalies.push("note-without-segment".into());
+    }
+
+    // Loaded sections must be mapped by a loaded segment, at the same address.
+    let has_mismatched_section = mapped_sections.iter().any(|s| {
+        !loads.iter().any(|p| {
+            s.sh_offset >= p.p_offset
+                && s.sh_offset.saturating_add(s.sh_size) <= p.p_offset.saturating_add(p.p_filesz)
+                && s.sh_addr == p.p_vaddr.wrapping_add(s.sh_offset - p.p_offset)
+        })
+    });
+
+    if has_mismatched_section {
+        anomalies.push("section-segment-mismatch".into());
+    }
+
+    let level = if anomalies.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("TAMPERED?", level, anomalies)
+}
+
+/// Returns anomalies of the location of the entry point, which suggest that the binary was
+/// packed or infected.
+///
+/// The entry point is expected inside an executable and read-only section, which is not the last
+/// loaded section. Binaries without sections are checked against their loaded segments instead.
+/// Binaries without an entry point, e.g., most shared libraries, have no anomalies.
+pub(crate) fn entry_point_anomalies(elf: &goblin::elf::Elf) -> DetailedStatus {
+    use goblin::elf::program_header::{PF_W, PF_X, PT_LOAD};
+    use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE};
+
+    let mut anomalies: Vec<String> = Vec::default();
+    let entry = elf.entry;
+
+    if entry == 0 {
+        return DetailedStatus::new("ENTRY-POINT", SupportLevel::Supported, anomalies);
+    }
+
+    let loaded_sections: Vec<_> = elf
+        .section_headers
+        .iter()
+        .filter(|s| (s.sh_flags & u64::from(SHF_ALLOC)) != 0 && s.sh_size > 0)
+        .collect();
+
+    if loaded_sections.is_empty() {
+        let load = elf.program_headers.iter().find(|p| {
+            p.p_type == PT_LOAD && (p.p_vaddr..p.p_vaddr.saturating_add(p.p_memsz)).contains(&entry)
+        });
+
+        match load {
+            None => anomalies.push("outside-segments".into()),
+
+            Some(load) => {
+                if (load.p_flags & PF_X) == 0 {
+                    anomalies.push("non-executable-segment".into());
+                }
+                if (load.p_flags & PF_W) != 0 {
+                    anomalies.push("writable-segment".into());
+                }
+            }
+        }
+    } else {
+        let section = loaded_sections
+            .iter()
+            .find(|s| (s.sh_addr..s.sh_addr.saturating_add(s.sh_size)).contains(&entry));
+
+        match section {
+            None => anomalies.push("outside-sections".into()),
+
+            Some(section) => {
+                if (section.sh_flags & u64::from(SHF_EXECINSTR)) == 0 {
+                    anomalies.push("non-executable-section".into());
+                }
+                if (section.sh_flags & u64::from(SHF_WRITE)) != 0 {
+                    anomalies.push("writable-section".into());
+                }
+
+                let last_address = loaded_sections.iter().map(|s| s.sh_addr).max();
+                if loaded_sections.len() > 1 && last_address == Some(section.sh_addr) {
+                    anomalies.push("last-section".into());
+                }
+            }
+        }
+    }
+
+    if !anomalies.is_empty() {
+        debug!("Entry point {entry:#x} is unexpectedly located: {anomalies:?}.");
+    }
+
+    let level = if anomalies.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("ENTRY-POINT", level, anomalies)
+}
+
+/// Returns inconsistencies between the section headers and the program headers of the binary,
+/// whose size is `file_size`.
+///
+/// Checks trust either table, as parsed, so binaries whose tables disagree might be analyzed
+/// differently than they are loaded. Loaded sections are expected inside loaded segments, loaded
+/// segments are not expected to overlap in memory, and the contents of sections are expected to
+/// be stored in the file.
+pub(crate) fn header_inconsistencies(elf: &goblin::elf::Elf, file_size: u64) -> DetailedStatus {
+    use goblin::elf::program_header::PT_LOAD;
+    use goblin::elf::section_header::{SHF_ALLOC, SHF_TLS, SHT_NOBITS};
+
+    let mut anomalies: Vec<String> = Vec::default();
+
+    let loads: Vec<_> = elf
+        .program_headers
+        .iter()
+        .filter(|p| p.p_type == PT_LOAD && p.p_memsz > 0)
+        .collect();
+
+    // Relocatable files have no segments.
+    if !loads.is_empty() {
+        // Thread-local uninitialized data occupies no memory in its segment.
+        let outside_section = elf.section_headers.iter().find(|s| {
+            let is_tbss = s.sh_type == SHT_NOBITS && (s.sh_flags & u64::from(SHF_TLS)) != 0;
+
+            (s.sh_flags & u64::from(SHF_ALLOC)) != 0
+                && s.sh_size > 0
+                && !is_tbss
+                && !loads.iter().any(|p| {
+                    s.sh_addr >= p.p_vaddr
+                        && s.sh_addr.saturating_add(s.sh_size)
+                            <= p.p_vaddr.saturating_add(p.p_memsz)
+                })
+        });
+
+        if let Some(section) = outside_section {
+            debug!(
+                "Loaded section at address {:#x} is outside loaded segments.",
+                section.sh_addr
+            );
+            anomalies.push("section-outside-segments".into());
+        }
+
+        let has_overlapping_loads = loads.iter().enumerate().any(|(index, p)| {
+            let p_end = p.p_vaddr.saturating_add(p.p_memsz);
+            loads[index + 1..]
+                .iter()
+                .any(|q| p.p_vaddr < q.p_vaddr.saturating_add(q.p_memsz) && q.p_vaddr < p_end)
+        });
+
+        if has_overlapping_loads {
+            anomalies.push("overlapping-loads".into());
+        }
+    }
+
+    let beyond_file_section = elf
+        .section_headers
+        .iter()
+        .find(|s| s.sh_type != SHT_NOBITS && s.sh_offset.saturating_add(s.sh_size) > file_size);
+
+    if let Some(section) = beyond_file_section {
+        debug!(
+            "Section at file offset {:#x} ends beyond the file size {file_size:#x}.",
+            section.sh_offset
+        );
+        anomalies.push("section-beyond-file".into());
+    }
+
+    let level = if anomalies.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("HEADER-INCONSISTENT", level, anomalies)
+}
+
+/// Returns the indirect functions defined by the binary, i.e., `STT_GNU_IFUNC` symbols.
+///
+/// The resolvers of indirect functions run during relocation, before `RELRO` makes relocated
+/// data read-only, and before the program starts. They are therefore valuable to backdoors, as
+/// in the `xz` supply chain attack. Their presence is especially notable in binaries that are
+/// set-user-ID or set-group-ID, which is indicated first.
+pub(crate) fn indirect_functions(
+    parser: &BinaryParser,
+    elf: &goblin::elf::Elf,
+) -> InformationalStatus {
+    use goblin::elf::section_header::SHN_UNDEF;
+    use goblin::elf::sym::STT_GNU_IFUNC;
+
+    let mut details: Vec<String> = set_id_bits(parser.path())
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+    let dynamic_symbols = elf.dynsyms.iter().map(|s| (s, &elf.dynstrtab));
+    let static_symbols = elf.syms.iter().map(|s| (s, &elf.strtab));
+
+    let mut names: Vec<String> = dynamic_symbols
+        .chain(static_symbols)
+        .filter(|(s, _)| s.st_type() == STT_GNU_IFUNC && s.st_shndx != SHN_UNDEF as usize)
+        .filter_map(|(s, strtab)| strtab.get_at(s.st_name))
+        .map(String::from)
+        .collect();
+
+    names.sort_unstable();
+    names.dedup();
+
+    debug!("Found {} indirect functions.", names.len());
+    details.extend(names);
+    InformationalStatus::new("IFUNC", details)
+}
+
+/// Returns the types of the `COPY` and `IRELATIVE` relocations of the architecture targeted by
+/// the binary, if they are known.
+fn copy_and_irelative_relocation_types(elf: &goblin::elf::Elf) -> Option<(u32, Option<u32>)> {
+    use goblin::elf::header::{
+        EM_386, EM_AARCH64, EM_ARM, EM_MIPS, EM_PPC, EM_PPC64, EM_RISCV, EM_S390, EM_SPARC,
+        EM_SPARCV9, EM_X86_64,
+    };
+    use goblin::elf::reloc::{
+        R_386_COPY, R_386_IRELATIVE, R_AARCH64_COPY, R_AARCH64_IRELATIVE, R_ARM_COPY,
+        R_ARM_IRELATIVE, R_MIPS_COPY, R_RISCV_COPY, R_X86_64_COPY, R_X86_64_IRELATIVE,
+    };
+
+    // Relocation types that are not defined by `goblin`.
+    const R_PPC_COPY: u32 = 19;
+    const R_PPC_IRELATIVE: u32 = 248;
+    const R_RISCV_IRELATIVE: u32 = 58;
+    const R_390_COPY: u32 = 9;
+    const R_390_IRELATIVE: u32 = 61;
+    const R_SPARC_COPY: u32 = 19;
+    const R_SPARC_IRELATIVE: u32 = 249;
+    const R_LARCH_COPY: u32 = 4;
+    const R_LARCH_IRELATIVE: u32 = 12;
+
+    match elf.header.e_machine {
+        EM_X86_64 => Some((R_X86_64_COPY, Some(R_X86_64_IRELATIVE))),
+        EM_386 => Some((R_386_COPY, Some(R_386_IRELATIVE))),
+        EM_AARCH64 => Some((R_AARCH64_COPY, Some(R_AARCH64_IRELATIVE))),
+        EM_ARM => Some((R_ARM_COPY, Some(R_ARM_IRELATIVE))),
+        // 32-bits and 64-bits PowerPC share these relocation types.
+        EM_PPC | EM_PPC64 => Some((R_PPC_COPY, Some(R_PPC_IRELATIVE))),
+        EM_RISCV => Some((R_RISCV_COPY, Some(R_RISCV_IRELATIVE))),
+        EM_S390 => Some((R_390_COPY, Some(R_390_IRELATIVE))),
+        EM_SPARC | EM_SPARCV9 => Some((R_SPARC_COPY, Some(R_SPARC_IRELATIVE))),
+        EM_LOONGARCH => Some((R_LARCH_COPY, Some(R_LARCH_IRELATIVE))),
+        EM_MIPS => Some((R_MIPS_COPY, None)),
+        _ => None,
+    }
+}
+
+/// Returns the numbers of `COPY` and `IRELATIVE` relocations of the binary.
+///
+/// `COPY` relocations copy data defined by shared libraries into executables that were not
+/// compiled as position-independent code, which ties the executable to the layout of the data
+/// of these libraries. Binaries having some are reported as partially supported.
+/// Each `IRELATIVE` relocation calls the resolver of an indirect function during relocation,
+/// before `RELRO` makes relocated data read-only, which enlarges the attack surface of startup.
+///
+/// Relocations of dynamically linked binaries are listed by their dynamic section, while those of
+/// statically linked executables are only found in their sections, e.g., `.rela.iplt`.
+pub(crate) fn relocation_types(elf: &goblin::elf::Elf) -> DetailedStatus {
+    let Some((copy_type, irelative_type)) = copy_and_irelative_relocation_types(elf) else {
+        debug!(
+            "Relocation types of machine {} are unknown.",
+            elf.header.e_machine
+        );
+        return DetailedStatus::unknown("RELOC-TYPES");
+    };
+
+    let types: Vec<u32> = if elf.dynamic.is_some() {
+        elf.dynrelas
+            .iter()
+            .chain(elf.dynrels.iter())
+            .chain(elf.pltrelocs.iter())
+            .map(|r| r.r_type)
+            .collect()
+    } else {
+        elf.shdr_relocs
+            .iter()
+            .flat_map(|(_index, relocs)| relocs.iter())
+            .map(|r| r.r_type)
+            .collect()
+    };
+
+    let copy = types.iter().filter(|&&t| t == copy_type).count();
+    let irelative = irelative_type.map_or(0, |irelative_type| {
+        types.iter().filter(|&&t| t == irelative_type).count()
+    });
+
+    debug!("Found {copy} COPY and {irelative} IRELATIVE relocations.");
+
+    let level = if copy == 0 {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Partial
+    };
+    DetailedStatus::new(
+        "RELOC-TYPES",
+        level,
+        vec![format!("copy={copy}"), format!("irelative={irelative}")],
+    )
+}
+
+/// Binaries targeting older Linux kernels were built by toolchains and C runtime libraries that
+/// predate many mitigations. GNU C runtime library 2.26 and later require at least Linux 3.2.
+const MIN_EXPECTED_LINUX_VERSION: [u32; 3] = [3, 2, 0];
+
+/// Returns the operating system and the minimum kernel version targeted by the binary, as
+/// recorded in its `.note.ABI-tag` note by the GNU C runtime library.
+///
+/// Binaries targeting Linux kernels older than 3.2 are reported as partially supported.
+/// Binaries that do not carry this note are reported as unknown.
+pub(crate) fn abi_tag(parser: &BinaryParser, elf: &goblin::elf::Elf) -> CheckResult {
+    /// `NT_GNU_ABI_TAG` note type.
+    const NT_GNU_ABI_TAG: u32 = 1;
+
+    let mut descriptor = None;
+    for note in notes(parser, elf, ".note.ABI-tag") {
+        if note.n_type == NT_GNU_ABI_TAG && note.name == "GNU" && note.desc.len() >= 16 {
+            descriptor = Some(note.desc);
+            break;
+        }
+    }
+
+    let Some(descriptor) = descriptor else {
+        return DetailedStatus::unknown("ABI-TAG").into();
+    };
+
+    // The descriptor is made of 32-bits words: the operating system, then the version.
+    let words: Vec<u32> = descriptor
+        .chunks_exact(4)
+        .take(4)
+        .map(|chunk| {
+            let chunk = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            if elf.little_endian {
+                u32::from_le_bytes(chunk)
+            } else {
+                u32::from_be_bytes(chunk)
+            }
+        })
+        .collect();
+
+    let version = [words[1], words[2], words[3]];
+    let version_text = format!("{}.{}.{}", version[0], version[1], version[2]);
+
+    let os = match words[0] {
+        0 => "linux",
+        1 => "hurd",
+        2 => "solaris",
+        3 => "freebsd",
+        4 => "netbsd",
+        5 => "syllable",
+        _ => "unknown-os",
+    };
+
+    let details = vec![os.into(), version_text];
+    if os == "linux" {
+        let level = if version < MIN_EXPECTED_LINUX_VERSION {
+            SupportLevel::Partial
+        } else {
+            SupportLevel::Supported
+        };
+        DetailedStatus::new("ABI-TAG", level, details).into()
+    } else {
+        InformationalStatus::new("ABI-TAG", details).into()
+    }
+}
+
+/// Returns the notes of the binary, found in its note segments, or in its note sections named
+/// `section_name` when it has no segments.
+fn notes<'t>(
+    parser: &'t BinaryParser,
+    elf: &'t goblin::elf::Elf,
+    section_name: &'t str,
+) -> impl Iterator<Item = goblin::elf::note::Note<'t>> {
+    let bytes = parser.bytes();
+    // Notes that follow a malformed note cannot be located, and the iterators of notes keep
+    // returning the same error.
+    let segment_notes = elf
+        .iter_note_headers(bytes)
+        .into_iter()
+        .flatten()
+        .map_while(core::result::Result::ok);
+    let section_notes = elf
+        .iter_note_sections(bytes, Some(section_name))
+        .into_iter()
+        .flatten()
+        .map_while(core::result::Result::ok);
+    segment_notes.chain(section_notes)
+}
+
+/// Reads the 32-bits word at the start of `bytes`, in the byte order of the binary.
+fn read_u32(elf: &goblin::elf::Elf, bytes: &[u8]) -> Option<u32> {
+    let chunk: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+    Some(if elf.little_endian {
+        u32::from_le_bytes(chunk)
+    } else {
+        u32::from_be_bytes(chunk)
+    })
+}
+
+/// Returns `true` if the binary depends on bionic, the C runtime library of Android.
+///
+/// Such binaries are loaded by the dynamic linker of Android, or carry an Android identification
+/// note.
+pub(crate) fn depends_on_bionic(parser: &BinaryParser, elf: &goblin::elf::Elf) -> bool {
+    let loaded_by_android_linker = elf.interpreter.is_some_and(|interpreter| {
+        ANDROID_LINKERS
+            .iter()
+            .any(|linker| interpreter.ends_with(linker))
+    });
+
+    loaded_by_android_linker || android_api_level(parser, elf).is_some()
+}
+
+/// File names of the dynamic linkers of Android, e.g., `/system/bin/linker64`.
+static ANDROID_LINKERS: &[&str] = &["/bin/linker", "/bin/linker64"];
+
+/// Returns the minimum Android API level targeted by the binary, as recorded by the NDK in its
+/// `.note.android.ident` note.
+pub(crate) fn android_api_level(parser: &BinaryParser, elf: &goblin::elf::Elf) -> Option<u32> {
+    /// `NT_ANDROID_TYPE_IDENT` note type.
+    const NT_ANDROID_TYPE_IDENT: u32 = 1;
+
+    notes(parser, elf, ".note.android.ident")
+        .find(|note| note.n_type == NT_ANDROID_TYPE_IDENT && note.name == "Android")
+        .and_then(|note| read_u32(elf, note.desc))
+}
+
+/// Returns the format of the packed relocations of the binary, if any.
+///
+/// Relocations are packed by `lld --pack-dyn-relocs=android` into `DT_ANDROID_REL(A)` tables, or
+/// by `--pack-dyn-relocs=relr` into `DT_RELR` tables. Packed relocations let the loader apply
+/// relative relocations without writing to most pages of the binary.
+pub(crate) fn packed_relocations(elf: &goblin::elf::Elf) -> Option<&'static str> {
+    const DT_RELR: u64 = 36;
+    const DT_ANDROID_REL: u64 = 0x6000_000f;
+    const DT_ANDROID_RELA: u64 = 0x6000_0011;
+    const DT_ANDROID_RELR: u64 = 0x6fff_e000;
+
+    let dynamic = elf.dynamic.as_ref()?;
+    if dynamic
+        .dyns
+        .iter()
+        .any(|e| e.d_tag == DT_ANDROID_REL || e.d_tag == DT_ANDROID_RELA)
+    {
+        Some("android")
+    } else if dynamic
+        .dyns
+        .iter()
+        .any(|e| e.d_tag == DT_RELR || e.d_tag == DT_ANDROID_RELR)
+    {
+        Some("relr")
+    } else {
+        None
+    }
+}
+
+/// Returns the value of the program property `pr_type`, as recorded in the
+/// `NT_GNU_PROPERTY_TYPE_0` note of the binary.
+///
+/// The descriptor of the note is an array of properties, each made of its 32-bits type, the
+/// 32-bits size of its data, then its data padded to 8 bytes for 64-bits binaries, or to 4 bytes
+/// for 32-bits binaries.
+pub(crate) fn gnu_property(
+    parser: &BinaryParser,
+    elf: &goblin::elf::Elf,
+    pr_type: u32,
+) -> Option<u32> {
+    /// `NT_GNU_PROPERTY_TYPE_0` note type.
+    const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+    let alignment = if elf.is_64 { 8 } else { 4 };
+
+    notes(parser, elf, ".note.gnu.property")
+        .filter(|note| note.n_type == NT_GNU_PROPERTY_TYPE_0 && note.name == "GNU")
+        .find_map(|note| {
+            let mut properties = note.desc;
+            while let (Some(property_type), Some(data_size)) = (
+                read_u32(elf, properties),
+                properties.get(4..).and_then(|bytes| read_u32(elf, bytes)),
+            ) {
+                let data = properties.get(8..)?;
+                if property_type == pr_type {
+                    return read_u32(elf, data);
+                }
+
+                let padded_size = (data_size as usize).checked_next_multiple_of(alignment)?;
+                properties = data.get(padded_size..)?;
+            }
+            None
+        })
+}
+
+/// `GNU_PROPERTY_AARCH64_FEATURE_1_AND` program property.
+pub(crate) const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc000_0000;
+
+/// Returns the branch protection features of an `aarch64` binary, as recorded in its
+/// `GNU_PROPERTY_AARCH64_FEATURE_1_AND` program property: Branch Target Identification (`bti`)
+/// and Pointer Authentication (`pac`).
+///
+/// The property is only set when all linked objects were compiled with the feature, so binaries
+/// that do not record it are reported as unsupported. Other binaries are reported as unknown.
+pub(crate) fn branch_protection(parser: &BinaryParser, elf: &goblin::elf::Elf) -> DetailedStatus {
+    const GNU_PROPERTY_AARCH64_FEATURE_1_BTI: u32 = 1 << 0;
+    const GNU_PROPERTY_AARCH64_FEATURE_1_PAC: u32 = 1 << 1;
+
+    if elf.header.e_machine != goblin::elf::header::EM_AARCH64 {
+        return DetailedStatus::unknown("BRANCH-PROTECTION");
+    }
+
+    let features = gnu_property(parser, elf, GNU_PROPERTY_AARCH64_FEATURE_1_AND).unwrap_or(0);
+
+    let mut details: Vec<String> = Vec::default();
+    if (features & GNU_PROPERTY_AARCH64_FEATURE_1_BTI) != 0 {
+        details.push("bti".into());
+    }
+    if (features & GNU_PROPERTY_AARCH64_FEATURE_1_PAC) != 0 {
+        details.push("pac".into());
+    }
+
+    let level = match details.len() {
+        0 => SupportLevel::Unsupported,
+        1 => SupportLevel::Partial,
+        _ => SupportLevel::Supported,
+    };
+    DetailedStatus::new("BRANCH-PROTECTION", level, details)
+}
+
+/// Returns whether an `aarch64` binary requests the Memory Tagging Extension (MTE) for its heap,
+/// its stack, and its global variables, followed by the requested checking mode (`sync` or
+/// `async`).
+///
+/// Requests are recorded by the `DT_AARCH64_MEMTAG_*` dynamic tags, or by the older
+/// `NT_ANDROID_TYPE_MEMTAG` note of Android. The `GNU_PROPERTY_AARCH64_FEATURE_1_AND` program
+/// property defines no feature for memory tagging. Binaries requesting tagging of both their heap
+/// and their stack are reported as supported.
+pub(crate) fn memory_tagging(parser: &BinaryParser, elf: &goblin::elf::Elf) -> DetailedStatus {
+    const DT_AARCH64_MEMTAG_MODE: u64 = 0x7000_0009;
+    const DT_AARCH64_MEMTAG_HEAP: u64 = 0x7000_000b;
+    const DT_AARCH64_MEMTAG_STACK: u64 = 0x7000_000c;
+    const DT_AARCH64_MEMTAG_GLOBALS: u64 = 0x7000_000d;
+
+    /// `NT_ANDROID_TYPE_MEMTAG` note type.
+    const NT_ANDROID_TYPE_MEMTAG: u32 = 4;
+    const NT_MEMTAG_LEVEL_MASK: u32 = 3;
+    const NT_MEMTAG_LEVEL_ASYNC: u32 = 1;
+    const NT_MEMTAG_LEVEL_SYNC: u32 = 2;
+    const NT_MEMTAG_HEAP: u32 = 4;
+    const NT_MEMTAG_STACK: u32 = 8;
+
+    if elf.header.e_machine != goblin::elf::header::EM_AARCH64 {
+        return DetailedStatus::unknown("MTE");
+    }
+
+    let dynamic_value = |tag: u64| {
+        elf.dynamic
+            .as_ref()
+            .and_then(|dynamic| dynamic.dyns.iter().find(|e| e.d_tag == tag))
+            .map(|e| e.d_val)
+    };
+
+    let (mut heap, mut stack, globals, mut mode) = (
+        dynamic_value(DT_AARCH64_MEMTAG_HEAP).is_some_and(|value| value != 0),
+        dynamic_value(DT_AARCH64_MEMTAG_STACK).is_some_and(|value| value != 0),
+        dynamic_value(DT_AARCH64_MEMTAG_GLOBALS).is_some_and(|value| value != 0),
+        dynamic_value(DT_AARCH64_MEMTAG_MODE)
+            .map(|value| if value == 0 { "sync" } else { "async" }),
+    );
+
+    if !heap && !stack {
+        let descriptor = notes(parser, elf, ".note.android.memtag")
+            .find(|note| note.n_type == NT_ANDROID_TYPE_MEMTAG && note.name == "Android")
+            .and_then(|note| read_u32(elf, note.desc));
+
+        if let Some(descriptor) = descriptor {
+            heap = (descriptor & NT_MEMTAG_HEAP) != 0;
+            stack = (descriptor & NT_MEMTAG_STACK) != 0;
+            mode = match descriptor & NT_MEMTAG_LEVEL_MASK {
+                NT_MEMTAG_LEVEL_ASYNC => Some("async"),
+                NT_MEMTAG_LEVEL_SYNC => Some("sync"),
+                _ => None,
+            };
+        }
+    }
+
+    let mut details: Vec<String> = Vec::default();
+    for (name, requested) in [("heap", heap), ("stack", stack), ("globals", globals)] {
+        if requested {
+            details.push(name.into());
+        }
+    }
+
+    let level = match (heap, stack) {
+        (true, true) => SupportLevel::Supported,
+        (true, false) | (false, true) => SupportLevel::Partial,
+        (false, false) => SupportLevel::Unsupported,
+    };
+
+    if level != SupportLevel::Unsupported {
+        details.extend(mode.map(String::from));
+    }
+    DetailedStatus::new("MTE", level, details)
+}
+
+/// Returns `setuid` and `setgid` if the corresponding permission bits are set on the file.
+#[cfg(unix)]
+fn set_id_bits(path: &Path) -> Vec<&'static str> {
+    use std::os::unix::fs::PermissionsExt;
+
+    const S_ISUID: u32 = 0o4000;
+    const S_ISGID: u32 = 0o2000;
+
+    let mode = std::fs::metadata(path).map_or(0, |m| m.permissions().mode());
+
+    [(S_ISUID, "setuid"), (S_ISGID, "setgid")]
+        .into_iter()
+        .filter(|&(bit, _name)| (mode & bit) != 0)
+        .map(|(_bit, name)| name)
+        .collect()
+}
+
+/// Permission bits are not supported on this platform.
+#[cfg(not(unix))]
+fn set_id_bits(_path: &Path) -> Vec<&'static str> {
+    Vec::default()
+}
+
+/// Returns the offset, in the file, of the end of the data described by the headers.
+pub(crate) fn data_end(elf: &goblin::elf::Elf) -> u64 {
+    use goblin::elf::section_header::SHT_NOBITS;
+
+    let header = &elf.header;
+    let program_headers_end = header.e_phoff.saturating_add(
+        u64::from(header.e_phentsize).saturating_mul(elf.program_headers.len() as u64),
+    );
+    let section_headers_end = header.e_shoff.saturating_add(
+        u64::from(header.e_shentsize).saturating_mul(elf.section_headers.len() as u64),
+    );
+
+    let sections_end = elf
+        .section_headers
+        .iter()
+        .filter(|s| s.sh_type != SHT_NOBITS)
+        .map(|s| s.sh_offset.saturating_add(s.sh_size));
+
+    let segments_end = elf
+        .program_headers
+        .iter()
+        .map(|p| p.p_offset.saturating_add(p.p_filesz));
+
+    sections_end
+        .chain(segments_end)
+        .chain([program_headers_end, section_headers_end])
+        .max()
+        .unwrap_or_default()
+}
+
+/// C runtime library functions commonly interposed by preloaded implants, e.g., to hide files,
+/// processes or network connections, or to capture credentials.
+static INTERPOSED_LIBC_FUNCTIONS: &[&str] = &[
+    "accept",
+    "access",
+    "bind",
+    "connect",
+    "crypt",
+    "dlsym",
+    "execve",
+    "execv",
+    "fgets",
+    "fopen",
+    "fopen64",
+    "fork",
+    "fstat",
+    "fstat64",
+    "getpwnam",
+    "getspnam",
+    "kill",
+    "lstat",
+    "lstat64",
+    "open",
+    "open64",
+    "openat",
+    "opendir",
+    "ptrace",
+    "read",
+    "readdir",
+    "readdir64",
+    "readdir_r",
+    "recvmsg",
+    "rename",
+    "rmdir",
+    "stat",
+    "stat64",
+    "unlink",
+    "unlinkat",
+    "write",
+    "__fxstat",
+    "__fxstat64",
+    "__lxstat",
+    "__lxstat64",
+    "__xstat",
+    "__xstat64",
+];
+
+/// Functions that run code when a library is loaded or when a program starts, which libraries
+/// do not normally export. `la_*` functions are entry points of dynamic linker auditing.
+static EXPORTED_HOOKS: &[&str] = &[
+    "_init",
+    "_fini",
+    "__libc_start_main",
+    "la_version",
+    "la_objopen",
+    "la_symbind32",
+    "la_symbind64",
+];
+
+/// Libraries of the C runtime legitimately export C runtime library functions.
+static C_RUNTIME_SONAME_PREFIXES: &[&str] = &[
+    "libc.",
+    "libpthread.",
+    "librt.",
+    "libdl.",
+    "libutil.",
+    "ld-linux",
+    "ld-musl",
+];
+
+/// Returns `true` if the binary is a shared library, as opposed to an executable.
+pub(crate) fn is_shared_library(elf: &goblin::elf::Elf) -> bool {
+    let is_pie = elf.dynamic.as_ref().is_some_and(|dynamic| {
+        dynamic
+            .dyns
+            .iter()
+            .any(|e| (e.d_tag == goblin::elf::dynamic::DT_FLAGS_1) && ((e.d_val & DF_1_PIE) != 0))
+    });
+
+    elf.header.e_type == goblin::elf::header::ET_DYN && elf.interpreter.is_none() && !is_pie
+}
+
+/// Returns exported functions of a shared library that interpose C runtime library functions,
+/// or that hook into loading, unless they are allowed by `allowed_exports`.
+///
+/// Such exports are typical of implants loaded by `LD_PRELOAD` or `/etc/ld.so.preload`.
+pub(crate) fn suspicious_exports(
+    elf: &goblin::elf::Elf,
+    allowed_exports: &[String],
+) -> DetailedStatus {
+    let is_c_runtime = elf.soname.is_some_and(|soname| {
+        C_RUNTIME_SONAME_PREFIXES
+            .iter()
+            .any(|prefix| soname.starts_with(prefix))
+    });
+
+    let mut names: Vec<String> = if is_c_runtime {
+        debug!("Library is part of the C runtime.");
+        Vec::default()
+    } else {
+        elf.dynsyms
+            .iter()
+            .filter_map(|symbol| dynamic_symbol_is_named_exported_function(elf, &symbol))
+            .filter(|name| {
+                INTERPOSED_LIBC_FUNCTIONS.contains(name) || EXPORTED_HOOKS.contains(name)
+            })
+            .filter(|name| !allowed_exports.iter().any(|allowed| allowed == name))
+            .map(String::from)
+            .collect()
+    };
+
+    names.sort_unstable();
+    names.dedup();
+
+    let level = if names.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("SAFE-EXPORTS", level, names)
+}
+
+/// Executables exporting more dynamic symbols than this were probably built without
+/// `-fvisibility=hidden`, or were linked with `-rdynamic`.
+const MAX_EXECUTABLE_EXPORTED_SYMBOLS: usize = 32;
+
+/// Returns the number of exported dynamic symbols, and the hash tables used to look them up.
+///
+/// Shared libraries export their interface, so their exports are only reported as information.
+/// Executables rarely need to export symbols, and exporting many of them enlarges the binary,
+/// slows down symbol lookup, and lets shared libraries interpose or reach internal functions.
+pub(crate) fn symbol_visibility(
+    elf: &goblin::elf::Elf,
+    number_format: NumberFormat,
+) -> CheckResult {
+    use goblin::elf::dynamic::{DT_GNU_HASH, DT_HASH};
+    use goblin::elf::section_header::SHN_UNDEF;
+    use goblin::elf::sym::{STB_GLOBAL, STB_GNU_UNIQUE, STB_WEAK, STV_HIDDEN, STV_INTERNAL};
+
+    let exported_symbols = elf
+        .dynsyms
+        .iter()
+        .filter(|s| {
+            s.st_shndx != SHN_UNDEF as usize
+                && matches!(s.st_bind(), STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE)
+                && !matches!(s.st_visibility(), STV_HIDDEN | STV_INTERNAL)
+        })
+        .count();
+
+    let mut details = vec![format!(
+        "exports={}",
+        number_format.count(exported_symbols as u64)
+    )];
+
+    if let Some(dynamic) = elf.dynamic.as_ref() {
+        for (tag, name) in [(DT_GNU_HASH, "gnu-hash"), (DT_HASH, "sysv-hash")] {
+            if dynamic.dyns.iter().any(|e| e.d_tag == tag) {
+                details.push(name.into());
+            }
+        }
+    }
+
+    if is_shared_library(elf) {
+        InformationalStatus::new("SYMBOL-VISIBILITY", details).into()
+    } else {
+        let level = if exported_symbols > MAX_EXECUTABLE_EXPORTED_SYMBOLS {
+            SupportLevel::Unsupported
+        } else {
+            SupportLevel::Supported
+        };
+        DetailedStatus::new("SYMBOL-VISIBILITY", level, details).into()
+    }
+}
+
 /// Visibility is specified by binding type.
 const STV_DEFAULT: u8 = 0;
-/// Defined by processor supplements.
+// Defined by processor supplements.
 //const STV_INTERNAL: u8 = 1;
-/// Not visible to other components.
+// Not visible to other components.
 //const STV_HIDDEN: u8 = 2;
-/// Visible in other components but not preemptable.
+// Visible in other components but not preemptable.
 //const STV_PROTECTED: u8 = 3;
 
 pub(crate) fn dynamic_symbol_is_named_exported_function<'elf>(
@@ -254,6 +1914,34 @@ fn dynamic_symbol_is_named_imported_function<'elf>(
     None
 }
 
+/// Flags of `DT_FLAGS_1` that affect the attack surface of the binary, with their reported names.
+const ATTACK_SURFACE_FLAGS_1: [(u64, &str); 3] = [
+    // The object cannot be loaded by `dlopen()`, e.g., as a plugin.
+    (goblin::elf::dynamic::DF_1_NOOPEN, "noopen"),
+    // The object is never unloaded, so its code stays mapped after `dlclose()`.
+    (goblin::elf::dynamic::DF_1_NODELETE, "nodelete"),
+    // The object cannot be dumped by `dldump()`.
+    (goblin::elf::dynamic::DF_1_NODUMP, "nodump"),
+];
+
+/// Returns the names of the flags of `DT_FLAGS_1` that are set and affect the attack surface of
+/// the binary, e.g., when auditing plugins.
+fn attack_surface_dynamic_flags(elf: &goblin::elf::Elf) -> Vec<String> {
+    let flags_1 = elf
+        .dynamic
+        .as_ref()
+        .into_iter()
+        .flat_map(|dli| &dli.dyns)
+        .filter(|dyn_entry| dyn_entry.d_tag == goblin::elf::dynamic::DT_FLAGS_1)
+        .fold(0_u64, |flags_1, dyn_entry| flags_1 | dyn_entry.d_val);
+
+    ATTACK_SURFACE_FLAGS_1
+        .iter()
+        .filter(|&&(flag, _name)| (flags_1 & flag) != 0)
+        .map(|&(_flag, name)| name.into())
+        .collect()
+}
+
 /// - [`DT_BIND_NOW`](http://refspecs.linux-foundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/dynamicsection.html).
 /// - [`DF_BIND_NOW`, `DF_1_NOW`](http://refspecs.linux-foundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/libc-ddefs.html).
 pub(crate) fn requires_immediate_binding(elf: &goblin::elf::Elf) -> bool {
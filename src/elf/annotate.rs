@@ -0,0 +1,236 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Copies of ELF files annotated with their reports, written by `--annotate-elf`.
+//!
+//! The report is embedded in a `.note.bsc` section, which is not loaded in memory, so that the
+//! verdicts travel with the binary through packaging steps that preserve sections.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use goblin::container::{Container, Ctx};
+use goblin::elf::section_header::{SectionHeader, SHN_LORESERVE, SHN_XINDEX, SHT_NOTE, SHT_STRTAB};
+use goblin::elf::Elf;
+use log::debug;
+use scroll::{Endian, Pwrite};
+
+use crate::errors::{Error, Result};
+use crate::report::{BinaryFormat, FileResults};
+
+/// Name of the section embedding the report.
+pub(crate) const NOTE_SECTION_NAME: &str = ".note.bsc";
+
+/// Owner of the note embedding the report, including its terminating null byte.
+const NOTE_NAME: &[u8] = b"BSC\0";
+#[allow(clippy::cast_possible_truncation)]
+const NOTE_NAME_SIZE: u32 = NOTE_NAME.len() as u32;
+
+/// Type of the note embedding the report, as a JSON document.
+const NT_BSC_JSON_REPORT: u32 = 1;
+
+/// Offsets of `e_shoff` and `e_shnum` in the headers of 32-bits and 64-bits ELF files.
+const E_SHOFF_OFFSET_32: usize = 0x20;
+const E_SHNUM_OFFSET_32: usize = 0x30;
+const E_SHOFF_OFFSET_64: usize = 0x28;
+const E_SHNUM_OFFSET_64: usize = 0x3c;
+
+/// Appends zero bytes to `bytes` until its length is a multiple of `alignment`.
+fn pad(bytes: &mut Vec<u8>, alignment: usize) {
+    bytes.resize(bytes.len().next_multiple_of(alignment), 0);
+}
+
+/// Returns the bytes of a note owned by `BSC`, whose descriptor is `report`.
+fn report_note(report: &[u8], endian: Endian, output: &Path) -> Result<Vec<u8>> {
+    let report_size = u32::try_from(report.len())
+        .map_err(|_r| unsupported(output, "the report exceeds 4 GiB"))?;
+
+    let mut note = vec![0_u8; 12];
+    note.pwrite_with(NOTE_NAME_SIZE, 0, endian)?;
+    note.pwrite_with(report_size, 4, endian)?;
+    note.pwrite_with(NT_BSC_JSON_REPORT, 8, endian)?;
+    note.extend_from_slice(NOTE_NAME);
+    note.extend_from_slice(report);
+    pad(&mut note, 4);
+    Ok(note)
+}
+
+/// Returns the error reporting that the annotated copy `output` cannot be written.
+fn unsupported(output: &Path, reason: &'static str) -> Error {
+    Error::AnnotateElf {
+        path: output.into(),
+        reason,
+    }
+}
+
+/// Writes to `output` a copy of the only ELF file among the analyzed files, annotated with its
+/// JSON report.
+pub(crate) fn annotate(
+    output: &Path,
+    reports: &[(PathBuf, FileResults)],
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let mut elf_reports = reports
+        .iter()
+        .enumerate()
+        .filter(|(_index, (_path, results))| results.format == BinaryFormat::ELF);
+
+    let (Some((index, (input, _results))), None) = (elf_reports.next(), elf_reports.next()) else {
+        return Err(unsupported(output, "exactly one ELF file must be analyzed"));
+    };
+
+    let report = crate::report::json_document(&reports[index..=index], &[], options)?;
+    write_annotated_copy(input, output, &report)
+}
+
+/// Writes to `output` a copy of the ELF file at `input`, with a `.note.bsc` section whose note
+/// embeds `report`.
+///
+/// The note and a new section header table are appended to the copy, so that the contents of
+/// existing sections and segments do not move. If the file already has a `.note.bsc` section,
+/// then that section is replaced.
+fn write_annotated_copy(input: &Path, output: &Path, report: &[u8]) -> Result<()> {
+    let mut bytes = fs::read(input).map_err(|r| Error::from_io1(r, "read file", input))?;
+
+    let header = Elf::parse_header(&bytes).map_err(|source| Error::ParseFile { source })?;
+    let container = header
+        .container()
+        .map_err(|source| Error::ParseFile { source })?;
+    let endian = header
+        .endianness()
+        .map_err(|source| Error::ParseFile { source })?;
+    let ctx = Ctx::new(container, endian);
+
+    if header.e_shnum == 0 || u32::from(header.e_shstrndx) == SHN_XINDEX {
+        return Err(unsupported(
+            output,
+            "the section headers of the analyzed file are missing or use extended numbering",
+        ));
+    }
+
+    let mut section_headers = SectionHeader::parse(
+        &bytes,
+        usize::try_from(header.e_shoff).unwrap_or(usize::MAX),
+        usize::from(header.e_shnum),
+        ctx,
+    )
+    .map_err(|source| Error::ParseFile { source })?;
+
+    let shstrndx = usize::from(header.e_shstrndx);
+    let shstrtab = section_headers
+        .get(shstrndx)
+        .filter(|section| section.sh_type == SHT_STRTAB)
+        .and_then(|section| {
+            let start = usize::try_from(section.sh_offset).ok()?;
+            let end = start.checked_add(usize::try_from(section.sh_size).ok()?)?;
+            bytes.get(start..end)
+        })
+        .ok_or_else(|| {
+            unsupported(
+                output,
+                "the table of section names of the analyzed file is invalid",
+            )
+        })?
+        .to_vec();
+
+    let existing = section_headers.iter().position(|section| {
+        let name = shstrtab.get(section.sh_name..).unwrap_or_default();
+        name.split(|&c| c == 0).next() == Some(NOTE_SECTION_NAME.as_bytes())
+    });
+
+    let note_index = if let Some(index) = existing {
+        debug!("Replacing the existing '{NOTE_SECTION_NAME}' section.");
+        index
+    } else {
+        if section_headers.len() >= SHN_LORESERVE as usize {
+            return Err(unsupported(
+                output,
+                "the analyzed file has too many sections",
+            ));
+        }
+
+        // The name of the new section is appended to a copy of the table of section names.
+        let names_offset = bytes.len();
+        bytes.extend_from_slice(&shstrtab);
+        bytes.extend_from_slice(NOTE_SECTION_NAME.as_bytes());
+        bytes.push(0);
+
+        let names = &mut section_headers[shstrndx];
+        names.sh_offset = names_offset as u64;
+        names.sh_size = (bytes.len() - names_offset) as u64;
+
+        section_headers.push(SectionHeader {
+            sh_name: shstrtab.len(),
+            sh_type: SHT_NOTE,
+            sh_addralign: 4,
+            ..SectionHeader::default()
+        });
+        section_headers.len() - 1
+    };
+
+    pad(&mut bytes, 4);
+    let note = report_note(report, endian, output)?;
+    let note_section = &mut section_headers[note_index];
+    note_section.sh_offset = bytes.len() as u64;
+    note_section.sh_size = note.len() as u64;
+    bytes.extend_from_slice(&note);
+
+    write_section_headers(&mut bytes, section_headers, ctx, output)?;
+
+    fs::write(output, &bytes).map_err(|r| Error::from_io1(r, "write file", output))?;
+
+    // Executables remain executable.
+    if let Ok(metadata) = fs::metadata(input) {
+        let _ignored = fs::set_permissions(output, metadata.permissions());
+    }
+
+    debug!(
+        "Wrote '{}' with its report in a '{NOTE_SECTION_NAME}' section.",
+        output.display()
+    );
+    Ok(())
+}
+
+/// Appends `section_headers` to `bytes` as the section header table, and points the ELF header
+/// to it.
+fn write_section_headers(
+    bytes: &mut Vec<u8>,
+    section_headers: Vec<SectionHeader>,
+    ctx: Ctx,
+    output: &Path,
+) -> Result<()> {
+    let endian = ctx.le;
+    let (shoff_offset, shnum_offset, alignment) = match ctx.container {
+        Container::Little => (E_SHOFF_OFFSET_32, E_SHNUM_OFFSET_32, 4),
+        Container::Big => (E_SHOFF_OFFSET_64, E_SHNUM_OFFSET_64, 8),
+    };
+
+    pad(bytes, alignment);
+    let shoff = bytes.len();
+    let shnum = section_headers.len();
+    let entry_size = SectionHeader::size(ctx);
+    bytes.resize(shoff + shnum * entry_size, 0);
+    for (index, section) in section_headers.into_iter().enumerate() {
+        bytes
+            .pwrite_with(section, shoff + index * entry_size, ctx)
+            .map_err(|source| Error::ParseFile { source })?;
+    }
+
+    match ctx.container {
+        Container::Little => {
+            let shoff =
+                u32::try_from(shoff).map_err(|_r| unsupported(output, "it would exceed 4 GiB"))?;
+            bytes.pwrite_with(shoff, shoff_offset, endian)?;
+        }
+        Container::Big => {
+            bytes.pwrite_with(shoff as u64, shoff_offset, endian)?;
+        }
+    }
+    let shnum = u16::try_from(shnum)
+        .map_err(|_r| unsupported(output, "the analyzed file has too many sections"))?;
+    bytes.pwrite_with(shnum, shnum_offset, endian)?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Separate debug files, which hold the symbols stripped from binaries.
+
+use core::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+use crate::cmdline::Options;
+use crate::parser::{BinaryFile, BinaryParser};
+
+/// `NT_GNU_BUILD_ID` note type.
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// Returns the directories holding separate debug files, i.e., `--debug-dir`, or
+/// `/usr/lib/debug` in each system root.
+fn debug_dirs(options: &Options) -> Vec<PathBuf> {
+    if let Some(debug_dir) = &options.debug_dir {
+        vec![debug_dir.clone()]
+    } else if options.sysroot.is_empty() {
+        vec![PathBuf::from("/usr/lib/debug")]
+    } else {
+        options
+            .sysroot
+            .iter()
+            .map(|sys_root| sys_root.join("usr/lib/debug"))
+            .collect()
+    }
+}
+
+/// Returns the path of the separate debug file of the binary, if it is found.
+///
+/// As done by debuggers, the debug file is looked up by the build identifier of the binary
+/// first, e.g., `/usr/lib/debug/.build-id/ab/cdef.debug`, then by the name recorded in its
+/// `.gnu_debuglink` section, next to the binary, in its `.debug` subdirectory, and in the debug
+/// directories. Files found by name must match the checksum recorded along with it.
+fn find(parser: &BinaryParser, elf: &goblin::elf::Elf, options: &Options) -> Option<PathBuf> {
+    let debug_dirs = debug_dirs(options);
+
+    if let Some(build_id) = build_id(parser, elf) {
+        for debug_dir in &debug_dirs {
+            let path = debug_dir
+                .join(".build-id")
+                .join(&build_id[..2])
+                .join(format!("{}.debug", &build_id[2..]));
+
+            debug!("Looking for debug file '{}'.", path.display());
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+
+    let (file_name, crc) = debug_link(parser, elf)?;
+    let dir = parser
+        .path()
+        .canonicalize()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))?;
+
+    let mut candidates = vec![dir.join(file_name), dir.join(".debug").join(file_name)];
+    if let Ok(relative_dir) = dir.strip_prefix("/") {
+        candidates.extend(
+            debug_dirs
+                .iter()
+                .map(|debug_dir| debug_dir.join(relative_dir).join(file_name)),
+        );
+    }
+
+    candidates.into_iter().find(|path| {
+        debug!("Looking for debug file '{}'.", path.display());
+        fs::read(path).is_ok_and(|bytes| crc32(&bytes) == crc)
+    })
+}
+
+/// Returns the build identifier of the binary, as hexadecimal digits.
+fn build_id(parser: &BinaryParser, elf: &goblin::elf::Elf) -> Option<String> {
+    let note = super::notes(parser, elf, ".note.gnu.build-id").find(|note| {
+        note.n_type == NT_GNU_BUILD_ID && note.name == "GNU" && note.desc.len() >= 2
+    })?;
+
+    let mut r = String::with_capacity(note.desc.len() * 2);
+    for byte in note.desc {
+        let _ignored = write!(r, "{byte:02x}");
+    }
+    Some(r)
+}
+
+/// Returns the file name and the checksum of the debug file, recorded in the `.gnu_debuglink`
+/// section of the binary.
+fn debug_link<'t>(parser: &'t BinaryParser, elf: &'t goblin::elf::Elf) -> Option<(&'t str, u32)> {
+    let (_name, bytes) = super::sections_named(parser, elf, &[".gnu_debuglink"]).next()?;
+
+    let name_len = bytes.iter().position(|&b| b == 0)?;
+    let file_name = core::str::from_utf8(&bytes[..name_len]).ok()?;
+
+    // The checksum follows the name, aligned to 4 bytes.
+    let crc_offset = (name_len + 1).next_multiple_of(4);
+    let crc = super::read_u32(elf, bytes.get(crc_offset..)?)?;
+
+    // The debug file is looked up in directories, so its name must not escape them.
+    (!file_name.is_empty() && !file_name.contains('/')).then_some((file_name, crc))
+}
+
+/// Computes the CRC-32 checksum used by `.gnu_debuglink`, i.e., the one of `zlib`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0_u32, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Opens the separate debug file of the binary, if the binary has no symbol table and its debug
+/// file is found.
+pub(crate) fn open(
+    parser: &BinaryParser,
+    elf: &goblin::elf::Elf,
+    options: &Options,
+) -> Option<BinaryFile> {
+    if !elf.syms.is_empty() {
+        return None;
+    }
+
+    let path = find(parser, elf, options)?;
+    match BinaryFile::open(&path) {
+        Ok(debug_file) => {
+            debug!("Found debug file '{}'.", path.display());
+            Some(debug_file)
+        }
+
+        Err(_r) => {
+            debug!("Failed to open debug file '{}'.", path.display());
+            None
+        }
+    }
+}
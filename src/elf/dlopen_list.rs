@@ -0,0 +1,59 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Lists of libraries that applications load at runtime, given to `--dlopen-list`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+use super::needed_libc::LibCResolver;
+use crate::errors::{Error, Result};
+
+/// Returns the paths of the libraries named by the list file at `path`.
+///
+/// Each line names a library as it would be given to `dlopen`. Empty lines and lines starting
+/// with `#` are ignored. Names containing a `/` are paths. Other names are looked up in
+/// `--library-path`, in the dynamic loader cache, or in the known library directories of the
+/// system roots, as the C runtime library is.
+///
+/// Plugins are not recorded by `DT_NEEDED` entries, so they are otherwise easy to overlook.
+pub(crate) fn read(path: &Path, options: &crate::cmdline::Options) -> Result<Vec<PathBuf>> {
+    let text = fs::read_to_string(path).map_err(|r| Error::from_io1(r, "read file", path))?;
+
+    let mut resolver = None;
+    let mut libraries = Vec::default();
+
+    for name in text.lines().map(str::trim) {
+        if name.is_empty() || name.starts_with('#') {
+            continue;
+        }
+
+        let library = if name.contains('/') {
+            PathBuf::from(name)
+        } else {
+            let resolver = match &mut resolver {
+                Some(resolver) => resolver,
+                None => resolver.insert(LibCResolver::new(options)?),
+            };
+
+            resolver
+                .find_library(Path::new(name))
+                .ok_or_else(|| Error::NotFoundListedLibrary {
+                    name: name.into(),
+                    list: path.into(),
+                })?
+        };
+
+        debug!(
+            "Library '{name}' is loaded at runtime from '{}'.",
+            library.display()
+        );
+        libraries.push(library);
+    }
+    Ok(libraries)
+}
@@ -0,0 +1,230 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Hardening options of compilation units, as recorded by compilers in debugging information.
+
+use gimli::{EndianSlice, RunTimeEndian};
+use log::debug;
+
+use crate::options::status::{DetailedStatus, SupportLevel};
+use crate::parser::BinaryParser;
+
+/// Keyword of the result reporting the hardening options of compilation units.
+pub(crate) const DWARF_HARDENING: &str = "DWARF-HARDENING";
+
+/// Hardening option of compilers, enabled or disabled by some command line options.
+struct HardeningOption {
+    name: &'static str,
+    enabling: &'static [&'static str],
+    disabling: &'static [&'static str],
+}
+
+/// Hardening options recorded by GCC in `DW_AT_producer` strings, unless built with
+/// `-gno-record-gcc-switches`. The last command line option of a compilation unit wins.
+static HARDENING_OPTIONS: &[HardeningOption] = &[
+    HardeningOption {
+        name: "stack-protector",
+        enabling: &[
+            "-fstack-protector",
+            "-fstack-protector-strong",
+            "-fstack-protector-all",
+        ],
+        disabling: &["-fno-stack-protector", "-fstack-protector-explicit"],
+    },
+    HardeningOption {
+        name: "stack-clash-protection",
+        enabling: &["-fstack-clash-protection"],
+        disabling: &["-fno-stack-clash-protection"],
+    },
+    HardeningOption {
+        name: "cf-protection",
+        enabling: &[
+            "-fcf-protection",
+            "-fcf-protection=full",
+            "-fcf-protection=branch",
+            "-fcf-protection=return",
+        ],
+        disabling: &["-fcf-protection=none"],
+    },
+];
+
+/// Compilation unit described by debugging information.
+struct CompilationUnit {
+    name: String,
+    producer: String,
+}
+
+/// Returns the compilation units described in the `.debug_info` section, along with the
+/// `DW_AT_producer` strings naming the compilers that built them.
+///
+/// Compressed sections are not decompressed, so their compilation units are not returned.
+/// Malformed debugging information ends the enumeration of compilation units.
+fn compilation_units(parser: &BinaryParser, elf: &goblin::elf::Elf) -> Vec<CompilationUnit> {
+    let endian = if elf.little_endian {
+        RunTimeEndian::Little
+    } else {
+        RunTimeEndian::Big
+    };
+
+    let load_section = |id: gimli::SectionId| -> Result<_, gimli::Error> {
+        let bytes = elf
+            .section_headers
+            .iter()
+            .find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(id.name()))
+            .and_then(|section| super::section_bytes(parser, section))
+            .unwrap_or_default();
+        Ok(EndianSlice::new(bytes, endian))
+    };
+
+    let mut result = Vec::default();
+    let Ok(dwarf) = gimli::Dwarf::load(load_section) else {
+        return result;
+    };
+
+    let mut headers = dwarf.units();
+    loop {
+        let header = match headers.next() {
+            Ok(Some(header)) => header,
+            Ok(None) => break,
+
+            Err(r) => {
+                debug!("Failed to read header of compilation unit: {r}");
+                break;
+            }
+        };
+
+        let Ok(unit) = dwarf.unit(header) else {
+            continue;
+        };
+
+        let mut entries = unit.entries();
+        let Ok(Some((_, entry))) = entries.next_dfs() else {
+            continue;
+        };
+
+        let attribute_string = |name| {
+            let value = entry.attr_value(name).ok().flatten()?;
+            let string = dwarf.attr_string(&unit, value).ok()?;
+            Some(String::from_utf8_lossy(string.slice()).into_owned())
+        };
+
+        if let Some(producer) = attribute_string(gimli::DW_AT_producer) {
+            let name = attribute_string(gimli::DW_AT_name).unwrap_or_default();
+            debug!("Compilation unit '{name}' was produced by '{producer}'.");
+            result.push(CompilationUnit { name, producer });
+        }
+    }
+    result
+}
+
+/// Returns the name and version of the compiler named by a `DW_AT_producer` string, e.g.,
+/// `gcc-13.2.0` for `GNU C17 13.2.0 -mtune=generic -O2`, `clang-17.0.6` for
+/// `clang version 17.0.6`, and `rustc-1.75.0` for `clang LLVM (rustc version 1.75.0)`.
+fn compiler(producer: &str) -> Option<String> {
+    let version_after = |marker: &str| {
+        let start = producer.find(marker)? + marker.len();
+        producer[start..]
+            .split(|c: char| c.is_whitespace() || c == ')')
+            .next()
+            .filter(|version| !version.is_empty())
+    };
+
+    if let Some(rest) = producer.strip_prefix("GNU ") {
+        let mut words = rest.split_whitespace();
+        let language = words.next()?;
+        let version = words.next()?;
+        let name = if language == "AS" { "as" } else { "gcc" };
+        Some(format!("{name}-{version}"))
+    } else if let Some(version) = version_after("rustc version ") {
+        Some(format!("rustc-{version}"))
+    } else {
+        version_after("clang version ").map(|version| format!("clang-{version}"))
+    }
+}
+
+/// Returns whether the hardening option is enabled by the command line options recorded in a
+/// `DW_AT_producer` string, or `None` if they do not mention it.
+fn option_is_enabled(producer: &str, option: &HardeningOption) -> Option<bool> {
+    producer
+        .split_whitespace()
+        .filter_map(|word| {
+            if option.enabling.contains(&word) {
+                Some(true)
+            } else if option.disabling.contains(&word) {
+                Some(false)
+            } else {
+                None
+            }
+        })
+        .next_back()
+}
+
+/// Returns the hardening options of the compilation units of the binary, as recorded in the
+/// `DW_AT_producer` strings of its debugging information.
+///
+/// The compilers building the binary are reported, followed, for each hardening option, by the
+/// number of compilation units enabling it, out of those recording it, e.g.,
+/// `stack-protector=10/12`. Binaries whose compilation units do not agree are reported as
+/// partially supported, which symbol-based heuristics cannot detect. Compilation units not
+/// enabling an option are logged.
+pub(crate) fn hardening_evidence(parser: &BinaryParser, elf: &goblin::elf::Elf) -> DetailedStatus {
+    let units = compilation_units(parser, elf);
+    if units.is_empty() {
+        return DetailedStatus::unknown(DWARF_HARDENING);
+    }
+
+    let mut compilers: Vec<String> = units
+        .iter()
+        .filter_map(|unit| compiler(&unit.producer))
+        .collect();
+    compilers.sort_unstable();
+    compilers.dedup();
+
+    let mut details = vec![format!("units={}", units.len())];
+    if !compilers.is_empty() {
+        details.push(compilers.join("+"));
+    }
+
+    let mut level = None;
+    for option in HARDENING_OPTIONS {
+        let mut enabled = 0_usize;
+        let mut recorded = 0_usize;
+
+        for unit in &units {
+            match option_is_enabled(&unit.producer, option) {
+                Some(true) => enabled += 1,
+
+                Some(false) => debug!(
+                    "Compilation unit '{}' was built without '{}'.",
+                    unit.name, option.name
+                ),
+
+                None => continue,
+            }
+            recorded += 1;
+        }
+
+        if recorded == 0 {
+            continue;
+        }
+
+        let option_level = if enabled == recorded {
+            SupportLevel::Supported
+        } else if enabled == 0 {
+            SupportLevel::Unsupported
+        } else {
+            SupportLevel::Partial
+        };
+        level = Some(level.map_or(option_level, |level: SupportLevel| level.min(option_level)));
+        details.push(format!("{}={enabled}/{recorded}", option.name));
+    }
+
+    DetailedStatus::new(
+        DWARF_HARDENING,
+        level.unwrap_or(SupportLevel::Unknown),
+        details,
+    )
+}
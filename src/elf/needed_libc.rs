@@ -4,113 +4,314 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+use std::time::SystemTime;
+use std::{fmt, iter};
+use std::{fs, io};
 
 use log::{debug, log_enabled};
 use regex::{Regex, RegexBuilder};
 
-use super::checked_functions::{function_is_checked_version, CheckedFunction};
+use super::search_path::library_search_dirs;
 use crate::cmdline::LibCSpec;
 use crate::errors::{Error, Result};
-use crate::parser::BinaryParser;
+use crate::fortify::CheckedFunctions;
+use crate::parser::BinaryFile;
 
 #[derive(Debug)]
 pub(crate) struct LibCResolver {
-    sys_root: PathBuf,
+    /// System roots, searched in order, e.g., one per architecture of a firmware.
+    sys_roots: Vec<PathBuf>,
+    /// Directories given by `--library-path`, searched as `LD_LIBRARY_PATH`.
+    library_path: Vec<PathBuf>,
     ld_so_cache: Option<dynamic_loader_cache::Cache>,
+    /// Directories of the libraries of the bundle containing the analyzed files, if any.
+    bundle_dirs: Vec<PathBuf>,
+    /// Root directory given by `--chroot`, inside which symbolic links are resolved.
+    chroot: Option<PathBuf>,
 }
 
-static LIBC_RESOLVER: OnceLock<Option<LibCResolver>> = OnceLock::new();
-
 impl LibCResolver {
-    pub(crate) fn get(options: &crate::cmdline::Options) -> Result<&'static Self> {
-        let mut first_err = None;
-
-        let r = LIBC_RESOLVER.get_or_init(|| match Self::new(options) {
-            Ok(r) => Some(r),
-
-            Err(err) => {
-                first_err = Some(err);
-                None
-            }
-        });
-
-        if let Some(err) = first_err {
-            Err(err)
-        } else {
-            r.as_ref().ok_or_else(|| {
-                let err = std::io::ErrorKind::InvalidData.into();
-                Error::from_io1(err, "load linker cache", "")
-            })
-        }
-    }
-
-    fn new(options: &crate::cmdline::Options) -> Result<Self> {
-        let ld_so_cache = if options.sysroot.is_none() {
+    pub(crate) fn new(options: &crate::cmdline::Options) -> Result<Self> {
+        let ld_so_cache = if options.sysroot.is_empty() {
             Some(dynamic_loader_cache::Cache::load()?)
         } else {
             None
         };
 
-        let sys_root = options.sysroot.as_deref().unwrap_or_else(|| Path::new("/"));
+        let sys_roots = if options.sysroot.is_empty() {
+            vec![PathBuf::from("/")]
+        } else {
+            options.sysroot.clone()
+        };
 
         Ok(Self {
-            sys_root: sys_root.into(),
+            sys_roots,
+            library_path: options.library_path.clone(),
             ld_so_cache,
+            bundle_dirs: Vec::default(),
+            chroot: options.chroot.clone(),
         })
     }
 
-    pub(crate) fn find_needed_by_executable(&self, elf: &goblin::elf::Elf) -> Result<NeededLibC> {
-        elf.libraries
+    /// Looks up libraries in the given directories of a bundle, before the dynamic loader cache
+    /// and the known library directories.
+    pub(crate) fn with_bundle_dirs(mut self, bundle_dirs: Vec<PathBuf>) -> Self {
+        self.bundle_dirs = bundle_dirs;
+        self
+    }
+
+    /// Returns `path` with its symbolic links resolved inside the root directory given by
+    /// `--chroot`, if `path` is inside it.
+    fn confined(&self, path: PathBuf) -> PathBuf {
+        match &self.chroot {
+            Some(root) => match path.strip_prefix(root) {
+                Ok(inner_path) => crate::chroot::resolve(root, inner_path),
+                Err(_r) => path,
+            },
+            None => path,
+        }
+    }
+
+    /// Returns the C runtime library needed by the binary at `path`, as found in the first
+    /// system root providing a library that targets the architecture of the binary.
+    ///
+    /// If no system root provides it, then the reason why the first one does not is returned,
+    /// unless a library targeting another architecture was found.
+    pub(crate) fn find_needed_by_executable(
+        &self,
+        path: &Path,
+        elf: &goblin::elf::Elf,
+    ) -> Result<NeededLibC> {
+        first_found(
+            self.sys_roots
+                .iter()
+                .map(|sys_root| self.find_needed_in_sys_root(sys_root, path, elf)),
+            |first_error| first_error.unwrap_or(Error::UnrecognizedNeededLibC),
+        )
+    }
+
+    /// Returns the C runtime library needed by the binary at `path`, as found in `sys_root`.
+    ///
+    /// The library is looked up in the search paths recorded by the binary and in `--library-path`
+    /// first, so that bundled applications resolve their private copies of the library. As done
+    /// by the dynamic loader, `DT_RPATH` is searched before `--library-path`, and `DT_RUNPATH`
+    /// after it.
+    fn find_needed_in_sys_root(
+        &self,
+        sys_root: &Path,
+        path: &Path,
+        elf: &goblin::elf::Elf,
+    ) -> Result<NeededLibC> {
+        let mut search_dirs = library_search_dirs(path, elf, sys_root);
+        if elf.runpaths.is_empty() {
+            search_dirs.extend(self.library_path.iter().cloned());
+        } else {
+            search_dirs.splice(0..0, self.library_path.iter().cloned());
+        }
+
+        let candidates = elf
+            .libraries
             .iter()
             // Only consider libraries whose pattern is known.
             .filter(|needed_lib| KNOWN_LIBC_PATTERN.is_match(needed_lib))
             // Parse the library.
-            .map(|&lib| self.open_compatible_libc(elf, Path::new(lib)))
-            // Return the first that can be successfully parsed.
-            .find(Result::is_ok)
-            // Or return an error in case nothing is found or nothing can be parsed.
-            .unwrap_or(Err(Error::UnrecognizedNeededLibC))
+            .map(|&lib| self.open_compatible_libc(sys_root, elf, &search_dirs, Path::new(lib)))
+            // The dynamic loader of musl is also its C runtime library, and it is the only file
+            // that systems without development files provide.
+            .chain(iter::once_with(|| self.open_musl_interpreter(sys_root, elf)).flatten());
+
+        // Return the first that can be successfully parsed, or an error in case nothing is found
+        // or nothing can be parsed.
+        first_found(candidates, |_first_error| Error::UnrecognizedNeededLibC)
     }
 
-    fn open_compatible_libc(&self, elf: &goblin::elf::Elf, file_name: &Path) -> Result<NeededLibC> {
+    fn open_musl_interpreter(
+        &self,
+        sys_root: &Path,
+        elf: &goblin::elf::Elf,
+    ) -> Option<Result<NeededLibC>> {
+        let interpreter = Path::new(elf.interpreter?);
+        let is_musl = interpreter
+            .file_name()
+            .and_then(OsStr::to_str)
+            .is_some_and(|name| name.starts_with(MUSL_LOADER_PREFIX));
+
+        is_musl.then(|| {
+            let path = sys_root.join(interpreter.strip_prefix("/").unwrap_or(interpreter));
+            NeededLibC::open_elf_for_architecture(self.confined(path), elf)
+        })
+    }
+
+    fn open_compatible_libc(
+        &self,
+        sys_root: &Path,
+        elf: &goblin::elf::Elf,
+        search_dirs: &[PathBuf],
+        file_name: &Path,
+    ) -> Result<NeededLibC> {
         debug!("Looking for libc '{}'.", file_name.display());
 
-        if let Some(ld_so_cache) = self.ld_so_cache.as_ref() {
-            let found_in_ld_so_cache = ld_so_cache
-                .iter()?
-                .filter_map(dynamic_loader_cache::Result::ok)
-                .filter_map(|e| (e.file_name == file_name).then_some(e.full_path))
-                // For each known libc file location, parse the libc file.
-                .map(|path| NeededLibC::open_elf_for_architecture(path, elf))
-                // Return the first that can be successfully parsed.
-                .find(Result::is_ok);
-
-            if let Some(libc) = found_in_ld_so_cache {
-                return libc;
-            }
-        }
+        let found_in_search_dirs = search_dirs
+            .iter()
+            .chain(&self.bundle_dirs)
+            .map(|dir| self.confined(dir.join(file_name)))
+            .filter(|path| path.is_file());
+
+        let found_in_ld_so_cache = self
+            .ld_so_cache
+            .as_ref()
+            .map(dynamic_loader_cache::Cache::iter)
+            .transpose()?
+            .into_iter()
+            .flatten()
+            .filter_map(dynamic_loader_cache::Result::ok)
+            .filter_map(|e| (e.file_name == file_name).then(|| e.full_path.into_owned()));
+
+        let found_in_known_dirs = KNOWN_LIB_DIRS.iter().flat_map(|&lib| {
+            KNOWN_PREFIXES
+                .iter()
+                .map(move |&prefix| self.confined(sys_root.join(prefix).join(lib).join(file_name)))
+        });
+
+        let candidates = found_in_search_dirs
+            .chain(found_in_ld_so_cache)
+            .chain(found_in_known_dirs)
+            // For each library file in the search paths and the bundle, then for each known
+            // libc file location, parse the libc file.
+            .map(|path| NeededLibC::open_elf_for_architecture(path, elf));
+
+        // Return the first that can be successfully parsed, or an error in case nothing is found
+        // or nothing can be parsed.
+        first_found(candidates, |_first_error| {
+            Error::NotFoundNeededLibC(file_name.into())
+        })
+    }
 
-        KNOWN_LIB_DIRS
+    /// Returns the known library directories that exist in the system roots.
+    pub(crate) fn library_directories(&self) -> Vec<PathBuf> {
+        self.sys_roots
             .iter()
-            .flat_map(|&lib| {
-                KNOWN_PREFIXES
-                    .iter()
-                    .map(move |&prefix| self.sys_root.join(prefix).join(lib).join(file_name))
+            .flat_map(|sys_root| {
+                KNOWN_PREFIXES.iter().flat_map(move |&prefix| {
+                    KNOWN_LIB_DIRS
+                        .iter()
+                        .map(move |&lib| sys_root.join(prefix).join(lib))
+                })
             })
-            // For each known libc file location, parse the libc file.
-            .map(|path| NeededLibC::open_elf_for_architecture(path, elf))
-            // Return the first that can be successfully parsed.
-            .find(Result::is_ok)
-            // Or return an error in case nothing is found or nothing can be parsed.
-            .unwrap_or_else(|| Err(Error::NotFoundNeededLibC(file_name.into())))
+            .filter(|path| path.is_dir())
+            .collect()
     }
+
+    /// Returns the path of the library named `file_name`, as found in `--library-path`, in the
+    /// dynamic loader cache, or in the known library directories of the system roots.
+    pub(crate) fn find_library(&self, file_name: &Path) -> Option<PathBuf> {
+        debug!("Looking for library '{}'.", file_name.display());
+
+        let found_in_library_path = self
+            .library_path
+            .iter()
+            .map(|dir| self.confined(dir.join(file_name)))
+            .find(|path| path.is_file());
+
+        if found_in_library_path.is_some() {
+            return found_in_library_path;
+        }
+
+        let found_in_ld_so_cache = self
+            .ld_so_cache
+            .as_ref()
+            .and_then(|ld_so_cache| ld_so_cache.iter().ok())
+            .into_iter()
+            .flatten()
+            .filter_map(dynamic_loader_cache::Result::ok)
+            .find_map(|e| (e.file_name == file_name).then(|| e.full_path.into_owned()));
+
+        found_in_ld_so_cache.or_else(|| {
+            self.sys_roots
+                .iter()
+                .flat_map(|sys_root| {
+                    KNOWN_LIB_DIRS.iter().flat_map(move |&lib| {
+                        KNOWN_PREFIXES.iter().map(move |&prefix| {
+                            self.confined(sys_root.join(prefix).join(lib).join(file_name))
+                        })
+                    })
+                })
+                .find(|path| path.is_file())
+        })
+    }
+}
+
+/// Identifies a C runtime library file, loaded for binaries of an architecture.
+///
+/// The modification time distinguishes successive versions of the file, e.g., while a root file
+/// system is upgraded during a scan.
+#[derive(PartialEq, Eq, Hash)]
+struct LibCKey {
+    path: PathBuf,
+    modified: SystemTime,
+    /// Architecture of the binaries depending on the library, as recorded in `e_machine`.
+    machine: u16,
+}
+
+impl LibCKey {
+    fn new(path: &Path, machine: u16) -> io::Result<Self> {
+        let path = path.canonicalize()?;
+        let modified = fs::metadata(&path)?.modified()?;
+        Ok(Self {
+            path,
+            modified,
+            machine,
+        })
+    }
+}
+
+/// C runtime library loaded from a file, along with the architecture it targets.
+#[derive(Clone)]
+struct LoadedLibC {
+    machine: u16,
+    /// Name of the architecture, as reported for analyzed files.
+    architecture: String,
+    libc: NeededLibC,
+}
+
+/// C runtime library loaded once, or `None` until it is loaded.
+type LoadedLibCSlot = Arc<Mutex<Option<LoadedLibC>>>;
+
+/// Origin of the functions that the checked functions of binaries are compared against.
+#[derive(Debug, Clone)]
+pub(crate) enum LibCSource {
+    /// C runtime library file.
+    File(PathBuf),
+    /// Specification given by `--libc-spec`.
+    Spec(LibCSpec),
+    /// No C runtime library, e.g., due to `--no-libc`.
+    None,
+    /// C runtime library that could not be resolved.
+    Unresolved,
 }
 
+impl fmt::Display for LibCSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Spec(spec) => write!(f, "spec: {spec}"),
+            Self::None => write!(f, "none"),
+            Self::Unresolved => write!(f, "unresolved"),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub(crate) struct NeededLibC {
-    checked_functions: HashSet<CheckedFunction>,
+    /// Shared by the binaries depending on the same C runtime library.
+    checked_functions: Arc<CheckedFunctions>,
+    /// Whether this is the musl C runtime library, which exports no checked functions.
+    is_musl: bool,
+    source: LibCSource,
 }
 
 impl NeededLibC {
@@ -138,109 +339,222 @@ impl NeededLibC {
         }
 
         Self {
-            checked_functions: functions_with_checked_versions
-                .iter()
-                .map(|name| CheckedFunction::from_unchecked_name(name))
-                .collect(),
+            checked_functions: Arc::new(CheckedFunctions::from_unchecked_names(
+                functions_with_checked_versions,
+            )),
+            is_musl: false,
+            source: LibCSource::Spec(spec),
         }
     }
 
+    /// Opens the first C runtime library of `paths` that targets the machine type of
+    /// `other_elf`.
+    ///
+    /// If none does, then the reason why the first library is not suitable is returned.
+    pub(crate) fn open_first_elf_for_architecture(
+        paths: &[PathBuf],
+        other_elf: &goblin::elf::Elf,
+    ) -> Result<Self> {
+        first_found(
+            paths
+                .iter()
+                .map(|path| Self::open_elf_for_architecture(path, other_elf)),
+            |first_error| first_error.unwrap_or(Error::UnrecognizedNeededLibC),
+        )
+    }
+
+    /// Opens the C runtime library at `path`, which must target the machine type of `other_elf`.
     pub(crate) fn open_elf_for_architecture(
         path: impl AsRef<Path>,
         other_elf: &goblin::elf::Elf,
     ) -> Result<Self> {
-        let parser = BinaryParser::open(&path)?;
+        let path = path.as_ref();
+        let loaded = Self::open_elf(path, other_elf.header.e_machine)?;
+
+        if loaded.machine == other_elf.header.e_machine {
+            debug!(
+                "C runtime library file format is 'ELF'. Resolved to '{}'.",
+                path.display()
+            );
+            // Libraries loaded once may be found again through other paths.
+            Ok(Self {
+                source: LibCSource::File(path.into()),
+                ..loaded.libc
+            })
+        } else {
+            let expected = crate::elf::architecture(other_elf).name;
+            debug!(
+                "C runtime library '{}' targets {} instead of {expected}.",
+                path.display(),
+                loaded.architecture,
+            );
+            Err(Error::UnexpectedBinaryArchitecture {
+                path: path.into(),
+                found: loaded.architecture,
+                expected,
+            })
+        }
+    }
+
+    /// Opens the C runtime library at `path`, for binaries whose architecture is `machine`.
+    ///
+    /// Libraries are parsed once per process, as most analyzed binaries depend on the same few
+    /// C runtime libraries. They are parsed again when their file is modified.
+    fn open_elf(path: &Path, machine: u16) -> Result<LoadedLibC> {
+        static LIBRARIES: Mutex<Option<HashMap<LibCKey, LoadedLibCSlot>>> = Mutex::new(None);
+
+        let Ok(key) = LibCKey::new(path, machine) else {
+            return Self::load_elf(path);
+        };
+
+        // The global lock is only held while the slot of the library is looked up. The lock of
+        // the slot is held while the library is loaded, so that it is loaded only once, even when
+        // multiple files are analyzed concurrently, while different libraries are loaded
+        // concurrently.
+        let slot = Arc::clone(
+            LIBRARIES
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_or_insert_with(HashMap::default)
+                .entry(key)
+                .or_default(),
+        );
+
+        let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(loaded) = &*slot {
+            debug!("C runtime library '{}' is already loaded.", path.display());
+            return Ok(loaded.clone());
+        }
+
+        // Libraries that failed to load are loaded again when they are needed again.
+        let loaded = Self::load_elf(path)?;
+        *slot = Some(loaded.clone());
+        Ok(loaded)
+    }
+
+    fn load_elf(path: &Path) -> Result<LoadedLibC> {
+        let file = BinaryFile::open(path)?;
+        let parser = file.parse()?;
 
         match parser.object() {
             goblin::Object::Elf(elf) => {
-                if elf.header.e_machine == other_elf.header.e_machine {
-                    debug!(
-                        "C runtime library file format is 'ELF'. Resolved to '{}'.",
-                        path.as_ref().display()
-                    );
-
-                    Ok(Self {
-                        checked_functions: Self::get_checked_functions_elf(elf),
-                    })
-                } else {
-                    Err(Error::UnexpectedBinaryArchitecture(path.as_ref().into()))
+                let is_musl = Self::is_musl_elf(path, elf);
+                if is_musl {
+                    debug!("C runtime library '{}' is musl.", path.display());
                 }
+
+                Ok(LoadedLibC {
+                    machine: elf.header.e_machine,
+                    architecture: crate::elf::architecture(elf).name,
+                    libc: Self {
+                        checked_functions: Arc::new(Self::get_checked_functions_elf(elf)),
+                        is_musl,
+                        source: LibCSource::File(path.into()),
+                    },
+                })
             }
 
             goblin::Object::Unknown(magic) => Err(Error::UnsupportedBinaryFormat {
                 format: format!("Magic: 0x{magic:016X}"),
-                path: path.as_ref().into(),
+                path: path.into(),
             }),
 
             goblin::Object::PE(_) | goblin::Object::Mach(_) | goblin::Object::Archive(_) => {
                 Err(Error::UnexpectedBinaryFormat {
                     expected: "ELF",
-                    name: path.as_ref().into(),
+                    name: path.into(),
                 })
             }
 
             _ => Err(Error::UnsupportedBinaryFormat {
                 format: "Unknown".into(),
-                path: path.as_ref().into(),
+                path: path.into(),
             }),
         }
     }
 
-    fn get_checked_functions_elf(elf: &goblin::elf::Elf) -> HashSet<CheckedFunction> {
-        let checked_functions = elf
-            .dynsyms
-            .iter()
-            // Consider only named exported functions, and focus on their name.
-            .filter_map(|symbol| {
-                crate::elf::dynamic_symbol_is_named_exported_function(elf, &symbol)
-            })
-            // Consider only functions that are checked versions of libc functions.
-            .filter(|name| function_is_checked_version(name))
-            // Make up a new `CheckedFunction` for each found function.
-            .map(CheckedFunction::from_checked_name)
-            .collect::<HashSet<CheckedFunction>>();
+    /// The shared object of musl is named `libc.so`, while `libc.so` of glibc is a linker script.
+    /// Its file is usually named after its role as dynamic loader, e.g., `ld-musl-x86_64.so.1`.
+    fn is_musl_elf(path: &Path, elf: &goblin::elf::Elf) -> bool {
+        let file_name_is_musl = |path: &Path| {
+            path.file_name()
+                .and_then(OsStr::to_str)
+                .is_some_and(|name| name.contains("musl"))
+        };
+
+        elf.soname == Some("libc.so")
+            || file_name_is_musl(path)
+            || path
+                .canonicalize()
+                .is_ok_and(|path| file_name_is_musl(&path))
+    }
+
+    /// Returns where the checked functions come from, e.g., the path of the library file.
+    pub(crate) fn source(&self) -> &LibCSource {
+        &self.source
+    }
+
+    /// Returns `true` if this is the musl C runtime library.
+    pub(crate) fn is_musl(&self) -> bool {
+        self.is_musl
+    }
+
+    fn get_checked_functions_elf(elf: &goblin::elf::Elf) -> CheckedFunctions {
+        let checked_functions = CheckedFunctions::exported_by(elf);
 
         if log_enabled!(log::Level::Debug) {
-            let mut text = String::default();
-            let mut iter = checked_functions.iter();
-            if let Some(name) = iter.next() {
-                text.push_str(name.get_unchecked_name());
-                for name in iter {
-                    text.push(' ');
-                    text.push_str(name.get_unchecked_name());
-                }
-            } else {
-                text.push_str("(none)");
-            }
             debug!(
                 "Functions with checked versions, exported by the C runtime library: {}.",
-                text
+                checked_functions.to_text()
             );
         }
         checked_functions
     }
 
-    pub(crate) fn exports_function<'this>(&'this self, checked_name: &str) -> Option<&'this str> {
-        self.checked_functions
-            .get(&CheckedFunction::from_checked_name(checked_name))
-            .map(CheckedFunction::get_unchecked_name)
+    /// Returns the functions whose checked versions are exported by the C runtime library.
+    pub(crate) fn checked_functions(&self) -> &CheckedFunctions {
+        &self.checked_functions
     }
+}
+
+/// Returns the first C runtime library of `candidates` that is found, without opening the next
+/// candidates.
+///
+/// If none is found, then the first library targeting another architecture is reported, as the
+/// cause is then actionable, e.g., by `--sysroot`. Otherwise, `not_found` reports the cause,
+/// given the first error.
+fn first_found(
+    candidates: impl IntoIterator<Item = Result<NeededLibC>>,
+    not_found: impl FnOnce(Option<Error>) -> Error,
+) -> Result<NeededLibC> {
+    let mut first_error = None;
+    let mut unexpected_architecture = None;
+
+    for candidate in candidates {
+        match candidate {
+            Ok(libc) => return Ok(libc),
+
+            Err(error @ Error::UnexpectedBinaryArchitecture { .. }) => {
+                unexpected_architecture.get_or_insert(error);
+            }
 
-    pub(crate) fn exports_checked_version_of_function<'this>(
-        &'this self,
-        unchecked_name: &str,
-    ) -> Option<&'this str> {
-        self.checked_functions
-            .get(&CheckedFunction::from_unchecked_name(unchecked_name))
-            .map(CheckedFunction::get_unchecked_name)
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
     }
+
+    Err(unexpected_architecture.unwrap_or_else(|| not_found(first_error)))
 }
 
 // If this changes, then update the command line reference.
 static KNOWN_PREFIXES: &[&str] = &["", "usr"];
 static KNOWN_LIB_DIRS: &[&str] = &["lib", "lib64", "lib32"];
 
-static KNOWN_LIBC_PATTERN: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+/// Prefix of the file name of the dynamic loader of musl, e.g., `ld-musl-x86_64.so.1`.
+const MUSL_LOADER_PREFIX: &str = "ld-musl-";
+
+static KNOWN_LIBC_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
     RegexBuilder::new(r"\blib(c|bionic)\b[^/]+$")
         .case_insensitive(true)
         .multi_line(false)
@@ -0,0 +1,122 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Library search paths recorded by binaries in their `DT_RPATH` and `DT_RUNPATH` entries.
+
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+/// Returns the directories where the dynamic loader looks for the libraries needed by the binary
+/// at `path`, before looking into the dynamic loader cache and the default directories.
+///
+/// As done by the dynamic loader, `DT_RPATH` is ignored when `DT_RUNPATH` is present. Relative
+/// directories are ignored, as they depend on the working directory of the process. Absolute
+/// directories are located in `sys_root`, while directories relative to `$ORIGIN` are located
+/// next to the binary, e.g., in bundled applications.
+pub(crate) fn library_search_dirs(
+    path: &Path,
+    elf: &goblin::elf::Elf,
+    sys_root: &Path,
+) -> Vec<PathBuf> {
+    let entries = if elf.runpaths.is_empty() {
+        &elf.rpaths
+    } else {
+        &elf.runpaths
+    };
+
+    let origin = origin(path);
+    let mut dirs = Vec::default();
+
+    for dir in entries.iter().flat_map(|entry| entry.split(':')) {
+        for expanded in expand(dir, origin.as_deref(), elf) {
+            let expanded = PathBuf::from(expanded);
+
+            let dir = if dir.starts_with("$ORIGIN") || dir.starts_with("${ORIGIN}") {
+                expanded
+            } else if let Ok(relative) = expanded.strip_prefix("/") {
+                sys_root.join(relative)
+            } else {
+                debug!("Ignoring relative library search path '{dir}'.");
+                continue;
+            };
+
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// Returns the directory containing the binary at `path`, after resolving symbolic links, as the
+/// dynamic loader does when it expands `$ORIGIN`.
+fn origin(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.into());
+    path.parent().map(Path::to_path_buf)
+}
+
+/// Expands the `$ORIGIN`, `$LIB` and `$PLATFORM` dynamic string tokens of a search directory.
+///
+/// `$LIB` expands to `lib` or `lib64`, depending on the distribution, so both are returned for
+/// 64-bits binaries. Directories referring to `$ORIGIN` are dropped if the binary has no parent
+/// directory.
+fn expand(dir: &str, origin: Option<&Path>, elf: &goblin::elf::Elf) -> Vec<String> {
+    let mut expanded = vec![dir.to_owned()];
+
+    if dir.contains("ORIGIN") {
+        let Some(origin) = origin.and_then(Path::to_str) else {
+            return Vec::default();
+        };
+        expanded = replace_token(expanded, "ORIGIN", &[origin]);
+    }
+
+    if dir.contains("LIB") {
+        let lib_dirs: &[&str] = if elf.is_64 {
+            &["lib64", "lib"]
+        } else {
+            &["lib"]
+        };
+        expanded = replace_token(expanded, "LIB", lib_dirs);
+    }
+
+    if dir.contains("PLATFORM") {
+        let platform = platform(elf);
+        expanded = replace_token(expanded, "PLATFORM", &[&platform]);
+    }
+
+    expanded
+}
+
+/// Replaces `$NAME` and `${NAME}` in each directory by each of the given values.
+fn replace_token(dirs: Vec<String>, name: &str, values: &[&str]) -> Vec<String> {
+    let short = format!("${name}");
+    let long = format!("${{{name}}}");
+
+    dirs.into_iter()
+        .flat_map(|dir| {
+            values
+                .iter()
+                .map(|value| dir.replace(&long, value).replace(&short, value))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns the value of `$PLATFORM`, i.e., the name of the processor reported by the kernel in
+/// the `AT_PLATFORM` auxiliary vector entry.
+fn platform(elf: &goblin::elf::Elf) -> String {
+    let architecture = super::architecture(elf);
+    match architecture.name.as_str() {
+        "x86" => "i686".into(),
+        "powerpc64" if architecture.endianness == crate::report::Endianness::Little => {
+            "ppc64le".into()
+        }
+        "powerpc64" => "ppc64".into(),
+        "powerpc" => "ppc".into(),
+        name => name.into(),
+    }
+}
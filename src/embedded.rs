@@ -0,0 +1,140 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Executable images embedded in the overlay of other executables, e.g., the payloads of
+//! installers and of self-extracting archives.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use log::{debug, warn};
+
+use crate::context::AnalysisContext;
+use crate::errors::Result;
+use crate::extract::{ExtractionBudget, ExtractionLimits, Refusal};
+use crate::options::OverlayOption;
+use crate::parser::{BinaryFile, BinaryParser};
+use crate::report::FileResults;
+use crate::{elf, pe};
+
+/// Offset of the `e_lfanew` field of the DOS header, which locates the PE header.
+const E_LFANEW_OFFSET: usize = 0x3c;
+
+/// Signature starting the PE header.
+const PE_SIGNATURE: &[u8] = b"PE\0\0";
+
+/// Returns the path under which the image found at `offset` of the file at `path` is reported,
+/// e.g., `setup.exe@0x2a400`.
+pub(crate) fn image_path(path: &Path, offset: usize) -> PathBuf {
+    let mut image_path = OsString::from(path.as_os_str());
+    image_path.push(format!("@{offset:#x}"));
+    image_path.into()
+}
+
+/// Returns `true` if `bytes` start with the headers of a PE or an ELF image.
+fn starts_with_image(bytes: &[u8]) -> bool {
+    if bytes.starts_with(b"MZ") {
+        bytes
+            .get(E_LFANEW_OFFSET..E_LFANEW_OFFSET + 4)
+            .and_then(|e_lfanew| {
+                let e_lfanew = u32::from_le_bytes(e_lfanew.try_into().ok()?);
+                let e_lfanew = usize::try_from(e_lfanew).ok()?;
+                bytes.get(e_lfanew..e_lfanew.checked_add(PE_SIGNATURE.len())?)
+            })
+            == Some(PE_SIGNATURE)
+    } else {
+        // The class, the data encoding and the version of the ELF identification must be valid.
+        bytes.starts_with(goblin::elf::header::ELFMAG)
+            && matches!(bytes.get(4..7), Some([1 | 2, 1 | 2, 1]))
+    }
+}
+
+/// Returns the size of the image parsed by `parser`, i.e., the size of the data described by its
+/// headers.
+fn image_size(parser: &BinaryParser) -> Option<usize> {
+    let end = match parser.object() {
+        goblin::Object::Elf(elf) => elf::data_end(elf),
+        goblin::Object::PE(pe) => pe::data_end(parser, pe),
+        _ => return None,
+    };
+
+    usize::try_from(end)
+        .ok()
+        .filter(|&end| end > 0)
+        .map(|end| end.min(parser.bytes().len()))
+}
+
+/// Analyzes the PE and ELF images embedded in the overlay of the binary at `path`.
+///
+/// The overlay is scanned for the headers of images. Each image found is analyzed in memory, up
+/// to the end of the data described by its headers, where the scan resumes. Images exceeding
+/// the extraction limits are skipped, and images that cannot be analyzed are reported as
+/// warnings.
+pub(crate) fn analyze_images(
+    path: &Path,
+    context: &AnalysisContext,
+) -> Result<Vec<(PathBuf, FileResults)>> {
+    let file = BinaryFile::open(path)?;
+    let parser = file.parse()?;
+    let Some(overlay) = OverlayOption::overlay(&parser) else {
+        return Ok(Vec::default());
+    };
+    let overlay_offset = parser.bytes().len() - overlay.len();
+
+    let mut budget = ExtractionBudget::new(path, ExtractionLimits::new(context.options));
+    let mut results = Vec::default();
+    let mut offset = 0;
+
+    // Images start with `MZ` or with `\x7fELF`.
+    while let Some(position) = overlay[offset..]
+        .iter()
+        .position(|&b| b == b'M' || b == goblin::elf::header::ELFMAG[0])
+    {
+        offset += position;
+        let bytes = &overlay[offset..];
+        let image_offset = overlay_offset + offset;
+        let image_path = image_path(path, image_offset);
+
+        let size = starts_with_image(bytes)
+            .then(|| BinaryParser::parse(&image_path, bytes).ok())
+            .flatten()
+            .as_ref()
+            .and_then(image_size);
+
+        let Some(size) = size else {
+            offset += 1;
+            continue;
+        };
+
+        debug!(
+            "Found embedded image '{}' of {size} bytes.",
+            image_path.display()
+        );
+        offset += size;
+
+        match budget.admit(&format!("{image_offset:#x}"), size as u64) {
+            Ok(()) => {}
+            Err(Refusal::SkipMember) => continue,
+            Err(Refusal::StopContainer) => break,
+        }
+
+        let start = context.options.timings.then(Instant::now);
+        let r = BinaryParser::parse(&image_path, &bytes[..size])
+            .and_then(|image_parser| crate::analyze_parsed_file(&image_parser, context, start));
+
+        match r {
+            Ok(image_results) => results.push((image_path, image_results)),
+
+            Err(error) => warn!(
+                "{}: Embedded image is not analyzed. {}",
+                image_path.display(),
+                crate::format_error(&error)
+            ),
+        }
+    }
+    Ok(results)
+}
@@ -20,6 +20,9 @@ pub(crate) enum Error {
         //backtrace: Backtrace,
     },
 
+    #[error("file '{path}' is not analyzed because it is {reason}")]
+    SkippedFile { path: PathBuf, reason: &'static str },
+
     #[error("failed to parse file")]
     ParseFile {
         #[source]
@@ -37,6 +40,16 @@ pub(crate) enum Error {
         //backtrace: Backtrace,
     },
 
+    #[error("failed to parse report. Path: {path}")]
+    ParseReport {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("schema version {version} of report '{path}' is unsupported")]
+    UnsupportedReportSchema { version: u64, path: PathBuf },
+
     #[error("logging initialization failed")]
     LogInitialization(#[from] log::SetLoggerError),
 
@@ -49,8 +62,12 @@ pub(crate) enum Error {
         name: PathBuf,
     },
 
-    #[error("architecture of '{0}' is unexpected")]
-    UnexpectedBinaryArchitecture(PathBuf),
+    #[error("found C runtime library '{path}', but it targets {found} while the binary targets {expected}. Consider specifying --sysroot")]
+    UnexpectedBinaryArchitecture {
+        path: PathBuf,
+        found: String,
+        expected: String,
+    },
 
     #[error("binary format '{format}' of file '{path}' is recognized but unsupported")]
     UnsupportedBinaryFormat { format: String, path: PathBuf },
@@ -61,6 +78,47 @@ pub(crate) enum Error {
     #[error("dependent C runtime library '{0}' was not found")]
     NotFoundNeededLibC(PathBuf),
 
+    #[error("library '{name}' listed in '{list}' was not found. Consider specifying --sysroot")]
+    NotFoundListedLibrary { name: PathBuf, list: PathBuf },
+
+    #[error("failed to decompress '{path}' with 'upx': {message}")]
+    UnpackUpx { path: PathBuf, message: String },
+
+    #[error("failed to decompress the kernel of '{path}': {message}")]
+    UnpackKernel { path: PathBuf, message: String },
+
+    #[error("the vDSO is not mapped into this process")]
+    NotFoundVdso,
+
+    #[error("failed to extract the payload of '{path}' with 'unsquashfs': {message}")]
+    ExtractBundle { path: PathBuf, message: String },
+
+    #[error("extracted '{path}' is {size} bytes large, which exceeds the limit set by '{option}'")]
+    ExtractionLimit {
+        path: PathBuf,
+        size: u64,
+        option: &'static str,
+    },
+
+    #[error("failed to write annotated copy '{path}': {reason}")]
+    AnnotateElf { path: PathBuf, reason: &'static str },
+
+    #[error("failed to post report to webhook: {message}")]
+    PostReport { message: String },
+
+    #[error("failed to listen on '{address}': {message}")]
+    Listen {
+        address: std::net::SocketAddr,
+        message: String,
+    },
+
+    #[error("failed to sandbox the analysis: {message}")]
+    Sandbox { message: String },
+
+    /// Error reported by, or about, a worker process of `--isolate`.
+    #[error("{message}")]
+    Isolated { kind: &'static str, message: String },
+
     #[error(transparent)]
     FromBytesWithNul(#[from] core::ffi::FromBytesWithNulError),
 
@@ -75,6 +133,39 @@ pub(crate) enum Error {
 }
 
 impl Error {
+    /// Returns a keyword identifying the kind of this error in structured reports.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::IO1 { .. } => "io",
+            Self::SkippedFile { .. } => "skipped-file",
+            Self::ParseFile { .. } => "parse-file",
+            Self::ExtractArchiveMember { .. } => "extract-archive-member",
+            Self::ParseReport { .. } => "parse-report",
+            Self::UnsupportedReportSchema { .. } => "unsupported-report-schema",
+            Self::LogInitialization(_) => "log-initialization",
+            Self::UnknownBinaryFormat(_) => "unknown-binary-format",
+            Self::UnexpectedBinaryFormat { .. } => "unexpected-binary-format",
+            Self::UnexpectedBinaryArchitecture { .. } => "unexpected-binary-architecture",
+            Self::UnsupportedBinaryFormat { .. } => "unsupported-binary-format",
+            Self::UnrecognizedNeededLibC => "unrecognized-needed-libc",
+            Self::NotFoundNeededLibC(_) => "not-found-needed-libc",
+            Self::NotFoundListedLibrary { .. } => "not-found-listed-library",
+            Self::UnpackUpx { .. } => "unpack-upx",
+            Self::UnpackKernel { .. } => "unpack-kernel",
+            Self::NotFoundVdso => "not-found-vdso",
+            Self::ExtractBundle { .. } => "extract-bundle",
+            Self::ExtractionLimit { .. } => "extraction-limit",
+            Self::AnnotateElf { .. } => "annotate-elf",
+            Self::PostReport { .. } => "post-report",
+            Self::Listen { .. } => "listen",
+            Self::Sandbox { .. } => "sandbox",
+            Self::Isolated { kind, .. } => kind,
+            Self::FromBytesWithNul(_) | Self::FromBytesUntilNul(_) => "invalid-string",
+            Self::Scroll(_) => "read-data",
+            Self::DynamicLoaderCache(_) => "dynamic-loader-cache",
+        }
+    }
+
     pub(crate) fn from_io1(
         source: std::io::Error,
         operation: &'static str,
@@ -0,0 +1,134 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Limits on the members extracted from archives, packed executables and bundles, which defend
+//! against decompression bombs.
+
+use std::path::Path;
+
+use log::warn;
+
+use crate::cmdline::Options;
+
+/// Limits on the members extracted from a container, e.g., an archive or a bundle.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExtractionLimits {
+    /// Maximum number of members.
+    pub(crate) members: u64,
+    /// Maximum size of each member, in bytes.
+    pub(crate) member_size: u64,
+    /// Maximum total size of the members, in bytes.
+    pub(crate) total_size: u64,
+    /// Maximum depth of nested directories.
+    pub(crate) depth: u32,
+}
+
+impl ExtractionLimits {
+    pub(crate) fn new(options: &Options) -> Self {
+        Self {
+            members: options.extract_max_members,
+            member_size: options.extract_max_member_size,
+            total_size: options.extract_max_total_size,
+            depth: options.extract_max_depth,
+        }
+    }
+}
+
+/// Why extracting a member is refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Refusal {
+    /// The member is skipped, while the following members might still be extracted.
+    SkipMember,
+    /// No more members are extracted from the container.
+    StopContainer,
+}
+
+/// Accounts for the members extracted from a container, and refuses members that exceed the
+/// extraction limits, with a diagnostic naming the option raising the exceeded limit.
+#[derive(Debug)]
+pub(crate) struct ExtractionBudget<'t> {
+    container: &'t Path,
+    limits: ExtractionLimits,
+    members: u64,
+    total_size: u64,
+}
+
+impl<'t> ExtractionBudget<'t> {
+    pub(crate) fn new(container: &'t Path, limits: ExtractionLimits) -> Self {
+        Self {
+            container,
+            limits,
+            members: 0,
+            total_size: 0,
+        }
+    }
+
+    /// Accounts for the member `name` of `size` bytes, unless it exceeds the extraction limits.
+    pub(crate) fn admit(&mut self, name: &str, size: u64) -> core::result::Result<(), Refusal> {
+        if self.members >= self.limits.members {
+            warn!(
+                "{}: Skipping member '{name}' and the following ones, as the container has more \
+                 than {} members ('--extract-max-members').",
+                self.container.display(),
+                self.limits.members
+            );
+            return Err(Refusal::StopContainer);
+        }
+
+        if size > self.limits.member_size {
+            warn!(
+                "{}: Skipping member '{name}' of {size} bytes, which exceeds the maximum member \
+                 size of {} bytes ('--extract-max-member-size').",
+                self.container.display(),
+                self.limits.member_size
+            );
+            return Err(Refusal::SkipMember);
+        }
+
+        let total_size = self.total_size.saturating_add(size);
+        if total_size > self.limits.total_size {
+            warn!(
+                "{}: Skipping member '{name}' and the following ones, as the extracted members \
+                 exceed the maximum total size of {} bytes ('--extract-max-total-size').",
+                self.container.display(),
+                self.limits.total_size
+            );
+            return Err(Refusal::StopContainer);
+        }
+
+        self.members += 1;
+        self.total_size = total_size;
+        Ok(())
+    }
+
+    /// Returns `true` if the directory `name` at the nesting `depth` can be entered.
+    pub(crate) fn admit_depth(&self, name: &str, depth: u32) -> bool {
+        let r = depth <= self.limits.depth;
+        if !r {
+            warn!(
+                "{}: Skipping directory '{name}', which is nested deeper than {} directories \
+                 ('--extract-max-depth').",
+                self.container.display(),
+                self.limits.depth
+            );
+        }
+        r
+    }
+
+    /// Returns `true` if a container declaring `members` members can be extracted at all.
+    pub(crate) fn admit_declared_members(&self, members: u64) -> bool {
+        let r = members <= self.limits.members;
+        if !r {
+            warn!(
+                "{}: Skipping container of {members} members, which exceeds the maximum of {} \
+                 members ('--extract-max-members').",
+                self.container.display(),
+                self.limits.members
+            );
+        }
+        r
+    }
+}
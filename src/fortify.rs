@@ -0,0 +1,204 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Checked versions of C runtime library functions, e.g., `__memcpy_chk`, which are called
+//! instead of their unchecked versions by code built with `-D_FORTIFY_SOURCE`.
+//!
+//! Binaries provide the names of the functions they import and export through
+//! [`SymbolSource`], so that the functions they call are classified the same way for all
+//! binary formats.
+
+use std::collections::HashSet;
+
+use log::warn;
+
+/// Source of the names of the functions imported and exported by a binary.
+pub(crate) trait SymbolSource {
+    /// Returns the names of the functions imported by the binary.
+    fn imported_functions(&self) -> Vec<&str>;
+
+    /// Returns the names of the functions exported by the binary.
+    fn exported_functions(&self) -> Vec<&str>;
+}
+
+#[derive(Debug, Eq, PartialEq, Hash)]
+struct CheckedFunction {
+    checked_name: String,
+}
+
+impl CheckedFunction {
+    fn from_checked_name(checked_name: &str) -> Self {
+        Self {
+            checked_name: String::from(checked_name),
+        }
+    }
+
+    fn from_unchecked_name(unchecked_name: &str) -> Self {
+        Self {
+            checked_name: format!("__{unchecked_name}_chk"),
+        }
+    }
+
+    fn get_unchecked_name(&self) -> &str {
+        &self.checked_name[2..self.checked_name.len() - 4]
+    }
+}
+
+/// Functions whose checked versions are exported by a C runtime library.
+#[derive(Debug, Default)]
+pub(crate) struct CheckedFunctions(HashSet<CheckedFunction>);
+
+impl CheckedFunctions {
+    /// Returns the functions with the given unchecked names, e.g., as specified by a standard.
+    pub(crate) fn from_unchecked_names(unchecked_names: &[&str]) -> Self {
+        Self(
+            unchecked_names
+                .iter()
+                .map(|name| CheckedFunction::from_unchecked_name(name))
+                .collect(),
+        )
+    }
+
+    /// Returns the checked functions exported by a C runtime library.
+    pub(crate) fn exported_by(library: &impl SymbolSource) -> Self {
+        Self(
+            library
+                .exported_functions()
+                .into_iter()
+                .filter(|name| function_is_checked_version(name))
+                .map(CheckedFunction::from_checked_name)
+                .collect(),
+        )
+    }
+
+    /// Returns the unchecked names of the functions, separated by spaces, or `(none)`.
+    pub(crate) fn to_text(&self) -> String {
+        let names: Vec<&str> = self
+            .0
+            .iter()
+            .map(CheckedFunction::get_unchecked_name)
+            .collect();
+        if names.is_empty() {
+            "(none)".into()
+        } else {
+            names.join(" ")
+        }
+    }
+
+    /// Returns the unchecked name of the function whose checked version is `checked_name`, if
+    /// it is exported.
+    fn unchecked_name_of(&self, checked_name: &str) -> Option<&str> {
+        self.0
+            .get(&CheckedFunction::from_checked_name(checked_name))
+            .map(CheckedFunction::get_unchecked_name)
+    }
+
+    /// Returns `unchecked_name` if the checked version of that function is exported.
+    fn has_checked_version_of(&self, unchecked_name: &str) -> Option<&str> {
+        self.0
+            .get(&CheckedFunction::from_unchecked_name(unchecked_name))
+            .map(CheckedFunction::get_unchecked_name)
+    }
+}
+
+/// Returns the functions called by a binary through their checked versions, followed by those
+/// called through their unchecked versions although `library` exports checked versions of them.
+pub(crate) fn functions_by_protection<'t>(
+    binary: &impl SymbolSource,
+    library: &'t CheckedFunctions,
+) -> (HashSet<&'t str>, HashSet<&'t str>) {
+    let mut protected_functions = HashSet::<&str>::default();
+    let mut unprotected_functions = HashSet::<&str>::default();
+
+    for imported_function in binary.imported_functions() {
+        if function_is_checked_version(imported_function) {
+            if let Some(unchecked_function) = library.unchecked_name_of(imported_function) {
+                protected_functions.insert(unchecked_function);
+            } else {
+                warn!(
+                    "Checked function '{}' is not exported by the C runtime library. This might indicate a C runtime mismatch.",
+                    imported_function
+                );
+            }
+        } else if let Some(unchecked_function) = library.has_checked_version_of(imported_function) {
+            unprotected_functions.insert(unchecked_function);
+        }
+    }
+
+    (protected_functions, unprotected_functions)
+}
+
+/// [Functions prefixed by `__` and suffixed by `_chk`](http://refspecs.linux-foundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/libc.html).
+fn function_is_checked_version(name: &str) -> bool {
+    name.starts_with("__") && name.ends_with("_chk")
+}
+
+/// - [LSB 4.0.0](http://refspecs.linux-foundation.org/LSB_4.0.0/LSB-Core-generic/LSB-Core-generic/libc.html).
+/// - [LSB 4.1.0](http://refspecs.linux-foundation.org/LSB_4.1.0/LSB-Core-generic/LSB-Core-generic/libc.html).
+/// - [LSB 5.0.0](http://refspecs.linux-foundation.org/LSB_5.0.0/LSB-Core-generic/LSB-Core-generic/libc.html).
+pub(crate) static LSB_4_0_0_FUNCTIONS_WITH_CHECKED_VERSIONS: &[&str] = &[
+    "confstr",
+    "fgets",
+    "fgets_unlocked",
+    "fgetws",
+    "fgetws_unlocked",
+    "fprintf",
+    "fwprintf",
+    "getcwd",
+    "getgroups",
+    "gethostname",
+    "getlogin_r",
+    "mbsnrtowcs",
+    "mbsrtowcs",
+    "mbstowcs",
+    "memcpy",
+    "memmove",
+    "mempcpy",
+    "memset",
+    "pread64",
+    "pread",
+    "printf",
+    "read",
+    "readlink",
+    "realpath",
+    "recv",
+    "recvfrom",
+    "snprintf",
+    "sprintf",
+    "stpcpy",
+    "stpncpy",
+    "strcat",
+    "strcpy",
+    "strncat",
+    "strncpy",
+    "swprintf",
+    "syslog",
+    "ttyname_r",
+    "vfprintf",
+    "vfwprintf",
+    "vprintf",
+    "vsnprintf",
+    "vsprintf",
+    "vswprintf",
+    "vsyslog",
+    "vwprintf",
+    "wcpcpy",
+    "wcpncpy",
+    "wcrtomb",
+    "wcscat",
+    "wcscpy",
+    "wcsncat",
+    "wcsncpy",
+    "wcsnrtombs",
+    "wcsrtombs",
+    "wcstombs",
+    "wctomb",
+    "wmemcpy",
+    "wmemmove",
+    "wmempcpy",
+    "wmemset",
+    "wprintf",
+];
@@ -0,0 +1,207 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Inventory of input files by kind, performed by `--list-only` to scope an audit before
+//! analyzing files.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use serde::Serialize;
+
+use crate::cmdline::OutputFormat;
+use crate::elf;
+use crate::errors::{Error, Result};
+use crate::parser::BinaryFile;
+use crate::{efi, kernel, legacy};
+
+/// Kind of a file, as far as its analysis is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum FileKind {
+    ElfExecutable,
+    ElfSharedLibrary,
+    /// Relocatable object files, core dumps, and other ELF files.
+    ElfOther,
+    PE,
+    /// DOS, NE, LE and LX executables.
+    Legacy,
+    /// TE images, and UEFI capsules and firmware volumes.
+    EFI,
+    /// Linux kernel images: `vmlinux` and `bzImage` files.
+    Kernel,
+    Archive,
+    MachO,
+    Unknown,
+}
+
+impl FileKind {
+    /// Returns the keyword identifying this kind in structured reports.
+    fn keyword(self) -> &'static str {
+        match self {
+            Self::ElfExecutable => "elf-executable",
+            Self::ElfSharedLibrary => "elf-shared-library",
+            Self::ElfOther => "elf-other",
+            Self::PE => "pe",
+            Self::Legacy => "legacy",
+            Self::EFI => "efi",
+            Self::Kernel => "kernel",
+            Self::Archive => "archive",
+            Self::MachO => "mach-o",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::ElfExecutable => "ELF executables",
+            Self::ElfSharedLibrary => "ELF shared libraries",
+            Self::ElfOther => "Other ELF files",
+            Self::PE => "PE images",
+            Self::Legacy => "Legacy DOS, NE, LE and LX executables",
+            Self::EFI => "TE images, UEFI capsules and firmware volumes",
+            Self::Kernel => "Linux kernel images",
+            Self::Archive => "Archives",
+            Self::MachO => "Mach-O files",
+            Self::Unknown => "Unknown files",
+        }
+    }
+}
+
+/// Returns the kind of the file at `path`, without checking its security features.
+///
+/// Files that cannot be parsed are of an unknown kind.
+pub(crate) fn classify_file(path: &Path) -> Result<FileKind> {
+    use goblin::Object;
+
+    let file = BinaryFile::open(path)?;
+    let parser = match file.parse() {
+        Ok(parser) => parser,
+
+        Err(Error::ParseFile { source }) => {
+            if legacy::file_format(path).is_some() {
+                return Ok(FileKind::Legacy);
+            }
+
+            debug!("Failed to parse '{}': {source}.", path.display());
+            return Ok(FileKind::Unknown);
+        }
+
+        Err(error) => return Err(error),
+    };
+
+    let kind = match parser.object() {
+        Object::Elf(elf) if kernel::is_vmlinux(elf) => FileKind::Kernel,
+        Object::PE(_) | Object::Unknown(_) if kernel::is_bzimage(parser.bytes()) => {
+            FileKind::Kernel
+        }
+
+        Object::Elf(elf) => match elf.header.e_type {
+            goblin::elf::header::ET_DYN if elf::is_shared_library(elf) => {
+                FileKind::ElfSharedLibrary
+            }
+
+            // Position-independent executables are of type `ET_DYN`.
+            goblin::elf::header::ET_EXEC | goblin::elf::header::ET_DYN => FileKind::ElfExecutable,
+            _ => FileKind::ElfOther,
+        },
+
+        Object::PE(_pe) => FileKind::PE,
+        Object::Archive(_archive) => FileKind::Archive,
+        Object::Mach(_mach) => FileKind::MachO,
+        Object::Unknown(_magic)
+            if efi::is_te_image(parser.bytes()) || efi::is_container(parser.bytes()) =>
+        {
+            FileKind::EFI
+        }
+        _ => FileKind::Unknown,
+    };
+    Ok(kind)
+}
+
+#[derive(Serialize)]
+struct Inventory {
+    /// Number of files of each kind, identified by its keyword.
+    files: BTreeMap<&'static str, usize>,
+    total: usize,
+    /// Number of files that could not be read.
+    errors: usize,
+}
+
+/// Prints the number of files of each kind, followed by their total.
+pub(crate) fn print_counts(
+    kinds: &[(PathBuf, FileKind)],
+    errors: &[(PathBuf, Error)],
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let mut counts = BTreeMap::new();
+    for &(_, kind) in kinds {
+        *counts.entry(kind).or_insert(0_usize) += 1;
+    }
+
+    let mut out = io::stdout().lock();
+    match options.format {
+        OutputFormat::Text | OutputFormat::GitHubAnnotations => {
+            write_text(&mut out, &counts, kinds.len())
+        }
+
+        OutputFormat::Json => {
+            let inventory = Inventory {
+                files: counts
+                    .iter()
+                    .map(|(kind, &count)| (kind.keyword(), count))
+                    .collect(),
+                total: kinds.len(),
+                errors: errors.len(),
+            };
+
+            serde_json::to_writer_pretty(&mut out, &inventory)
+                .map_err(io::Error::from)
+                .and_then(|()| writeln!(out))
+        }
+
+        OutputFormat::Prometheus => write_prometheus(&mut out, &counts, errors.len()),
+    }
+    .map_err(|r| Error::from_io1(r, "write", "standard output stream"))
+}
+
+fn write_text(
+    out: &mut impl Write,
+    counts: &BTreeMap<FileKind, usize>,
+    total: usize,
+) -> io::Result<()> {
+    for (kind, count) in counts {
+        writeln!(out, "{}: {count}", kind.description())?;
+    }
+    writeln!(out, "Total: {total}")
+}
+
+fn write_prometheus(
+    out: &mut impl Write,
+    counts: &BTreeMap<FileKind, usize>,
+    error_count: usize,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "# HELP bsc_inventory_files Number of files of each kind."
+    )?;
+    writeln!(out, "# TYPE bsc_inventory_files gauge")?;
+    for (kind, count) in counts {
+        writeln!(
+            out,
+            "bsc_inventory_files{{kind=\"{}\"}} {count}",
+            kind.keyword()
+        )?;
+    }
+
+    writeln!(
+        out,
+        "# HELP bsc_failed_files Number of files that could not be read."
+    )?;
+    writeln!(out, "# TYPE bsc_failed_files gauge")?;
+    writeln!(out, "bsc_failed_files {error_count}")
+}
@@ -0,0 +1,346 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Analysis of files in separate worker processes, requested by `--isolate`, so that a file
+//! crafted to crash or exhaust the analysis only ends the worker analyzing it.
+//!
+//! Each worker runs this executable with the arguments of the analysis, preceded by
+//! `--isolated-worker FILE`. The worker analyzes `FILE`, then writes its results to its standard
+//! output stream as a JSON document, from which the results are reconstructed.
+
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+use crate::options::status::{CheckResult, DisplayInColorTerm, SupportLevel};
+use crate::report::{Architecture, BinaryFormat, FileResults};
+
+/// Kind of the error reported for files whose worker process failed.
+const WORKER_FAILED: &str = "isolated-worker";
+
+/// Results written by a worker process.
+#[derive(Serialize, Deserialize)]
+enum WorkerOutput {
+    /// Results of the file and of its payloads.
+    Analyzed(Vec<(PathBuf, WorkerFileResults)>),
+    Failed {
+        kind: String,
+        message: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorkerFileResults {
+    format: BinaryFormat,
+    architecture: Option<Architecture>,
+    checks: Vec<RecordedStatus>,
+    libc: Option<String>,
+    duration: Option<Duration>,
+}
+
+/// Result of a check, recorded as displayed by the worker process.
+#[derive(Serialize, Deserialize)]
+struct RecordedStatus {
+    name: String,
+    level: Option<SupportLevel>,
+    not_applicable_reason: Option<String>,
+    segments: Vec<Segment>,
+    duration: Option<Duration>,
+}
+
+/// Text displayed in the same color.
+#[derive(Serialize, Deserialize)]
+struct Segment {
+    color: Option<String>,
+    text: String,
+}
+
+/// Check result reconstructed from the results of a worker process.
+pub(crate) struct ReplayedStatus {
+    name: &'static str,
+    level: Option<SupportLevel>,
+    not_applicable_reason: Option<String>,
+    segments: Vec<(Option<termcolor::Color>, String)>,
+}
+
+impl DisplayInColorTerm for ReplayedStatus {
+    fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+        let set_color_err = |r| Error::from_io1(r, "set color", "standard output stream");
+
+        for (color, text) in &self.segments {
+            match color {
+                Some(color) => wc
+                    .set_color(termcolor::ColorSpec::new().set_fg(Some(*color)))
+                    .map_err(set_color_err)?,
+
+                None => wc.reset().map_err(set_color_err)?,
+            }
+            write!(wc, "{text}")
+                .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+        }
+        wc.reset().map_err(set_color_err)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        self.level
+    }
+
+    fn not_applicable_reason(&self) -> Option<&str> {
+        self.not_applicable_reason.as_deref()
+    }
+}
+
+/// Records the text written by a check result, along with its colors.
+#[derive(Default)]
+struct Recorder {
+    color: Option<termcolor::Color>,
+    segments: Vec<Segment>,
+}
+
+impl io::Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let color = self.color.map(|color| format!("{color:?}").to_lowercase());
+
+        match self.segments.last_mut() {
+            Some(segment) if segment.color == color => segment.text.push_str(&text),
+            _ => self.segments.push(Segment {
+                color,
+                text: text.into_owned(),
+            }),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl termcolor::WriteColor for Recorder {
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn set_color(&mut self, spec: &termcolor::ColorSpec) -> io::Result<()> {
+        self.color = spec.fg().copied();
+        Ok(())
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.color = None;
+        Ok(())
+    }
+}
+
+/// Returns a static string equal to `text`, allocating it only the first time it is requested,
+/// as check names and error kinds are few.
+fn intern(text: &str) -> &'static str {
+    static STRINGS: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+    let mut strings = STRINGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let strings = strings.get_or_insert_with(HashSet::default);
+    if let Some(&r) = strings.get(text) {
+        r
+    } else {
+        let r: &'static str = Box::leak(text.into());
+        strings.insert(r);
+        r
+    }
+}
+
+/// Analyzes the file at `path`, and its payloads, in a worker process limited by
+/// `--isolate-max-memory` and `--isolate-max-cpu`.
+///
+/// Workers that terminate without writing results are reported as errors of the file.
+pub(crate) fn analyze_file_and_payload(
+    path: &Path,
+    options: &crate::cmdline::Options,
+) -> Result<Vec<(PathBuf, FileResults)>> {
+    let executable =
+        env::current_exe().map_err(|r| Error::from_io1(r, "locate", "current executable"))?;
+
+    let mut arguments = vec![OsString::from("--isolated-worker"), path.into()];
+    arguments.extend(env::args_os().skip(1));
+
+    let mut command = Command::new(&executable);
+    command
+        .args(arguments)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    limit_resources(&mut command, options);
+
+    let output = command
+        .output()
+        .map_err(|r| Error::from_io1(r, "run worker process for", path))?;
+
+    match serde_json::from_slice::<WorkerOutput>(&output.stdout) {
+        Ok(WorkerOutput::Analyzed(results)) => Ok(results
+            .into_iter()
+            .map(|(path, results)| (path, replay(results)))
+            .collect()),
+
+        Ok(WorkerOutput::Failed { kind, message }) => Err(Error::Isolated {
+            kind: intern(&kind),
+            message,
+        }),
+
+        Err(_r) => Err(Error::Isolated {
+            kind: WORKER_FAILED,
+            message: format!(
+                "worker process analyzing '{}' failed ({}), possibly by exceeding \
+                 '--isolate-max-memory' or '--isolate-max-cpu'",
+                path.display(),
+                output.status
+            ),
+        }),
+    }
+}
+
+fn replay(results: WorkerFileResults) -> FileResults {
+    let checks = results
+        .checks
+        .into_iter()
+        .map(|check| {
+            let segments = check
+                .segments
+                .into_iter()
+                .map(|segment| {
+                    let color = segment.color.and_then(|color| color.parse().ok());
+                    (color, segment.text)
+                })
+                .collect();
+
+            let result = CheckResult::from(ReplayedStatus {
+                name: intern(&check.name),
+                level: check.level,
+                not_applicable_reason: check.not_applicable_reason,
+                segments,
+            });
+
+            match check.duration {
+                Some(duration) => result.with_duration(duration),
+                None => result,
+            }
+        })
+        .collect();
+
+    FileResults {
+        format: results.format,
+        architecture: results.architecture,
+        checks,
+        libc: results.libc,
+        duration: results.duration,
+    }
+}
+
+/// Limits the address space and the processor time of the worker process.
+#[cfg(unix)]
+fn limit_resources(command: &mut Command, options: &crate::cmdline::Options) {
+    use std::os::unix::process::CommandExt;
+
+    let max_memory = options.isolate_max_memory;
+    let max_cpu = options.isolate_max_cpu;
+
+    let set_limit = |resource, soft: u64, hard: u64| {
+        let limit = libc::rlimit {
+            rlim_cur: soft,
+            rlim_max: hard,
+        };
+
+        // SAFETY: `setrlimit` is async-signal-safe, and `limit` is valid.
+        if unsafe { libc::setrlimit(resource, &raw const limit) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    };
+
+    // SAFETY: The closure only calls async-signal-safe functions, and does not allocate.
+    unsafe {
+        command.pre_exec(move || {
+            set_limit(libc::RLIMIT_AS, max_memory, max_memory)?;
+            // The worker is killed shortly after it is signaled that it exceeded its time.
+            set_limit(libc::RLIMIT_CPU, max_cpu, max_cpu.saturating_add(1))
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn limit_resources(_command: &mut Command, _options: &crate::cmdline::Options) {}
+
+/// Runs the worker process analyzing the file at `path`, and writes its results to the standard
+/// output stream.
+pub(crate) fn run_worker(path: &Path, options: &crate::cmdline::Options) -> ExitCode {
+    let context = AnalysisContext::new(options);
+
+    let output = match crate::analyze_file_and_payload(path, &context) {
+        Ok(results) => results
+            .into_iter()
+            .map(|(path, results)| Ok((path, record(&results)?)))
+            .collect::<Result<Vec<_>>>()
+            .map_or_else(|error| failed(&error), WorkerOutput::Analyzed),
+
+        Err(error) => failed(&error),
+    };
+
+    let mut out = io::stdout().lock();
+    if serde_json::to_writer(&mut out, &output).is_ok() && out.flush().is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn failed(error: &Error) -> WorkerOutput {
+    WorkerOutput::Failed {
+        kind: error.kind().into(),
+        message: crate::format_error(error).trim_end_matches('.').to_owned(),
+    }
+}
+
+fn record(results: &FileResults) -> Result<WorkerFileResults> {
+    let checks = results
+        .checks
+        .iter()
+        .map(|check| {
+            let mut recorder = Recorder::default();
+            check.display_in_color_term(&mut recorder)?;
+
+            Ok(RecordedStatus {
+                name: check.name().into(),
+                level: check.level(),
+                not_applicable_reason: check.not_applicable_reason().map(str::to_owned),
+                segments: recorder.segments,
+                duration: check.duration(),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(WorkerFileResults {
+        format: results.format,
+        architecture: results.architecture.clone(),
+        checks,
+        libc: results.libc.clone(),
+        duration: results.duration,
+    })
+}
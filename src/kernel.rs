@@ -0,0 +1,477 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Linux kernel images: `vmlinux` ELF files, `bzImage` files started by boot loaders, and the
+//! vDSO mapped by the running kernel into processes.
+//!
+//! The kernel is not loaded by the dynamic loader, so the checks of user space executables do
+//! not apply to `vmlinux` files. Instead, they are checked for the evidence that the kernel can
+//! be relocated at boot time, and for sections that are both writable and executable.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use goblin::elf::header::{ET_DYN, ET_REL};
+use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_REL, SHT_RELA};
+use log::debug;
+use scroll::{Pread, LE};
+
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+use crate::extract::ExtractionLimits;
+use crate::options::status::{CheckResult, DetailedStatus, InformationalStatus, SupportLevel};
+use crate::parser::{BinaryFile, BinaryParser};
+use crate::report::{Architecture, Endianness, FileResults};
+
+/// Keyword of the result describing a kernel image.
+pub(crate) const KERNEL: &str = "KERNEL";
+
+/// Path under which the vDSO of the running kernel is reported.
+pub(crate) const VDSO_PATH: &str = "[vdso]";
+
+/// Offsets of the fields of the header of the x86 boot protocol, in `bzImage` files.
+const SETUP_SECTS_OFFSET: usize = 0x1f1;
+const BOOT_FLAG_OFFSET: usize = 0x1fe;
+const HEADER_OFFSET: usize = 0x202;
+const VERSION_OFFSET: usize = 0x206;
+const RELOCATABLE_KERNEL_OFFSET: usize = 0x234;
+const XLOADFLAGS_OFFSET: usize = 0x236;
+const PAYLOAD_OFFSET_OFFSET: usize = 0x248;
+const PAYLOAD_LENGTH_OFFSET: usize = 0x24c;
+
+const BOOT_FLAG: u16 = 0xaa55;
+const HEADER_SIGNATURE: &[u8] = b"HdrS";
+const XLF_KERNEL_64: u16 = 0x0001;
+
+/// Versions of the boot protocol introducing `relocatable_kernel`, `xloadflags` and the location
+/// of the compressed payload.
+const RELOCATABLE_KERNEL_VERSION: u16 = 0x0205;
+const XLOADFLAGS_VERSION: u16 = 0x020c;
+const PAYLOAD_VERSION: u16 = 0x0208;
+
+/// Symbols of the randomization of the kernel base address (`CONFIG_RANDOMIZE_BASE`) and of its
+/// memory regions (`CONFIG_RANDOMIZE_MEMORY`).
+static KASLR_SYMBOLS: &[&str] = &["kaslr_early_init", "kaslr_init", "kernel_randomize_memory"];
+
+/// Fields of the header of the x86 boot protocol.
+struct BootHeader {
+    version: u16,
+    /// Whether the protected-mode kernel can be loaded at any address, if known.
+    relocatable: Option<bool>,
+    is_64: bool,
+    /// Offset and length of the compressed `vmlinux`, if known.
+    payload: Option<(usize, usize)>,
+}
+
+fn boot_header(bytes: &[u8]) -> Option<BootHeader> {
+    if bytes.pread_with::<u16>(BOOT_FLAG_OFFSET, LE).ok()? != BOOT_FLAG
+        || bytes.get(HEADER_OFFSET..HEADER_OFFSET + HEADER_SIGNATURE.len())? != HEADER_SIGNATURE
+    {
+        return None;
+    }
+
+    let version: u16 = bytes.pread_with(VERSION_OFFSET, LE).ok()?;
+
+    let relocatable = (version >= RELOCATABLE_KERNEL_VERSION)
+        .then(|| bytes.get(RELOCATABLE_KERNEL_OFFSET).map(|&b| b != 0))
+        .flatten();
+
+    let is_64 = version >= XLOADFLAGS_VERSION
+        && bytes
+            .pread_with::<u16>(XLOADFLAGS_OFFSET, LE)
+            .is_ok_and(|flags| (flags & XLF_KERNEL_64) != 0);
+
+    let payload = (version >= PAYLOAD_VERSION)
+        .then(|| {
+            // The protected-mode kernel follows the boot sector and the setup sectors.
+            let setup_sects = match bytes.get(SETUP_SECTS_OFFSET)? {
+                0 => 4,
+                &n => usize::from(n),
+            };
+            let offset: u32 = bytes.pread_with(PAYLOAD_OFFSET_OFFSET, LE).ok()?;
+            let length: u32 = bytes.pread_with(PAYLOAD_LENGTH_OFFSET, LE).ok()?;
+            let offset = (setup_sects + 1) * 512 + usize::try_from(offset).ok()?;
+            let length = usize::try_from(length).ok()?;
+
+            (offset.checked_add(length)? <= bytes.len()).then_some((offset, length))
+        })
+        .flatten();
+
+    Some(BootHeader {
+        version,
+        relocatable,
+        is_64,
+        payload,
+    })
+}
+
+/// Returns `true` if `bytes` is a `bzImage`, i.e., a kernel started by boot loaders through the
+/// x86 boot protocol.
+///
+/// Kernels including the EFI stub are also PE images, yet they are analyzed as kernels.
+pub(crate) fn is_bzimage(bytes: &[u8]) -> bool {
+    boot_header(bytes).is_some()
+}
+
+/// Returns `true` if `elf` is a `vmlinux` file, i.e., a kernel that is not compressed.
+///
+/// Kernels have initialization code that is freed once they start, and tables of the
+/// instructions allowed to fault when accessing user memory. Loadable modules have them too,
+/// but they are relocatable object files.
+pub(crate) fn is_vmlinux(elf: &goblin::elf::Elf) -> bool {
+    let has_section = |name: &str| {
+        elf.section_headers
+            .iter()
+            .any(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(name))
+    };
+
+    elf.header.e_type != ET_REL
+        && elf.interpreter.is_none()
+        && elf.libraries.is_empty()
+        && has_section(".init.text")
+        && (has_section("__ex_table") || has_section("__ksymtab_strings"))
+}
+
+/// Compression of the `vmlinux` of a `bzImage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Bzip2,
+    Lzma,
+    Xz,
+    Lzo,
+    Lz4,
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression of `payload`, identified by its signature.
+    fn of(payload: &[u8]) -> Option<Self> {
+        [
+            (&[0x1f_u8, 0x8b][..], Self::Gzip),
+            (b"BZh", Self::Bzip2),
+            (&[0x5d, 0x00, 0x00], Self::Lzma),
+            (&[0xfd, b'7', b'z', b'X', b'Z', 0x00], Self::Xz),
+            (&[0x89, b'L', b'Z', b'O'], Self::Lzo),
+            (&[0x02, 0x21, 0x4c, 0x18], Self::Lz4),
+            (&[0x28, 0xb5, 0x2f, 0xfd], Self::Zstd),
+        ]
+        .into_iter()
+        .find_map(|(signature, compression)| payload.starts_with(signature).then_some(compression))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Bzip2 => "bzip2",
+            Self::Lzma => "lzma",
+            Self::Xz => "xz",
+            Self::Lzo => "lzo",
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the program decompressing the standard input stream to the standard output
+    /// stream, and its arguments.
+    fn decompressor(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Self::Gzip => ("gzip", &["-d", "-c"]),
+            Self::Bzip2 => ("bzip2", &["-d", "-c"]),
+            Self::Lzma => ("xz", &["-d", "-c", "--format=lzma"]),
+            Self::Xz => ("xz", &["-d", "-c"]),
+            Self::Lzo => ("lzop", &["-d", "-c"]),
+            Self::Lz4 => ("lz4", &["-d", "-c"]),
+            Self::Zstd => ("zstd", &["-d", "-c"]),
+        }
+    }
+}
+
+/// Returns the architecture targeted by the `bzImage` file, and the results of its analysis.
+///
+/// - `KERNEL` names the boot protocol version and the compression of `vmlinux`, e.g.,
+///   `*KERNEL(bzimage,protocol=2.15,payload=zstd)`.
+/// - `ASLR` is supported if boot loaders can load the kernel at any address, which is needed to
+///   randomize its address.
+pub(crate) fn analyze_bzimage(bytes: &[u8]) -> Result<(Architecture, Vec<CheckResult>)> {
+    let header = boot_header(bytes).ok_or_else(|| Error::ParseFile {
+        source: goblin::error::Error::Malformed("boot protocol header is truncated".into()),
+    })?;
+
+    let mut details = vec![
+        "bzimage".to_owned(),
+        format!(
+            "protocol={}.{:02}",
+            header.version >> 8,
+            header.version & 0xff
+        ),
+    ];
+    if let Some(compression) = header
+        .payload
+        .and_then(|(offset, length)| Compression::of(&bytes[offset..offset + length]))
+    {
+        details.push(format!("payload={}", compression.name()));
+    }
+
+    let relocatable = match header.relocatable {
+        Some(true) => {
+            DetailedStatus::new("ASLR", SupportLevel::Supported, vec!["relocatable".into()])
+        }
+        Some(false) => DetailedStatus::new("ASLR", SupportLevel::Unsupported, Vec::default()),
+        None => DetailedStatus::unknown("ASLR"),
+    };
+
+    let architecture = Architecture {
+        name: if header.is_64 { "x86_64" } else { "x86" }.into(),
+        bits: if header.is_64 { 64 } else { 32 },
+        endianness: Endianness::Little,
+    };
+
+    Ok((
+        architecture,
+        vec![
+            InformationalStatus::new(KERNEL, details).into(),
+            relocatable.into(),
+        ],
+    ))
+}
+
+/// Returns the results of the analysis of the `vmlinux` file.
+///
+/// - `KERNEL` reports `vmlinux`.
+/// - `ASLR` is supported if the kernel can be relocated at boot time (`CONFIG_RELOCATABLE`),
+///   i.e., if it is position-independent (`pie`), if its relocations were kept by the linker
+///   (`emitted-relocations`), or if it randomizes its addresses (`kaslr`).
+/// - `DATA-EXEC-PREVENT` is supported if no section is both writable and executable.
+pub(crate) fn analyze_vmlinux(elf: &goblin::elf::Elf) -> Vec<CheckResult> {
+    let section_name = |section: &goblin::elf::SectionHeader| {
+        elf.shdr_strtab.get_at(section.sh_name).unwrap_or_default()
+    };
+
+    let mut evidence = Vec::default();
+    if elf.header.e_type == ET_DYN {
+        evidence.push("pie".to_owned());
+    }
+    if elf.section_headers.iter().any(|section| {
+        matches!(section.sh_type, SHT_REL | SHT_RELA)
+            && (section.sh_flags & u64::from(SHF_ALLOC)) == 0
+    }) {
+        evidence.push("emitted-relocations".into());
+    }
+    // Randomizing the kernel addresses requires relocating it.
+    if elf
+        .syms
+        .iter()
+        .filter_map(|symbol| elf.strtab.get_at(symbol.st_name))
+        .any(|name| KASLR_SYMBOLS.contains(&name))
+    {
+        evidence.push("kaslr".into());
+    }
+    let level = if evidence.is_empty() {
+        SupportLevel::Unsupported
+    } else {
+        SupportLevel::Supported
+    };
+
+    let writable_executable: Vec<_> = elf
+        .section_headers
+        .iter()
+        .filter(|section| {
+            let flags = u64::from(SHF_ALLOC | SHF_WRITE | SHF_EXECINSTR);
+            (section.sh_flags & flags) == flags
+        })
+        .map(section_name)
+        .collect();
+
+    let data_exec_prevent = if writable_executable.is_empty() {
+        DetailedStatus::new("DATA-EXEC-PREVENT", SupportLevel::Supported, Vec::default())
+    } else {
+        DetailedStatus::new(
+            "DATA-EXEC-PREVENT",
+            SupportLevel::Unsupported,
+            vec![format!(
+                "writable-executable={}",
+                writable_executable.join("+")
+            )],
+        )
+    };
+
+    vec![
+        InformationalStatus::new(KERNEL, vec!["vmlinux".into()]).into(),
+        DetailedStatus::new("ASLR", level, evidence).into(),
+        data_exec_prevent.into(),
+    ]
+}
+
+/// Returns the path under which the `vmlinux` of the `bzImage` at `path` is reported, e.g.,
+/// `bzImage#vmlinux`.
+pub(crate) fn payload_path(path: &Path) -> PathBuf {
+    let mut payload_path = OsString::from(path.as_os_str());
+    payload_path.push("#vmlinux");
+    payload_path.into()
+}
+
+/// Decompresses the `vmlinux` of the `bzImage` at `path`, then analyzes it. Returns `None` if the
+/// kernel at `path` is not a `bzImage`.
+///
+/// The decompressor is chosen after the signature of the payload, and it is run with the
+/// payload as its standard input stream. `vmlinux` is written to a temporary file, which is
+/// removed once it is analyzed. Payloads whose decompressed size exceeds
+/// `--extract-max-member-size` are not decompressed.
+pub(crate) fn analyze_payload(
+    path: &Path,
+    context: &AnalysisContext,
+) -> Result<Option<FileResults>> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let file = BinaryFile::open(path)?;
+    let bytes = file.parse()?.bytes();
+    let Some(header) = boot_header(bytes) else {
+        return Ok(None);
+    };
+
+    let (offset, length) = header.payload.ok_or_else(|| Error::UnpackKernel {
+        path: path.into(),
+        message: "the location of the compressed kernel is unknown".into(),
+    })?;
+    let payload = &bytes[offset..offset + length];
+
+    let compression = Compression::of(payload).ok_or_else(|| Error::UnpackKernel {
+        path: path.into(),
+        message: "the compression of the kernel is not recognized".into(),
+    })?;
+
+    // The size of `vmlinux` is appended to the payload, after the compressed stream.
+    let size_offset = payload.len().saturating_sub(4);
+    let size: u32 = payload.pread_with(size_offset, LE).unwrap_or_default();
+    if u64::from(size) > ExtractionLimits::new(context.options).member_size {
+        return Err(Error::ExtractionLimit {
+            path: payload_path(path),
+            size: size.into(),
+            option: "--extract-max-member-size",
+        });
+    }
+
+    // The size is part of the `gzip` stream, but it follows the other streams.
+    let stream = if compression == Compression::Gzip {
+        payload
+    } else {
+        &payload[..size_offset]
+    };
+
+    let unpacked_path = env::temp_dir().join(format!(
+        "binary-security-check-kernel-{}-{}",
+        process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let r = decompress(path, compression, stream, &unpacked_path)
+        .and_then(|()| crate::analyze_file(&unpacked_path, context));
+
+    let _ignored = fs::remove_file(&unpacked_path);
+    r.map(Some)
+}
+
+fn decompress(
+    path: &Path,
+    compression: Compression,
+    stream: &[u8],
+    unpacked_path: &Path,
+) -> Result<()> {
+    let (program, args) = compression.decompressor();
+    debug!(
+        "Decompressing the kernel of '{}' to '{}' with '{program}'.",
+        path.display(),
+        unpacked_path.display()
+    );
+
+    let output = fs::File::create(unpacked_path)
+        .map_err(|r| Error::from_io1(r, "create file", unpacked_path))?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(output)
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|r| Error::from_io1(r, "run decompressor on", path))?;
+
+    // The decompressor might exit before reading all its input, e.g., on errors, which are then
+    // reported by its exit status.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ignored = stdin.write_all(stream);
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|r| Error::from_io1(r, "run decompressor on", path))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::UnpackKernel {
+            path: path.into(),
+            message: format!(
+                "'{program}' failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        })
+    }
+}
+
+/// Returns the address range of the vDSO mapped into this process, as listed by
+/// `/proc/self/maps`.
+fn vdso_range() -> Result<(u64, u64)> {
+    let maps_path = Path::new("/proc/self/maps");
+    let maps =
+        fs::read_to_string(maps_path).map_err(|r| Error::from_io1(r, "read file", maps_path))?;
+
+    maps.lines()
+        .filter(|line| line.split_whitespace().nth(5) == Some(VDSO_PATH))
+        .find_map(|line| {
+            let (start, end) = line.split_whitespace().next()?.split_once('-')?;
+            let start = u64::from_str_radix(start, 16).ok()?;
+            let end = u64::from_str_radix(end, 16).ok()?;
+            (start < end).then_some((start, end))
+        })
+        .ok_or(Error::NotFoundVdso)
+}
+
+/// Analyzes the vDSO that the running kernel maps into processes, as an `ELF` shared library.
+///
+/// The vDSO is read from the memory of this process, and it is reported as `[vdso]`. It calls
+/// no functions of the C runtime library, so `FORTIFY-SOURCE` is not checked.
+pub(crate) fn analyze_vdso(options: &crate::cmdline::Options) -> Result<FileResults> {
+    let context = &AnalysisContext::for_vdso(options);
+    let start_time = context.options.timings.then(Instant::now);
+    let (start, end) = vdso_range()?;
+
+    let mem_path = Path::new("/proc/self/mem");
+    let mut mem =
+        fs::File::open(mem_path).map_err(|r| Error::from_io1(r, "open file", mem_path))?;
+
+    let size = usize::try_from(end - start).map_err(|_r| Error::NotFoundVdso)?;
+    let mut bytes = vec![0_u8; size];
+    mem.seek(SeekFrom::Start(start))
+        .and_then(|_position| mem.read_exact(&mut bytes))
+        .map_err(|r| Error::from_io1(r, "read file", mem_path))?;
+
+    // The mapping is padded to the end of memory pages, which is not part of the vDSO.
+    let path = Path::new(VDSO_PATH);
+    let size = match BinaryParser::parse(path, &bytes)?.object() {
+        goblin::Object::Elf(elf) => usize::try_from(crate::elf::data_end(elf)).unwrap_or(size),
+        _ => size,
+    };
+
+    let parser = BinaryParser::parse(path, &bytes[..size.min(bytes.len())])?;
+    crate::analyze_parsed_file(&parser, context, start_time)
+}
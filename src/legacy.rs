@@ -0,0 +1,105 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Legacy executable formats, which predate the security features checked for modern formats:
+//! DOS executables without a newer header, 16-bits NE executables, and LE and LX executables.
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::options::status::InformationalStatus;
+use crate::report::{Architecture, BinaryFormat, Endianness, FileResults};
+
+/// Offset of the `e_lfanew` field of the DOS header, which locates the newer header.
+const E_LFANEW_OFFSET: u64 = 0x3c;
+
+/// Legacy format of an executable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LegacyFormat {
+    /// DOS executable, made only of the `MZ` header and its program.
+    Dos,
+    /// New Executable of 16-bits Windows and OS/2.
+    NE,
+    /// Linear Executable of Windows virtual device drivers and DOS extenders.
+    LE,
+    /// Linear Executable of 32-bits OS/2.
+    LX,
+}
+
+impl LegacyFormat {
+    fn bits(self) -> u8 {
+        match self {
+            Self::Dos | Self::NE => 16,
+            Self::LE | Self::LX => 32,
+        }
+    }
+}
+
+impl core::fmt::Display for LegacyFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match *self {
+            Self::Dos => "MZ",
+            Self::NE => "NE",
+            Self::LE => "LE",
+            Self::LX => "LX",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Returns the legacy format of the executable at `path`, if it has one.
+///
+/// Only the headers are read. Files starting with the `MZ` signature whose newer header is not
+/// found are DOS executables. PE images are not legacy executables.
+pub(crate) fn file_format(path: &Path) -> Option<LegacyFormat> {
+    let mut file = fs::File::open(path).ok()?;
+
+    let mut signature = [0_u8; 2];
+    file.read_exact(&mut signature).ok()?;
+    if &signature != b"MZ" && &signature != b"ZM" {
+        return None;
+    }
+
+    let mut e_lfanew = [0_u8; 4];
+    let new_header = file
+        .seek(SeekFrom::Start(E_LFANEW_OFFSET))
+        .and_then(|_| file.read_exact(&mut e_lfanew))
+        .and_then(|()| file.seek(SeekFrom::Start(u32::from_le_bytes(e_lfanew).into())))
+        .and_then(|_| {
+            let mut new_signature = [0_u8; 4];
+            file.read_exact(&mut new_signature).map(|()| new_signature)
+        });
+
+    match new_header.ok() {
+        Some([b'P', b'E', 0, 0]) => None,
+        Some([b'N', b'E', ..]) => Some(LegacyFormat::NE),
+        Some([b'L', b'E', ..]) => Some(LegacyFormat::LE),
+        Some([b'L', b'X', ..]) => Some(LegacyFormat::LX),
+        _ => Some(LegacyFormat::Dos),
+    }
+}
+
+/// Returns the results of a legacy executable, to which no modern mitigation applies.
+pub(crate) fn results(format: LegacyFormat, duration: Option<Duration>) -> FileResults {
+    let status = InformationalStatus::new(
+        "LEGACY-FORMAT",
+        vec![format.to_string(), "no-mitigations-applicable".into()],
+    );
+
+    FileResults {
+        format: BinaryFormat::Legacy,
+        architecture: Some(Architecture {
+            name: "x86".into(),
+            bits: format.bits(),
+            endianness: Endianness::Little,
+        }),
+        checks: vec![status.into()],
+        libc: None,
+        duration,
+    }
+}
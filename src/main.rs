@@ -28,32 +28,60 @@
     clippy::similar_names
 )]
 
+mod admission;
 mod archive;
+mod bundle;
+mod chroot;
 mod cmdline;
+mod context;
+#[cfg(unix)]
+mod daemon;
+mod doctor;
+mod efi;
 mod elf;
+mod embedded;
 mod errors;
+mod extract;
+mod fortify;
+mod inventory;
+mod isolate;
+mod kernel;
+mod legacy;
 mod options;
 mod parser;
 mod pe;
+mod policy;
+mod report;
+mod sandbox;
+mod serve;
+mod stamp;
 mod ui;
+mod upx;
+mod vulnerable_libs;
+mod walk;
+mod weg;
+mod xattrs;
 
-use core::iter;
-use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
 
 use clap::Parser;
 use flexi_logger::{FlexiLoggerError, LoggerHandle};
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use rayon::prelude::*;
 
+use crate::admission::MappedBytesLimiter;
 use crate::cmdline::UseColor;
+use crate::context::AnalysisContext;
 use crate::errors::{Error, Result};
-use crate::parser::BinaryParser;
-use crate::ui::ColorBuffer;
+use crate::parser::{BinaryFile, BinaryParser};
+use crate::report::{BaselineReport, BinaryFormat, FileResults, PreviousReport};
 
 fn main() -> ExitCode {
-    let options = cmdline::Options::parse();
+    let mut options = cmdline::Options::parse();
 
     let _log_handle = match init_logger(&options) {
         Ok(h) => h,
@@ -66,69 +94,389 @@ fn main() -> ExitCode {
 
     trace!("{:?}", &options);
 
-    let mut exit_code = 0_u8;
-    match run(options) {
-        Ok((successes, errors)) => {
-            // Print successful results.
-            for (path, color_buffer) in successes {
-                print!("{}: ", path.display());
-                if color_buffer.print().is_err() {
-                    exit_code = 1;
-                    break;
-                }
+    // Input files are inside the foreign system, whose root is also the system root.
+    if let Some(root) = options.chroot.clone() {
+        for path in &mut options.input_files {
+            *path = chroot::resolve(&root, path);
+        }
+        options.sysroot = vec![root];
+    }
+
+    // Files are parsed only once the sandbox is applied, if requested.
+    if let Err(error) = sandbox::apply(&options) {
+        error!("{}", format_error(&error));
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(path) = options.isolated_worker.as_deref() {
+        return isolate::run_worker(path, &options);
+    }
+
+    if let Some(command) = options.command.clone() {
+        return run_command(command, &options);
+    }
+
+    if let Some(schema) = options.print_schema {
+        return match report::print_schema(schema) {
+            Ok(()) => ExitCode::SUCCESS,
+
+            Err(error) => {
+                error!("{}", format_error(&error));
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Files that failed in a previous report are analyzed again.
+    let previous_report = match options.retry_from.as_deref().map(PreviousReport::read) {
+        None => None,
+
+        Some(Ok(previous_report)) => {
+            options
+                .input_files
+                .extend(previous_report.retried_files.iter().cloned());
+            Some(previous_report)
+        }
+
+        Some(Err(error)) => {
+            error!("{}", format_error(&error));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Libraries loaded at runtime are analyzed along with the input files.
+    if let Some(path) = options.dlopen_list.as_deref() {
+        match elf::dlopen_list::read(path, &options) {
+            Ok(libraries) => options.input_files.extend(libraries),
+
+            Err(error) => {
+                error!("{}", format_error(&error));
+                return ExitCode::FAILURE;
             }
+        }
+    }
+
+    // Files are only classified, without checking their security features.
+    if options.list_only {
+        return list_files(&mut options);
+    }
+
+    // Results are compared with those of a baseline report.
+    let baseline_report = match options
+        .compare_with
+        .as_deref()
+        .map(BaselineReport::read)
+        .transpose()
+    {
+        Ok(baseline_report) => baseline_report,
+
+        Err(error) => {
+            error!("{}", format_error(&error));
+            return ExitCode::FAILURE;
+        }
+    };
 
-            // Print errors related to files.
-            if exit_code == 0 {
-                for (path, error) in errors {
-                    exit_code = 1;
-                    error!("{}: {}", path.display(), format_error(&error));
+    let start = Instant::now();
+    match run(&mut options) {
+        Ok((successes, errors)) => {
+            let summary =
+                (!options.no_summary).then(|| report::Summary::new(&successes, errors.len()));
+
+            let exit_code = report_results(
+                successes,
+                errors,
+                previous_report,
+                baseline_report.as_ref(),
+                &options,
+            );
+
+            if let Some(summary) = summary {
+                if exit_code != 0 || !options.quiet_success {
+                    summary.print(start.elapsed());
                 }
             }
+            ExitCode::from(exit_code)
         }
 
         Err(error) => {
+            error!("{}", format_error(&error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs a command other than the analysis of the input files, and returns the exit status.
+fn run_command(command: cmdline::Command, options: &cmdline::Options) -> ExitCode {
+    let r = match command {
+        cmdline::Command::Doctor => doctor::run(options),
+
+        cmdline::Command::Serve {
+            listen,
+            max_upload_size,
+        } => serve::run(listen, max_upload_size, options).map(|()| true),
+
+        #[cfg(unix)]
+        cmdline::Command::Daemon { socket } => daemon::run(&socket, options).map(|()| true),
+
+        #[cfg(unix)]
+        cmdline::Command::Client { socket, files } => daemon::run_client(&socket, &files),
+    };
+
+    match r {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+
+        Err(error) => {
+            error!("{}", format_error(&error));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reports the results of the analysis, and returns the exit status.
+fn report_results(
+    mut successes: SuccessResults,
+    errors: ErrorResults,
+    previous_report: Option<PreviousReport>,
+    baseline_report: Option<&BaselineReport>,
+    options: &cmdline::Options,
+) -> u8 {
+    let mut exit_code = 0_u8;
+
+    if let Some(path) = &options.export_weg {
+        if let Err(error) = weg::export_policy(path, &successes) {
+            exit_code = 1;
+            error!("{}", format_error(&error));
+        }
+    }
+
+    if let Some(path) = &options.annotate_elf {
+        if let Err(error) = elf::annotate::annotate(path, &successes, options) {
+            exit_code = 1;
+            error!("{}", format_error(&error));
+        }
+    }
+
+    // Files are verified against the policy before results are filtered.
+    if let Some(policy) = options.policy {
+        if policy::apply(policy, &mut successes) {
+            exit_code = 1;
+        }
+    }
+
+    let all_unknown = warn_about_all_unknown(&successes);
+    if options.strict_unknown && all_unknown {
+        exit_code = 1;
+    }
+
+    // Dependencies are all analyzed files, before results are filtered.
+    if let (Some(path), Some(stamp)) = (&options.depfile, &options.stamp_file) {
+        if let Err(error) = stamp::write_depfile(path, stamp, &successes, options) {
+            exit_code = 1;
+            error!("{}", format_error(&error));
+        }
+    }
+
+    // The score covers all analyzed files, before results are filtered.
+    if let Some(path) = &options.badge {
+        if let Err(error) = report::write_badge(path, &successes, options) {
             exit_code = 1;
             error!("{}", format_error(&error));
         }
     }
 
-    ExitCode::from(exit_code)
+    report::prepare_results(&mut successes, options);
+
+    // Post all results, before printing them.
+    if let Some(url) = &options.webhook {
+        let r = report::post_results(url, &successes, &errors, previous_report.as_ref(), options);
+
+        if let Err(error) = r {
+            exit_code = 1;
+            error!("{}", format_error(&error));
+        }
+    }
+
+    // Print successful results, unless nothing is printed on success.
+    let quiet = options.quiet_success && exit_code == 0 && errors.is_empty();
+    if !quiet {
+        let r = report::print_results(
+            successes,
+            &errors,
+            previous_report,
+            baseline_report,
+            options,
+        );
+        if r.is_err() {
+            exit_code = 1;
+        }
+    }
+
+    // Print errors related to files.
+    for (path, error) in errors {
+        exit_code = exit_code.max(1);
+        error!("{}: {}", path.display(), format_error(&error));
+    }
+
+    if let Some(path) = &options.stamp_file {
+        if let Err(error) = stamp::update_stamp(path, exit_code == 0) {
+            exit_code = 1;
+            error!("{}", format_error(&error));
+        }
+    }
+    exit_code
 }
 
-type SuccessResults = Vec<(PathBuf, ColorBuffer)>;
+type SuccessResults = Vec<(PathBuf, FileResults)>;
 type ErrorResults = Vec<(PathBuf, Error)>;
 
-fn run(mut options: cmdline::Options) -> Result<(SuccessResults, ErrorResults)> {
+fn run(options: &mut cmdline::Options) -> Result<(SuccessResults, ErrorResults)> {
+    let mut input_files = core::mem::take(&mut options.input_files);
+    let options = &*options;
+    let context = AnalysisContext::new(options);
+
+    // Flatpak and Snap trees are analyzed with their own libraries.
+    let bundle_trees = if options.bundles {
+        let (trees, files) = input_files
+            .into_iter()
+            .partition(|path| path.is_dir() && bundle::tree_kind(path).is_some());
+        input_files = files;
+        trees
+    } else {
+        Vec::default()
+    };
+
+    let (successes, mut errors) = process_files(input_files, options, |path| {
+        if options.isolate {
+            isolate::analyze_file_and_payload(path, options)
+        } else {
+            analyze_file_and_payload(path, &context)
+        }
+    })?;
+
+    // Payloads of packed executables and bundles are reported after them.
+    let mut successes: SuccessResults = successes
+        .into_iter()
+        .flat_map(|(_path, results)| results)
+        .collect();
+
+    for root in bundle_trees {
+        debug!("Analyzing bundle tree '{}'.", root.display());
+        successes.extend(bundle::analyze_tree(&root, &root, options));
+    }
+
+    // The vDSO is reported after the input files.
+    if options.vdso {
+        match kernel::analyze_vdso(options) {
+            Ok(results) => successes.push((kernel::VDSO_PATH.into(), results)),
+            Err(error) => errors.push((kernel::VDSO_PATH.into(), error)),
+        }
+    }
+    Ok((successes, errors))
+}
+
+/// Processes the input files, and the files found inside input directories, in parallel.
+fn process_files<T: Send>(
+    input_files: Vec<PathBuf>,
+    options: &cmdline::Options,
+    process: impl Fn(&Path) -> Result<T> + Sync,
+) -> Result<(Vec<(PathBuf, T)>, ErrorResults)> {
     use rayon::iter::Either;
 
-    let icb_stdout = ColorBuffer::for_stdout(options.color);
+    // Files are analyzed while they are still being discovered. The number of discovered files
+    // waiting to be analyzed is bounded, so that memory usage stays bounded as well.
+    let (sender, receiver) = mpsc::sync_channel(options.max_pending as usize);
 
-    let input_files = core::mem::take(&mut options.input_files);
+    // Files being analyzed are mapped in memory, so the total size of mapped files is bounded too.
+    let limiter = MappedBytesLimiter::new(options.max_mapped_bytes);
 
-    let result: (Vec<_>, Vec<_>) = input_files
-        .into_iter()
-        // Zip one color buffer with each file to process.
-        .zip(iter::repeat(icb_stdout))
-        // Collect all inputs before starting processing.
-        .collect::<Vec<_>>()
-        .into_par_iter()
-        // Process each file.
-        .map(|(path, mut out)| {
-            let r = process_file(&path, &mut out.color_buffer, &options);
-            (path, out, r)
-        })
-        .partition_map(|(path, out, result)| match result {
-            // On success, retain the path and output buffer, discard the result.
-            Ok(()) => Either::Left((path, out)),
-            // On error, retain the path and error, discard the output buffer.
-            Err(r) => Either::Right((path, r)),
+    let (mut successes, mut errors): (Vec<_>, Vec<_>) = thread::scope(|scope| {
+        scope.spawn(move || {
+            walk::walk_inputs(input_files, options, &sender);
         });
 
-    Ok(result)
+        receiver
+            .into_iter()
+            .par_bridge()
+            // Process each file.
+            .map(|entry| {
+                let r = if let Some(error) = entry.error {
+                    Err(error)
+                } else {
+                    let _permit = limiter.acquire(&entry.path);
+                    process(&entry.path)
+                };
+                (entry.index, entry.path, r)
+            })
+            .partition_map(|(index, path, result)| match result {
+                // On success, retain the path and results.
+                Ok(results) => Either::Left((index, path, results)),
+                // On error, retain the path and error.
+                Err(r) => Either::Right((index, path, r)),
+            })
+    });
+
+    // Report results in the order files were discovered, unless any order is acceptable.
+    if !options.unordered {
+        successes.sort_unstable_by_key(|&(index, ..)| index);
+        errors.sort_unstable_by_key(|&(index, ..)| index);
+    }
+
+    Ok((
+        successes
+            .into_iter()
+            .map(|(_index, path, results)| (path, results))
+            .collect(),
+        errors
+            .into_iter()
+            .map(|(_index, path, error)| (path, error))
+            .collect(),
+    ))
 }
 
-fn format_error(mut r: &dyn std::error::Error) -> String {
+/// Warns about files for which the status of every security feature is unknown, which often
+/// reveals a configuration problem. Returns `true` if there are such files.
+fn warn_about_all_unknown(successes: &SuccessResults) -> bool {
+    let mut found = false;
+    for (path, _results) in successes
+        .iter()
+        .filter(|(_path, results)| report::is_all_unknown(results))
+    {
+        found = true;
+        warn!(
+            "{}: The status of every security feature is unknown. \
+             Verify the options of the analysis, e.g., '--libc-spec'.",
+            path.display()
+        );
+    }
+    found
+}
+
+fn list_files(options: &mut cmdline::Options) -> ExitCode {
+    let input_files = core::mem::take(&mut options.input_files);
+
+    let (kinds, errors) = match process_files(input_files, options, inventory::classify_file) {
+        Ok(r) => r,
+
+        Err(error) => {
+            error!("{}", format_error(&error));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut exit_code = ExitCode::SUCCESS;
+    if inventory::print_counts(&kinds, &errors, options).is_err() {
+        exit_code = ExitCode::FAILURE;
+    }
+
+    for (path, error) in errors {
+        exit_code = ExitCode::FAILURE;
+        error!("{}: {}", path.display(), format_error(&error));
+    }
+    exit_code
+}
+
+pub(crate) fn format_error(mut r: &dyn std::error::Error) -> String {
     use core::fmt::Write;
 
     // Format the error as a message.
@@ -163,56 +511,209 @@ fn init_logger(options: &cmdline::Options) -> std::result::Result<LoggerHandle,
     logger.start()
 }
 
-fn process_file(
-    path: &impl AsRef<Path>,
-    color_buffer: &mut termcolor::Buffer,
-    options: &cmdline::Options,
-) -> Result<()> {
+/// Analyzes a file, followed by its payload if it is packed by UPX and `--unpack-upx` is
+/// specified, if it is an `AppImage` and `--bundles` is specified, if it is a `bzImage` and
+/// `--unpack-kernel` is specified, or if it is a UEFI capsule or firmware volume.
+fn analyze_file_and_payload(path: &Path, context: &AnalysisContext) -> Result<SuccessResults> {
+    let results = analyze_file(&path, context)?;
+    let is_packed = results.checks.iter().any(|r| r.name() == upx::PACKED);
+    let is_bundle = results.checks.iter().any(|r| r.name() == bundle::BUNDLE);
+    let has_overlay = results.checks.iter().any(|r| r.name() == options::OVERLAY);
+    let is_kernel = results.checks.iter().any(|r| r.name() == kernel::KERNEL);
+    let is_efi_container = results
+        .checks
+        .iter()
+        .any(|r| r.name() == efi::EFI_CONTAINER);
+
+    let mut r = vec![(path.to_path_buf(), results)];
+    if context.options.unpack_upx && is_packed {
+        match upx::analyze_payload(path, context) {
+            Ok(payload_results) => r.push((upx::payload_path(path), payload_results)),
+
+            Err(error) => warn!(
+                "{}: Payload is not analyzed. {}",
+                path.display(),
+                format_error(&error)
+            ),
+        }
+    }
+
+    if context.options.bundles && is_bundle {
+        match bundle::analyze_appimage(path, context) {
+            Ok(bundled_results) => r.extend(bundled_results),
+
+            Err(error) => warn!(
+                "{}: Payload is not analyzed. {}",
+                path.display(),
+                format_error(&error)
+            ),
+        }
+    }
+
+    if context.options.unpack_kernel && is_kernel {
+        match kernel::analyze_payload(path, context) {
+            Ok(Some(payload_results)) => r.push((kernel::payload_path(path), payload_results)),
+            Ok(None) => {}
+
+            Err(error) => warn!(
+                "{}: Kernel is not analyzed. {}",
+                path.display(),
+                format_error(&error)
+            ),
+        }
+    }
+
+    if is_efi_container {
+        match efi::analyze_images(path, context) {
+            Ok(image_results) => r.extend(image_results),
+
+            Err(error) => warn!(
+                "{}: Firmware images are not analyzed. {}",
+                path.display(),
+                format_error(&error)
+            ),
+        }
+    }
+
+    if context.options.embedded && has_overlay {
+        match embedded::analyze_images(path, context) {
+            Ok(embedded_results) => r.extend(embedded_results),
+
+            Err(error) => warn!(
+                "{}: Embedded images are not analyzed. {}",
+                path.display(),
+                format_error(&error)
+            ),
+        }
+    }
+    Ok(r)
+}
+
+fn analyze_file(path: &impl AsRef<Path>, context: &AnalysisContext) -> Result<FileResults> {
+    let start = context.options.timings.then(Instant::now);
+    let file = BinaryFile::open(path.as_ref())?;
+    let parser = match file.parse() {
+        Ok(parser) => parser,
+
+        // Legacy executables are not parsed, yet they are reported as such.
+        Err(error @ Error::ParseFile { .. }) => {
+            let format = legacy::file_format(path.as_ref()).ok_or(error)?;
+            debug!("Binary file format is '{format}'.");
+            return Ok(legacy::results(format, start.map(|start| start.elapsed())));
+        }
+
+        Err(error) => return Err(error),
+    };
+
+    analyze_parsed_file(&parser, context, start)
+}
+
+/// Analyzes a parsed binary file, whose analysis started at `start` if it is measured.
+fn analyze_parsed_file(
+    parser: &BinaryParser,
+    context: &AnalysisContext,
+    start: Option<Instant>,
+) -> Result<FileResults> {
     use goblin::Object;
 
-    let parser = BinaryParser::open(path.as_ref())?;
+    let options = context.options;
+    let path = parser.path();
+
+    let mut libc = None;
+    let (format, architecture, mut checks) = match parser.object() {
+        // Kernels are not loaded by the dynamic loader.
+        Object::Elf(elf) if kernel::is_vmlinux(elf) => {
+            debug!("Binary file format is 'vmlinux'.");
+            (
+                BinaryFormat::Kernel,
+                Some(elf::architecture(elf)),
+                kernel::analyze_vmlinux(elf),
+            )
+        }
 
-    let results = match parser.object() {
-        Object::Elf(_elf) => {
+        // Kernels including the EFI stub are also PE images.
+        Object::PE(_) | Object::Unknown(_) if kernel::is_bzimage(parser.bytes()) => {
+            debug!("Binary file format is 'bzImage'.");
+            let (architecture, checks) = kernel::analyze_bzimage(parser.bytes())?;
+            (BinaryFormat::Kernel, Some(architecture), checks)
+        }
+
+        Object::Elf(elf) => {
             debug!("Binary file format is 'ELF'.");
-            elf::analyze_binary(&parser, options)
+            let (checks, libc_source) = elf::analyze_binary(parser, context)?;
+            libc = libc_source.map(|source| source.to_string());
+            (BinaryFormat::ELF, Some(elf::architecture(elf)), checks)
         }
 
-        Object::PE(_pe) => {
+        Object::PE(pe) => {
             debug!("Binary file format is 'PE'.");
-            pe::analyze_binary(&parser, options)
+            (
+                BinaryFormat::PE,
+                Some(pe::architecture(pe)),
+                pe::analyze_binary(parser, context)?,
+            )
         }
 
         Object::Mach(_mach) => {
             debug!("Binary file format is 'MACH'.");
-            Err(Error::UnsupportedBinaryFormat {
+            return Err(Error::UnsupportedBinaryFormat {
                 format: "MACH".into(),
-                path: path.as_ref().into(),
-            })
+                path: path.into(),
+            });
         }
 
         Object::Archive(_archive) => {
             debug!("Binary file format is 'Archive'.");
-            archive::analyze_binary(&parser, options)
+            (
+                BinaryFormat::Archive,
+                None,
+                archive::analyze_binary(parser, context)?,
+            )
         }
 
-        Object::Unknown(_magic) => Err(Error::UnknownBinaryFormat(path.as_ref().into())),
-
-        _ => Err(Error::UnknownBinaryFormat(path.as_ref().into())),
-    }?;
+        Object::Unknown(_magic) if efi::is_te_image(parser.bytes()) => {
+            debug!("Binary file format is 'TE'.");
+            let (architecture, checks) = efi::analyze_te_image(parser.bytes())?;
+            (BinaryFormat::EFI, Some(architecture), checks)
+        }
 
-    // Print results in the color buffer.
-    let mut iter = results.into_iter();
-    if let Some(first) = iter.next() {
-        first.as_ref().display_in_color_term(color_buffer)?;
-        for opt in iter {
-            write!(color_buffer, " ")
-                .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
-            opt.as_ref().display_in_color_term(color_buffer)?;
+        // The images of capsules and firmware volumes are analyzed as their payload.
+        Object::Unknown(_magic) if efi::is_container(parser.bytes()) => {
+            debug!("Binary file format is 'EFI container'.");
+            (
+                BinaryFormat::EFI,
+                None,
+                vec![efi::container_status(parser.bytes())],
+            )
         }
+
+        Object::Unknown(_magic) => return Err(Error::UnknownBinaryFormat(path.into())),
+
+        _ => return Err(Error::UnknownBinaryFormat(path.into())),
+    };
+
+    // Packed executables hide the properties of their payload.
+    if matches!(format, BinaryFormat::ELF | BinaryFormat::PE)
+        && upx::is_packed(parser.bytes(), parser.object())
+    {
+        checks.push(upx::packed_status());
+    }
+
+    // AppImage files hide the binaries of their payload.
+    if format == BinaryFormat::ELF && bundle::is_appimage(parser.bytes()) {
+        checks.push(bundle::appimage_status());
+    }
+
+    // Archives are not executed, so they are not confined.
+    if options.with_mac_context && format == BinaryFormat::ELF {
+        checks.push(xattrs::mac_context(path));
     }
 
-    writeln!(color_buffer)
-        .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
-    Ok(())
+    Ok(FileResults {
+        format,
+        architecture,
+        checks,
+        libc,
+        duration: start.map(|start| start.elapsed()),
+    })
 }
@@ -4,23 +4,39 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
+pub(crate) mod hints;
+pub(crate) mod ids;
 pub(crate) mod status;
 
-use crate::elf::needed_libc::{LibCResolver, NeededLibC};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use log::warn;
+
+use crate::context::AnalysisContext;
+use crate::elf::needed_libc::{LibCSource, NeededLibC};
 use crate::errors::Result;
 use crate::parser::BinaryParser;
-use crate::{archive, cmdline, elf, pe};
+use crate::{archive, cmdline, elf, pe, vulnerable_libs};
 
 use self::status::{
-    DisplayInColorTerm, ELFFortifySourceStatus, PEControlFlowGuardLevel, YesNoUnknownStatus,
+    CheckResult, DetailedStatus, ELFFortifySourceStatus, InformationalStatus, NotApplicableStatus,
+    PEControlFlowGuardLevel, SupportLevel, YesNoUnknownStatus,
 };
 
 pub(crate) trait BinarySecurityOption<'t> {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>>;
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult>;
+
+    /// Performs the check, and measures the time it takes if `--timings` is specified.
+    fn timed_check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
+        if !context.options.timings {
+            return self.check(parser, context);
+        }
+
+        let start = Instant::now();
+        let status = self.check(parser, context)?;
+        Ok(status.with_duration(start.elapsed()))
+    }
 }
 
 struct PEDllCharacteristicsBitOption {
@@ -30,62 +46,48 @@ struct PEDllCharacteristicsBitOption {
     present: bool,
 }
 
-impl<'t> BinarySecurityOption<'t> for PEDllCharacteristicsBitOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for PEDllCharacteristicsBitOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         if let goblin::Object::PE(pe) = parser.object() {
             if let Some(bit_is_set) =
                 pe::dll_characteristics_bit_is_set(pe, self.mask_name, self.mask)
             {
-                return Ok(Box::new(YesNoUnknownStatus::new(
-                    self.name,
-                    bit_is_set == self.present,
-                )));
+                return Ok(YesNoUnknownStatus::new(self.name, bit_is_set == self.present).into());
             }
         }
-        Ok(Box::new(YesNoUnknownStatus::unknown(self.name)))
+        Ok(YesNoUnknownStatus::unknown(self.name).into())
     }
 }
 
 #[derive(Default)]
 pub(crate) struct PEHasCheckSumOption;
 
-impl<'t> BinarySecurityOption<'t> for PEHasCheckSumOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for PEHasCheckSumOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         let r = if let goblin::Object::PE(pe) = parser.object() {
             pe::has_check_sum(pe)
         } else {
             None
         };
 
-        Ok(Box::new(r.map_or_else(
+        Ok(r.map_or_else(
             || YesNoUnknownStatus::unknown("CHECKSUM"),
             |r| YesNoUnknownStatus::new("CHECKSUM", r),
-        )))
+        )
+        .into())
     }
 }
 
 #[derive(Default)]
 pub(crate) struct DataExecutionPreventionOption;
 
-impl<'t> BinarySecurityOption<'t> for DataExecutionPreventionOption {
+impl BinarySecurityOption<'_> for DataExecutionPreventionOption {
     /// Returns information about support of Data Execution Prevention (DEP) in the executable.
     ///
     /// When DEP is supported, a virtual memory page can be marked as non-executable (NX), in which
     /// case trying to execute any code from that pages will raise an exception, and likely crash
     /// the application, instead of running arbitrary code.
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
         if let goblin::Object::PE(_pe) = parser.object() {
             PEDllCharacteristicsBitOption {
                 name: "DATA-EXEC-PREVENT",
@@ -93,9 +95,9 @@ impl<'t> BinarySecurityOption<'t> for DataExecutionPreventionOption {
                 mask: pe::IMAGE_DLLCHARACTERISTICS_NX_COMPAT,
                 present: true,
             }
-            .check(parser, options)
+            .check(parser, context)
         } else {
-            Ok(Box::new(YesNoUnknownStatus::unknown("DATA-EXEC-PREVENT")))
+            Ok(YesNoUnknownStatus::unknown("DATA-EXEC-PREVENT").into())
         }
     }
 }
@@ -103,38 +105,30 @@ impl<'t> BinarySecurityOption<'t> for DataExecutionPreventionOption {
 #[derive(Default)]
 pub(crate) struct PERunsOnlyInAppContainerOption;
 
-impl<'t> BinarySecurityOption<'t> for PERunsOnlyInAppContainerOption {
+impl BinarySecurityOption<'_> for PERunsOnlyInAppContainerOption {
     /// Returns information about the requirement to run this executable inside `AppContainer`.
     ///
     /// This option indicates whether the executable must be run in the `AppContainer`
     /// process-isolation environment, such as a Universal Windows Platform (UWP) or Windows
     /// Phone 8.x app.
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
         PEDllCharacteristicsBitOption {
             name: "RUNS-IN-APP-CONTAINER",
             mask_name: "IMAGE_DLLCHARACTERISTICS_APPCONTAINER",
             mask: pe::IMAGE_DLLCHARACTERISTICS_APPCONTAINER,
             present: true,
         }
-        .check(parser, options)
+        .check(parser, context)
     }
 }
 
 #[derive(Default)]
 pub(crate) struct RequiresIntegrityCheckOption;
 
-impl<'t> BinarySecurityOption<'t> for RequiresIntegrityCheckOption {
+impl BinarySecurityOption<'_> for RequiresIntegrityCheckOption {
     /// Returns whether the operating system must to verify the digital signature of this executable
     /// at load time.
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
         if let goblin::Object::PE(_pe) = parser.object() {
             PEDllCharacteristicsBitOption {
                 name: "VERIFY-DIGITAL-CERT",
@@ -142,9 +136,9 @@ impl<'t> BinarySecurityOption<'t> for RequiresIntegrityCheckOption {
                 mask: pe::IMAGE_DLLCHARACTERISTICS_FORCE_INTEGRITY,
                 present: true,
             }
-            .check(parser, options)
+            .check(parser, context)
         } else {
-            Ok(Box::new(YesNoUnknownStatus::unknown("VERIFY-DIGITAL-CERT")))
+            Ok(YesNoUnknownStatus::unknown("VERIFY-DIGITAL-CERT").into())
         }
     }
 }
@@ -152,7 +146,7 @@ impl<'t> BinarySecurityOption<'t> for RequiresIntegrityCheckOption {
 #[derive(Default)]
 pub(crate) struct PEEnableManifestHandlingOption;
 
-impl<'t> BinarySecurityOption<'t> for PEEnableManifestHandlingOption {
+impl BinarySecurityOption<'_> for PEEnableManifestHandlingOption {
     /// Returns whether the operating system is allowed to consider manifest files when loading
     /// this executable.
     ///
@@ -161,48 +155,119 @@ impl<'t> BinarySecurityOption<'t> for PEEnableManifestHandlingOption {
     /// application manifest for the newly created process. The new process will not have a default
     /// activation context, even if there is a manifest inside the executable or placed in the same
     /// directory as the executable with name `executable-name.exe.manifest`.
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
         PEDllCharacteristicsBitOption {
             name: "CONSIDER-MANIFEST",
             mask_name: "IMAGE_DLLCHARACTERISTICS_NO_ISOLATION",
             mask: pe::IMAGE_DLLCHARACTERISTICS_NO_ISOLATION,
             present: false,
         }
-        .check(parser, options)
+        .check(parser, context)
     }
 }
 
 #[derive(Default)]
 pub(crate) struct PEControlFlowGuardOption;
 
-impl<'t> BinarySecurityOption<'t> for PEControlFlowGuardOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for PEControlFlowGuardOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         let r = if let goblin::Object::PE(pe) = parser.object() {
             pe::supports_control_flow_guard(pe)
         } else {
             PEControlFlowGuardLevel::Unknown
         };
-        Ok(Box::new(r))
+        Ok(r.into())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct PEGuardFlagsOption;
+
+impl BinarySecurityOption<'_> for PEGuardFlagsOption {
+    /// Returns the decoded guard flags of the image load configuration directory.
+    ///
+    /// Guard flags detail the Control Flow Guard features the executable was linked with, e.g.,
+    /// export suppression, long jump target tables and Return Flow Guard.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let mut details = Vec::default();
+
+        if let goblin::Object::PE(pe) = parser.object() {
+            if let Some(flags) = pe::guard_flags(parser, pe)? {
+                details.extend(
+                    pe::IMAGE_GUARD_FLAGS
+                        .iter()
+                        .filter(|&&(mask, _name)| (flags & mask) != 0)
+                        .map(|&(_mask, name)| {
+                            name.trim_start_matches("IMAGE_GUARD_").replace('_', "-")
+                        }),
+                );
+
+                let stride = (flags & pe::IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK)
+                    >> pe::IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT;
+                if stride != 0 {
+                    details.push(format!("CF-FUNCTION-TABLE-STRIDE={stride}"));
+                }
+
+                if (flags & pe::IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT) != 0 {
+                    if let Some(count) = pe::eh_continuation_count(parser, pe)? {
+                        details.push(format!("EH-CONTINUATION-TARGETS={count}"));
+                    }
+                }
+            } else {
+                details.push("undefined".into());
+            }
+        }
+        Ok(InformationalStatus::new("GUARD-FLAGS", details).into())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct PEHybridImageOption;
+
+impl BinarySecurityOption<'_> for PEHybridImageOption {
+    /// Returns hybrid-specific information about ARM64EC, ARM64X and CHPE executables.
+    ///
+    /// Hybrid executables mix native ARM64 code with code of an emulated instruction set (x64 or
+    /// x86). Control Flow Guard needs to be supported by both the native code and the dispatcher
+    /// of indirect calls made from the emulated code.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let mut details = Vec::default();
+
+        if let goblin::Object::PE(pe) = parser.object() {
+            if let Some(metadata) = pe::hybrid_image_metadata(parser, pe)? {
+                details.push(metadata.architecture.name().into());
+
+                let ranges = [
+                    ("arm64", metadata.arm64_code),
+                    ("arm64ec", metadata.arm64ec_code),
+                    ("emulated", metadata.emulated_code),
+                ];
+                for (name, code_ranges) in ranges {
+                    if code_ranges.count > 0 {
+                        details.push(format!(
+                            "{name}-ranges={}:{}",
+                            code_ranges.count, code_ranges.size
+                        ));
+                    }
+                }
+
+                if metadata.native_guard {
+                    details.push("native-guard".into());
+                }
+                if metadata.emulated_guard {
+                    details.push("emulated-guard".into());
+                }
+            }
+        }
+        Ok(InformationalStatus::new("HYBRID", details).into())
     }
 }
 
 #[derive(Default)]
 pub(crate) struct PEHandlesAddressesLargerThan2GBOption;
 
-impl<'t> BinarySecurityOption<'t> for PEHandlesAddressesLargerThan2GBOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for PEHandlesAddressesLargerThan2GBOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         let r = if let goblin::Object::PE(pe) = parser.object() {
             YesNoUnknownStatus::new(
                 "HANDLES-ADDR-GT-2GB",
@@ -211,146 +276,651 @@ impl<'t> BinarySecurityOption<'t> for PEHandlesAddressesLargerThan2GBOption {
         } else {
             YesNoUnknownStatus::unknown("HANDLES-ADDR-GT-2GB")
         };
-        Ok(Box::new(r))
+        Ok(r.into())
     }
 }
 
 #[derive(Default)]
 pub(crate) struct AddressSpaceLayoutRandomizationOption;
 
-impl<'t> BinarySecurityOption<'t> for AddressSpaceLayoutRandomizationOption {
+impl BinarySecurityOption<'_> for AddressSpaceLayoutRandomizationOption {
     /// Returns the level of support of Address Space Layout Randomization (ASLR).
     ///
     /// When ASLR is supported, the executable should be randomly re-based at load time, enabling
     /// virtual address allocation randomization, which affects the virtual memory location of heaps,
     /// stacks, and other operating system allocations.
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         match parser.object() {
-            goblin::Object::PE(pe) => Ok(Box::new(pe::supports_aslr(pe))),
-            goblin::Object::Elf(elf_obj) => Ok(Box::new(elf::supports_aslr(elf_obj))),
-            _ => Ok(Box::new(YesNoUnknownStatus::unknown("ASLR"))),
+            goblin::Object::PE(pe) => Ok(pe::supports_aslr(pe).into()),
+            goblin::Object::Elf(elf_obj) => Ok(elf::supports_aslr(elf_obj).into()),
+            _ => Ok(YesNoUnknownStatus::unknown("ASLR").into()),
         }
     }
 }
 
+#[derive(Default)]
+pub(crate) struct HeapHardeningOption;
+
+impl BinarySecurityOption<'_> for HeapHardeningOption {
+    /// Returns whether the binary relies on a memory allocator that hardens its heap against
+    /// corruption.
+    ///
+    /// Heap hardening techniques, such as safe unlinking of free chunks or termination of the
+    /// process on heap corruption, make heap overflows harder to exploit. Binaries that compile
+    /// in their own legacy allocator, or that do not opt into termination on heap corruption,
+    /// do not benefit from these techniques.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        match parser.object() {
+            goblin::Object::Elf(elf_obj) => Ok(elf::heap_hardening(elf_obj).into()),
+            goblin::Object::PE(pe) => Ok(pe::heap_hardening(pe).into()),
+            _ => Ok(DetailedStatus::unknown("HEAP-HARDENING").into()),
+        }
+    }
+}
+
+pub(crate) struct VulnerableLibrariesOption;
+
+impl BinarySecurityOption<'_> for VulnerableLibrariesOption {
+    /// Returns the versions of common libraries embedded in the binary, and the critical
+    /// vulnerabilities known to affect them.
+    ///
+    /// Binaries that link libraries statically keep the version strings of these libraries in
+    /// their read-only data, but are not updated when the libraries are fixed.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        Ok(vulnerable_libs::vulnerable_libraries(parser).into())
+    }
+}
+
+pub(crate) struct EntryPointOption;
+
+impl BinarySecurityOption<'_> for EntryPointOption {
+    /// Returns anomalies of the location of the entry point, e.g., in a writable section, in the
+    /// last section, or outside any section.
+    ///
+    /// Packers and file infectors commonly append code to the binary, and redirect the entry
+    /// point to it.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = match parser.object() {
+            goblin::Object::Elf(elf) => elf::entry_point_anomalies(elf),
+            goblin::Object::PE(pe) => pe::entry_point_anomalies(pe),
+            _ => DetailedStatus::unknown("ENTRY-POINT"),
+        };
+        Ok(r.into())
+    }
+}
+
+/// Keyword of the result reporting the data appended to a binary.
+pub(crate) const OVERLAY: &str = "OVERLAY";
+
+pub(crate) struct OverlayOption;
+
+impl OverlayOption {
+    /// Overlays smaller than this are considered as padding.
+    const MIN_SIZE: usize = 512;
+
+    /// Returns the data appended to the binary, beyond all the data described by its headers.
+    pub(crate) fn overlay<'t>(parser: &BinaryParser<'t>) -> Option<&'t [u8]> {
+        let data_end = match parser.object() {
+            goblin::Object::Elf(elf) => elf::data_end(elf),
+            goblin::Object::PE(pe) => pe::data_end(parser, pe),
+            _ => return None,
+        };
+
+        let data_end = usize::try_from(data_end).ok()?;
+        parser
+            .bytes()
+            .get(data_end..)
+            .filter(|overlay| overlay.len() >= Self::MIN_SIZE)
+    }
+}
+
+impl BinarySecurityOption<'_> for OverlayOption {
+    /// Returns the size and the entropy of data appended to the binary.
+    ///
+    /// Such data is ignored by loaders, and is invisible to other checks. It is used by
+    /// installers and self-extracting archives, but also by malware to hide payloads.
+    /// An entropy close to 8 bits per byte suggests compressed or encrypted data.
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
+        let number_format = context.number_format;
+
+        let details = Self::overlay(parser)
+            .map(|overlay| {
+                vec![
+                    format!("size={}", number_format.count(overlay.len() as u64)),
+                    format!(
+                        "entropy={}",
+                        number_format.decimal(shannon_entropy(overlay), 2)
+                    ),
+                ]
+            })
+            .unwrap_or_default();
+
+        Ok(InformationalStatus::new(OVERLAY, details).into())
+    }
+}
+
+/// Returns the Shannon entropy of `bytes`, in bits per byte.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn shannon_entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0_usize; 256];
+    for &b in bytes {
+        counts[usize::from(b)] += 1;
+    }
+
+    // H = log2(n) - (1/n) * sum(count * log2(count))
+    let len = bytes.len() as f64;
+    let sum = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let count = count as f64;
+            count * count.log2()
+        })
+        .sum::<f64>();
+
+    len.log2() - sum / len
+}
+
 #[derive(Default)]
 pub(crate) struct PESafeStructuredExceptionHandlingOption;
 
-impl<'t> BinarySecurityOption<'t> for PESafeStructuredExceptionHandlingOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for PESafeStructuredExceptionHandlingOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         let r = if let goblin::Object::PE(pe) = parser.object() {
             YesNoUnknownStatus::new(
                 "SAFE-SEH",
-                pe::has_safe_structured_exception_handlers(parser, pe),
+                pe::has_safe_structured_exception_handlers(parser, pe)?,
             )
         } else {
             YesNoUnknownStatus::unknown("SAFE-SEH")
         };
-        Ok(Box::new(r))
+        Ok(r.into())
     }
 }
 
 #[derive(Default)]
 pub(crate) struct ELFReadOnlyAfterRelocationsOption;
 
-impl<'t> BinarySecurityOption<'t> for ELFReadOnlyAfterRelocationsOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
-        let r = if let goblin::Object::Elf(elf) = parser.object() {
-            YesNoUnknownStatus::new(
-                "READ-ONLY-RELOC",
-                elf::becomes_read_only_after_relocations(elf),
-            )
-        } else {
-            YesNoUnknownStatus::unknown("READ-ONLY-RELOC")
+impl BinarySecurityOption<'_> for ELFReadOnlyAfterRelocationsOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let goblin::Object::Elf(elf) = parser.object() else {
+            return Ok(YesNoUnknownStatus::unknown("READ-ONLY-RELOC").into());
         };
-        Ok(Box::new(r))
+
+        if !elf::becomes_read_only_after_relocations(elf) {
+            return Ok(YesNoUnknownStatus::new("READ-ONLY-RELOC", false).into());
+        }
+
+        let mut details = Vec::default();
+        if elf::uses_powerpc_bss_plt(elf) {
+            // The PLT remains writable and executable.
+            details.push("bss-plt".into());
+        }
+
+        let uncovered = elf::sections_outside_relro(elf);
+        if !uncovered.is_empty() {
+            details.push(format!("uncovered={}", uncovered.join("+")));
+        }
+
+        if details.is_empty() {
+            Ok(YesNoUnknownStatus::new("READ-ONLY-RELOC", true).into())
+        } else {
+            let r = DetailedStatus::new("READ-ONLY-RELOC", SupportLevel::Partial, details);
+            Ok(r.into())
+        }
     }
 }
 
 #[derive(Default)]
 pub(crate) struct ELFStackProtectionOption;
 
-impl<'t> BinarySecurityOption<'t> for ELFStackProtectionOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for ELFStackProtectionOption {
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
         let r = match parser.object() {
             goblin::Object::Elf(elf_obj) => {
-                YesNoUnknownStatus::new("STACK-PROT", elf::has_stack_protection(elf_obj))
+                // Full symbol tables and debug files are not walked by `--fast`.
+                let protected = elf::has_stack_protection(elf_obj)
+                    || (!context.options.fast
+                        && (elf::symbol_table_has_stack_protection(elf_obj)
+                            || elf::debug_file_has_stack_protection(
+                                parser,
+                                elf_obj,
+                                context.options,
+                            )));
+
+                // Binaries importing no functions might be protected, even if stripped.
+                if !protected && elf_obj.libraries.is_empty() {
+                    if context.options.fast {
+                        return Ok(DetailedStatus::skipped("STACK-PROT").into());
+                    }
+
+                    if let Some(r) = elf::stack_protection_heuristic(parser, elf_obj) {
+                        return Ok(r.into());
+                    }
+                }
+
+                if protected && elf::imports_global_stack_guard(elf_obj) {
+                    let details = vec!["global-guard".into()];
+                    let r = DetailedStatus::new("STACK-PROT", SupportLevel::Partial, details);
+                    return Ok(r.into());
+                }
+                YesNoUnknownStatus::new("STACK-PROT", protected)
             }
 
             goblin::Object::Archive(archive) => {
-                let r = archive::has_stack_protection(parser, archive)?;
-                YesNoUnknownStatus::new("STACK-PROT", r)
+                return archive::stack_protection(parser, archive, context);
             }
 
             _ => YesNoUnknownStatus::unknown("STACK-PROT"),
         };
-        Ok(Box::new(r))
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ArchiveDeterminismOption;
+
+impl BinarySecurityOption<'_> for ArchiveDeterminismOption {
+    /// Returns whether the archive was created in deterministic mode, so that building the same
+    /// sources produces the same archive.
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Archive(archive) = parser.object() {
+            archive::determinism(parser, archive, context)
+        } else {
+            DetailedStatus::unknown(archive::DETERMINISTIC_AR)
+        };
+        Ok(r.into())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ELFAutoVarInitOption;
+
+impl BinarySecurityOption<'_> for ELFAutoVarInitOption {
+    /// Returns whether automatic variables are initialized by the compiler.
+    ///
+    /// When `-ftrivial-auto-var-init=zero` or `-ftrivial-auto-var-init=pattern` is specified,
+    /// the compiler initializes automatic variables that would otherwise be left uninitialized,
+    /// which mitigates information leaks and uses of uninitialized memory.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::auto_var_init(parser, elf)
+        } else {
+            DetailedStatus::unknown("AUTO-VAR-INIT")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct PETLSCallbacksOption;
+
+impl BinarySecurityOption<'_> for PETLSCallbacksOption {
+    /// Returns the number of TLS callbacks, which run before the entry point.
+    ///
+    /// TLS callbacks are rarely needed by applications, but they are used to run code before
+    /// debuggers break on the entry point, and to persist code in every new thread.
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
+        let count = if let goblin::Object::PE(pe) = parser.object() {
+            pe::tls_callbacks(parser, pe)
+        } else {
+            None
+        };
+
+        // Runtimes register TLS callbacks for thread-local storage, so they do not imply missing
+        // hardening.
+        let details = count
+            .map(|count| vec![context.number_format.count(count as u64)])
+            .unwrap_or_default();
+        Ok(InformationalStatus::new("TLS-CALLBACKS", details).into())
+    }
+}
+
+pub(crate) struct PEResourceAnomaliesOption;
+
+impl BinarySecurityOption<'_> for PEResourceAnomaliesOption {
+    /// Returns the resources that embed executable images, scripts, or large high-entropy data,
+    /// which is typical of droppers.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let details = if let goblin::Object::PE(pe) = parser.object() {
+            pe::resource_anomalies(parser, pe)
+        } else {
+            Vec::default()
+        };
+        Ok(InformationalStatus::new("RES-ANOMALY", details).into())
+    }
+}
+
+pub(crate) struct PEExportAuditOption;
+
+impl BinarySecurityOption<'_> for PEExportAuditOption {
+    /// Returns the exports of the DLL that expose it to hijacking.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        if let goblin::Object::PE(pe) = parser.object() {
+            Ok(pe::export_audit(pe))
+        } else {
+            Ok(DetailedStatus::unknown("EXPORT-AUDIT").into())
+        }
+    }
+}
+
+pub(crate) struct ELFDwarfHardeningOption;
+
+impl BinarySecurityOption<'_> for ELFDwarfHardeningOption {
+    /// Returns the hardening options of the compilation units, as recorded in debugging
+    /// information.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        if let goblin::Object::Elf(elf) = parser.object() {
+            Ok(elf::dwarf::hardening_evidence(parser, elf).into())
+        } else {
+            Ok(DetailedStatus::unknown(elf::dwarf::DWARF_HARDENING).into())
+        }
+    }
+}
+
+pub(crate) struct ImageBaseOption;
+
+impl BinarySecurityOption<'_> for ImageBaseOption {
+    /// Returns the address at which the image is loaded when it is not randomized, and whether it
+    /// is a default address of linkers.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let (base, default_bases) = match parser.object() {
+            goblin::Object::Elf(elf) => (elf::load_address(elf), elf::DEFAULT_LOAD_ADDRESSES),
+            goblin::Object::PE(pe) => (pe::image_base(pe), pe::DEFAULT_IMAGE_BASES),
+            _ => (None, &[][..]),
+        };
+
+        let Some(base) = base else {
+            return Ok(DetailedStatus::unknown("IMAGE-BASE").into());
+        };
+
+        let mut details = vec![format!("{base:#x}")];
+        if default_bases.contains(&base) {
+            details.push("well-known".into());
+        }
+        Ok(InformationalStatus::new("IMAGE-BASE", details).into())
+    }
+}
+
+pub(crate) struct PESafeDllSearchOption;
+
+impl BinarySecurityOption<'_> for PESafeDllSearchOption {
+    /// Returns whether the DLLs imported by the executable are only searched in safe directories.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        if let goblin::Object::PE(pe) = parser.object() {
+            Ok(pe::safe_dll_search(parser, pe)?.into())
+        } else {
+            Ok(DetailedStatus::unknown("SAFE-DLL-SEARCH").into())
+        }
+    }
+}
+
+pub(crate) struct PEImportAnomaliesOption;
+
+impl BinarySecurityOption<'_> for PEImportAnomaliesOption {
+    /// Returns whether the imports of the image are obfuscated or unusually located.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::PE(pe) = parser.object() {
+            pe::iat_anomalies(pe)
+        } else {
+            DetailedStatus::unknown("IAT-ANOMALY")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct PEManifestOption;
+
+impl BinarySecurityOption<'_> for PEManifestOption {
+    /// Returns the privileges and compatibility claims of the manifest embedded in the image.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        if let goblin::Object::PE(pe) = parser.object() {
+            if let Some(manifest) = pe::manifest(parser, pe) {
+                return Ok(pe::manifest_audit(&manifest));
+            }
+        }
+        Ok(DetailedStatus::unknown("MANIFEST").into())
+    }
+}
+
+pub(crate) struct ELFAbiTagOption;
+
+impl BinarySecurityOption<'_> for ELFAbiTagOption {
+    /// Returns the operating system and the minimum kernel version targeted by the binary.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        if let goblin::Object::Elf(elf) = parser.object() {
+            Ok(elf::abi_tag(parser, elf))
+        } else {
+            Ok(DetailedStatus::unknown("ABI-TAG").into())
+        }
+    }
+}
+
+pub(crate) struct ELFAndroidOption;
+
+impl BinarySecurityOption<'_> for ELFAndroidOption {
+    /// Returns the minimum Android API level targeted by the binary, and the format of its packed
+    /// relocations.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let mut details: Vec<String> = Vec::default();
+        if let goblin::Object::Elf(elf) = parser.object() {
+            if let Some(api_level) = elf::android_api_level(parser, elf) {
+                details.push(format!("api={api_level}"));
+            }
+
+            let packed_relocations = elf::packed_relocations(elf).unwrap_or("none");
+            details.push(format!("packed-relocs={packed_relocations}"));
+        }
+        Ok(InformationalStatus::new("ANDROID", details).into())
+    }
+}
+
+pub(crate) struct ELFBranchProtectionOption;
+
+impl BinarySecurityOption<'_> for ELFBranchProtectionOption {
+    /// Returns whether branch targets are identified, and return addresses are authenticated.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::branch_protection(parser, elf)
+        } else {
+            DetailedStatus::unknown("BRANCH-PROTECTION")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ELFMemoryTaggingOption;
+
+impl BinarySecurityOption<'_> for ELFMemoryTaggingOption {
+    /// Returns whether memory tagging is requested for the heap and the stack.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::memory_tagging(parser, elf)
+        } else {
+            DetailedStatus::unknown("MTE")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ELFIndirectFunctionsOption;
+
+impl BinarySecurityOption<'_> for ELFIndirectFunctionsOption {
+    /// Returns the indirect functions defined by the binary, whose resolvers run before
+    /// relocated data becomes read-only.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::indirect_functions(parser, elf)
+        } else {
+            InformationalStatus::new("IFUNC", Vec::default())
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ELFRelocationTypesOption;
+
+impl BinarySecurityOption<'_> for ELFRelocationTypesOption {
+    /// Returns the numbers of `COPY` and `IRELATIVE` relocations of the binary.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::relocation_types(elf)
+        } else {
+            DetailedStatus::unknown("RELOC-TYPES")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ELFSuspiciousExportsOption;
+
+impl BinarySecurityOption<'_> for ELFSuspiciousExportsOption {
+    /// Returns whether a shared library exports functions that interpose C runtime library
+    /// functions, or that hook into loading, which is typical of preloaded implants.
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::suspicious_exports(elf, &context.options.allowed_exports)
+        } else {
+            DetailedStatus::unknown("SAFE-EXPORTS")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ELFSymbolVisibilityOption;
+
+impl BinarySecurityOption<'_> for ELFSymbolVisibilityOption {
+    /// Returns whether an executable avoids exporting many dynamic symbols, which it rarely needs
+    /// to do.
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
+        if let goblin::Object::Elf(elf) = parser.object() {
+            Ok(elf::symbol_visibility(elf, context.number_format))
+        } else {
+            Ok(DetailedStatus::unknown("SYMBOL-VISIBILITY").into())
+        }
+    }
+}
+
+pub(crate) struct ELFTamperingOption;
+
+impl BinarySecurityOption<'_> for ELFTamperingOption {
+    /// Returns anomalies of program headers and section headers, which suggest that the binary
+    /// was modified after it was linked, e.g., to inject code.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::tampering_anomalies(elf)
+        } else {
+            DetailedStatus::unknown("TAMPERED?")
+        };
+        Ok(r.into())
+    }
+}
+
+pub(crate) struct ELFHeaderConsistencyOption;
+
+impl BinarySecurityOption<'_> for ELFHeaderConsistencyOption {
+    /// Returns inconsistencies between section headers and program headers, e.g., loaded
+    /// sections outside loaded segments, overlapping loaded segments, or sections stored beyond
+    /// the end of the file.
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
+        let r = if let goblin::Object::Elf(elf) = parser.object() {
+            elf::header_inconsistencies(elf, parser.bytes().len() as u64)
+        } else {
+            DetailedStatus::unknown("HEADER-INCONSISTENT")
+        };
+        Ok(r.into())
     }
 }
 
 #[derive(Default)]
 pub(crate) struct ELFImmediateBindingOption;
 
-impl<'t> BinarySecurityOption<'t> for ELFImmediateBindingOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        _options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for ELFImmediateBindingOption {
+    fn check(&self, parser: &BinaryParser, _context: &AnalysisContext) -> Result<CheckResult> {
         let r = if let goblin::Object::Elf(elf) = parser.object() {
             YesNoUnknownStatus::new("IMMEDIATE-BIND", elf::requires_immediate_binding(elf))
         } else {
             YesNoUnknownStatus::unknown("IMMEDIATE-BIND")
         };
-        Ok(Box::new(r))
+        Ok(r.into())
     }
 }
 
 pub(crate) struct ELFFortifySourceOption {
     libc_spec: Option<cmdline::LibCSpec>,
+    /// C runtime library used by the check, once it is resolved.
+    libc_source: OnceLock<LibCSource>,
 }
 
 impl ELFFortifySourceOption {
     pub(crate) fn new(libc_spec: Option<cmdline::LibCSpec>) -> Self {
-        Self { libc_spec }
+        Self {
+            libc_spec,
+            libc_source: OnceLock::new(),
+        }
+    }
+
+    /// Returns the C runtime library that checked functions were compared against, if the check
+    /// resolved it.
+    pub(crate) fn libc_source(self) -> Option<LibCSource> {
+        self.libc_source.into_inner()
+    }
+
+    /// Returns `true` if the check failed because the C runtime library could not be resolved.
+    pub(crate) fn libc_is_unresolved(&self) -> bool {
+        matches!(self.libc_source.get(), Some(LibCSource::Unresolved))
     }
 }
 
-impl<'t> BinarySecurityOption<'t> for ELFFortifySourceOption {
-    fn check(
-        &self,
-        parser: &BinaryParser,
-        options: &crate::cmdline::Options,
-    ) -> Result<Box<dyn DisplayInColorTerm>> {
+impl BinarySecurityOption<'_> for ELFFortifySourceOption {
+    fn check(&self, parser: &BinaryParser, context: &AnalysisContext) -> Result<CheckResult> {
         if let goblin::Object::Elf(elf) = parser.object() {
             let libc = if let Some(spec) = self.libc_spec {
                 NeededLibC::from_spec(spec)
-            } else if let Some(path) = &options.libc {
-                NeededLibC::open_elf_for_architecture(path, elf)?
             } else {
-                LibCResolver::get(options)?.find_needed_by_executable(elf)?
+                let libc = if context.options.libc.is_empty() {
+                    context
+                        .libc_resolver()
+                        .and_then(|resolver| resolver.find_needed_by_executable(parser.path(), elf))
+                } else {
+                    NeededLibC::open_first_elf_for_architecture(&context.options.libc, elf)
+                };
+
+                match (libc, context.options.libc_fallback) {
+                    (Ok(libc), _) => libc,
+
+                    (Err(error), Some(spec)) => {
+                        warn!(
+                            "{}: Assuming that the C runtime library conforms to {spec}. {}",
+                            parser.path().display(),
+                            crate::format_error(&error)
+                        );
+                        NeededLibC::from_spec(spec)
+                    }
+
+                    (Err(error), None) => {
+                        let _ignored = self.libc_source.set(LibCSource::Unresolved);
+                        return Err(error);
+                    }
+                }
             };
+            let _ignored = self.libc_source.set(libc.source().clone());
+
+            // The lack of checked functions in musl does not imply missing hardening.
+            if libc.is_musl() {
+                return Ok(if elf::uses_fortify_headers(parser, elf) {
+                    DetailedStatus::new(
+                        "FORTIFY-SOURCE",
+                        SupportLevel::Supported,
+                        vec!["fortify-headers".into()],
+                    )
+                    .into()
+                } else {
+                    NotApplicableStatus::new("FORTIFY-SOURCE", "musl").into()
+                });
+            }
 
-            let result = ELFFortifySourceStatus::new(libc, elf)?;
-            Ok(Box::new(result))
+            Ok(ELFFortifySourceStatus::new(&libc, elf).into())
         } else {
-            Ok(Box::new(YesNoUnknownStatus::unknown("FORTIFY-SOURCE")))
+            Ok(YesNoUnknownStatus::unknown("FORTIFY-SOURCE").into())
         }
     }
 }
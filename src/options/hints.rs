@@ -0,0 +1,163 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+/// Remediation hints, by keyword of security feature.
+static REMEDIATION_HINTS: &[(&str, &str)] = &[
+    (
+        "ASLR",
+        "GCC, Clang: compile with -fPIE and link with -pie. \
+         MSVC: link with /DYNAMICBASE and without /FIXED, and with /HIGHENTROPYVA and \
+         /LARGEADDRESSAWARE for 64-bits. \
+         Rust: use the default relocation model (-C relocation-model=pie). \
+         Go: build with -buildmode=pie.",
+    ),
+    (
+        "STACK-PROT",
+        "GCC, Clang: compile with -fstack-protector-strong. \
+         Rust: compile with -Z stack-protector=strong (nightly only). \
+         Go: not applicable. \
+         For a thread-local canary, GCC on PowerPC, s390x, AArch64, RISC-V: \
+         compile with -mstack-protector-guard=tls.",
+    ),
+    (
+        "READ-ONLY-RELOC",
+        "GCC, Clang: link with -Wl,-z,relro. \
+         Rust: use the default RELRO level (-C relro-level=full). \
+         Go: build with -buildmode=pie, or link externally with -ldflags=-linkmode=external. \
+         32-bits PowerPC: compile with -msecure-plt and link with --secure-plt.",
+    ),
+    (
+        "IMMEDIATE-BIND",
+        "GCC, Clang: link with -Wl,-z,now. \
+         Rust: use the default RELRO level (-C relro-level=full). \
+         Go: link externally with -ldflags='-linkmode=external -extldflags=-Wl,-z,now'.",
+    ),
+    (
+        "FORTIFY-SOURCE",
+        "GCC, Clang: compile with optimizations enabled (-O1 or higher) and -D_FORTIFY_SOURCE=2 \
+         or -D_FORTIFY_SOURCE=3.",
+    ),
+    (
+        "DATA-EXEC-PREVENT",
+        "MSVC: link with /NXCOMPAT. MinGW GCC, Clang: link with -Wl,--nxcompat.",
+    ),
+    (
+        "CONTROL-FLOW-GUARD",
+        "MSVC: compile and link with /guard:cf, and link with /DYNAMICBASE. \
+         Clang: compile with -Xclang -cfguard, or use clang-cl /guard:cf. \
+         Rust: compile with -C control-flow-guard.",
+    ),
+    (
+        "HANDLES-ADDR-GT-2GB",
+        "MSVC: link with /LARGEADDRESSAWARE. MinGW GCC, Clang: link with -Wl,--large-address-aware.",
+    ),
+    ("CHECKSUM", "MSVC: link with /RELEASE."),
+    ("RUNS-IN-APP-CONTAINER", "MSVC: link with /APPCONTAINER."),
+    (
+        "VERIFY-DIGITAL-CERT",
+        "MSVC: link with /INTEGRITYCHECK, then sign the executable.",
+    ),
+    ("CONSIDER-MANIFEST", "MSVC: do not link with /ALLOWISOLATION:NO."),
+    (
+        "MANIFEST",
+        "Do not combine level=\"requireAdministrator\" with uiAccess=\"true\" in the \
+         application manifest; request uiAccess only for accessibility tools.",
+    ),
+    (
+        "EXPORT-AUDIT",
+        "MSVC: link COM servers with /INTEGRITYCHECK, then sign them, and register them from a \
+         directory that only administrators can write to.",
+    ),
+    (
+        "SAFE-DLL-SEARCH",
+        "MSVC: link with /DEPENDENTLOADFLAG:0x800 to search imported DLLs in System32 only, or \
+         with /DEPENDENTLOADFLAG:0x1000 to also search the directory of the application.",
+    ),
+    (
+        "IAT-ANOMALY",
+        "Import functions by name, by exporting them without NONAME in the .def files of DLLs, \
+         and keep the placement of import address tables chosen by the linker.",
+    ),
+    (
+        "ENTRY-POINT",
+        "Obtain the binary again from a trusted source; packers and file infectors redirect the \
+         entry point to the code they add.",
+    ),
+    (
+        "HEADER-INCONSISTENT",
+        "Obtain the binary again from a trusted source; linkers produce consistent section \
+         headers and program headers, but tools that modify binaries might not.",
+    ),
+    (
+        "DETERMINISTIC-AR",
+        "GNU ar, llvm-ar: create archives with the D modifier (e.g., ar rcsD), or build binutils \
+         with --enable-deterministic-archives. ranlib: specify -D.",
+    ),
+    (
+        "POLICY",
+        "Enable the hardening options of the missing security features, as hinted for each of \
+         them.",
+    ),
+    (
+        "VULNERABLE-LIBS",
+        "Upgrade the listed libraries to versions that fix the listed vulnerabilities, and \
+         rebuild the binaries that link them statically.",
+    ),
+    (
+        "BRANCH-PROTECTION",
+        "GCC, Clang: compile all linked objects with -mbranch-protection=standard. \
+         Rust: compile with -Z branch-protection=bti,pac-ret (nightly only).",
+    ),
+    (
+        "MTE",
+        "Clang: compile and link with -fsanitize=memtag-heap,memtag-stack -march=armv8+memtag. \
+         Android: set android:memtagMode in the application manifest.",
+    ),
+    (
+        "SAFE-SEH",
+        "MSVC: link with /SAFESEH (32-bits x86 only), which requires all linked objects to be \
+         compiled with SafeSEH support.",
+    ),
+    (
+        "HEAP-HARDENING",
+        "Use the memory allocator of the system instead of an embedded legacy allocator. \
+         For 32-bits Windows executables, call \
+         HeapSetInformation(NULL, HeapEnableTerminationOnCorruption, NULL, 0) on startup.",
+    ),
+    (
+        "DWARF-HARDENING",
+        "GCC: compile all objects with -fstack-protector-strong, -fstack-clash-protection and, \
+         for x86, -fcf-protection, including static libraries and objects built by other build \
+         systems.",
+    ),
+    (
+        "AUTO-VAR-INIT",
+        "GCC 12+, Clang 16+: compile with -ftrivial-auto-var-init=zero, and with \
+         -frecord-gcc-switches so that the option can be verified.",
+    ),
+    (
+        "RELOC-TYPES",
+        "GCC, Clang: compile executables with -fPIE, so that they access data of shared \
+         libraries through the GOT instead of COPY relocations.",
+    ),
+    (
+        "ABI-TAG",
+        "Rebuild with a current toolchain and C runtime library, targeting a supported kernel.",
+    ),
+    (
+        "SYMBOL-VISIBILITY",
+        "GCC, Clang: compile with -fvisibility=hidden, and do not link with -rdynamic or \
+         --export-dynamic unless the executable loads plugins.",
+    ),
+];
+
+/// Returns a hint about how to enable the security feature identified by `name`.
+pub(crate) fn remediation_hint(name: &str) -> Option<&'static str> {
+    REMEDIATION_HINTS
+        .iter()
+        .find(|&&(feature, _hint)| feature == name)
+        .map(|&(_feature, hint)| hint)
+}
@@ -0,0 +1,100 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Stable identifiers of checks, e.g., `ELF001` for `ASLR` of ELF files.
+//!
+//! Identifiers are never reused nor renumbered, so that stored reports, baselines and filters
+//! keep referring to the same checks, even if the keywords of security features change.
+
+use crate::report::BinaryFormat;
+
+/// Identifiers of checks, by binary format and keyword of security feature.
+///
+/// New checks are appended with the next free number of their binary format.
+static CHECK_IDS: &[(BinaryFormat, &str, &str)] = &[
+    (BinaryFormat::ELF, "ASLR", "ELF001"),
+    (BinaryFormat::ELF, "STACK-PROT", "ELF002"),
+    (BinaryFormat::ELF, "READ-ONLY-RELOC", "ELF003"),
+    (BinaryFormat::ELF, "IMMEDIATE-BIND", "ELF004"),
+    (BinaryFormat::ELF, "FORTIFY-SOURCE", "ELF005"),
+    (BinaryFormat::ELF, "HEAP-HARDENING", "ELF006"),
+    (BinaryFormat::ELF, "AUTO-VAR-INIT", "ELF007"),
+    (BinaryFormat::ELF, "TAMPERED?", "ELF008"),
+    (BinaryFormat::ELF, "OVERLAY", "ELF009"),
+    (BinaryFormat::ELF, "IFUNC", "ELF010"),
+    (BinaryFormat::ELF, "SAFE-EXPORTS", "ELF011"),
+    (BinaryFormat::ELF, "SYMBOL-VISIBILITY", "ELF012"),
+    (BinaryFormat::ELF, "CAPABILITIES", "ELF013"),
+    (BinaryFormat::ELF, "ENTRY-POINT", "ELF014"),
+    (BinaryFormat::ELF, "ABI-TAG", "ELF015"),
+    (BinaryFormat::ELF, "MAC-CONTEXT", "ELF016"),
+    (BinaryFormat::ELF, "ANDROID", "ELF017"),
+    (BinaryFormat::ELF, "BRANCH-PROTECTION", "ELF018"),
+    (BinaryFormat::ELF, "MTE", "ELF019"),
+    (BinaryFormat::ELF, "PACKED", "ELF020"),
+    (BinaryFormat::ELF, "RELOC-TYPES", "ELF021"),
+    (BinaryFormat::ELF, "BUNDLE", "ELF022"),
+    (BinaryFormat::ELF, "IMAGE-BASE", "ELF023"),
+    (BinaryFormat::ELF, "DWARF-HARDENING", "ELF024"),
+    (BinaryFormat::ELF, "HEADER-INCONSISTENT", "ELF025"),
+    (BinaryFormat::ELF, "VULNERABLE-LIBS", "ELF026"),
+    (BinaryFormat::ELF, "POLICY", "ELF027"),
+    (BinaryFormat::ELF, "DETERMINISTIC-AR", "ELF028"),
+    (BinaryFormat::ELF, "DYNAMIC-FLAGS", "ELF029"),
+    (BinaryFormat::PE, "ASLR", "PE001"),
+    (BinaryFormat::PE, "DATA-EXEC-PREVENT", "PE002"),
+    (BinaryFormat::PE, "HANDLES-ADDR-GT-2GB", "PE003"),
+    (BinaryFormat::PE, "CONTROL-FLOW-GUARD", "PE004"),
+    (BinaryFormat::PE, "SAFE-SEH", "PE005"),
+    (BinaryFormat::PE, "CHECKSUM", "PE006"),
+    (BinaryFormat::PE, "RUNS-IN-APP-CONTAINER", "PE007"),
+    (BinaryFormat::PE, "CONSIDER-MANIFEST", "PE008"),
+    (BinaryFormat::PE, "VERIFY-DIGITAL-CERT", "PE009"),
+    (BinaryFormat::PE, "HEAP-HARDENING", "PE010"),
+    (BinaryFormat::PE, "GUARD-FLAGS", "PE011"),
+    (BinaryFormat::PE, "HYBRID", "PE012"),
+    (BinaryFormat::PE, "MANIFEST", "PE013"),
+    (BinaryFormat::PE, "RES-ANOMALY", "PE014"),
+    (BinaryFormat::PE, "TLS-CALLBACKS", "PE015"),
+    (BinaryFormat::PE, "ENTRY-POINT", "PE016"),
+    (BinaryFormat::PE, "OVERLAY", "PE017"),
+    (BinaryFormat::PE, "PACKED", "PE018"),
+    (BinaryFormat::PE, "EXPORT-AUDIT", "PE019"),
+    (BinaryFormat::PE, "SAFE-DLL-SEARCH", "PE020"),
+    (BinaryFormat::PE, "IMAGE-BASE", "PE021"),
+    (BinaryFormat::PE, "VULNERABLE-LIBS", "PE022"),
+    (BinaryFormat::PE, "IAT-ANOMALY", "PE023"),
+    (BinaryFormat::Legacy, "LEGACY-FORMAT", "LEG001"),
+    (BinaryFormat::EFI, "ASLR", "EFI001"),
+    (BinaryFormat::EFI, "DATA-EXEC-PREVENT", "EFI002"),
+    (BinaryFormat::EFI, "ENTRY-POINT", "EFI003"),
+    (BinaryFormat::EFI, "EFI-CONTAINER", "EFI004"),
+    (BinaryFormat::Kernel, "ASLR", "KRN001"),
+    (BinaryFormat::Kernel, "DATA-EXEC-PREVENT", "KRN002"),
+    (BinaryFormat::Kernel, "KERNEL", "KRN003"),
+];
+
+/// Returns the stable identifier of the check reporting the security feature identified by
+/// `name`, in files of the given format.
+///
+/// Archives are made of ELF objects, so their checks share the identifiers of ELF checks.
+pub(crate) fn check_id(format: BinaryFormat, name: &str) -> Option<&'static str> {
+    let format = match format {
+        BinaryFormat::Archive => BinaryFormat::ELF,
+        format => format,
+    };
+
+    CHECK_IDS
+        .iter()
+        .find(|&&(id_format, feature, _id)| id_format == format && feature == name)
+        .map(|&(_format, _feature, id)| id)
+}
+
+/// Returns the binary format, the keyword of the security feature, and the stable identifier of
+/// every check.
+pub(crate) fn check_ids() -> impl Iterator<Item = (BinaryFormat, &'static str, &'static str)> {
+    CHECK_IDS.iter().copied()
+}
@@ -4,26 +4,160 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
-use core::marker::PhantomPinned;
-use core::pin::Pin;
-use core::ptr::NonNull;
+use core::fmt;
 use std::collections::HashSet;
+use std::io;
+use std::time::Duration;
 
 use crate::elf;
 use crate::elf::needed_libc::NeededLibC;
 use crate::errors::{Error, Result};
+use crate::isolate::ReplayedStatus;
 
 pub(crate) const MARKER_GOOD: char = '+';
 pub(crate) const MARKER_BAD: char = '!';
 pub(crate) const MARKER_MAYBE: char = '~';
 pub(crate) const MARKER_UNKNOWN: char = '?';
+pub(crate) const MARKER_INFO: char = '*';
 
 pub(crate) const COLOR_GOOD: termcolor::Color = termcolor::Color::Green;
 pub(crate) const COLOR_BAD: termcolor::Color = termcolor::Color::Red;
 pub(crate) const COLOR_UNKNOWN: termcolor::Color = termcolor::Color::Yellow;
+pub(crate) const COLOR_INFO: termcolor::Color = termcolor::Color::Cyan;
 
-pub(crate) trait DisplayInColorTerm {
+pub(crate) trait DisplayInColorTerm: Send {
     fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()>;
+
+    /// Returns the keyword identifying the security feature.
+    fn name(&self) -> &'static str;
+
+    /// Returns the level of support of the security feature, or `None` if the result is only
+    /// informational.
+    fn level(&self) -> Option<SupportLevel>;
+
+    /// Returns the reason why the security feature does not apply to the file, and was not
+    /// checked, or `None` if it applies.
+    fn not_applicable_reason(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Result of a check, along with the time spent performing the check, if it was measured.
+///
+/// Results are plain values, rendered only when they are reported, so that no allocation is
+/// needed per check beyond the details the check records.
+pub(crate) struct CheckResult {
+    status: CheckStatus,
+    duration: Option<Duration>,
+}
+
+enum CheckStatus {
+    YesNoUnknown(YesNoUnknownStatus),
+    Detailed(DetailedStatus),
+    Informational(InformationalStatus),
+    NotApplicable(NotApplicableStatus),
+    ControlFlowGuard(PEControlFlowGuardLevel),
+    ASLR(ASLRCompatibilityLevel),
+    FortifySource(ELFFortifySourceStatus),
+    Replayed(ReplayedStatus),
+}
+
+impl CheckResult {
+    /// Records the time spent performing the check.
+    pub(crate) fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Returns the time spent checking the security feature, if it was measured.
+    pub(crate) fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub(crate) fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+        self.status().display_in_color_term(wc)
+    }
+
+    /// Returns the keyword identifying the security feature.
+    pub(crate) fn name(&self) -> &'static str {
+        self.status().name()
+    }
+
+    /// Returns the level of support of the security feature, or `None` if the result is only
+    /// informational.
+    pub(crate) fn level(&self) -> Option<SupportLevel> {
+        self.status().level()
+    }
+
+    /// Returns the reason why the security feature does not apply to the file, and was not
+    /// checked, or `None` if it applies.
+    pub(crate) fn not_applicable_reason(&self) -> Option<&str> {
+        self.status().not_applicable_reason()
+    }
+
+    fn status(&self) -> &dyn DisplayInColorTerm {
+        match &self.status {
+            CheckStatus::YesNoUnknown(status) => status,
+            CheckStatus::Detailed(status) => status,
+            CheckStatus::Informational(status) => status,
+            CheckStatus::NotApplicable(status) => status,
+            CheckStatus::ControlFlowGuard(status) => status,
+            CheckStatus::ASLR(status) => status,
+            CheckStatus::FortifySource(status) => status,
+            CheckStatus::Replayed(status) => status,
+        }
+    }
+}
+
+/// Displays the result as plain text, as reported in structured reports.
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut text = termcolor::NoColor::new(FormatterWriter(f));
+        self.display_in_color_term(&mut text)
+            .map_err(|_r| fmt::Error)
+    }
+}
+
+/// Writes text to a formatter.
+struct FormatterWriter<'f, 'a>(&'f mut fmt::Formatter<'a>);
+
+impl io::Write for FormatterWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .write_str(&String::from_utf8_lossy(buf))
+            .map_err(io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! check_result_from {
+    ($($variant:ident($type:ty),)*) => {
+        $(
+            impl From<$type> for CheckResult {
+                fn from(status: $type) -> Self {
+                    Self {
+                        status: CheckStatus::$variant(status),
+                        duration: None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+check_result_from! {
+    YesNoUnknown(YesNoUnknownStatus),
+    Detailed(DetailedStatus),
+    Informational(InformationalStatus),
+    NotApplicable(NotApplicableStatus),
+    ControlFlowGuard(PEControlFlowGuardLevel),
+    ASLR(ASLRCompatibilityLevel),
+    FortifySource(ELFFortifySourceStatus),
+    Replayed(ReplayedStatus),
 }
 
 pub(crate) struct YesNoUnknownStatus {
@@ -45,6 +179,18 @@ impl YesNoUnknownStatus {
 }
 
 impl DisplayInColorTerm for YesNoUnknownStatus {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        Some(match self.status {
+            Some(true) => SupportLevel::Supported,
+            Some(false) => SupportLevel::Unsupported,
+            None => SupportLevel::Unknown,
+        })
+    }
+
     fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
         let (marker, color) = match self.status {
             Some(true) => (MARKER_GOOD, COLOR_GOOD),
@@ -62,6 +208,183 @@ impl DisplayInColorTerm for YesNoUnknownStatus {
     }
 }
 
+/// Level of support of a security feature.
+///
+/// Levels are ordered from the most problematic to the least problematic.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub(crate) enum SupportLevel {
+    /// The security feature is unsupported.
+    Unsupported,
+    /// The security feature is probably supported, or only partially supported.
+    Partial,
+    /// Support of the security feature is unknown.
+    Unknown,
+    /// The security feature is supported.
+    Supported,
+}
+
+impl SupportLevel {
+    /// All support levels, from the least to the most supported.
+    pub(crate) const ALL: [Self; 4] = [
+        SupportLevel::Unsupported,
+        SupportLevel::Partial,
+        SupportLevel::Unknown,
+        SupportLevel::Supported,
+    ];
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            SupportLevel::Unsupported => "Unsupported",
+            SupportLevel::Partial => "Partially supported",
+            SupportLevel::Unknown => "Unknown",
+            SupportLevel::Supported => "Supported",
+        }
+    }
+
+    pub(crate) fn marker_and_color(self) -> (char, termcolor::Color) {
+        match self {
+            SupportLevel::Unknown => (MARKER_UNKNOWN, COLOR_UNKNOWN),
+            SupportLevel::Unsupported => (MARKER_BAD, COLOR_BAD),
+            SupportLevel::Partial => (MARKER_MAYBE, COLOR_UNKNOWN),
+            SupportLevel::Supported => (MARKER_GOOD, COLOR_GOOD),
+        }
+    }
+}
+
+/// Level of support of a security feature, along with details explaining that level.
+pub(crate) struct DetailedStatus {
+    name: &'static str,
+    level: SupportLevel,
+    details: Vec<String>,
+}
+
+impl DetailedStatus {
+    pub(crate) fn new(name: &'static str, level: SupportLevel, details: Vec<String>) -> Self {
+        Self {
+            name,
+            level,
+            details,
+        }
+    }
+
+    pub(crate) fn unknown(name: &'static str) -> Self {
+        Self::new(name, SupportLevel::Unknown, Vec::default())
+    }
+
+    /// Returns the status of a check that was not performed because of `--fast`.
+    pub(crate) fn skipped(name: &'static str) -> Self {
+        Self::new(name, SupportLevel::Unknown, vec!["skipped".into()])
+    }
+}
+
+impl DisplayInColorTerm for DetailedStatus {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        Some(self.level)
+    }
+
+    fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+        let (marker, color) = self.level.marker_and_color();
+
+        wc.set_color(termcolor::ColorSpec::new().set_fg(Some(color)))
+            .map_err(|r| Error::from_io1(r, "set color", "standard output stream"))?;
+
+        write!(wc, "{marker}{}", self.name)
+            .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+        if !self.details.is_empty() {
+            write!(wc, "({})", self.details.join(","))
+                .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+        }
+
+        wc.reset()
+            .map_err(|r| Error::from_io1(r, "reset", "standard output stream"))
+    }
+}
+
+/// Information that neither indicates the presence nor the absence of a security feature, but
+/// helps interpreting other results.
+pub(crate) struct InformationalStatus {
+    name: &'static str,
+    details: Vec<String>,
+}
+
+impl InformationalStatus {
+    pub(crate) fn new(name: &'static str, details: Vec<String>) -> Self {
+        Self { name, details }
+    }
+}
+
+impl DisplayInColorTerm for InformationalStatus {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        None
+    }
+
+    fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+        wc.set_color(termcolor::ColorSpec::new().set_fg(Some(COLOR_INFO)))
+            .map_err(|r| Error::from_io1(r, "set color", "standard output stream"))?;
+
+        write!(wc, "{MARKER_INFO}{}", self.name)
+            .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+        if !self.details.is_empty() {
+            write!(wc, "({})", self.details.join(","))
+                .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+        }
+
+        wc.reset()
+            .map_err(|r| Error::from_io1(r, "reset", "standard output stream"))
+    }
+}
+
+/// Security feature that was not checked because it does not apply to the file, e.g., due to
+/// `--no-libc`.
+pub(crate) struct NotApplicableStatus {
+    name: &'static str,
+    /// Reason why the security feature does not apply, e.g., `--no-libc`.
+    reason: &'static str,
+}
+
+impl NotApplicableStatus {
+    pub(crate) fn new(name: &'static str, reason: &'static str) -> Self {
+        Self { name, reason }
+    }
+}
+
+impl DisplayInColorTerm for NotApplicableStatus {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        None
+    }
+
+    fn not_applicable_reason(&self) -> Option<&str> {
+        Some(self.reason)
+    }
+
+    fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+        wc.set_color(termcolor::ColorSpec::new().set_fg(Some(COLOR_INFO)))
+            .map_err(|r| Error::from_io1(r, "set color", "standard output stream"))?;
+
+        write!(wc, "{MARKER_INFO}{}(N/A,{})", self.name, self.reason)
+            .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+        wc.reset()
+            .map_err(|r| Error::from_io1(r, "reset", "standard output stream"))
+    }
+}
+
 /// [Control Flow Guard](https://docs.microsoft.com/en-us/cpp/build/reference/guard-enable-guard-checks).
 pub(crate) enum PEControlFlowGuardLevel {
     /// Control Flow Guard support is unknown.
@@ -76,6 +399,19 @@ pub(crate) enum PEControlFlowGuardLevel {
 }
 
 impl DisplayInColorTerm for PEControlFlowGuardLevel {
+    fn name(&self) -> &'static str {
+        "CONTROL-FLOW-GUARD"
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        Some(match *self {
+            PEControlFlowGuardLevel::Unknown => SupportLevel::Unknown,
+            PEControlFlowGuardLevel::Unsupported => SupportLevel::Unsupported,
+            PEControlFlowGuardLevel::Ineffective => SupportLevel::Partial,
+            PEControlFlowGuardLevel::Supported => SupportLevel::Supported,
+        })
+    }
+
     fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
         let (marker, color) = match *self {
             PEControlFlowGuardLevel::Unknown => (MARKER_UNKNOWN, COLOR_UNKNOWN),
@@ -99,6 +435,9 @@ pub(crate) enum ASLRCompatibilityLevel {
     Unknown,
     /// Address Space Layout Randomization is unsupported.
     Unsupported,
+    /// Address Space Layout Randomization is claimed, but the image has no base relocations, so
+    /// the loader cannot relocate it.
+    UnsupportedWithoutRelocations,
     /// Address Space Layout Randomization is supported, but might be expensive.
     /// This usually happens when an executable has a preferred base address explicitly specified.
     Expensive,
@@ -115,10 +454,30 @@ pub(crate) enum ASLRCompatibilityLevel {
 }
 
 impl DisplayInColorTerm for ASLRCompatibilityLevel {
+    fn name(&self) -> &'static str {
+        "ASLR"
+    }
+
+    fn level(&self) -> Option<SupportLevel> {
+        Some(match *self {
+            ASLRCompatibilityLevel::Unknown => SupportLevel::Unknown,
+            ASLRCompatibilityLevel::Unsupported
+            | ASLRCompatibilityLevel::UnsupportedWithoutRelocations => SupportLevel::Unsupported,
+            ASLRCompatibilityLevel::Expensive
+            | ASLRCompatibilityLevel::SupportedLowEntropyBelow2G
+            | ASLRCompatibilityLevel::SupportedLowEntropy
+            | ASLRCompatibilityLevel::SupportedBelow2G => SupportLevel::Partial,
+            ASLRCompatibilityLevel::Supported => SupportLevel::Supported,
+        })
+    }
+
     fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
         let (marker, color, text) = match *self {
             ASLRCompatibilityLevel::Unknown => (MARKER_UNKNOWN, COLOR_UNKNOWN, "ASLR"),
             ASLRCompatibilityLevel::Unsupported => (MARKER_BAD, COLOR_BAD, "ASLR"),
+            ASLRCompatibilityLevel::UnsupportedWithoutRelocations => {
+                (MARKER_BAD, COLOR_BAD, "ASLR(no-base-relocs)")
+            }
             ASLRCompatibilityLevel::Expensive => (MARKER_MAYBE, COLOR_UNKNOWN, "ASLR-EXPENSIVE"),
             ASLRCompatibilityLevel::SupportedLowEntropyBelow2G => {
                 (MARKER_MAYBE, COLOR_UNKNOWN, "ASLR-LOW-ENTROPY-LT-2GB")
@@ -143,87 +502,52 @@ impl DisplayInColorTerm for ASLRCompatibilityLevel {
 }
 
 pub(crate) struct ELFFortifySourceStatus {
-    libc: NeededLibC,
-    protected_functions: HashSet<&'static str>,
-    unprotected_functions: HashSet<&'static str>,
-    _pin: PhantomPinned,
+    protected_functions: HashSet<String>,
+    unprotected_functions: HashSet<String>,
 }
 
 impl ELFFortifySourceStatus {
-    pub(crate) fn new(libc: NeededLibC, elf_object: &goblin::elf::Elf) -> Result<Pin<Box<Self>>> {
-        let mut result = Box::pin(Self {
-            libc,
-            protected_functions: HashSet::default(),
-            unprotected_functions: HashSet::default(),
-            _pin: PhantomPinned,
-        });
-
-        // SAFETY:
-        // `result` is now allocated, initialized and pinned on the heap.
-        // Its location is therefore stable, and we can store references to it
-        // in other places.
-        //
-        // Construct a reference to `result.libc` that lives for the 'static
-        // life time:
-        //     &ref => pointer => 'static ref
-        //
-        // This is safe because the `Drop` implementation drops the fields
-        // `Self::protected_functions` and `Self::unprotected_functions`
-        // before the field `Self::libc`.
-        let libc_ref: &'static NeededLibC =
-            unsafe { NonNull::from(&result.libc).as_ptr().as_ref().unwrap() };
-
-        let (prot_fn, unprot_fn) = elf::get_libc_functions_by_protection(elf_object, libc_ref);
-
-        // SAFETY: Storing to the field `protected_functions` does not move `result`.
-        unsafe { Pin::get_unchecked_mut(result.as_mut()) }.protected_functions = prot_fn;
-
-        // SAFETY: Storing to the field `unprotected_functions` does not move `result`.
-        unsafe { Pin::get_unchecked_mut(result.as_mut()) }.unprotected_functions = unprot_fn;
-
-        Ok(result)
-    }
-
-    fn drop_pinned(mut self: Pin<&mut Self>) {
-        // SAFETY: Drop fields `protected_functions` and `unprotected_functions`
-        // before field `libc` is dropped.
-        let this = Pin::as_mut(&mut self);
-
-        // SAFETY: Calling `HashSet::clear()` does not move `this`.
-        let this = unsafe { Pin::get_unchecked_mut(this) };
-
-        this.protected_functions.clear();
-        this.unprotected_functions.clear();
+    pub(crate) fn new(libc: &NeededLibC, elf_object: &goblin::elf::Elf) -> Self {
+        let (prot_fn, unprot_fn) = elf::get_libc_functions_by_protection(elf_object, libc);
+
+        Self {
+            protected_functions: prot_fn.into_iter().map(str::to_owned).collect(),
+            unprotected_functions: unprot_fn.into_iter().map(str::to_owned).collect(),
+        }
     }
 }
 
-impl Drop for ELFFortifySourceStatus {
-    fn drop(&mut self) {
-        // SAFETY: All instances of `Self` are pinned.
-        unsafe { Pin::new_unchecked(self) }.drop_pinned();
+impl DisplayInColorTerm for ELFFortifySourceStatus {
+    fn name(&self) -> &'static str {
+        "FORTIFY-SOURCE"
     }
-}
 
-impl DisplayInColorTerm for Pin<Box<ELFFortifySourceStatus>> {
-    fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+    fn level(&self) -> Option<SupportLevel> {
         let no_protected_functions = self.protected_functions.is_empty();
         let no_unprotected_functions = self.unprotected_functions.is_empty();
 
-        let (marker, color) = match (no_protected_functions, no_unprotected_functions) {
+        Some(match (no_protected_functions, no_unprotected_functions) {
             // Neither protected not unprotected functions are used. The binary can still be secure,
             // if it does not use these functions.
-            (true, true) => (MARKER_UNKNOWN, COLOR_UNKNOWN),
+            (true, true) => SupportLevel::Unknown,
             // Only unprotected functions are used.
-            (true, false) => (MARKER_BAD, COLOR_BAD),
+            (true, false) => SupportLevel::Unsupported,
             // Only protected functions are used.
-            (false, true) => (MARKER_GOOD, COLOR_GOOD),
+            (false, true) => SupportLevel::Supported,
             // Both protected and unprotected functions are used. This usually indicates a compiler
             // that, through static analysis, proves that some usage of the unprotected functions
             // is actually safe, and for those instances, does not call the protected functions.
             // It can also indicate that multiple object files have been compiled with different
             // compiler flags (with and without `FORTIFY_SOURCE`) then linked together.
-            (false, false) => (MARKER_MAYBE, COLOR_UNKNOWN),
-        };
+            (false, false) => SupportLevel::Partial,
+        })
+    }
+
+    fn display_in_color_term(&self, wc: &mut dyn termcolor::WriteColor) -> Result<()> {
+        let (marker, color) = self.level().map_or(
+            (MARKER_UNKNOWN, COLOR_UNKNOWN),
+            SupportLevel::marker_and_color,
+        );
 
         let set_color_err = |r| Error::from_io1(r, "set color", "standard output stream");
 
@@ -241,7 +565,7 @@ impl DisplayInColorTerm for Pin<Box<ELFFortifySourceStatus>> {
             .map_err(set_color_err)?;
 
         let mut separator = "";
-        for &name in &self.protected_functions {
+        for name in &self.protected_functions {
             write!(wc, "{separator}{MARKER_GOOD}{name}")
                 .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
             separator = ",";
@@ -250,7 +574,7 @@ impl DisplayInColorTerm for Pin<Box<ELFFortifySourceStatus>> {
         wc.set_color(termcolor::ColorSpec::new().set_fg(Some(COLOR_BAD)))
             .map_err(set_color_err)?;
 
-        for &name in &self.unprotected_functions {
+        for name in &self.unprotected_functions {
             write!(wc, "{separator}{MARKER_BAD}{name}")
                 .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
             separator = ",";
@@ -258,8 +582,7 @@ impl DisplayInColorTerm for Pin<Box<ELFFortifySourceStatus>> {
 
         wc.reset()
             .map_err(|r| Error::from_io1(r, "reset", "standard output stream"))?;
-        writeln!(wc, ")")
-            .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+        write!(wc, ")").map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
         Ok(())
     }
 }
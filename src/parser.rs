@@ -4,86 +4,186 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
-use core::marker::PhantomPinned;
-use core::pin::Pin;
-use core::ptr;
+use std::borrow::Cow;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use log::debug;
 use memmap2::{Mmap, MmapOptions};
 
 use crate::errors::{Error, Result};
 
-pub(crate) struct BinaryParser {
+/// Binary file mapped in memory, which owns the bytes parsed by `BinaryParser`.
+pub(crate) struct BinaryFile {
+    path: PathBuf,
     bytes: Mmap,
-    object: Option<goblin::Object<'static>>,
-    _pin: PhantomPinned,
 }
 
-impl BinaryParser {
-    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Pin<Box<Self>>> {
+/// Binary file parsed into an object, which borrows the bytes of its `BinaryFile`.
+pub(crate) struct BinaryParser<'t> {
+    path: &'t Path,
+    bytes: &'t [u8],
+    object: goblin::Object<'t>,
+}
+
+/// Returns `path` as an extended-length path (e.g., `\\?\C:\dir\file.exe` or
+/// `\\?\UNC\server\share\file.exe`), which is not limited to `MAX_PATH` characters.
+///
+/// Extended-length paths are passed as they are to the file system, so `path` is made absolute
+/// and normalized first. Paths that cannot be made absolute are returned as they are.
+#[cfg(windows)]
+pub(crate) fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    let Ok(absolute) = std::path::absolute(path) else {
+        return Cow::Borrowed(path);
+    };
+
+    let mut components = absolute.components();
+    let mut r = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut r = OsString::from(r"\\?\");
+                r.push(prefix.as_os_str());
+                r
+            }
+
+            Prefix::UNC(server, share) => {
+                let mut r = OsString::from(r"\\?\UNC\");
+                r.push(server);
+                r.push(r"\");
+                r.push(share);
+                r
+            }
+
+            // Verbatim paths and device paths are already passed as they are.
+            Prefix::Verbatim(_)
+            | Prefix::VerbatimUNC(..)
+            | Prefix::VerbatimDisk(_)
+            | Prefix::DeviceNS(_) => return Cow::Owned(absolute),
+        },
+
+        _ => return Cow::Owned(absolute),
+    };
+
+    let mut has_names = false;
+    for component in components {
+        if let Component::Normal(name) = component {
+            r.push(r"\");
+            r.push(name);
+            has_names = true;
+        }
+    }
+    if !has_names {
+        r.push(r"\");
+    }
+    Cow::Owned(r.into())
+}
+
+/// Paths are not limited in length on this platform.
+#[cfg(not(windows))]
+pub(crate) fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// Returns why a file cannot be analyzed, if it is not a regular file, or if it is empty.
+pub(crate) fn unsupported_file_reason(metadata: &fs::Metadata) -> Option<&'static str> {
+    let file_type = metadata.file_type();
+
+    if file_type.is_file() {
+        return (metadata.len() == 0).then_some("empty");
+    }
+
+    if file_type.is_dir() {
+        return Some("a directory");
+    }
+
+    if file_type.is_symlink() {
+        return Some("a symbolic link");
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_fifo() {
+            return Some("a named pipe");
+        }
+        if file_type.is_socket() {
+            return Some("a socket");
+        }
+        if file_type.is_char_device() {
+            return Some("a character device");
+        }
+        if file_type.is_block_device() {
+            return Some("a block device");
+        }
+    }
+
+    Some("not a regular file")
+}
+
+impl BinaryFile {
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self> {
         debug!("Opening binary file '{}'.", path.as_ref().display());
+        let os_path = extended_length_path(path.as_ref());
+
+        // Opening a named pipe blocks, and mapping special or empty files fails cryptically.
+        let metadata = fs::metadata(&os_path)
+            .map_err(|r| Error::from_io1(r, "get metadata of file", path.as_ref()))?;
+        if let Some(reason) = unsupported_file_reason(&metadata) {
+            return Err(Error::SkippedFile {
+                path: path.as_ref().into(),
+                reason,
+            });
+        }
+
         let file =
-            fs::File::open(&path).map_err(|r| Error::from_io1(r, "open file", path.as_ref()))?;
+            fs::File::open(&os_path).map_err(|r| Error::from_io1(r, "open file", path.as_ref()))?;
 
         let bytes = unsafe { MmapOptions::new().map(&file) }
             .map_err(|r| Error::from_io1(r, "map file", path.as_ref()))?;
 
-        let mut result = Box::pin(Self {
+        Ok(Self {
+            path: path.as_ref().into(),
             bytes,
-            object: None,
-            _pin: PhantomPinned,
-        });
-
-        // SAFETY:
-        // `result` is now allocated, initialized and pinned on the heap.
-        // Its location is therefore stable, and we can store references to it
-        // in other places.
-        //
-        // Construct a reference to `result.bytes` that lives for the 'static
-        // life time:
-        //     &ref => pointer => 'static ref
-        //
-        // This is safe because the `Drop` implementation drops `Self::object`
-        // before `Self::bytes`.
-        let bytes_ref: &'static Mmap =
-            unsafe { ptr::NonNull::from(&result.bytes).as_ptr().as_ref().unwrap() };
-
-        debug!("Parsing binary file '{}'.", path.as_ref().display());
-        let object =
-            goblin::Object::parse(bytes_ref).map_err(|source| Error::ParseFile { source })?;
-
-        result.as_mut().set_object(Some(object));
-        Ok(result)
+        })
     }
 
-    pub(crate) fn object(&self) -> &goblin::Object {
-        // SAFETY: All instances of `Self` that are created and still in scope
-        // must have `Some(_)` in the `object` field.
-        self.object.as_ref().unwrap()
+    /// Parses the file. The returned parser borrows the bytes of the file.
+    pub(crate) fn parse(&self) -> Result<BinaryParser<'_>> {
+        BinaryParser::parse(&self.path, &self.bytes)
     }
 
-    pub(crate) fn bytes(&self) -> &[u8] {
-        &self.bytes
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
     }
+}
+
+impl<'t> BinaryParser<'t> {
+    /// Parses `bytes`, which are reported as the binary file at `path`, e.g., an image embedded
+    /// in another file.
+    pub(crate) fn parse(path: &'t Path, bytes: &'t [u8]) -> Result<Self> {
+        debug!("Parsing binary file '{}'.", path.display());
+        let object = goblin::Object::parse(bytes).map_err(|source| Error::ParseFile { source })?;
 
-    fn set_object(mut self: Pin<&mut Self>, object: Option<goblin::Object<'static>>) {
-        let this = Pin::as_mut(&mut self);
+        Ok(Self {
+            path,
+            bytes,
+            object,
+        })
+    }
 
-        // SAFETY: Storing to the field `object` does not move `this`.
-        unsafe { Pin::get_unchecked_mut(this) }.object = object;
+    pub(crate) fn object(&self) -> &goblin::Object<'t> {
+        &self.object
     }
 
-    fn drop_pinned(self: Pin<&mut Self>) {
-        // SAFETY: Drop `object` before `bytes` is dropped.
-        self.set_object(None);
+    pub(crate) fn path(&self) -> &'t Path {
+        self.path
     }
-}
 
-impl Drop for BinaryParser {
-    fn drop(&mut self) {
-        // SAFETY: All instances of `Self` are pinned.
-        unsafe { Pin::new_unchecked(self) }.drop_pinned();
+    pub(crate) fn bytes(&self) -> &'t [u8] {
+        self.bytes
     }
 }
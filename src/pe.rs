@@ -4,41 +4,56 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
+pub(crate) mod resources;
+
 use core::mem::{offset_of, size_of};
+use std::sync::LazyLock;
 
 use goblin::pe::section_table::{IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_READ};
 use log::debug;
+use regex::Regex;
 use scroll::Pread;
 
-use crate::errors::Result;
-use crate::options::status::{ASLRCompatibilityLevel, DisplayInColorTerm, PEControlFlowGuardLevel};
+use crate::cmdline::OptionalCheck;
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+use crate::options::status::{
+    ASLRCompatibilityLevel, CheckResult, DetailedStatus, InformationalStatus,
+    PEControlFlowGuardLevel, SupportLevel,
+};
 use crate::options::{
-    AddressSpaceLayoutRandomizationOption, BinarySecurityOption, DataExecutionPreventionOption,
-    PEControlFlowGuardOption, PEEnableManifestHandlingOption,
-    PEHandlesAddressesLargerThan2GBOption, PEHasCheckSumOption, PERunsOnlyInAppContainerOption,
-    PESafeStructuredExceptionHandlingOption, RequiresIntegrityCheckOption,
+    shannon_entropy, AddressSpaceLayoutRandomizationOption, BinarySecurityOption,
+    DataExecutionPreventionOption, EntryPointOption, HeapHardeningOption, ImageBaseOption,
+    OverlayOption, PEControlFlowGuardOption, PEEnableManifestHandlingOption, PEExportAuditOption,
+    PEGuardFlagsOption, PEHandlesAddressesLargerThan2GBOption, PEHasCheckSumOption,
+    PEHybridImageOption, PEImportAnomaliesOption, PEManifestOption, PEResourceAnomaliesOption,
+    PERunsOnlyInAppContainerOption, PESafeDllSearchOption, PESafeStructuredExceptionHandlingOption,
+    PETLSCallbacksOption, RequiresIntegrityCheckOption, VulnerableLibrariesOption,
 };
 use crate::parser::BinaryParser;
+use crate::report::{is_failing, Architecture, Endianness};
+use crate::vulnerable_libs::VULNERABLE_LIBS;
 
 pub(crate) fn analyze_binary(
     parser: &BinaryParser,
-    options: &crate::cmdline::Options,
-) -> Result<Vec<Box<dyn DisplayInColorTerm>>> {
-    let has_checksum = PEHasCheckSumOption.check(parser, options)?;
+    context: &AnalysisContext,
+) -> Result<Vec<CheckResult>> {
+    let options = context.options;
+    let has_checksum = PEHasCheckSumOption.timed_check(parser, context)?;
     let supports_data_execution_prevention =
-        DataExecutionPreventionOption.check(parser, options)?;
-    let runs_only_in_app_container = PERunsOnlyInAppContainerOption.check(parser, options)?;
-    let enable_manifest_handling = PEEnableManifestHandlingOption.check(parser, options)?;
-    let requires_integrity_check = RequiresIntegrityCheckOption.check(parser, options)?;
-    let supports_control_flow_guard = PEControlFlowGuardOption.check(parser, options)?;
+        DataExecutionPreventionOption.timed_check(parser, context)?;
+    let runs_only_in_app_container = PERunsOnlyInAppContainerOption.timed_check(parser, context)?;
+    let enable_manifest_handling = PEEnableManifestHandlingOption.timed_check(parser, context)?;
+    let requires_integrity_check = RequiresIntegrityCheckOption.timed_check(parser, context)?;
+    let supports_control_flow_guard = PEControlFlowGuardOption.timed_check(parser, context)?;
     let handles_addresses_larger_than_2_gigabytes =
-        PEHandlesAddressesLargerThan2GBOption.check(parser, options)?;
+        PEHandlesAddressesLargerThan2GBOption.timed_check(parser, context)?;
     let supports_address_space_layout_randomization =
-        AddressSpaceLayoutRandomizationOption.check(parser, options)?;
+        AddressSpaceLayoutRandomizationOption.timed_check(parser, context)?;
     let supports_safe_structured_exception_handling =
-        PESafeStructuredExceptionHandlingOption.check(parser, options)?;
+        PESafeStructuredExceptionHandlingOption.timed_check(parser, context)?;
 
-    Ok(vec![
+    let mut result = vec![
         has_checksum,
         supports_data_execution_prevention,
         runs_only_in_app_container,
@@ -48,7 +63,527 @@ pub(crate) fn analyze_binary(
         handles_addresses_larger_than_2_gigabytes,
         supports_address_space_layout_randomization,
         supports_safe_structured_exception_handling,
-    ])
+    ];
+
+    if options.check_is_enabled(OptionalCheck::HeapHardening) {
+        result.push(HeapHardeningOption.timed_check(parser, context)?);
+    }
+
+    if options.check_is_enabled(OptionalCheck::ResourceAnomalies) {
+        if options.fast {
+            result.push(DetailedStatus::skipped("RES-ANOMALY").into());
+        } else {
+            result.push(PEResourceAnomaliesOption.timed_check(parser, context)?);
+        }
+    }
+
+    // Guard flags are detailed only on demand, as they are mostly relevant to people validating
+    // linker settings.
+    if options.verbose {
+        result.push(PEGuardFlagsOption.timed_check(parser, context)?);
+    }
+
+    if OverlayOption::overlay(parser).is_some() {
+        result.push(OverlayOption.timed_check(parser, context)?);
+    }
+
+    // Files are only reported when their entry point is unexpectedly located.
+    let entry_point = EntryPointOption.timed_check(parser, context)?;
+    if is_failing(&entry_point) {
+        result.push(entry_point);
+    }
+
+    if let goblin::Object::PE(pe) = parser.object() {
+        // Images that are not randomized are loaded at their preferred address.
+        if matches!(
+            supports_aslr(pe),
+            ASLRCompatibilityLevel::Unsupported
+                | ASLRCompatibilityLevel::UnsupportedWithoutRelocations
+                | ASLRCompatibilityLevel::Expensive
+        ) {
+            result.push(ImageBaseOption.timed_check(parser, context)?);
+        }
+
+        if tls_callbacks(parser, pe).is_some_and(|count| count > 0) {
+            result.push(PETLSCallbacksOption.timed_check(parser, context)?);
+        }
+
+        if manifest(parser, pe).is_some() {
+            result.push(PEManifestOption.timed_check(parser, context)?);
+        }
+
+        // Executables do not export functions for other processes to load.
+        if pe.is_lib && options.check_is_enabled(OptionalCheck::ExportAudit) {
+            result.push(PEExportAuditOption.timed_check(parser, context)?);
+        }
+
+        if options.check_is_enabled(OptionalCheck::SafeDllSearch) {
+            result.push(PESafeDllSearchOption.timed_check(parser, context)?);
+        }
+
+        if options.check_is_enabled(OptionalCheck::IatAnomalies) {
+            result.push(PEImportAnomaliesOption.timed_check(parser, context)?);
+        }
+
+        if options.check_is_enabled(OptionalCheck::VulnerableLibs) {
+            // Images that do not embed recognized library versions are not reported.
+            if options.fast {
+                result.push(DetailedStatus::skipped(VULNERABLE_LIBS).into());
+            } else {
+                let vulnerable_libs = VulnerableLibrariesOption.timed_check(parser, context)?;
+                if vulnerable_libs.level() != Some(SupportLevel::Unknown) {
+                    result.push(vulnerable_libs);
+                }
+            }
+        }
+
+        // Hybrid executables are otherwise reported as plain x64, x86 or ARM64 executables.
+        if hybrid_image_metadata(parser, pe)?.is_some() {
+            result.push(PEHybridImageOption.timed_check(parser, context)?);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Returns the text of the application manifest embedded in the image, if any.
+pub(crate) fn manifest(parser: &BinaryParser, pe: &goblin::pe::PE) -> Option<String> {
+    let resource = resources::resources(parser.bytes(), pe)
+        .into_iter()
+        .find(|r| r.kind == resources::ResourceId::Id(resources::RT_MANIFEST))?;
+
+    let data = resource.data;
+    let text = if let Some(data) = data.strip_prefix(b"\xFF\xFE") {
+        // UTF-16 little endian.
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+        String::from_utf8_lossy(data).into_owned()
+    };
+    Some(text)
+}
+
+/// Returns anomalies of the location of the entry point, which suggest that the image was packed
+/// or infected.
+///
+/// The entry point is expected inside an executable and read-only section, which is not the last
+/// section. Images without an entry point, e.g., resource-only DLLs, have no anomalies.
+pub(crate) fn entry_point_anomalies(pe: &goblin::pe::PE) -> DetailedStatus {
+    entry_point_anomalies_in_sections(pe.entry as u64, &pe.sections)
+}
+
+/// Reports anomalies of the location of the entry point at the relative virtual address `entry`,
+/// among `sections`.
+pub(crate) fn entry_point_anomalies_in_sections(
+    entry: u64,
+    sections: &[goblin::pe::section_table::SectionTable],
+) -> DetailedStatus {
+    use goblin::pe::section_table::{
+        IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_WRITE,
+    };
+
+    let mut anomalies: Vec<String> = Vec::default();
+
+    if entry == 0 {
+        return DetailedStatus::new("ENTRY-POINT", SupportLevel::Supported, anomalies);
+    }
+
+    let section_range = |section: &goblin::pe::section_table::SectionTable| {
+        let start = u64::from(section.virtual_address);
+        let size = section.virtual_size.max(section.size_of_raw_data);
+        start..start.saturating_add(u64::from(size))
+    };
+
+    match sections.iter().find(|s| section_range(s).contains(&entry)) {
+        None => anomalies.push("outside-sections".into()),
+
+        Some(section) => {
+            if (section.characteristics & (IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_CNT_CODE)) == 0 {
+                anomalies.push("non-executable-section".into());
+            }
+            if (section.characteristics & IMAGE_SCN_MEM_WRITE) != 0 {
+                anomalies.push("writable-section".into());
+            }
+
+            let last_address = sections.iter().map(|s| s.virtual_address).max();
+            if sections.len() > 1 && last_address == Some(section.virtual_address) {
+                anomalies.push("last-section".into());
+            }
+        }
+    }
+
+    if !anomalies.is_empty() {
+        debug!("Entry point {entry:#x} is unexpectedly located: {anomalies:?}.");
+    }
+
+    let level = if anomalies.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("ENTRY-POINT", level, anomalies)
+}
+
+/// Upper bound of the number of TLS callbacks read from malformed images that do not terminate
+/// their callbacks array.
+const MAX_TLS_CALLBACKS: usize = 1024;
+
+/// Returns the number of callbacks listed by the TLS directory, if the image has one.
+///
+/// TLS callbacks are called by the loader before the entry point, whenever a process or thread
+/// starts or exits. Their array, referenced by `AddressOfCallBacks`, ends with a null pointer.
+pub(crate) fn tls_callbacks(parser: &BinaryParser, pe: &goblin::pe::PE) -> Option<usize> {
+    let tls_table = pe
+        .header
+        .optional_header
+        .and_then(|optional_header| optional_header.data_directories.get_tls_table().copied())
+        .filter(|table| table.virtual_address != 0 && table.size != 0)?;
+
+    let bytes = parser.bytes();
+    let tls_directory = rva_to_file_offset(pe, u64::from(tls_table.virtual_address))?;
+
+    // `AddressOfCallBacks` follows `StartAddressOfRawData`, `EndAddressOfRawData` and
+    // `AddressOfIndex`, which are all pointers.
+    let pointer_size = if pe.is_64 { 8 } else { 4 };
+    let read_pointer = |offset: usize| -> Option<u64> {
+        if pe.is_64 {
+            bytes.pread_with::<u64>(offset, scroll::LE).ok()
+        } else {
+            bytes
+                .pread_with::<u32>(offset, scroll::LE)
+                .ok()
+                .map(u64::from)
+        }
+    };
+
+    let callbacks_va = read_pointer(tls_directory.checked_add(3 * pointer_size)?)?;
+    if callbacks_va == 0 {
+        return Some(0);
+    }
+
+    let Some(callbacks) = va_to_file_offset(pe, callbacks_va) else {
+        debug!("TLS callbacks array is not inside the file.");
+        return Some(0);
+    };
+
+    let count = (0..MAX_TLS_CALLBACKS)
+        .map_while(|index| read_pointer(callbacks.checked_add(index * pointer_size)?))
+        .take_while(|&callback| callback != 0)
+        .count();
+
+    debug!("TLS directory lists {count} callbacks.");
+    Some(count)
+}
+
+/// Resources larger than this, with an entropy of at least `RESOURCE_MAX_ENTROPY`, probably
+/// contain compressed or encrypted payloads.
+const RESOURCE_MIN_SUSPICIOUS_SIZE: usize = 256 * 1024;
+const RESOURCE_MAX_ENTROPY: f64 = 7.5;
+
+/// Returns the resources that embed executable images, scripts, or large high-entropy data.
+///
+/// Each anomaly is reported as `KIND@TYPE/NAME`, where `KIND` is `pe`, `script` or
+/// `high-entropy`. Icons are not considered, as they are often compressed images.
+pub(crate) fn resource_anomalies(parser: &BinaryParser, pe: &goblin::pe::PE) -> Vec<String> {
+    let mut anomalies = Vec::default();
+
+    for resource in resources::resources(parser.bytes(), pe) {
+        let data = resource.data;
+
+        let kind = if embeds_pe_image(data) {
+            "pe"
+        } else if looks_like_script(data) {
+            "script"
+        } else if data.len() >= RESOURCE_MIN_SUSPICIOUS_SIZE
+            && resource.kind != resources::ResourceId::Id(resources::RT_ICON)
+            && shannon_entropy(data) >= RESOURCE_MAX_ENTROPY
+        {
+            "high-entropy"
+        } else {
+            continue;
+        };
+
+        anomalies.push(format!("{kind}@{}", resource.location()));
+    }
+
+    debug!("Found {} resource anomalies.", anomalies.len());
+    anomalies
+}
+
+/// Returns `true` if `data` starts with the headers of a PE image.
+fn embeds_pe_image(data: &[u8]) -> bool {
+    if !data.starts_with(b"MZ") {
+        return false;
+    }
+
+    data.pread_with::<u32>(goblin::pe::header::PE_POINTER_OFFSET as usize, scroll::LE)
+        .ok()
+        .and_then(|pe_offset| data.get(pe_offset as usize..)?.get(..4))
+        .is_some_and(|signature| signature == b"PE\0\0")
+}
+
+/// Returns `true` if `data` starts like a shell, batch, `PowerShell` or Windows Script Host
+/// script.
+fn looks_like_script(data: &[u8]) -> bool {
+    const MARKERS: [&str; 6] = [
+        "@echo off",
+        "powershell",
+        "<script",
+        "wscript.",
+        "createobject(",
+        "invoke-expression",
+    ];
+
+    if data.starts_with(b"#!") {
+        return true;
+    }
+
+    let head = data.get(..4096).unwrap_or(data);
+    let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+    MARKERS.iter().any(|marker| head.contains(marker))
+}
+
+/// Operating systems declared as supported by `supportedOS` elements of manifests.
+const SUPPORTED_OS_IDS: [(&str, &str); 5] = [
+    ("8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a", "win10"),
+    ("1f676c76-80e1-4239-95bb-83d0f6d0da78", "win8.1"),
+    ("4a2f28e3-53b9-4441-ba9c-d69d4a4a6e38", "win8"),
+    ("35138b9a-5d96-4fbd-8e2d-a2440225f93a", "win7"),
+    ("e2011457-1546-43c5-a5fe-008deee3d3f0", "vista"),
+];
+
+/// Returns the privileges and compatibility claims of an application manifest.
+///
+/// The requested execution level is reported, followed by `ui-access` and `auto-elevate` when
+/// these are enabled, the DPI awareness, and the supported operating systems. Executables that
+/// require administrator privileges and also bypass user interface privilege isolation
+/// (`uiAccess`) are reported as unsupported, as they can drive elevated windows of other
+/// applications.
+pub(crate) fn manifest_audit(manifest: &str) -> CheckResult {
+    static EXECUTION_LEVEL: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"<(?:\w+:)?requestedExecutionLevel\b([^>]*)>")
+            .expect("Invalid static regular expression.")
+    });
+    static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"\b(level|uiAccess)\s*=\s*["']([^"']*)["']"#)
+            .expect("Invalid static regular expression.")
+    });
+    static ELEMENT: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"<(?:\w+:)?(autoElevate|dpiAware|dpiAwareness)\b[^>]*>\s*([^<]*?)\s*<")
+            .expect("Invalid static regular expression.")
+    });
+    static SUPPORTED_OS: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"<(?:\w+:)?supportedOS\s+Id\s*=\s*["']\{?([0-9A-Fa-f-]+)\}?["']"#)
+            .expect("Invalid static regular expression.")
+    });
+
+    let mut level = None;
+    let mut ui_access = false;
+    if let Some(attributes) = EXECUTION_LEVEL.captures(manifest) {
+        for attribute in ATTRIBUTE.captures_iter(&attributes[1]) {
+            match &attribute[1] {
+                "level" => level = Some(attribute[2].to_owned()),
+                _ => ui_access = attribute[2].eq_ignore_ascii_case("true"),
+            }
+        }
+    }
+
+    let mut details = vec![format!("level={}", level.as_deref().unwrap_or("none"))];
+    if ui_access {
+        details.push("ui-access".into());
+    }
+
+    for element in ELEMENT.captures_iter(manifest) {
+        let value = &element[2];
+        match &element[1] {
+            "autoElevate" if value.eq_ignore_ascii_case("true") => {
+                details.push("auto-elevate".into());
+            }
+            "dpiAware" => details.push(format!("dpi-aware={value}")),
+            "dpiAwareness" => details.push(format!("dpi-awareness={value}")),
+            _ => {}
+        }
+    }
+
+    let supported_os: Vec<&str> = SUPPORTED_OS
+        .captures_iter(manifest)
+        .map(|os| {
+            let id = os[1].to_ascii_lowercase();
+            SUPPORTED_OS_IDS
+                .iter()
+                .find(|(known_id, _name)| *known_id == id)
+                .map_or("unknown-os", |&(_id, name)| name)
+        })
+        .collect();
+    if !supported_os.is_empty() {
+        details.push(format!("os={}", supported_os.join("+")));
+    }
+
+    if ui_access && level.as_deref() == Some("requireAdministrator") {
+        DetailedStatus::new("MANIFEST", SupportLevel::Unsupported, details).into()
+    } else {
+        InformationalStatus::new("MANIFEST", details).into()
+    }
+}
+
+/// Entry points of COM servers, which `regsvr32` and COM clients call when loading the DLL.
+static COM_ENTRY_POINTS: &[&str] = &[
+    "DllRegisterServer",
+    "DllUnregisterServer",
+    "DllInstall",
+    "DllGetClassObject",
+    "DllCanUnloadNow",
+];
+
+/// Audits the exports of a DLL, which outline the surface exposed to DLL hijacking.
+///
+/// The numbers of forwarded exports and of exports with no associated code (data exports) are
+/// reported, followed by the exported COM entry points. DLLs exporting COM entry points without
+/// requiring integrity checks are reported as unsupported, as they are registered to be loaded by
+/// other processes, possibly from a location writable by an attacker.
+pub(crate) fn export_audit(pe: &goblin::pe::PE) -> CheckResult {
+    use goblin::pe::section_table::{IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE};
+
+    let is_code = |rva: usize| {
+        pe.sections.iter().any(|section| {
+            let start = section.virtual_address as usize;
+            let size = section.virtual_size.max(section.size_of_raw_data) as usize;
+            (start..start.saturating_add(size)).contains(&rva)
+                && (section.characteristics & (IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_CNT_CODE)) != 0
+        })
+    };
+
+    let mut forwarded = Vec::default();
+    let mut data = Vec::default();
+    let mut com_entry_points = Vec::default();
+    for export in &pe.exports {
+        let name = export.name.unwrap_or("(ordinal)");
+
+        if export.reexport.is_some() {
+            forwarded.push(name);
+        } else if !is_code(export.rva) {
+            data.push(name);
+        } else if COM_ENTRY_POINTS.contains(&name) {
+            com_entry_points.push(name);
+        }
+    }
+
+    if !forwarded.is_empty() {
+        debug!("Forwarded exports: {}.", forwarded.join(" "));
+    }
+    if !data.is_empty() {
+        debug!("Data exports: {}.", data.join(" "));
+    }
+
+    let mut details = vec![
+        format!("forwarded={}", forwarded.len()),
+        format!("data={}", data.len()),
+    ];
+    if !com_entry_points.is_empty() {
+        details.push(format!("com={}", com_entry_points.join("+")));
+    }
+
+    let requires_integrity_check = pe.header.optional_header.is_some_and(|header| {
+        (header.windows_fields.dll_characteristics & IMAGE_DLLCHARACTERISTICS_FORCE_INTEGRITY) != 0
+    });
+
+    if com_entry_points.is_empty() || requires_integrity_check {
+        InformationalStatus::new("EXPORT-AUDIT", details).into()
+    } else {
+        details.push("no-integrity-check".into());
+        DetailedStatus::new("EXPORT-AUDIT", SupportLevel::Unsupported, details).into()
+    }
+}
+
+/// Size of an entry of the COFF symbol table.
+const SIZEOF_COFF_SYMBOL: u64 = 18;
+
+/// Returns the offset, in the file, of the end of the data described by the headers.
+///
+/// Besides sections, this includes the certificate table, the COFF symbol table and its string
+/// table, and debug data, which are all referenced by file offsets, and which can be located
+/// after all sections.
+pub(crate) fn data_end(parser: &BinaryParser, pe: &goblin::pe::PE) -> u64 {
+    let mut ends = Vec::default();
+
+    if let Some(optional_header) = pe.header.optional_header {
+        ends.push(u64::from(optional_header.windows_fields.size_of_headers));
+
+        // The address of the certificate table is an offset in the file.
+        if let Some(certificates) = optional_header.data_directories.get_certificate_table() {
+            ends.push(u64::from(certificates.virtual_address) + u64::from(certificates.size));
+        }
+    }
+
+    ends.extend(
+        pe.sections
+            .iter()
+            .map(|s| u64::from(s.pointer_to_raw_data) + u64::from(s.size_of_raw_data)),
+    );
+
+    if let Some(debug_data) = &pe.debug_data {
+        let directory = &debug_data.image_debug_directory;
+        ends.push(u64::from(directory.pointer_to_raw_data) + u64::from(directory.size_of_data));
+    }
+
+    let coff_header = &pe.header.coff_header;
+    if coff_header.pointer_to_symbol_table != 0 {
+        let symbols_end = u64::from(coff_header.pointer_to_symbol_table)
+            + u64::from(coff_header.number_of_symbol_table) * SIZEOF_COFF_SYMBOL;
+
+        // The string table follows the symbol table, and starts with its own size.
+        let strings_size = usize::try_from(symbols_end)
+            .ok()
+            .and_then(|offset| parser.bytes().pread_with::<u32>(offset, scroll::LE).ok())
+            .unwrap_or_default();
+
+        ends.push(symbols_end + u64::from(strings_size));
+    }
+
+    ends.into_iter().max().unwrap_or_default()
+}
+
+/// Returns the architecture targeted by the image.
+pub(crate) fn architecture(pe: &goblin::pe::PE) -> Architecture {
+    machine_architecture(pe.header.coff_header.machine, pe.is_64)
+}
+
+/// Returns the architecture identified by the `Machine` field of COFF headers.
+pub(crate) fn machine_architecture(machine: u16, is_64: bool) -> Architecture {
+    use goblin::pe::header::{
+        machine_to_str, COFF_MACHINE_ARM, COFF_MACHINE_ARM64, COFF_MACHINE_ARMNT,
+        COFF_MACHINE_RISCV32, COFF_MACHINE_RISCV64, COFF_MACHINE_THUMB, COFF_MACHINE_X86,
+        COFF_MACHINE_X86_64,
+    };
+
+    let name = match machine {
+        COFF_MACHINE_X86 | IMAGE_FILE_MACHINE_CHPE_X86 => "x86",
+        COFF_MACHINE_X86_64 => "x86_64",
+        COFF_MACHINE_ARM | COFF_MACHINE_ARMNT | COFF_MACHINE_THUMB => "arm",
+        COFF_MACHINE_ARM64 => "aarch64",
+        IMAGE_FILE_MACHINE_ARM64EC => "arm64ec",
+        IMAGE_FILE_MACHINE_ARM64X => "arm64x",
+        COFF_MACHINE_RISCV32 => "riscv32",
+        COFF_MACHINE_RISCV64 => "riscv64",
+        machine => machine_to_str(machine),
+    };
+
+    let name = if name == "COFF_UNKNOWN" {
+        format!("{machine:#06x}")
+    } else {
+        name.to_lowercase()
+    };
+
+    Architecture {
+        name,
+        bits: if is_64 { 64 } else { 32 },
+        // All Windows architectures are little-endian.
+        endianness: Endianness::Little,
+    }
 }
 
 pub(crate) const IMAGE_DLLCHARACTERISTICS_NX_COMPAT: u16 = 0x0100;
@@ -60,7 +595,6 @@ pub(crate) const IMAGE_DLLCHARACTERISTICS_GUARD_CF: u16 = 0x4000;
 pub(crate) const IMAGE_FILE_LARGE_ADDRESS_AWARE: u16 = 0x0020;
 pub(crate) const IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA: u16 = 0x0020;
 pub(crate) const IMAGE_FILE_RELOCS_STRIPPED: u16 = 0x0001;
-pub(crate) const RDATA_CHARACTERISTICS: u32 = IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ;
 pub(crate) const PDATA_CHARACTERISTICS: u32 = IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ;
 
 #[repr(C)]
@@ -96,7 +630,7 @@ pub(crate) struct ImageLoadConfigDirectory32 {
     EditList: u32,
     SecurityCookie: u32,
     SEHandlerTable: u32,
-    pub(crate) SEHandlerCount: u32,
+    SEHandlerCount: u32,
     GuardCFCheckFunctionPointer: u32,
     GuardCFDispatchFunctionPointer: u32,
     GuardCFFunctionTable: u32,
@@ -118,6 +652,9 @@ pub(crate) struct ImageLoadConfigDirectory32 {
     HotPatchTableOffset: u32,
     Reserved3: u32,
     EnclaveConfigurationPointer: u32,
+    VolatileMetadataPointer: u32,
+    GuardEHContinuationTable: u32,
+    GuardEHContinuationCount: u32,
 }
 
 #[repr(C)]
@@ -143,7 +680,7 @@ pub(crate) struct ImageLoadConfigDirectory64 {
     EditList: u64,
     SecurityCookie: u64,
     SEHandlerTable: u64,
-    pub(crate) SEHandlerCount: u64,
+    SEHandlerCount: u64,
     GuardCFCheckFunctionPointer: u64,
     GuardCFDispatchFunctionPointer: u64,
     GuardCFFunctionTable: u64,
@@ -165,15 +702,11 @@ pub(crate) struct ImageLoadConfigDirectory64 {
     HotPatchTableOffset: u32,
     Reserved3: u32,
     EnclaveConfigurationPointer: u64,
+    VolatileMetadataPointer: u64,
+    GuardEHContinuationTable: u64,
+    GuardEHContinuationCount: u64,
 }
 
-#[allow(non_camel_case_types)]
-pub(crate) type ImageLoadConfigDirectory_Size_Type = u32;
-#[allow(non_camel_case_types)]
-pub(crate) type ImageLoadConfigDirectory32_SEHandlerCount_Type = u32;
-#[allow(non_camel_case_types)]
-pub(crate) type ImageLoadConfigDirectory64_SEHandlerCount_Type = u64;
-
 pub(crate) fn dll_characteristics_bit_is_set(
     pe: &goblin::pe::PE,
     mask_name: &'static str,
@@ -239,6 +772,26 @@ pub(crate) fn handles_addresses_larger_than_2_gigabytes(pe: &goblin::pe::PE) ->
     r
 }
 
+/// Returns `true` if the base relocation table of the image is not empty.
+fn has_base_relocations(optional_header: &goblin::pe::optional_header::OptionalHeader) -> bool {
+    optional_header
+        .data_directories
+        .get_base_relocation_table()
+        .is_some_and(|table| table.virtual_address != 0 && table.size != 0)
+}
+
+/// Default preferred addresses of images, set by linkers for 32-bits and 64-bits executables and
+/// DLLs, which attackers assume first.
+pub(crate) static DEFAULT_IMAGE_BASES: &[u64] =
+    &[0x40_0000, 0x1000_0000, 0x1_4000_0000, 0x1_8000_0000];
+
+/// Returns the preferred address of the image.
+pub(crate) fn image_base(pe: &goblin::pe::PE) -> Option<u64> {
+    pe.header
+        .optional_header
+        .map(|optional_header| optional_header.windows_fields.image_base)
+}
+
 pub(crate) fn supports_aslr(pe: &goblin::pe::PE) -> ASLRCompatibilityLevel {
     if (pe.header.coff_header.characteristics & IMAGE_FILE_RELOCS_STRIPPED) != 0 {
         // Base relocation information are absent. The loader cannot relocate the image.
@@ -252,6 +805,11 @@ pub(crate) fn supports_aslr(pe: &goblin::pe::PE) -> ASLRCompatibilityLevel {
             // The executable has a preferred address. ASLR will probably not be used, as it might
             // be expensive to relocate the executable.
             ASLRCompatibilityLevel::Expensive
+        } else if !pe.is_64 && !has_base_relocations(&optional_header) {
+            // The image claims to be relocatable, but the loader has nothing to apply when
+            // relocating it, so it is loaded at its preferred address.
+            debug!("Bit 'IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE' is set in 'DllCharacteristics' inside optional Windows header, but the base relocation table is empty.");
+            ASLRCompatibilityLevel::UnsupportedWithoutRelocations
         } else {
             let handles_addresses_larger_than_2_gigabytes =
                 (pe.header.coff_header.characteristics & IMAGE_FILE_LARGE_ADDRESS_AWARE) != 0;
@@ -290,6 +848,34 @@ pub(crate) fn supports_aslr(pe: &goblin::pe::PE) -> ASLRCompatibilityLevel {
     }
 }
 
+/// Returns whether the process terminates when the Windows heap detects a corruption.
+///
+/// Termination on heap corruption is enabled by default in 64-bits processes. 32-bits processes
+/// must opt into it by calling `HeapSetInformation(HeapEnableTerminationOnCorruption)`, so an
+/// executable that does not import `HeapSetInformation` probably does not enable it.
+pub(crate) fn heap_hardening(pe: &goblin::pe::PE) -> DetailedStatus {
+    if pe.is_64 {
+        debug!("Termination on heap corruption is enabled by default for PE32+ executables.");
+        return DetailedStatus::new("HEAP-HARDENING", SupportLevel::Supported, Vec::default());
+    }
+
+    let imports_heap_set_information = pe
+        .imports
+        .iter()
+        .any(|import| import.name == "HeapSetInformation");
+
+    if imports_heap_set_information {
+        debug!("Function 'HeapSetInformation' is imported.");
+        DetailedStatus::new("HEAP-HARDENING", SupportLevel::Supported, Vec::default())
+    } else {
+        DetailedStatus::new(
+            "HEAP-HARDENING",
+            SupportLevel::Partial,
+            vec!["no-HeapSetInformation".into()],
+        )
+    }
+}
+
 /// Returns information about support of Safe Structured Exception Handlers (`SafeSEH`).
 ///
 /// When `SafeSEH` is supported, the executable has a table of safe exception handlers. This table
@@ -300,11 +886,11 @@ pub(crate) fn supports_aslr(pe: &goblin::pe::PE) -> ASLRCompatibilityLevel {
 pub(crate) fn has_safe_structured_exception_handlers(
     parser: &BinaryParser,
     pe: &goblin::pe::PE,
-) -> bool {
-    match has_safe_seh_handlers(parser, pe) {
+) -> Result<bool> {
+    Ok(match has_safe_seh_handlers(parser, pe)? {
         Some(true) => true,
         Some(false) | None => has_pdata_section(pe),
-    }
+    })
 }
 
 /// Returns `true` if the executable has a `PDATA` (`.pdata`) section, where all exception handlers
@@ -314,7 +900,7 @@ fn has_pdata_section(pe: &goblin::pe::PE) -> bool {
         if (section.characteristics & PDATA_CHARACTERISTICS) == PDATA_CHARACTERISTICS {
             // If this section name is valid UTF-8, then `r` will be `true` if the name equals
             // `.pdata`, and false otherwise. For non UTF-8-valid names, `r` will be `false`.
-            let r = section.name().map(|name| name == ".pdata").unwrap_or(false);
+            let r = section.name().is_ok_and(|name| name == ".pdata");
             if r {
                 debug!("Section '.pdata' found in the executable.");
             }
@@ -330,10 +916,154 @@ fn has_pdata_section(pe: &goblin::pe::PE) -> bool {
 ///
 /// This returns `Some(false)` if the executable has an image load configuration directory,
 /// in which no `SafeSEH` handlers are referenced. It returns `None` in all other cases.
-fn has_safe_seh_handlers(parser: &BinaryParser, pe: &goblin::pe::PE) -> Option<bool> {
-    pe.header
+fn has_safe_seh_handlers(parser: &BinaryParser, pe: &goblin::pe::PE) -> Result<Option<bool>> {
+    Ok(
+        read_load_config_field(parser, pe, &LOAD_CONFIG_SE_HANDLER_COUNT)?
+            // Return `Some(true)` if the load configuration table references a least one safe
+            // structured exception handler.
+            .map(|se_handler_count| {
+                debug!(
+                    "Image load configuration directory defines {} structured exceptions handlers.",
+                    se_handler_count
+                );
+                se_handler_count > 0
+            }),
+    )
+}
+
+/// Location of a field inside the image load configuration directory, in PE32 and PE32+
+/// executables.
+pub(crate) struct LoadConfigField {
+    name: &'static str,
+    offset32: usize,
+    size32: usize,
+    offset64: usize,
+    size64: usize,
+}
+
+/// Returns the size of the field selected by `_field`.
+const fn size_of_field<S, F>(_field: fn(&S) -> &F) -> usize {
+    size_of::<F>()
+}
+
+/// Defines the location of fields inside the image load configuration directory, with offsets
+/// and sizes derived from the layouts of `ImageLoadConfigDirectory32` and
+/// `ImageLoadConfigDirectory64`.
+macro_rules! load_config_fields {
+    ($($constant:ident => $field:ident,)*) => {
+        $(
+            const $constant: LoadConfigField = LoadConfigField {
+                name: stringify!($field),
+                offset32: offset_of!(ImageLoadConfigDirectory32, $field),
+                size32: size_of_field(|directory: &ImageLoadConfigDirectory32| &directory.$field),
+                offset64: offset_of!(ImageLoadConfigDirectory64, $field),
+                size64: size_of_field(|directory: &ImageLoadConfigDirectory64| &directory.$field),
+            };
+        )*
+    };
+}
+
+load_config_fields! {
+    LOAD_CONFIG_SIZE => Size,
+    LOAD_CONFIG_DEPENDENT_LOAD_FLAGS => DependentLoadFlags,
+    LOAD_CONFIG_SE_HANDLER_TABLE => SEHandlerTable,
+    LOAD_CONFIG_SE_HANDLER_COUNT => SEHandlerCount,
+    LOAD_CONFIG_GUARD_CF_CHECK_FUNCTION_POINTER => GuardCFCheckFunctionPointer,
+    LOAD_CONFIG_GUARD_CF_FUNCTION_TABLE => GuardCFFunctionTable,
+    LOAD_CONFIG_GUARD_CF_FUNCTION_COUNT => GuardCFFunctionCount,
+    LOAD_CONFIG_GUARD_FLAGS => GuardFlags,
+    LOAD_CONFIG_GUARD_ADDRESS_TAKEN_IAT_ENTRY_TABLE => GuardAddressTakenIatEntryTable,
+    LOAD_CONFIG_GUARD_ADDRESS_TAKEN_IAT_ENTRY_COUNT => GuardAddressTakenIatEntryCount,
+    LOAD_CONFIG_GUARD_LONG_JUMP_TARGET_TABLE => GuardLongJumpTargetTable,
+    LOAD_CONFIG_GUARD_LONG_JUMP_TARGET_COUNT => GuardLongJumpTargetCount,
+    LOAD_CONFIG_CHPE_METADATA_POINTER => CHPEMetadataPointer,
+    LOAD_CONFIG_GUARD_EH_CONTINUATION_TABLE => GuardEHContinuationTable,
+    LOAD_CONFIG_GUARD_EH_CONTINUATION_COUNT => GuardEHContinuationCount,
+}
+
+/// Image load configuration directory, whose bounds were checked against the file.
+struct LoadConfigDirectory<'t> {
+    /// Bytes of the directory, as many as declared by its `Size` field.
+    bytes: &'t [u8],
+    is_64: bool,
+}
+
+impl LoadConfigDirectory<'_> {
+    /// Returns the value of a field of the directory, or `None` if the directory is not large
+    /// enough to define the field, as is the case for directories of older linkers.
+    fn field(&self, field: &LoadConfigField) -> Option<u64> {
+        // Based on the architecture of the PE32/PE32+ file, find out relatively where and exactly
+        // how large is the data representing the field.
+        let (offset_of_field, size_of_field) = if self.is_64 {
+            (field.offset64, field.size64)
+        } else {
+            (field.offset32, field.size32)
+        };
+
+        let bytes = self
+            .bytes
+            .get(offset_of_field..offset_of_field.checked_add(size_of_field)?)?;
+
+        // To unify the handling of the value, convert it into the largest type.
+        match size_of_field {
+            2 => bytes.pread_with::<u16>(0, scroll::LE).map(u64::from),
+            4 => bytes.pread_with::<u32>(0, scroll::LE).map(u64::from),
+            _ => bytes.pread_with::<u64>(0, scroll::LE),
+        }
+        .ok()
+    }
+}
+
+/// Returns an error reporting a malformed executable.
+fn malformed(message: String) -> Error {
+    Error::ParseFile {
+        source: goblin::error::Error::Malformed(message),
+    }
+}
+
+/// Returns the offset, in the file, of `size` bytes located at the relative virtual address
+/// `rva`, if these bytes are entirely stored in the raw data of a single section.
+fn rva_range_to_file_offset(
+    pe: &goblin::pe::PE,
+    file_size: usize,
+    rva: u64,
+    size: u64,
+) -> Option<usize> {
+    let section = pe.sections.iter().find(|section| {
+        let virtual_size = if section.virtual_size == 0 {
+            section.size_of_raw_data
+        } else {
+            section.virtual_size
+        };
+
+        let start = u64::from(section.virtual_address);
+        rva >= start && rva < start.saturating_add(u64::from(virtual_size))
+    })?;
+
+    let offset_in_section = rva - u64::from(section.virtual_address);
+    let end_in_section = offset_in_section.checked_add(size)?;
+    if end_in_section > u64::from(section.size_of_raw_data) {
+        return None;
+    }
+
+    let offset = u64::from(section.pointer_to_raw_data).checked_add(offset_in_section)?;
+    let end = offset.checked_add(size)?;
+    (end <= file_size as u64).then_some(usize::try_from(offset).ok()?)
+}
+
+/// Returns the image load configuration directory of the executable, if it has one.
+///
+/// The directory is checked to be stored in the file, and so are the tables it references that
+/// are read by the loader: the `SafeSEH` handler table, and the Control Flow Guard tables of
+/// functions, address-taken IAT entries, long jump targets and exception handler continuations.
+/// Directories failing these checks are reported as errors, instead of being partially read.
+fn load_config_directory<'t>(
+    parser: &'t BinaryParser,
+    pe: &goblin::pe::PE,
+) -> Result<Option<LoadConfigDirectory<'t>>> {
+    let Some(load_config_table) = pe
+        .header
         .optional_header
-        // If we actually have an optional header, get its load configuration table.
         .and_then(|optional_header| {
             optional_header
                 .data_directories
@@ -342,110 +1072,542 @@ fn has_safe_seh_handlers(parser: &BinaryParser, pe: &goblin::pe::PE) -> Option<b
         })
         // Continue only if the load configuration table has some bytes.
         .filter(|load_config_table| load_config_table.size > 0)
-        .and_then(|load_config_table| {
-            debug!("Reference to Image load configuration directory found in the executable.");
+    else {
+        return Ok(None);
+    };
 
-            let load_config_table_end = load_config_table
-                .virtual_address
-                .saturating_add(load_config_table.size);
+    debug!("Reference to Image load configuration directory found in the executable.");
 
-            pe.sections
-                .iter()
-                // Find the `.rdata` section that has the bytes of this load configuration table.
-                .find(|&section| {
-                    (section.characteristics & RDATA_CHARACTERISTICS) == RDATA_CHARACTERISTICS
-                        && (load_config_table.virtual_address >= section.virtual_address)
-                        && (load_config_table_end
-                            <= section.virtual_address.saturating_add(section.virtual_size))
-                })
-                // We still need `load_config_table`, so carry it forward to the next steps.
-                .map(|section| (section, load_config_table))
-        })
-        // Find out if the load configuration table references some safe structured exception
-        // handlers. The section is needed to read the bytes of the load configuration table.
-        .and_then(|(section, load_config_table)| {
-            image_load_configuration_directory_has_safe_seh_handlers(
-                parser,
-                pe,
-                section,
-                load_config_table,
-            )
-        })
-}
+    let bytes = parser.bytes();
+    let rva = u64::from(load_config_table.virtual_address);
+    let size_of_size = LOAD_CONFIG_SIZE.size32;
+
+    let offset =
+        rva_range_to_file_offset(pe, bytes.len(), rva, size_of_size as u64).ok_or_else(|| {
+            malformed(format!(
+                "image load configuration directory at RVA {rva:#x} is not stored in the file"
+            ))
+        })?;
+
+    let size = bytes
+        .pread_with::<u32>(offset, scroll::LE)
+        .map_err(|source| Error::ParseFile {
+            source: source.into(),
+        })?;
+
+    if (size as usize) < size_of_size
+        || rva_range_to_file_offset(pe, bytes.len(), rva, u64::from(size)).is_none()
+    {
+        return Err(malformed(format!(
+            "image load configuration directory at RVA {rva:#x} declares a size of {size} bytes, \
+             which is not stored in the file"
+        )));
+    }
 
-fn image_load_configuration_directory_has_safe_seh_handlers(
-    parser: &BinaryParser,
-    pe: &goblin::pe::PE,
-    section: &goblin::pe::section_table::SectionTable,
-    load_config_table: goblin::pe::data_directories::DataDirectory,
-) -> Option<bool> {
     debug!("Image load configuration directory found in the executable.");
 
-    // Based on the architecture of the PE32/PE32+ file, find out relatively where and exactly
-    // how large is the data representing the number of safe structured exception handlers.
-    let (offset_of_se_handler_count, size_of_se_handler_count) = if pe.is_64 {
+    let directory = LoadConfigDirectory {
+        bytes: &bytes[offset..offset + size as usize],
+        is_64: pe.is_64,
+    };
+
+    // Entries of Control Flow Guard tables are relative virtual addresses, followed by as many
+    // bytes of metadata as encoded in the guard flags.
+    let guard_flags = directory
+        .field(&LOAD_CONFIG_GUARD_FLAGS)
+        .and_then(|flags| u32::try_from(flags).ok())
+        .unwrap_or(0);
+    let guard_entry_size = 4 + u64::from(
+        (guard_flags & IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK)
+            >> IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT,
+    );
+
+    let tables = [
         (
-            offset_of!(ImageLoadConfigDirectory64, SEHandlerCount),
-            size_of::<ImageLoadConfigDirectory64_SEHandlerCount_Type>(),
-        )
-    } else {
+            &LOAD_CONFIG_SE_HANDLER_TABLE,
+            &LOAD_CONFIG_SE_HANDLER_COUNT,
+            4,
+        ),
         (
-            offset_of!(ImageLoadConfigDirectory32, SEHandlerCount),
-            size_of::<ImageLoadConfigDirectory32_SEHandlerCount_Type>(),
-        )
+            &LOAD_CONFIG_GUARD_CF_FUNCTION_TABLE,
+            &LOAD_CONFIG_GUARD_CF_FUNCTION_COUNT,
+            guard_entry_size,
+        ),
+        (
+            &LOAD_CONFIG_GUARD_ADDRESS_TAKEN_IAT_ENTRY_TABLE,
+            &LOAD_CONFIG_GUARD_ADDRESS_TAKEN_IAT_ENTRY_COUNT,
+            guard_entry_size,
+        ),
+        (
+            &LOAD_CONFIG_GUARD_LONG_JUMP_TARGET_TABLE,
+            &LOAD_CONFIG_GUARD_LONG_JUMP_TARGET_COUNT,
+            guard_entry_size,
+        ),
+        (
+            &LOAD_CONFIG_GUARD_EH_CONTINUATION_TABLE,
+            &LOAD_CONFIG_GUARD_EH_CONTINUATION_COUNT,
+            guard_entry_size,
+        ),
+    ];
+
+    for (table_field, count_field, entry_size) in tables {
+        let (Some(table), Some(count)) =
+            (directory.field(table_field), directory.field(count_field))
+        else {
+            continue;
+        };
+
+        if table == 0 || count == 0 {
+            continue;
+        }
+
+        let stored = table
+            .checked_sub(pe.image_base as u64)
+            .zip(count.checked_mul(entry_size))
+            .and_then(|(rva, size)| rva_range_to_file_offset(pe, bytes.len(), rva, size));
+
+        if stored.is_none() {
+            return Err(malformed(format!(
+                "image load configuration directory references {count} entries in '{}' at \
+                 {table:#x}, which are not stored in the file",
+                table_field.name
+            )));
+        }
+    }
+
+    Ok(Some(directory))
+}
+
+/// Reads the value of a field of the image load configuration directory.
+///
+/// This returns `None` if the executable has no image load configuration directory, or if that
+/// directory is not large enough to define the field.
+fn read_load_config_field(
+    parser: &BinaryParser,
+    pe: &goblin::pe::PE,
+    field: &LoadConfigField,
+) -> Result<Option<u64>> {
+    let value = load_config_directory(parser, pe)?.and_then(|directory| directory.field(field));
+    if value.is_some() {
+        debug!(
+            "Image load configuration directory defines '{}'.",
+            field.name
+        );
+    }
+    Ok(value)
+}
+
+/// [Guard flags](https://learn.microsoft.com/en-us/windows/win32/debug/pe-format#load-configuration-layout)
+/// of the image load configuration directory.
+pub(crate) static IMAGE_GUARD_FLAGS: &[(u32, &str)] = &[
+    (0x0000_0100, "IMAGE_GUARD_CF_INSTRUMENTED"),
+    (0x0000_0200, "IMAGE_GUARD_CFW_INSTRUMENTED"),
+    (0x0000_0400, "IMAGE_GUARD_CF_FUNCTION_TABLE_PRESENT"),
+    (0x0000_0800, "IMAGE_GUARD_SECURITY_COOKIE_UNUSED"),
+    (0x0000_1000, "IMAGE_GUARD_PROTECT_DELAYLOAD_IAT"),
+    (0x0000_2000, "IMAGE_GUARD_DELAYLOAD_IAT_IN_ITS_OWN_SECTION"),
+    (
+        0x0000_4000,
+        "IMAGE_GUARD_CF_EXPORT_SUPPRESSION_INFO_PRESENT",
+    ),
+    (0x0000_8000, "IMAGE_GUARD_CF_ENABLE_EXPORT_SUPPRESSION"),
+    (0x0001_0000, "IMAGE_GUARD_CF_LONGJUMP_TABLE_PRESENT"),
+    (0x0002_0000, "IMAGE_GUARD_RF_INSTRUMENTED"),
+    (0x0004_0000, "IMAGE_GUARD_RF_ENABLE"),
+    (0x0008_0000, "IMAGE_GUARD_RF_STRICT"),
+    (0x0010_0000, "IMAGE_GUARD_RETPOLINE_PRESENT"),
+    (
+        IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT,
+        "IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT",
+    ),
+    (0x0080_0000, "IMAGE_GUARD_XFG_ENABLED"),
+    (0x0100_0000, "IMAGE_GUARD_CASTGUARD_PRESENT"),
+    (0x0200_0000, "IMAGE_GUARD_MEMCPY_PRESENT"),
+];
+
+/// Guard flag set when the image load configuration directory references a table of valid
+/// exception handler continuation targets.
+pub(crate) const IMAGE_GUARD_EH_CONTINUATION_TABLE_PRESENT: u32 = 0x0040_0000;
+
+/// Bits of the guard flags that encode the size of each entry of the Control Flow Guard function
+/// table, in addition to the size of a relative virtual address.
+pub(crate) const IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_MASK: u32 = 0xF000_0000;
+pub(crate) const IMAGE_GUARD_CF_FUNCTION_TABLE_SIZE_SHIFT: u32 = 28;
+
+/// Returns the guard flags of the image load configuration directory, if the executable defines
+/// them.
+pub(crate) fn guard_flags(parser: &BinaryParser, pe: &goblin::pe::PE) -> Result<Option<u32>> {
+    let Some(flags) = read_load_config_field(parser, pe, &LOAD_CONFIG_GUARD_FLAGS)?
+        .and_then(|flags| u32::try_from(flags).ok())
+    else {
+        return Ok(None);
     };
 
-    // Convert virtual addresses into file offsets.
-    let config_table_offset_in_section = load_config_table
-        .virtual_address
-        .saturating_sub(section.virtual_address);
-    let config_table_offset_in_file = (section.pointer_to_raw_data as usize)
-        .saturating_add(config_table_offset_in_section as usize);
-    let se_handler_count_offset_in_file =
-        config_table_offset_in_file.saturating_add(offset_of_se_handler_count);
-
-    parser
-        .bytes()
-        .pread_with::<ImageLoadConfigDirectory_Size_Type>(config_table_offset_in_file, scroll::LE)
-        .ok()
-        // Only continue if the load configuration table size is big enough to read the number of
-        // safe structured exception handlers.
-        .filter(|load_config_directory_size| {
-            (*load_config_directory_size as usize)
-                >= offset_of_se_handler_count.saturating_add(size_of_se_handler_count)
-        })
-        .and_then(|_load_config_directory_size| {
-            debug!("Image load configuration directory defines 'SEHandlerCount'.");
-
-            if pe.is_64 {
-                // Read the number of safe structured exception handlers in a PE32+ executable.
-                parser
-                    .bytes()
-                    .pread_with::<ImageLoadConfigDirectory64_SEHandlerCount_Type>(
-                        se_handler_count_offset_in_file,
-                        scroll::LE,
-                    )
+    for &(mask, name) in IMAGE_GUARD_FLAGS {
+        debug!(
+            "Bit '{}' is {} in 'GuardFlags' inside image load configuration directory.",
+            name,
+            if (flags & mask) == 0 {
+                "cleared"
             } else {
-                // Read the number of safe structured exception handlers in a PE32 executable.
-                parser
-                    .bytes()
-                    .pread_with::<ImageLoadConfigDirectory32_SEHandlerCount_Type>(
-                        se_handler_count_offset_in_file,
-                        scroll::LE,
-                    )
-                    // To unify the comparison below, convert the count into the same type as in
-                    // the PE32+ executable.
-                    .map(ImageLoadConfigDirectory64_SEHandlerCount_Type::from)
+                "set"
             }
-            .ok()
+        );
+    }
+    Ok(Some(flags))
+}
+
+/// Returns the number of targets in the exception handler continuation table, if the image load
+/// configuration directory defines it.
+pub(crate) fn eh_continuation_count(
+    parser: &BinaryParser,
+    pe: &goblin::pe::PE,
+) -> Result<Option<u64>> {
+    read_load_config_field(parser, pe, &LOAD_CONFIG_GUARD_EH_CONTINUATION_COUNT)
+}
+
+/// [Flags](https://learn.microsoft.com/en-us/windows/win32/api/libloaderapi/nf-libloaderapi-loadlibraryexw)
+/// applied by the loader to the DLLs imported by the executable, as set by `/DEPENDENTLOADFLAG`
+/// in the `DependentLoadFlags` field of the image load configuration directory.
+pub(crate) static LOAD_LIBRARY_FLAGS: &[(u16, &str)] = &[
+    (0x0080, "LOAD_LIBRARY_REQUIRE_SIGNED_TARGET"),
+    (0x0100, "LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR"),
+    (0x0200, "LOAD_LIBRARY_SEARCH_APPLICATION_DIR"),
+    (0x0400, "LOAD_LIBRARY_SEARCH_USER_DIRS"),
+    (0x0800, "LOAD_LIBRARY_SEARCH_SYSTEM32"),
+    (0x1000, "LOAD_LIBRARY_SEARCH_DEFAULT_DIRS"),
+    (0x2000, "LOAD_LIBRARY_SAFE_CURRENT_DIRS"),
+    (0x4000, "LOAD_LIBRARY_SEARCH_SYSTEM32_NO_FORWARDER"),
+];
+
+/// Flags restricting the directories searched for DLLs, which exclude the current directory and
+/// the directories of `PATH`.
+const LOAD_LIBRARY_SEARCH_MASK: u16 = 0x0100 | 0x0200 | 0x0400 | 0x0800 | 0x1000 | 0x4000;
+
+/// Returns the flags applied by the loader to the DLLs imported by the executable, if the image
+/// load configuration directory defines them.
+pub(crate) fn dependent_load_flags(
+    parser: &BinaryParser,
+    pe: &goblin::pe::PE,
+) -> Result<Option<u16>> {
+    Ok(
+        read_load_config_field(parser, pe, &LOAD_CONFIG_DEPENDENT_LOAD_FLAGS)?
+            .and_then(|flags| u16::try_from(flags).ok()),
+    )
+}
+
+/// Reports whether the DLLs imported by the executable are only searched in safe directories.
+///
+/// Without such flags, imported DLLs that are not known DLLs are also searched in the current
+/// directory and in the directories of `PATH`, which exposes the executable to DLL planting.
+/// The flags that are set are reported, e.g., `system32` for `LOAD_LIBRARY_SEARCH_SYSTEM32`.
+pub(crate) fn safe_dll_search(
+    parser: &BinaryParser,
+    pe: &goblin::pe::PE,
+) -> Result<DetailedStatus> {
+    let flags = dependent_load_flags(parser, pe)?.unwrap_or(0);
+    debug!("'DependentLoadFlags' of image load configuration directory is {flags:#06x}.");
+
+    let details = LOAD_LIBRARY_FLAGS
+        .iter()
+        .filter(|&&(mask, _name)| (flags & mask) != 0)
+        .map(|&(_mask, name)| {
+            name.trim_start_matches("LOAD_LIBRARY_SEARCH_")
+                .trim_start_matches("LOAD_LIBRARY_")
+                .to_ascii_lowercase()
+                .replace('_', "-")
         })
-        // Return `Some(true)` if the load configuration table references a least one safe
-        // structured exception handler.
-        .map(|se_handler_count| {
+        .collect();
+
+    let level = if (flags & LOAD_LIBRARY_SEARCH_MASK) == 0 {
+        SupportLevel::Unsupported
+    } else {
+        SupportLevel::Supported
+    };
+    Ok(DetailedStatus::new("SAFE-DLL-SEARCH", level, details))
+}
+
+/// DLLs of the system that export functions by ordinal only, or whose imports by ordinal are
+/// common, e.g., `ws2_32.dll`, `oleaut32.dll` and the MFC libraries.
+static ORDINAL_IMPORT_DLLS: &[&str] = &[
+    "advapi32.dll",
+    "comctl32.dll",
+    "crypt32.dll",
+    "gdi32.dll",
+    "kernel32.dll",
+    "msvcrt.dll",
+    "netapi32.dll",
+    "ntdll.dll",
+    "ole32.dll",
+    "oleaut32.dll",
+    "shell32.dll",
+    "shlwapi.dll",
+    "user32.dll",
+    "winmm.dll",
+    "ws2_32.dll",
+    "wsock32.dll",
+];
+
+/// Prefixes of the names of DLLs of the system and of the runtime libraries of Visual C++.
+static ORDINAL_IMPORT_DLL_PREFIXES: &[&str] = &["api-ms-win-", "ext-ms-win-", "mfc"];
+
+/// Returns whether imports by ordinal from the DLL named `dll` are common.
+fn imports_by_ordinal_are_common(dll: &str) -> bool {
+    let dll = dll.to_ascii_lowercase();
+    ORDINAL_IMPORT_DLLS.contains(&dll.as_str())
+        || ORDINAL_IMPORT_DLL_PREFIXES
+            .iter()
+            .any(|prefix| dll.starts_with(prefix))
+}
+
+/// Reports anomalies of the imports of the image, which are common in obfuscated images.
+///
+/// Reported anomalies are:
+/// - `ordinal-only=...`: DLLs that are not part of the system, from which all functions are
+///   imported by ordinal, which hides the names of the imported functions.
+/// - `writable-section=...`: sections holding import address tables, which are writable and are
+///   not the `.idata` section where linkers place imports.
+pub(crate) fn iat_anomalies(pe: &goblin::pe::PE) -> DetailedStatus {
+    use goblin::pe::import::SyntheticImportLookupTableEntry;
+    use goblin::pe::section_table::IMAGE_SCN_MEM_WRITE;
+
+    let mut ordinal_only: Vec<String> = Vec::default();
+    let mut writable_sections: Vec<&str> = Vec::default();
+
+    for entry in pe.import_data.iter().flat_map(|data| &data.import_data) {
+        let by_ordinal = entry.import_lookup_table.as_ref().is_some_and(|table| {
+            !table.is_empty()
+                && table
+                    .iter()
+                    .all(|entry| matches!(entry, SyntheticImportLookupTableEntry::OrdinalNumber(_)))
+        });
+        if by_ordinal && !imports_by_ordinal_are_common(entry.name) {
+            ordinal_only.push(entry.name.to_ascii_lowercase());
+        }
+
+        let iat = entry.import_directory_entry.import_address_table_rva;
+        let section = pe.sections.iter().find(|section| {
+            let start = section.virtual_address;
+            let size = section.virtual_size.max(section.size_of_raw_data);
+            (start..start.saturating_add(size)).contains(&iat)
+        });
+
+        if let Some(section) = section {
+            let name = section.name().unwrap_or_default();
+            if (section.characteristics & IMAGE_SCN_MEM_WRITE) != 0
+                && name != ".idata"
+                && !writable_sections.contains(&name)
+            {
+                debug!(
+                    "Import address table of '{}' is in writable section '{name}'.",
+                    entry.name
+                );
+                writable_sections.push(name);
+            }
+        }
+    }
+
+    let mut anomalies = Vec::default();
+    if !ordinal_only.is_empty() {
+        ordinal_only.sort_unstable();
+        ordinal_only.dedup();
+        anomalies.push(format!("ordinal-only={}", ordinal_only.join("+")));
+    }
+    if !writable_sections.is_empty() {
+        anomalies.push(format!("writable-section={}", writable_sections.join("+")));
+    }
+
+    let level = if anomalies.is_empty() {
+        SupportLevel::Supported
+    } else {
+        SupportLevel::Unsupported
+    };
+    DetailedStatus::new("IAT-ANOMALY", level, anomalies)
+}
+
+/// Converts a relative virtual address into an offset in the file.
+fn rva_to_file_offset(pe: &goblin::pe::PE, rva: u64) -> Option<usize> {
+    let file_alignment = pe
+        .header
+        .optional_header
+        .map(|optional_header| optional_header.windows_fields.file_alignment)?;
+
+    goblin::pe::utils::find_offset(
+        usize::try_from(rva).ok()?,
+        &pe.sections,
+        file_alignment,
+        &goblin::pe::options::ParseOptions::default(),
+    )
+}
+
+/// Converts a virtual address into an offset in the file.
+fn va_to_file_offset(pe: &goblin::pe::PE, va: u64) -> Option<usize> {
+    va.checked_sub(pe.image_base as u64)
+        .and_then(|rva| rva_to_file_offset(pe, rva))
+}
+
+pub(crate) const IMAGE_FILE_MACHINE_ARM64EC: u16 = 0xA641;
+pub(crate) const IMAGE_FILE_MACHINE_ARM64X: u16 = 0xA64E;
+pub(crate) const IMAGE_FILE_MACHINE_CHPE_X86: u16 = 0x3A64;
+
+/// Architecture of a hybrid executable, which mixes native ARM64 code with code of an emulated
+/// instruction set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum HybridArchitecture {
+    /// ARM64EC code, interoperable with emulated x64 code.
+    ARM64EC,
+    /// ARM64X executable, containing both ARM64 code and ARM64EC code.
+    ARM64X,
+    /// Compiled Hybrid Portable Executable, containing x86 code and ARM64 code.
+    CHPEX86,
+}
+
+impl HybridArchitecture {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            HybridArchitecture::ARM64EC => "ARM64EC",
+            HybridArchitecture::ARM64X => "ARM64X",
+            HybridArchitecture::CHPEX86 => "CHPE-X86",
+        }
+    }
+}
+
+/// Code address ranges of a hybrid executable, by instruction set.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct HybridCodeRanges {
+    pub(crate) count: u64,
+    pub(crate) size: u64,
+}
+
+/// Hybrid-specific information about an executable.
+#[derive(Debug)]
+pub(crate) struct HybridImageMetadata {
+    pub(crate) architecture: HybridArchitecture,
+    /// Ranges of native ARM64 code.
+    pub(crate) arm64_code: HybridCodeRanges,
+    /// Ranges of ARM64EC code, compatible with emulated x64 code.
+    pub(crate) arm64ec_code: HybridCodeRanges,
+    /// Ranges of emulated code (x64 for ARM64EC and ARM64X, x86 for CHPE).
+    pub(crate) emulated_code: HybridCodeRanges,
+    /// Control Flow Guard check function is defined for the native instruction set.
+    pub(crate) native_guard: bool,
+    /// Control Flow Guard dispatcher is defined for indirect calls from the emulated instruction
+    /// set.
+    pub(crate) emulated_guard: bool,
+}
+
+/// `IMAGE_ARM64EC_METADATA` fields.
+const ARM64EC_METADATA_CODE_MAP_OFFSET: usize = 4;
+const ARM64EC_METADATA_CODE_MAP_COUNT_OFFSET: usize = 8;
+const ARM64EC_METADATA_DISPATCH_ICALL_CFG_OFFSET: usize = 36;
+/// `IMAGE_CHPE_METADATA_X86` fields.
+const CHPE_X86_METADATA_CODE_RANGE_OFFSET: usize = 4;
+const CHPE_X86_METADATA_CODE_RANGE_COUNT_OFFSET: usize = 8;
+const CHPE_X86_METADATA_DISPATCH_ICALL_CFG_OFFSET: usize = 24;
+
+/// Returns hybrid-specific information about the executable, if it is a hybrid executable.
+///
+/// Hybrid executables (ARM64EC, ARM64X and CHPE) are identified by the hybrid metadata referenced
+/// by the image load configuration directory. ARM64EC executables declare the x64 machine type in
+/// their COFF header, so they would otherwise be indistinguishable from plain x64 executables.
+pub(crate) fn hybrid_image_metadata(
+    parser: &BinaryParser,
+    pe: &goblin::pe::PE,
+) -> Result<Option<HybridImageMetadata>> {
+    use goblin::pe::header::{COFF_MACHINE_ARM64, COFF_MACHINE_X86, COFF_MACHINE_X86_64};
+
+    let Some(chpe_metadata_pointer) =
+        read_load_config_field(parser, pe, &LOAD_CONFIG_CHPE_METADATA_POINTER)?
+            .filter(|&pointer| pointer != 0)
+    else {
+        return Ok(None);
+    };
+
+    let architecture = match pe.header.coff_header.machine {
+        COFF_MACHINE_X86_64 | IMAGE_FILE_MACHINE_ARM64EC => HybridArchitecture::ARM64EC,
+        COFF_MACHINE_ARM64 | IMAGE_FILE_MACHINE_ARM64X => HybridArchitecture::ARM64X,
+        COFF_MACHINE_X86 | IMAGE_FILE_MACHINE_CHPE_X86 => HybridArchitecture::CHPEX86,
+
+        machine => {
             debug!(
-                "Image load configuration directory defines {} structured exceptions handlers.",
-                se_handler_count
+                "Hybrid metadata is referenced by an executable of unexpected machine type 0x{:04X}.",
+                machine
             );
-            se_handler_count > 0
-        })
+            return Ok(None);
+        }
+    };
+
+    debug!(
+        "Image load configuration directory references {} hybrid metadata.",
+        architecture.name()
+    );
+
+    let Some(metadata_offset) = va_to_file_offset(pe, chpe_metadata_pointer) else {
+        return Ok(None);
+    };
+    let bytes = parser.bytes();
+    let read_u32 = |offset: usize| -> Option<u32> {
+        bytes
+            .pread_with::<u32>(metadata_offset.saturating_add(offset), scroll::LE)
+            .ok()
+    };
+
+    let (code_map_offset, code_map_count_offset, dispatch_icall_cfg_offset) =
+        if architecture == HybridArchitecture::CHPEX86 {
+            (
+                CHPE_X86_METADATA_CODE_RANGE_OFFSET,
+                CHPE_X86_METADATA_CODE_RANGE_COUNT_OFFSET,
+                CHPE_X86_METADATA_DISPATCH_ICALL_CFG_OFFSET,
+            )
+        } else {
+            (
+                ARM64EC_METADATA_CODE_MAP_OFFSET,
+                ARM64EC_METADATA_CODE_MAP_COUNT_OFFSET,
+                ARM64EC_METADATA_DISPATCH_ICALL_CFG_OFFSET,
+            )
+        };
+
+    let mut result = HybridImageMetadata {
+        architecture,
+        arm64_code: HybridCodeRanges::default(),
+        arm64ec_code: HybridCodeRanges::default(),
+        emulated_code: HybridCodeRanges::default(),
+        native_guard: read_load_config_field(
+            parser,
+            pe,
+            &LOAD_CONFIG_GUARD_CF_CHECK_FUNCTION_POINTER,
+        )?
+        .is_some_and(|pointer| pointer != 0),
+        emulated_guard: read_u32(dispatch_icall_cfg_offset).is_some_and(|pointer| pointer != 0),
+    };
+
+    let code_map_offset = read_u32(code_map_offset)
+        .and_then(|code_map_rva| rva_to_file_offset(pe, u64::from(code_map_rva)));
+    let code_map_count = read_u32(code_map_count_offset).unwrap_or(0);
+
+    if let Some(code_map_offset) = code_map_offset {
+        // Each entry of the code map is a pair of `u32` values: a start offset, whose lowest bits
+        // encode the instruction set of the range, and a length.
+        let entries = (0..code_map_count).map_while(|index| {
+            let entry_offset = code_map_offset.saturating_add((index as usize).saturating_mul(8));
+            let start_offset = bytes.pread_with::<u32>(entry_offset, scroll::LE).ok()?;
+            let length = bytes
+                .pread_with::<u32>(entry_offset.saturating_add(4), scroll::LE)
+                .ok()?;
+            Some((start_offset, length))
+        });
+
+        for (start_offset, length) in entries {
+            let ranges = match (architecture, start_offset & 0b11) {
+                (HybridArchitecture::CHPEX86, 0 | 2) | (_, 2) => &mut result.emulated_code,
+                (HybridArchitecture::CHPEX86, _) | (_, 0) => &mut result.arm64_code,
+                _ => &mut result.arm64ec_code,
+            };
+            ranges.count = ranges.count.saturating_add(1);
+            ranges.size = ranges.size.saturating_add(u64::from(length));
+        }
+    }
+
+    debug!("Hybrid executable metadata: {:?}.", result);
+    Ok(Some(result))
 }
@@ -0,0 +1,226 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Resources embedded in PE images, as described by their resource directory.
+
+use log::debug;
+use scroll::Pread;
+
+use super::rva_to_file_offset;
+
+/// Resource type of icons, which are often compressed images.
+pub(crate) const RT_ICON: u32 = 3;
+/// Resource type of side-by-side assembly manifests.
+pub(crate) const RT_MANIFEST: u32 = 24;
+
+/// Size of `IMAGE_RESOURCE_DIRECTORY`.
+const SIZEOF_RESOURCE_DIRECTORY: usize = 16;
+/// Size of `IMAGE_RESOURCE_DIRECTORY_ENTRY`.
+const SIZEOF_RESOURCE_DIRECTORY_ENTRY: usize = 8;
+/// Set in fields of directory entries that reference a name or a subdirectory.
+const IMAGE_RESOURCE_HIGH_BIT: u32 = 0x8000_0000;
+
+/// Identifier of a resource, or of its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ResourceId {
+    Id(u32),
+    Name(String),
+}
+
+impl core::fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ResourceId::Id(id) => write!(f, "{id}"),
+            ResourceId::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Resource embedded in a PE image.
+pub(crate) struct Resource<'t> {
+    /// Type of the resource, e.g., `RT_MANIFEST`.
+    pub(crate) kind: ResourceId,
+    pub(crate) name: ResourceId,
+    pub(crate) data: &'t [u8],
+}
+
+impl Resource<'_> {
+    /// Returns the location of the resource, as `TYPE/NAME`, naming predefined resource types
+    /// without their `RT_` prefix, e.g., `RCDATA/101`.
+    pub(crate) fn location(&self) -> String {
+        let kind = match self.kind {
+            ResourceId::Id(id) => PREDEFINED_RESOURCE_TYPES
+                .iter()
+                .find(|&&(predefined_id, _name)| predefined_id == id)
+                .map(|&(_id, name)| name),
+            ResourceId::Name(_) => None,
+        };
+
+        match kind {
+            Some(kind) => format!("{kind}/{}", self.name),
+            None => format!("{}/{}", self.kind, self.name),
+        }
+    }
+}
+
+/// Resource types predefined by Windows.
+const PREDEFINED_RESOURCE_TYPES: [(u32, &str); 22] = [
+    (1, "CURSOR"),
+    (2, "BITMAP"),
+    (RT_ICON, "ICON"),
+    (4, "MENU"),
+    (5, "DIALOG"),
+    (6, "STRING"),
+    (7, "FONTDIR"),
+    (8, "FONT"),
+    (9, "ACCELERATOR"),
+    (10, "RCDATA"),
+    (11, "MESSAGETABLE"),
+    (12, "GROUP_CURSOR"),
+    (14, "GROUP_ICON"),
+    (16, "VERSION"),
+    (17, "DLGINCLUDE"),
+    (19, "PLUGPLAY"),
+    (20, "VXD"),
+    (21, "ANICURSOR"),
+    (22, "ANIICON"),
+    (23, "HTML"),
+    (RT_MANIFEST, "MANIFEST"),
+    (240, "DLGINIT"),
+];
+
+/// Returns the resources embedded in a PE image.
+///
+/// Resources are organized in three levels of directories: types, names, then languages.
+/// Resources whose data is not inside the file are not returned.
+pub(crate) fn resources<'t>(bytes: &'t [u8], pe: &goblin::pe::PE) -> Vec<Resource<'t>> {
+    let Some(table) = pe
+        .header
+        .optional_header
+        .and_then(|optional_header| {
+            optional_header
+                .data_directories
+                .get_resource_table()
+                .copied()
+        })
+        .filter(|table| table.virtual_address != 0 && table.size != 0)
+    else {
+        return Vec::default();
+    };
+
+    let Some(root) = rva_to_file_offset(pe, u64::from(table.virtual_address)) else {
+        debug!("Resource directory is not inside the file.");
+        return Vec::default();
+    };
+
+    let mut result = Vec::default();
+    for (resource_type, type_entry) in directory_entries(bytes, root, 0) {
+        let DirectoryEntry::Subdirectory(names_offset) = type_entry else {
+            continue;
+        };
+
+        for (name, name_entry) in directory_entries(bytes, root, names_offset) {
+            let DirectoryEntry::Subdirectory(languages_offset) = name_entry else {
+                continue;
+            };
+
+            for (_language, language_entry) in directory_entries(bytes, root, languages_offset) {
+                let DirectoryEntry::Data(data_entry_offset) = language_entry else {
+                    continue;
+                };
+
+                if let Some(data) = resource_data(bytes, pe, root, data_entry_offset) {
+                    result.push(Resource {
+                        kind: resource_type.clone(),
+                        name: name.clone(),
+                        data,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+enum DirectoryEntry {
+    /// Offset of a subdirectory, relative to the resource directory.
+    Subdirectory(usize),
+    /// Offset of a data entry, relative to the resource directory.
+    Data(usize),
+}
+
+/// Returns the entries of the directory located at `offset` from the resource directory `root`.
+fn directory_entries(
+    bytes: &[u8],
+    root: usize,
+    offset: usize,
+) -> Vec<(ResourceId, DirectoryEntry)> {
+    let directory = root.saturating_add(offset);
+    let read_u16 = |offset: usize| bytes.pread_with::<u16>(offset, scroll::LE).ok();
+    let read_u32 = |offset: usize| bytes.pread_with::<u32>(offset, scroll::LE).ok();
+
+    let (Some(named_count), Some(id_count)) = (
+        read_u16(directory.saturating_add(12)),
+        read_u16(directory.saturating_add(14)),
+    ) else {
+        return Vec::default();
+    };
+
+    let entries = directory.saturating_add(SIZEOF_RESOURCE_DIRECTORY);
+    (0..usize::from(named_count) + usize::from(id_count))
+        .map_while(|index| {
+            let entry = entries.saturating_add(index * SIZEOF_RESOURCE_DIRECTORY_ENTRY);
+            Some((read_u32(entry)?, read_u32(entry.saturating_add(4))?))
+        })
+        .filter_map(|(name, target)| {
+            let id = if (name & IMAGE_RESOURCE_HIGH_BIT) == 0 {
+                ResourceId::Id(name)
+            } else {
+                let name_offset = root.saturating_add((name & !IMAGE_RESOURCE_HIGH_BIT) as usize);
+                ResourceId::Name(resource_name(bytes, name_offset)?)
+            };
+
+            let target_offset = (target & !IMAGE_RESOURCE_HIGH_BIT) as usize;
+            let entry = if (target & IMAGE_RESOURCE_HIGH_BIT) == 0 {
+                DirectoryEntry::Data(target_offset)
+            } else {
+                DirectoryEntry::Subdirectory(target_offset)
+            };
+            Some((id, entry))
+        })
+        .collect()
+}
+
+/// Reads a length-prefixed UTF-16 resource name.
+fn resource_name(bytes: &[u8], offset: usize) -> Option<String> {
+    let length = bytes.pread_with::<u16>(offset, scroll::LE).ok()?;
+    let units: Vec<u16> = (0..usize::from(length))
+        .map(|index| {
+            bytes
+                .pread_with::<u16>(offset.saturating_add(2 + index * 2), scroll::LE)
+                .ok()
+        })
+        .collect::<Option<_>>()?;
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Returns the data described by the `IMAGE_RESOURCE_DATA_ENTRY` located at `offset` from the
+/// resource directory `root`.
+fn resource_data<'t>(
+    bytes: &'t [u8],
+    pe: &goblin::pe::PE,
+    root: usize,
+    offset: usize,
+) -> Option<&'t [u8]> {
+    let data_entry = root.saturating_add(offset);
+    let rva = bytes.pread_with::<u32>(data_entry, scroll::LE).ok()?;
+    let size = bytes
+        .pread_with::<u32>(data_entry.saturating_add(4), scroll::LE)
+        .ok()?;
+
+    let start = rva_to_file_offset(pe, u64::from(rva))?;
+    bytes.get(start..start.checked_add(size as usize)?)
+}
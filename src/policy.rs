@@ -0,0 +1,187 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Hardening requirements of the packaging guidelines of distributions, verified by `--policy`.
+//!
+//! Requirements only name security features checked by this tool. Features that distributions
+//! also require but that cannot be verified in binaries (e.g., `-fstack-clash-protection`) are
+//! not part of the presets.
+
+use std::path::PathBuf;
+
+use crate::cmdline::Policy;
+use crate::options::status::{CheckResult, DetailedStatus, SupportLevel};
+use crate::report::{BinaryFormat, FileResults};
+
+/// Keyword of the result reporting whether a file meets the requirements of a policy.
+pub(crate) const POLICY: &str = "POLICY";
+
+/// Security feature required by a policy.
+struct Requirement {
+    /// Keyword of the security feature.
+    feature: &'static str,
+    /// Whether partial support meets the requirement.
+    accepts_partial: bool,
+}
+
+const fn full(feature: &'static str) -> Requirement {
+    Requirement {
+        feature,
+        accepts_partial: false,
+    }
+}
+
+const fn partial(feature: &'static str) -> Requirement {
+    Requirement {
+        feature,
+        accepts_partial: true,
+    }
+}
+
+/// Fedora builds with `redhat-rpm-config` flags: PIE, full RELRO, `-fstack-protector-strong`,
+/// and `-D_FORTIFY_SOURCE=3`.
+static FEDORA_40: &[Requirement] = &[
+    full("ASLR"),
+    full("STACK-PROT"),
+    full("READ-ONLY-RELOC"),
+    full("IMMEDIATE-BIND"),
+    partial("FORTIFY-SOURCE"),
+];
+
+/// Debian builds with the default flags of `dpkg-buildflags`, which do not include
+/// `-Wl,-z,now` unless `hardening=+bindnow` is requested.
+static DEBIAN_12: &[Requirement] = &[
+    full("ASLR"),
+    full("STACK-PROT"),
+    full("READ-ONLY-RELOC"),
+    partial("FORTIFY-SOURCE"),
+];
+
+/// Ubuntu adds `-Wl,-z,now` to the default flags of Debian.
+static UBUNTU_24_04: &[Requirement] = &[
+    full("ASLR"),
+    full("STACK-PROT"),
+    full("READ-ONLY-RELOC"),
+    full("IMMEDIATE-BIND"),
+    partial("FORTIFY-SOURCE"),
+];
+
+/// Alpine builds with PIE, full RELRO and `-fstack-protector-strong` by default. Fortified
+/// functions of `musl` are inlined by `fortify-headers`, so they cannot be verified.
+static ALPINE_3_20: &[Requirement] = &[
+    full("ASLR"),
+    full("STACK-PROT"),
+    full("READ-ONLY-RELOC"),
+    full("IMMEDIATE-BIND"),
+];
+
+impl Policy {
+    fn requirements(self) -> &'static [Requirement] {
+        match self {
+            Self::Fedora40 => FEDORA_40,
+            Self::Debian12 => DEBIAN_12,
+            Self::Ubuntu2404 => UBUNTU_24_04,
+            Self::Alpine320 => ALPINE_3_20,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Fedora40 => "fedora40",
+            Self::Debian12 => "debian12",
+            Self::Ubuntu2404 => "ubuntu24.04",
+            Self::Alpine320 => "alpine3.20",
+        }
+    }
+}
+
+/// Returns the required security features of `policy` that are not supported by a file,
+/// followed by those whose support is unknown or that were not checked.
+fn unmet_requirements(
+    policy: Policy,
+    results: &FileResults,
+) -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut missing = Vec::default();
+    let mut unknown = Vec::default();
+
+    for requirement in policy.requirements() {
+        let level = results
+            .checks
+            .iter()
+            .find(|check| check.name() == requirement.feature)
+            .and_then(CheckResult::level);
+
+        match level {
+            Some(SupportLevel::Supported) => {}
+            Some(SupportLevel::Partial) if requirement.accepts_partial => {}
+            Some(SupportLevel::Partial | SupportLevel::Unsupported) => {
+                missing.push(requirement.feature);
+            }
+            Some(SupportLevel::Unknown) | None => unknown.push(requirement.feature),
+        }
+    }
+    (missing, unknown)
+}
+
+/// Returns whether the results of a file meet the requirements of `policy`.
+///
+/// The details name the policy, followed by the required security features that are not
+/// supported as `missing=...`, and those whose support is unknown or that were not checked as
+/// `unknown=...`.
+fn evaluate(policy: Policy, results: &FileResults) -> DetailedStatus {
+    let (missing, unknown) = unmet_requirements(policy, results);
+
+    let level = if !missing.is_empty() {
+        SupportLevel::Unsupported
+    } else if !unknown.is_empty() {
+        SupportLevel::Unknown
+    } else {
+        SupportLevel::Supported
+    };
+
+    let mut details = vec![policy.name().to_owned()];
+    if !missing.is_empty() {
+        details.push(format!("missing={}", missing.join("+")));
+    }
+    if !unknown.is_empty() {
+        details.push(format!("unknown={}", unknown.join("+")));
+    }
+    DetailedStatus::new(POLICY, level, details)
+}
+
+/// Reports whether each `ELF` file meets the requirements of `policy`, and returns `true` if
+/// some files do not meet them.
+pub(crate) fn apply(policy: Policy, reports: &mut [(PathBuf, FileResults)]) -> bool {
+    let mut violated = false;
+
+    for (_path, results) in reports
+        .iter_mut()
+        .filter(|(_path, results)| results.format == BinaryFormat::ELF)
+    {
+        let status = CheckResult::from(evaluate(policy, results));
+        violated |= status.level() == Some(SupportLevel::Unsupported);
+        results.checks.push(status);
+    }
+    violated
+}
+
+/// Returns the number of requirements of `policy` met by all `ELF` files, followed by the
+/// number of requirements verified in these files.
+pub(crate) fn requirements_met(policy: Policy, reports: &[(PathBuf, FileResults)]) -> (u32, u32) {
+    let required = u32::try_from(policy.requirements().len()).unwrap_or(u32::MAX);
+
+    reports
+        .iter()
+        .filter(|(_path, results)| results.format == BinaryFormat::ELF)
+        .map(|(_path, results)| {
+            let (missing, unknown) = unmet_requirements(policy, results);
+            let unmet = u32::try_from(missing.len() + unknown.len()).unwrap_or(u32::MAX);
+            required.saturating_sub(unmet)
+        })
+        .fold((0_u32, 0_u32), |(met, total), file_met| {
+            (met.saturating_add(file_met), total.saturating_add(required))
+        })
+}
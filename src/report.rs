@@ -0,0 +1,594 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+mod badge;
+mod compare;
+mod github;
+mod json;
+mod prometheus;
+mod webhook;
+
+pub(crate) use self::badge::write_badge;
+pub(crate) use self::compare::BaselineReport;
+pub(crate) use self::json::PreviousReport;
+pub(crate) use self::webhook::post_results;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::cmdline::{GroupBy, OutputFormat, ReportSchema, SortOrder};
+use crate::errors::{Error, Result};
+use crate::options::hints::remediation_hint;
+use crate::options::ids::check_id;
+use crate::options::status::{CheckResult, SupportLevel};
+use crate::ui::ColorBuffer;
+
+/// Format of an analyzed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum BinaryFormat {
+    ELF,
+    PE,
+    Archive,
+    /// DOS, NE, LE and LX executables.
+    Legacy,
+    /// TE images, and UEFI capsules and firmware volumes.
+    EFI,
+    /// Linux kernel images: `vmlinux` and `bzImage` files.
+    Kernel,
+}
+
+impl BinaryFormat {
+    /// All formats of analyzed files.
+    pub(crate) const ALL: [Self; 6] = [
+        BinaryFormat::ELF,
+        BinaryFormat::PE,
+        BinaryFormat::Archive,
+        BinaryFormat::Legacy,
+        BinaryFormat::EFI,
+        BinaryFormat::Kernel,
+    ];
+}
+
+impl core::fmt::Display for BinaryFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match *self {
+            BinaryFormat::ELF => "ELF",
+            BinaryFormat::PE => "PE",
+            BinaryFormat::Archive => "Archive",
+            BinaryFormat::Legacy => "Legacy",
+            BinaryFormat::EFI => "EFI",
+            BinaryFormat::Kernel => "Kernel",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Architecture targeted by an analyzed file.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Architecture {
+    /// Name of the instruction set, similar to the `target_arch` names of Rust.
+    pub(crate) name: String,
+    pub(crate) bits: u8,
+    pub(crate) endianness: Endianness,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Endianness {
+    Little,
+    Big,
+}
+
+/// Results of the analysis of a file.
+pub(crate) struct FileResults {
+    pub(crate) format: BinaryFormat,
+    /// Architecture of the file, unless it is not unique, e.g., for archives.
+    pub(crate) architecture: Option<Architecture>,
+    /// Results of the checks, in the order they are reported.
+    pub(crate) checks: Vec<CheckResult>,
+    /// C runtime library that the checked functions of `ELF` files were compared against, e.g.,
+    /// its path, `spec: Linux Standard Base 5.0.0` or `none`.
+    pub(crate) libc: Option<String>,
+    /// Time spent analyzing the file, if it was measured.
+    pub(crate) duration: Option<Duration>,
+}
+
+/// Filters, sorts and redacts the results of all analyzed files, as requested, before they are
+/// reported.
+pub(crate) fn prepare_results(
+    reports: &mut Vec<(PathBuf, FileResults)>,
+    options: &crate::cmdline::Options,
+) {
+    if options.only.is_some() || options.failing {
+        filter_results(reports, options);
+    }
+
+    sort_results(reports, options.sort);
+
+    if options.redact_paths {
+        for (path, _results) in reports {
+            *path = redact_path(path);
+        }
+    }
+}
+
+/// Prints the results of all analyzed files, in the requested format.
+///
+/// Errors and results kept from a previous report are only part of structured reports. Only the
+/// number of errors is part of Prometheus reports. Errors are annotations of GitHub Actions.
+/// If a baseline report is given, then only the changes compared to it are printed.
+pub(crate) fn print_results(
+    reports: Vec<(PathBuf, FileResults)>,
+    errors: &[(PathBuf, Error)],
+    previous_report: Option<PreviousReport>,
+    baseline_report: Option<&BaselineReport>,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    if let Some(baseline_report) = baseline_report {
+        return compare::print_changes(&reports, baseline_report, options);
+    }
+
+    match options.format {
+        OutputFormat::Text => print_text_results(reports, options),
+        OutputFormat::Json => json::print_results(&reports, errors, previous_report, options),
+        OutputFormat::Prometheus => prometheus::print_results(&reports, errors),
+        OutputFormat::GitHubAnnotations => github::print_results(&reports, errors, options),
+    }
+}
+
+/// Prints the schema describing the structure of reports of the requested format.
+pub(crate) fn print_schema(schema: ReportSchema) -> Result<()> {
+    match schema {
+        ReportSchema::Json => json::print_schema(),
+    }
+}
+
+/// Returns the JSON document reporting the results of the given files, as printed by
+/// `--format json`.
+pub(crate) fn json_document(
+    reports: &[(PathBuf, FileResults)],
+    errors: &[(PathBuf, Error)],
+    options: &crate::cmdline::Options,
+) -> Result<Vec<u8>> {
+    let report = json::report(reports, errors, Vec::default(), options)?;
+    serde_json::to_vec(&report).map_err(|r| Error::from_io1(r.into(), "serialize", "JSON document"))
+}
+
+/// Prints the results of all analyzed files as text, grouped as requested.
+fn print_text_results(
+    reports: Vec<(PathBuf, FileResults)>,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let mut out = ColorBuffer::for_stdout(options.color);
+
+    match options.group_by {
+        None => {
+            for (path, results) in &reports {
+                print_file_results(&mut out, path, results, options)?;
+            }
+        }
+
+        Some(GroupBy::Directory) => {
+            let groups = group_results(reports, |path, _results| {
+                path.parent().map(Path::to_path_buf).unwrap_or_default()
+            });
+
+            for (dir, reports) in groups {
+                print_group_header(&mut out, &dir.display())?;
+                for (path, results) in &reports {
+                    print_file_results(&mut out, path, results, options)?;
+                }
+            }
+        }
+
+        Some(GroupBy::Status) => {
+            let groups = group_results(reports, |_path, results| worst_level(results));
+
+            for (level, reports) in groups {
+                print_group_header(&mut out, &level.description())?;
+                for (path, results) in &reports {
+                    print_file_results(&mut out, path, results, options)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Retains only the results selected by `--only` and `--failing`.
+/// `--only` selects checks by keyword of security feature, or by stable identifier.
+/// Files for which no results remain are not reported.
+fn filter_results(reports: &mut Vec<(PathBuf, FileResults)>, options: &crate::cmdline::Options) {
+    for (_path, results) in reports.iter_mut() {
+        let format = results.format;
+        results.checks.retain(|r| {
+            let name_matches = options.only.as_ref().is_none_or(|pattern| {
+                pattern.is_match(r.name())
+                    || check_id(format, r.name()).is_some_and(|id| pattern.is_match(id))
+            });
+
+            name_matches && (!options.failing || is_failing(r))
+        });
+    }
+
+    reports.retain(|(_path, results)| !results.checks.is_empty());
+}
+
+/// Numbers of analyzed files by outcome, printed once all files are reported.
+pub(crate) struct Summary {
+    /// Files supporting all checked security features.
+    passed: usize,
+    /// Files supporting some security features partially, or whose support is unknown.
+    warned: usize,
+    /// Files not supporting some security features.
+    failed: usize,
+    /// Files that could not be analyzed.
+    errors: usize,
+}
+
+impl Summary {
+    pub(crate) fn new(reports: &[(PathBuf, FileResults)], errors: usize) -> Self {
+        let mut summary = Self {
+            passed: 0,
+            warned: 0,
+            failed: 0,
+            errors,
+        };
+
+        for (_path, results) in reports {
+            match worst_level(results) {
+                SupportLevel::Supported => summary.passed += 1,
+                SupportLevel::Partial | SupportLevel::Unknown => summary.warned += 1,
+                SupportLevel::Unsupported => summary.failed += 1,
+            }
+        }
+        summary
+    }
+
+    /// Prints the summary to the standard error stream, so that it does not interfere with
+    /// structured reports.
+    pub(crate) fn print(&self, elapsed: Duration) {
+        let total = self.passed + self.warned + self.failed + self.errors;
+        let files = if total == 1 { "file" } else { "files" };
+        let errors = if self.errors == 1 { "error" } else { "errors" };
+        eprintln!(
+            "Scanned {total} {files}: {} pass, {} warn, {} fail, {} {errors} ({:.1}s)",
+            self.passed,
+            self.warned,
+            self.failed,
+            self.errors,
+            elapsed.as_secs_f64()
+        );
+    }
+}
+
+/// Returns `true` if a security feature is not fully supported.
+/// Informational results are never considered as failing.
+pub(crate) fn is_failing(check: &CheckResult) -> bool {
+    check
+        .level()
+        .is_some_and(|level| level != SupportLevel::Supported)
+}
+
+/// Returns `true` if security features of a file were checked, but the status of none of them is
+/// known. Informational results are not considered.
+pub(crate) fn is_all_unknown(results: &FileResults) -> bool {
+    let mut levels = results
+        .checks
+        .iter()
+        .filter_map(CheckResult::level)
+        .peekable();
+    levels.peek().is_some() && levels.all(|level| level == SupportLevel::Unknown)
+}
+
+/// Returns `path` with the bytes that are not valid UTF-8 escaped as `\xHH`, and backslashes
+/// escaped as `\\`, or `None` if `path` is valid UTF-8.
+///
+/// Structured reports show such paths lossily, so the escaped path is reported as well, which
+/// identifies the file without ambiguity, e.g., in firmware file systems with arbitrary names.
+pub(crate) fn escaped_path(path: &Path) -> Option<String> {
+    use core::fmt::Write;
+
+    if path.to_str().is_some() {
+        return None;
+    }
+
+    let mut r = String::default();
+    for chunk in path.as_os_str().as_encoded_bytes().utf8_chunks() {
+        r.push_str(&chunk.valid().replace('\\', r"\\"));
+        for byte in chunk.invalid() {
+            let _ignored = write!(r, r"\x{byte:02x}");
+        }
+    }
+    Some(r)
+}
+
+/// Returns the path escaped by `escaped_path()`, or `None` if `text` is not escaped correctly.
+pub(crate) fn unescaped_path(text: &str) -> Option<PathBuf> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '\\' => bytes.push(b'\\'),
+                'x' => {
+                    let digits = [chars.next()?, chars.next()?];
+                    let digits: String = digits.iter().collect();
+                    bytes.push(u8::from_str_radix(&digits, 16).ok()?);
+                }
+                _ => return None,
+            },
+
+            c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        Some(std::ffi::OsString::from_vec(bytes).into())
+    }
+
+    // Paths that are not valid UTF-8 cannot be safely rebuilt on other platforms.
+    #[cfg(not(unix))]
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// Replaces the user directory at the start of `path` by `~`, so that user names do not appear
+/// in reports.
+///
+/// The home directory of the current user is replaced, as well as `/home/*`, `/Users/*` and
+/// `C:\Users\*` directories of other users.
+pub(crate) fn redact_path(path: &Path) -> PathBuf {
+    let home_dir = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+
+    if let Some(rest) = home_dir.and_then(|home_dir| path.strip_prefix(home_dir).ok()) {
+        return Path::new("~").join(rest);
+    }
+
+    let components: Vec<_> = path
+        .components()
+        .skip_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+        .collect();
+
+    match components.as_slice() {
+        [parent, _user, rest @ ..]
+            if path.has_root()
+                && [OsStr::new("home"), OsStr::new("Users")].contains(&parent.as_os_str()) =>
+        {
+            let mut result = PathBuf::from("~");
+            result.extend(rest);
+            result
+        }
+
+        _ => path.to_path_buf(),
+    }
+}
+
+fn sort_results(reports: &mut [(PathBuf, FileResults)], order: SortOrder) {
+    // Sorting is stable, so files that compare equal remain in input order.
+    match order {
+        SortOrder::Input => {}
+        SortOrder::Name => reports.sort_by(|(path1, _), (path2, _)| path1.cmp(path2)),
+        SortOrder::Status => reports.sort_by_key(|(_path, results)| worst_level(results)),
+        SortOrder::Score => reports.sort_by_key(|(_path, results)| score(results)),
+    }
+}
+
+fn group_results<K: Ord>(
+    reports: Vec<(PathBuf, FileResults)>,
+    key: impl Fn(&Path, &FileResults) -> K,
+) -> BTreeMap<K, Vec<(PathBuf, FileResults)>> {
+    let mut groups = BTreeMap::<K, Vec<_>>::default();
+    for (path, results) in reports {
+        groups
+            .entry(key(&path, &results))
+            .or_default()
+            .push((path, results));
+    }
+    groups
+}
+
+/// Returns the most problematic level of support among the results of a file.
+/// Informational results are not considered.
+pub(crate) fn worst_level(results: &FileResults) -> SupportLevel {
+    results
+        .checks
+        .iter()
+        .filter_map(CheckResult::level)
+        .min()
+        .unwrap_or(SupportLevel::Unknown)
+}
+
+/// Returns the percentage of security features supported by a file.
+///
+/// Partially supported features count as half supported. Informational results are not
+/// considered.
+pub(crate) fn score(results: &FileResults) -> u32 {
+    let (points, max_points) = score_points(results);
+
+    points
+        .saturating_mul(100)
+        .checked_div(max_points)
+        .unwrap_or_default()
+}
+
+/// Returns the points scored by the security features of a file, followed by the maximum
+/// points it could score. Supported features score two points, and partially supported
+/// features score one point.
+pub(crate) fn score_points(results: &FileResults) -> (u32, u32) {
+    results
+        .checks
+        .iter()
+        .filter_map(CheckResult::level)
+        .map(|level| match level {
+            SupportLevel::Supported => 2_u32,
+            SupportLevel::Partial => 1_u32,
+            SupportLevel::Unsupported | SupportLevel::Unknown => 0_u32,
+        })
+        .fold((0_u32, 0_u32), |(points, max_points), p| {
+            (points.saturating_add(p), max_points.saturating_add(2))
+        })
+}
+
+fn print_group_header(out: &mut ColorBuffer, title: &dyn core::fmt::Display) -> Result<()> {
+    out.color_buffer.clear();
+    writeln!(out.color_buffer, "{title}:")
+        .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+    out.print()
+}
+
+fn print_file_results(
+    out: &mut ColorBuffer,
+    path: &Path,
+    results: &FileResults,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let color_buffer = &mut out.color_buffer;
+    color_buffer.clear();
+
+    write!(color_buffer, "{}", path.display())
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+    if options.show_architecture {
+        if let Some(architecture) = &results.architecture {
+            write!(color_buffer, " [{} {}]", architecture.name, results.format)
+        } else {
+            write!(color_buffer, " [{}]", results.format)
+        }
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+    }
+
+    write!(color_buffer, ": ")
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+    let mut iter = results.checks.iter();
+    if let Some(first) = iter.next() {
+        first.display_in_color_term(color_buffer)?;
+        for opt in iter {
+            write!(color_buffer, " ")
+                .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+            opt.display_in_color_term(color_buffer)?;
+        }
+    }
+
+    writeln!(color_buffer)
+        .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+
+    if let Some(libc) = results.libc.as_ref().filter(|_| options.verbose) {
+        writeln!(color_buffer, "    libc: {libc}")
+            .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+    }
+
+    if let Some(duration) = results.duration {
+        write!(color_buffer, "    time: {duration:.3?}")
+            .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+        for r in &results.checks {
+            if let Some(duration) = r.duration() {
+                write!(color_buffer, ", {}: {duration:.3?}", r.name())
+                    .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+            }
+        }
+
+        writeln!(color_buffer)
+            .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+    }
+
+    if options.hints {
+        for r in results.checks.iter().filter(|r| is_failing(r)) {
+            if let Some(hint) = remediation_hint(r.name()) {
+                writeln!(color_buffer, "    {}: {hint}", r.name())
+                    .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+            }
+        }
+    }
+
+    out.print()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::{Path, PathBuf};
+
+    use super::{escaped_path, redact_path, unescaped_path};
+
+    #[test]
+    fn valid_utf8_paths_are_not_escaped() {
+        assert_eq!(escaped_path(Path::new(r"/usr/bin/ls\tool")), None);
+    }
+
+    #[test]
+    fn escaped_paths_are_unescaped() {
+        assert_eq!(
+            unescaped_path(r"/opt/a\\b\x2f"),
+            Some(PathBuf::from(r"/opt/a\b/"))
+        );
+        assert_eq!(
+            unescaped_path("/opt/caf\u{e9}"),
+            Some(PathBuf::from("/opt/caf\u{e9}"))
+        );
+    }
+
+    #[test]
+    fn malformed_escapes_are_rejected() {
+        assert_eq!(unescaped_path(r"/opt/\n"), None);
+        assert_eq!(unescaped_path(r"/opt/\x4"), None);
+        assert_eq!(unescaped_path(r"/opt/\xzz"), None);
+        assert_eq!(unescaped_path("/opt/\\"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/opt/\\lib\xff\xfe-caf\xc3\xa9.so"));
+        let escaped = escaped_path(path).unwrap();
+        assert_eq!(escaped, "/opt/\\\\lib\\xff\\xfe-caf\u{e9}.so");
+        assert_eq!(unescaped_path(&escaped).as_deref(), Some(path));
+    }
+
+    #[test]
+    fn user_directories_are_redacted() {
+        assert_eq!(
+            redact_path(Path::new("/home/alice/bin/tool")),
+            Path::new("~/bin/tool")
+        );
+        assert_eq!(
+            redact_path(Path::new("/Users/bob/Library/tool.dylib")),
+            Path::new("~/Library/tool.dylib")
+        );
+    }
+
+    #[test]
+    fn home_directory_is_redacted() {
+        if let Some(home_dir) = env::var_os("HOME") {
+            let path = Path::new(&home_dir).join(".local/bin/tool");
+            assert_eq!(redact_path(&path), Path::new("~/.local/bin/tool"));
+        }
+    }
+
+    #[test]
+    fn other_paths_are_not_redacted() {
+        assert_eq!(
+            redact_path(Path::new("/usr/bin/ls")),
+            Path::new("/usr/bin/ls")
+        );
+        assert_eq!(
+            redact_path(Path::new("home/alice/tool")),
+            Path::new("home/alice/tool")
+        );
+        assert_eq!(redact_path(Path::new("/home")), Path::new("/home"));
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Badges in the style of `shields.io`, written by `--badge`, showing the hardening score of
+//! analyzed files, e.g., in the `README` of projects publishing binaries.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{score_points, FileResults};
+use crate::errors::{Error, Result};
+
+const LABEL: &str = "hardening";
+
+/// Color of the badge when the score is unknown.
+const UNKNOWN_COLOR: &str = "#9f9f9f";
+
+/// Colors of the badge, by minimum score.
+const SCORE_COLORS: &[(u32, &str)] = &[
+    (100, "#4c1"),
+    (90, "#97ca00"),
+    (75, "#dfb317"),
+    (50, "#fe7d37"),
+    (0, "#e05d44"),
+];
+
+/// Returns the hardening score of all files, as a percentage.
+///
+/// If a policy is given, then the score is the percentage of its requirements that are met by
+/// all `ELF` files. Otherwise, it is the percentage of security features supported by all files,
+/// where partially supported features count as half supported.
+fn score(reports: &[(PathBuf, FileResults)], options: &crate::cmdline::Options) -> Option<u32> {
+    let (points, max_points) = if let Some(policy) = options.policy {
+        crate::policy::requirements_met(policy, reports)
+    } else {
+        reports
+            .iter()
+            .map(|(_path, results)| score_points(results))
+            .fold((0_u32, 0_u32), |(points, max_points), (p, m)| {
+                (points.saturating_add(p), max_points.saturating_add(m))
+            })
+    };
+
+    points.saturating_mul(100).checked_div(max_points)
+}
+
+/// Returns the approximate width, in pixels, of a text field of a badge.
+fn field_width(text: &str) -> u32 {
+    let chars = u32::try_from(text.chars().count()).unwrap_or(u32::MAX);
+    chars.saturating_mul(7).saturating_add(10)
+}
+
+/// Returns the SVG document of a badge showing `value` over a background of `color`.
+fn render(value: &str, color: &str) -> String {
+    let label_width = field_width(LABEL);
+    let value_width = field_width(value);
+    let width = label_width + value_width;
+    let label_x = f64::from(label_width) / 2.0;
+    let value_x = f64::from(label_width) + f64::from(value_width) / 2.0;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{LABEL}: {value}">
+<title>{LABEL}: {value}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{LABEL}</text><text x="{label_x}" y="14">{LABEL}</text>
+<text x="{value_x}" y="15" fill="#010101" fill-opacity=".3">{value}</text><text x="{value_x}" y="14">{value}</text>
+</g>
+</svg>
+"##
+    )
+}
+
+/// Writes to `path` a badge showing the hardening score of all files, e.g., `hardening: 92%`.
+///
+/// The badge shows `unknown` if no security features were checked.
+pub(crate) fn write_badge(
+    path: &Path,
+    reports: &[(PathBuf, FileResults)],
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let svg = if let Some(score) = score(reports, options) {
+        let color = SCORE_COLORS
+            .iter()
+            .find_map(|&(min_score, color)| (score >= min_score).then_some(color))
+            .unwrap_or(UNKNOWN_COLOR);
+        render(&format!("{score}%"), color)
+    } else {
+        render("unknown", UNKNOWN_COLOR)
+    };
+
+    fs::write(path, svg).map_err(|r| Error::from_io1(r, "write file", path))
+}
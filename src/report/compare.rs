@@ -0,0 +1,291 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Comparison of analysis results with those of a previous JSON report.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use termcolor::WriteColor;
+
+use super::github::{write_annotation, Severity};
+use super::json::{
+    invocation, previous_path, status_keyword, Invocation, PreviousReportDocument, Tool,
+    SCHEMA_VERSION,
+};
+use super::{escaped_path, FileResults};
+use crate::cmdline::OutputFormat;
+use crate::errors::{Error, Result};
+use crate::options::ids::check_id;
+use crate::options::status::{SupportLevel, COLOR_BAD, COLOR_GOOD};
+use crate::ui::ColorBuffer;
+
+#[derive(Deserialize)]
+struct BaselineFile {
+    path: PathBuf,
+    #[serde(default)]
+    path_escaped: Option<String>,
+    checks: Vec<BaselineCheck>,
+}
+
+#[derive(Deserialize)]
+struct BaselineCheck {
+    /// Stable identifier of the check, absent from reports of older versions.
+    #[serde(default)]
+    id: Option<String>,
+    name: String,
+    status: String,
+    text: String,
+}
+
+/// Report given to `--compare-with`, against which results are compared.
+pub(crate) struct BaselineReport {
+    files: HashMap<PathBuf, Vec<BaselineCheck>>,
+}
+
+impl BaselineReport {
+    pub(crate) fn read(path: &Path) -> Result<Self> {
+        let document = PreviousReportDocument::read(path)?;
+
+        let files = document
+            .files
+            .into_iter()
+            .map(|file| {
+                serde_json::from_value::<BaselineFile>(file)
+                    .map(|file| {
+                        let path = previous_path(file.path, file.path_escaped.as_deref());
+                        (path, file.checks)
+                    })
+                    .map_err(|source| Error::ParseReport {
+                        path: path.into(),
+                        source,
+                    })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { files })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ChangeKind {
+    Regression,
+    Improvement,
+}
+
+#[derive(Serialize)]
+struct CheckState {
+    status: &'static str,
+    text: String,
+}
+
+/// Change of the status of a security feature of a file.
+#[derive(Serialize)]
+struct Change {
+    path: String,
+    /// Path with the bytes that are not valid UTF-8 escaped, present only if `path` is not valid
+    /// UTF-8, in which case `path` is lossy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_escaped: Option<String>,
+    check: &'static str,
+    kind: ChangeKind,
+    before: CheckState,
+    after: CheckState,
+}
+
+#[derive(Serialize)]
+struct ComparisonReport {
+    schema_version: u32,
+    tool: Tool,
+    invocation: Invocation,
+    changes: Vec<Change>,
+    regressions: usize,
+    improvements: usize,
+}
+
+/// Returns the level of support identified by a status keyword of a JSON report.
+fn level_from_keyword(keyword: &str) -> Option<SupportLevel> {
+    match keyword {
+        "unsupported" => Some(SupportLevel::Unsupported),
+        "partial" => Some(SupportLevel::Partial),
+        "unknown" => Some(SupportLevel::Unknown),
+        "supported" => Some(SupportLevel::Supported),
+        _ => None,
+    }
+}
+
+/// Returns the changes of the levels of support of security features between the baseline report
+/// and the current results.
+///
+/// Files and security features that are not reported by both are not compared.
+fn changes(reports: &[(PathBuf, FileResults)], baseline: &BaselineReport) -> Result<Vec<Change>> {
+    let mut changes = Vec::default();
+
+    for (path, results) in reports {
+        let Some(baseline_checks) = baseline.files.get(path) else {
+            continue;
+        };
+
+        for check in &results.checks {
+            let Some(after) = check.level() else {
+                continue;
+            };
+
+            // Keywords of security features may have been renamed since the baseline report.
+            let id = check_id(results.format, check.name());
+            let Some(before_check) = baseline_checks.iter().find(|c| match (&c.id, id) {
+                (Some(before_id), Some(id)) => before_id == id,
+                _ => c.name == check.name(),
+            }) else {
+                continue;
+            };
+
+            let Some(before) = level_from_keyword(&before_check.status) else {
+                continue;
+            };
+
+            let kind = match before.cmp(&after) {
+                core::cmp::Ordering::Less => ChangeKind::Improvement,
+                core::cmp::Ordering::Greater => ChangeKind::Regression,
+                core::cmp::Ordering::Equal => continue,
+            };
+
+            changes.push(Change {
+                path: path.to_string_lossy().into_owned(),
+                path_escaped: escaped_path(path),
+                check: check.name(),
+                kind,
+                before: CheckState {
+                    status: status_keyword(Some(before)),
+                    text: before_check.text.clone(),
+                },
+                after: CheckState {
+                    status: status_keyword(Some(after)),
+                    text: check.to_string(),
+                },
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Prints the changes of the levels of support of security features, followed by the numbers of
+/// regressions and improvements.
+pub(super) fn print_changes(
+    reports: &[(PathBuf, FileResults)],
+    baseline: &BaselineReport,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let changes = changes(reports, baseline)?;
+    let regressions = changes
+        .iter()
+        .filter(|c| c.kind == ChangeKind::Regression)
+        .count();
+    let improvements = changes.len() - regressions;
+
+    match options.format {
+        OutputFormat::Text => print_text_changes(&changes, regressions, improvements, options),
+
+        OutputFormat::Prometheus => super::prometheus::print_changes(regressions, improvements),
+
+        OutputFormat::GitHubAnnotations => print_annotated_changes(&changes),
+
+        OutputFormat::Json => {
+            let report = ComparisonReport {
+                schema_version: SCHEMA_VERSION,
+                tool: Tool::this(),
+                invocation: invocation(options),
+                changes,
+                regressions,
+                improvements,
+            };
+
+            let mut out = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut out, &report)
+                .map_err(|r| Error::from_io1(r.into(), "write", "standard output stream"))?;
+            writeln!(out).map_err(|r| Error::from_io1(r, "write line", "standard output stream"))
+        }
+    }
+}
+
+/// Prints each regression as an error annotation, and each improvement as a notice annotation.
+fn print_annotated_changes(changes: &[Change]) -> Result<()> {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+
+    for change in changes {
+        let severity = match change.kind {
+            ChangeKind::Regression => Severity::Error,
+            ChangeKind::Improvement => Severity::Notice,
+        };
+
+        let message = format!("{} \u{2192} {}", change.before.text, change.after.text);
+        write_annotation(
+            &mut out,
+            severity,
+            &change.path,
+            Some(change.check),
+            &message,
+        )
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+    }
+
+    out.flush()
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))
+}
+
+/// Prints each change as `path: -!ASLR → +ASLR`, colored as a regression or an improvement.
+fn print_text_changes(
+    changes: &[Change],
+    regressions: usize,
+    improvements: usize,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let mut out = ColorBuffer::for_stdout(options.color);
+
+    for change in changes {
+        let color_buffer = &mut out.color_buffer;
+        color_buffer.clear();
+
+        let color = match change.kind {
+            ChangeKind::Regression => COLOR_BAD,
+            ChangeKind::Improvement => COLOR_GOOD,
+        };
+
+        write!(color_buffer, "{}: ", change.path)
+            .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+        color_buffer
+            .set_color(termcolor::ColorSpec::new().set_fg(Some(color)))
+            .map_err(|r| Error::from_io1(r, "set color", "standard output stream"))?;
+
+        write!(
+            color_buffer,
+            "-{} \u{2192} {}",
+            change.before.text, change.after.text
+        )
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+
+        color_buffer
+            .reset()
+            .map_err(|r| Error::from_io1(r, "reset", "standard output stream"))?;
+
+        writeln!(color_buffer)
+            .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+
+        out.print()?;
+    }
+
+    out.color_buffer.clear();
+    writeln!(
+        out.color_buffer,
+        "{regressions} regression(s), {improvements} improvement(s)."
+    )
+    .map_err(|r| Error::from_io1(r, "write line", "standard output stream"))?;
+    out.print()
+}
@@ -0,0 +1,136 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Reports as workflow commands of GitHub Actions, which annotate the analyzed files in the
+//! summary of the job.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use super::FileResults;
+use crate::errors::{Error, Result};
+use crate::options::hints::remediation_hint;
+use crate::options::status::SupportLevel;
+
+/// Severity of an annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Severity {
+    Error,
+    Warning,
+    Notice,
+}
+
+impl Severity {
+    fn command(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Notice => "notice",
+        }
+    }
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            '\r' => escaped.push_str("%0D"),
+            '\n' => escaped.push_str("%0A"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a property value of a workflow command, e.g., the annotated file.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Writes an annotation as `::error file=path,title=ASLR::message`.
+pub(super) fn write_annotation(
+    out: &mut impl Write,
+    severity: Severity,
+    file: &str,
+    title: Option<&str>,
+    message: &str,
+) -> io::Result<()> {
+    write!(
+        out,
+        "::{} file={}",
+        severity.command(),
+        escape_property(file)
+    )?;
+    if let Some(title) = title {
+        write!(out, ",title={}", escape_property(title))?;
+    }
+    writeln!(out, "::{}", escape_data(message))
+}
+
+/// Prints an annotation for each failing check of each analyzed file, and for each file that
+/// could not be analyzed.
+///
+/// Unsupported security features are reported as errors, partially supported ones as warnings,
+/// and those whose status is unknown as notices. Remediation hints are part of the messages if
+/// they were requested.
+pub(super) fn print_results(
+    reports: &[(PathBuf, FileResults)],
+    errors: &[(PathBuf, Error)],
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+
+    for (path, results) in reports {
+        let file = path.to_string_lossy();
+
+        for check in &results.checks {
+            let severity = match check.level() {
+                None | Some(SupportLevel::Supported) => continue,
+                Some(SupportLevel::Unsupported) => Severity::Error,
+                Some(SupportLevel::Partial) => Severity::Warning,
+                Some(SupportLevel::Unknown) => Severity::Notice,
+            };
+
+            let mut message = check.to_string();
+            // Checked functions are meaningless without what they were compared against.
+            if let Some(libc) = results
+                .libc
+                .as_ref()
+                .filter(|_| check.name() == "FORTIFY-SOURCE")
+            {
+                message.push_str("\nC runtime library: ");
+                message.push_str(libc);
+            }
+            if let Some(hint) = options
+                .hints
+                .then(|| remediation_hint(check.name()))
+                .flatten()
+            {
+                message.push('\n');
+                message.push_str(hint);
+            }
+
+            write_annotation(&mut out, severity, &file, Some(check.name()), &message)
+                .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+        }
+    }
+
+    for (path, error) in errors {
+        write_annotation(
+            &mut out,
+            Severity::Error,
+            &path.to_string_lossy(),
+            None,
+            &crate::format_error(error),
+        )
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))?;
+    }
+
+    out.flush()
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))
+}
@@ -0,0 +1,538 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    escaped_path, is_failing, redact_path, unescaped_path, Architecture, BinaryFormat, FileResults,
+};
+use crate::errors::{Error, Result};
+use crate::options::hints::remediation_hint;
+use crate::options::ids::{check_id, check_ids};
+use crate::options::status::SupportLevel;
+
+/// Version of the structure of JSON reports.
+///
+/// This is incremented whenever the structure changes in a way that is not backward compatible.
+pub(super) const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub(super) struct Report {
+    schema_version: u32,
+    tool: Tool,
+    invocation: Invocation,
+    files: Vec<FileEntry>,
+    /// Files that could not be analyzed.
+    errors: Vec<ErrorReport>,
+}
+
+#[derive(Serialize)]
+pub(super) struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+impl Tool {
+    pub(super) fn this() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct Invocation {
+    /// Time of the report, in RFC 3339 format.
+    timestamp: String,
+    host: Option<String>,
+    /// Command line arguments, excluding the program name.
+    arguments: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum FileEntry {
+    Analyzed(FileReport),
+    /// Results kept as they were in the report given to `--retry-from`.
+    Kept(serde_json::Value),
+}
+
+#[derive(Serialize)]
+struct FileReport {
+    path: String,
+    /// Path with the bytes that are not valid UTF-8 escaped, present only if `path` is not valid
+    /// UTF-8, in which case `path` is lossy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_escaped: Option<String>,
+    format: BinaryFormat,
+    architecture: Option<Architecture>,
+    checks: Vec<CheckReport>,
+    /// C runtime library that the checked functions were compared against, for `ELF` files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    libc: Option<String>,
+    /// Time spent analyzing the file, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct CheckReport {
+    /// Stable identifier of the check, e.g., `ELF001`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'static str>,
+    name: &'static str,
+    status: &'static str,
+    /// Reason why the security feature does not apply to the file, for `not-applicable` statuses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// Text displayed for the check in the text report.
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<&'static str>,
+    /// Time spent checking the security feature, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct ErrorReport {
+    path: String,
+    /// Path with the bytes that are not valid UTF-8 escaped, present only if `path` is not valid
+    /// UTF-8, in which case `path` is lossy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path_escaped: Option<String>,
+    /// Keyword identifying the kind of error, e.g., `parse-file` for files that are not valid
+    /// binaries.
+    kind: &'static str,
+    message: String,
+    /// Messages of the errors that caused this error, from the closest to the root cause.
+    sources: Vec<String>,
+}
+
+impl ErrorReport {
+    fn new(path: &Path, error: &Error) -> Self {
+        let sources = core::iter::successors(std::error::Error::source(error), |r| r.source())
+            .map(ToString::to_string)
+            .collect();
+
+        Self {
+            path: path.to_string_lossy().into_owned(),
+            path_escaped: escaped_path(path),
+            kind: error.kind(),
+            message: error.to_string(),
+            sources,
+        }
+    }
+}
+
+/// Fields of a previous report that are needed to retry or compare the analysis of its files.
+#[derive(Deserialize)]
+pub(super) struct PreviousReportDocument {
+    schema_version: u64,
+    pub(super) files: Vec<serde_json::Value>,
+    #[serde(default)]
+    errors: Vec<PreviousError>,
+}
+
+impl PreviousReportDocument {
+    pub(super) fn read(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|r| Error::from_io1(r, "read file", path))?;
+
+        let document: Self =
+            serde_json::from_slice(&bytes).map_err(|source| Error::ParseReport {
+                path: path.into(),
+                source,
+            })?;
+
+        if document.schema_version == u64::from(SCHEMA_VERSION) {
+            Ok(document)
+        } else {
+            Err(Error::UnsupportedReportSchema {
+                version: document.schema_version,
+                path: path.into(),
+            })
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PreviousError {
+    path: PathBuf,
+    #[serde(default)]
+    path_escaped: Option<String>,
+}
+
+/// Returns the path of a file or an error of a previous report, which is the escaped path if the
+/// path is not valid UTF-8.
+pub(super) fn previous_path(path: PathBuf, path_escaped: Option<&str>) -> PathBuf {
+    path_escaped.and_then(unescaped_path).unwrap_or(path)
+}
+
+/// Report given to `--retry-from`.
+pub(crate) struct PreviousReport {
+    /// Files that could not be analyzed, or that do not support all security features.
+    pub(crate) retried_files: Vec<PathBuf>,
+    /// Results of the other files, which are reported again as they were.
+    pub(super) kept_files: Vec<serde_json::Value>,
+}
+
+impl PreviousReport {
+    pub(crate) fn read(path: &Path) -> Result<Self> {
+        let document = PreviousReportDocument::read(path)?;
+
+        let mut retried_files: Vec<PathBuf> = document
+            .errors
+            .into_iter()
+            .map(|error| previous_path(error.path, error.path_escaped.as_deref()))
+            .collect();
+        let mut kept_files = Vec::with_capacity(document.files.len());
+
+        for file in document.files {
+            match file.get("path").and_then(serde_json::Value::as_str) {
+                Some(path) if previous_file_is_failing(&file) => {
+                    let path_escaped = file.get("path_escaped").and_then(serde_json::Value::as_str);
+                    retried_files.push(previous_path(path.into(), path_escaped));
+                }
+                _ => kept_files.push(file),
+            }
+        }
+
+        Ok(Self {
+            retried_files,
+            kept_files,
+        })
+    }
+}
+
+/// Returns `true` if a file of a previous report does not support all security features.
+fn previous_file_is_failing(file: &serde_json::Value) -> bool {
+    file.get("checks")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|checks| {
+            checks.iter().any(|check| {
+                matches!(
+                    check.get("status").and_then(serde_json::Value::as_str),
+                    Some("unsupported" | "partial" | "unknown")
+                )
+            })
+        })
+}
+
+/// Prints the results of all analyzed files as a JSON document.
+///
+/// Results kept from a previous report are printed first, followed by the results of files
+/// analyzed now.
+pub(super) fn print_results(
+    reports: &[(PathBuf, FileResults)],
+    errors: &[(PathBuf, Error)],
+    previous_report: Option<PreviousReport>,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let kept_files = previous_report
+        .map(|previous_report| previous_report.kept_files)
+        .unwrap_or_default();
+
+    let report = report(reports, errors, kept_files, options)?;
+
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, &report)
+        .map_err(|r| Error::from_io1(r.into(), "write", "standard output stream"))?;
+    writeln!(out).map_err(|r| Error::from_io1(r, "write line", "standard output stream"))
+}
+
+/// Returns the JSON document reporting the results of the given files, starting with the results
+/// `kept_files` kept from a previous report.
+pub(super) fn report(
+    reports: &[(PathBuf, FileResults)],
+    errors: &[(PathBuf, Error)],
+    kept_files: Vec<serde_json::Value>,
+    options: &crate::cmdline::Options,
+) -> Result<Report> {
+    let mut files = Vec::with_capacity(kept_files.len() + reports.len());
+    files.extend(kept_files.into_iter().map(FileEntry::Kept));
+    for (path, results) in reports {
+        files.push(FileEntry::Analyzed(file_report(path, results, options)?));
+    }
+
+    let errors = errors
+        .iter()
+        .map(|(path, error)| {
+            if options.redact_paths {
+                ErrorReport::new(&redact_path(path), error)
+            } else {
+                ErrorReport::new(path, error)
+            }
+        })
+        .collect();
+
+    Ok(Report {
+        schema_version: SCHEMA_VERSION,
+        tool: Tool::this(),
+        invocation: invocation(options),
+        files,
+        errors,
+    })
+}
+
+pub(super) fn invocation(options: &crate::cmdline::Options) -> Invocation {
+    let arguments = env::args_os()
+        .skip(1)
+        .map(|arg| {
+            if options.redact_paths {
+                redact_path(Path::new(&arg)).to_string_lossy().into_owned()
+            } else {
+                arg.to_string_lossy().into_owned()
+            }
+        })
+        .collect();
+
+    Invocation {
+        timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        host: hostname::get()
+            .ok()
+            .map(|host| host.to_string_lossy().into_owned()),
+        arguments,
+    }
+}
+
+fn file_report(
+    path: &Path,
+    results: &FileResults,
+    options: &crate::cmdline::Options,
+) -> Result<FileReport> {
+    let checks = results
+        .checks
+        .iter()
+        .map(|check| {
+            let hint = if options.hints && is_failing(check) {
+                remediation_hint(check.name())
+            } else {
+                None
+            };
+
+            Ok(CheckReport {
+                id: check_id(results.format, check.name()),
+                name: check.name(),
+                status: if check.not_applicable_reason().is_some() {
+                    NOT_APPLICABLE
+                } else {
+                    status_keyword(check.level())
+                },
+                reason: check.not_applicable_reason().map(str::to_owned),
+                text: check.to_string(),
+                hint,
+                duration_ms: check.duration().map(milliseconds),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(FileReport {
+        path: path.to_string_lossy().into_owned(),
+        path_escaped: escaped_path(path),
+        format: results.format,
+        architecture: results.architecture.clone(),
+        checks,
+        libc: results.libc.clone(),
+        duration_ms: results.duration.map(milliseconds),
+    })
+}
+
+/// Prints the JSON schema describing the structure of JSON reports, for validating them or for
+/// generating code reading them.
+///
+/// The statuses, binary formats and check identifiers are enumerated from the definitions used to
+/// write reports, so that the schema does not drift from the reports.
+pub(super) fn print_schema() -> Result<()> {
+    let formats: Vec<_> = BinaryFormat::ALL.iter().map(ToString::to_string).collect();
+
+    let schema = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("{} JSON report", env!("CARGO_PKG_NAME")),
+        "type": "object",
+        "required": ["schema_version", "tool", "invocation", "files", "errors"],
+        "properties": {
+            "schema_version": { "const": SCHEMA_VERSION },
+            "tool": {
+                "type": "object",
+                "required": ["name", "version"],
+                "properties": {
+                    "name": { "type": "string" },
+                    "version": { "type": "string" },
+                },
+            },
+            "invocation": {
+                "type": "object",
+                "required": ["timestamp", "host", "arguments"],
+                "properties": {
+                    "timestamp": {
+                        "description": "Time of the report, in RFC 3339 format.",
+                        "type": "string",
+                        "format": "date-time",
+                    },
+                    "host": { "type": ["string", "null"] },
+                    "arguments": {
+                        "description": "Command line arguments, excluding the program name.",
+                        "type": "array",
+                        "items": { "type": "string" },
+                    },
+                },
+            },
+            "files": {
+                "type": "array",
+                "items": { "$ref": "#/$defs/file" },
+            },
+            "errors": {
+                "description": "Files that could not be analyzed.",
+                "type": "array",
+                "items": { "$ref": "#/$defs/error" },
+            },
+        },
+        "$defs": {
+            "file": {
+                "type": "object",
+                "required": ["path", "format", "architecture", "checks"],
+                "properties": {
+                    "path": { "type": "string" },
+                    "path_escaped": { "$ref": "#/$defs/path_escaped" },
+                    "format": { "enum": formats },
+                    "architecture": {
+                        "oneOf": [{ "$ref": "#/$defs/architecture" }, { "type": "null" }],
+                    },
+                    "checks": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/check" },
+                    },
+                    "libc": {
+                        "description": "C runtime library that the checked functions of ELF files were compared against: its path, `spec: ` followed by the specification given by `--libc-spec`, or `none`.",
+                        "type": "string",
+                    },
+                    "duration_ms": {
+                        "description": "Time spent analyzing the file, in milliseconds.",
+                        "type": "number",
+                    },
+                },
+            },
+            "architecture": {
+                "type": "object",
+                "required": ["name", "bits", "endianness"],
+                "properties": {
+                    "name": {
+                        "description": "Name of the instruction set, similar to the `target_arch` names of Rust.",
+                        "type": "string",
+                    },
+                    "bits": { "type": "integer", "minimum": 0 },
+                    "endianness": { "enum": ["little", "big"] },
+                },
+            },
+            "path_escaped": {
+                "description": "Path with the bytes that are not valid UTF-8 escaped as `\\xHH`, and backslashes escaped as `\\\\`, present only if the path is not valid UTF-8.",
+                "type": "string",
+            },
+            "check": check_schema(),
+            "error": error_schema(),
+        },
+    });
+
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, &schema)
+        .map_err(|r| Error::from_io1(r.into(), "write", "standard output stream"))?;
+    writeln!(out).map_err(|r| Error::from_io1(r, "write line", "standard output stream"))
+}
+
+/// Returns the JSON Schema of the errors reported for files that could not be analyzed.
+fn error_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "required": ["path", "kind", "message", "sources"],
+        "properties": {
+            "path": { "type": "string" },
+            "path_escaped": { "$ref": "#/$defs/path_escaped" },
+            "kind": {
+                "description": "Keyword identifying the kind of error, e.g., `parse-file`.",
+                "type": "string",
+            },
+            "message": { "type": "string" },
+            "sources": {
+                "description": "Messages of the errors that caused this error, from the closest to the root cause.",
+                "type": "array",
+                "items": { "type": "string" },
+            },
+        },
+    })
+}
+
+/// Returns the JSON Schema of the security features reported for each file.
+fn check_schema() -> serde_json::Value {
+    let statuses: Vec<_> = core::iter::once(None)
+        .chain(SupportLevel::ALL.map(Some))
+        .map(status_keyword)
+        .chain(core::iter::once(NOT_APPLICABLE))
+        .collect();
+
+    let check_ids: Vec<_> = check_ids()
+        .map(|(format, name, id)| {
+            serde_json::json!({
+                "const": id,
+                "description": format!("{name} of {format} files"),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "type": "object",
+        "required": ["name", "status", "text"],
+        "properties": {
+            "id": {
+                "description": "Stable identifier of the check.",
+                "oneOf": check_ids,
+            },
+            "name": {
+                "description": "Keyword of the security feature, e.g., `ASLR`.",
+                "type": "string",
+            },
+            "status": { "enum": statuses },
+            "reason": {
+                "description": "Reason why the security feature does not apply to the file, for `not-applicable` statuses, e.g., `--no-libc`.",
+                "type": "string",
+            },
+            "text": {
+                "description": "Text displayed for the check in the text report.",
+                "type": "string",
+            },
+            "hint": {
+                "description": "Remediation hint, reported by `--hints` for failing checks.",
+                "type": "string",
+            },
+            "duration_ms": {
+                "description": "Time spent checking the security feature, in milliseconds.",
+                "type": "number",
+            },
+        },
+    })
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Status of security features that do not apply to a file, and were not checked.
+const NOT_APPLICABLE: &str = "not-applicable";
+
+pub(super) fn status_keyword(level: Option<SupportLevel>) -> &'static str {
+    match level {
+        None => "informational",
+        Some(SupportLevel::Unsupported) => "unsupported",
+        Some(SupportLevel::Partial) => "partial",
+        Some(SupportLevel::Unknown) => "unknown",
+        Some(SupportLevel::Supported) => "supported",
+    }
+}
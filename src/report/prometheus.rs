@@ -0,0 +1,150 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Reports in the Prometheus text exposition format, as read by the textfile collector of
+//! `node_exporter`.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use super::{escaped_path, FileResults};
+use crate::errors::{Error, Result};
+use crate::options::status::SupportLevel;
+
+/// Returns the value of the `bsc_check_status` metric for a level of support.
+fn status_value(level: SupportLevel) -> &'static str {
+    match level {
+        SupportLevel::Supported => "1",
+        SupportLevel::Partial => "0.5",
+        SupportLevel::Unsupported => "0",
+        SupportLevel::Unknown => "NaN",
+    }
+}
+
+/// Escapes a label value, as required by the text exposition format.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_metric_header(out: &mut impl Write, name: &str, help: &str) -> io::Result<()> {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} gauge")
+}
+
+/// Prints the status of each security feature of each analyzed file as a metric, followed by
+/// the numbers of analyzed files and of files that could not be analyzed.
+///
+/// Informational entries have no status, so they are not reported.
+pub(super) fn print_results(
+    reports: &[(PathBuf, FileResults)],
+    errors: &[(PathBuf, Error)],
+) -> Result<()> {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    write_results(&mut out, reports, errors.len())
+        .and_then(|()| out.flush())
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))
+}
+
+fn write_results(
+    out: &mut impl Write,
+    reports: &[(PathBuf, FileResults)],
+    error_count: usize,
+) -> io::Result<()> {
+    write_metric_header(
+        out,
+        "bsc_check_status",
+        "Status of a security feature of a file: 1 if supported, 0.5 if partially supported, \
+         0 if unsupported, NaN if unknown.",
+    )?;
+
+    for (path, results) in reports {
+        // Lossy paths could be shared by several files, which would duplicate series.
+        let file = escaped_path(path).unwrap_or_else(|| path.to_string_lossy().into_owned());
+        let file = escape_label_value(&file);
+
+        for check in &results.checks {
+            if let Some(level) = check.level() {
+                writeln!(
+                    out,
+                    "bsc_check_status{{file=\"{file}\",format=\"{}\",check=\"{}\"}} {}",
+                    results.format,
+                    check.name(),
+                    status_value(level)
+                )?;
+            }
+        }
+    }
+
+    write_metric_header(
+        out,
+        "bsc_file_libc",
+        "C runtime library that the checked functions of a file were compared against.",
+    )?;
+
+    for (path, results) in reports {
+        if let Some(libc) = &results.libc {
+            let file = escaped_path(path).unwrap_or_else(|| path.to_string_lossy().into_owned());
+            writeln!(
+                out,
+                "bsc_file_libc{{file=\"{}\",libc=\"{}\"}} 1",
+                escape_label_value(&file),
+                escape_label_value(libc)
+            )?;
+        }
+    }
+
+    write_metric_header(out, "bsc_analyzed_files", "Number of analyzed files.")?;
+    writeln!(out, "bsc_analyzed_files {}", reports.len())?;
+
+    write_metric_header(
+        out,
+        "bsc_failed_files",
+        "Number of files that could not be analyzed.",
+    )?;
+    writeln!(out, "bsc_failed_files {error_count}")?;
+
+    write_metric_header(
+        out,
+        "bsc_report_timestamp_seconds",
+        "Time of the report, in seconds since the Unix epoch.",
+    )?;
+    writeln!(
+        out,
+        "bsc_report_timestamp_seconds {}",
+        chrono::Utc::now().timestamp()
+    )
+}
+
+/// Prints the numbers of regressions and improvements compared to a baseline report.
+pub(super) fn print_changes(regressions: usize, improvements: usize) -> Result<()> {
+    let write = |out: &mut io::StdoutLock| -> io::Result<()> {
+        write_metric_header(
+            out,
+            "bsc_regressions",
+            "Number of security features whose status regressed since the baseline report.",
+        )?;
+        writeln!(out, "bsc_regressions {regressions}")?;
+
+        write_metric_header(
+            out,
+            "bsc_improvements",
+            "Number of security features whose status improved since the baseline report.",
+        )?;
+        writeln!(out, "bsc_improvements {improvements}")
+    };
+
+    write(&mut io::stdout().lock())
+        .map_err(|r| Error::from_io1(r, "write", "standard output stream"))
+}
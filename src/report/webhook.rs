@@ -0,0 +1,135 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! JSON reports posted to HTTP endpoints, e.g., of asset inventories or of security information
+//! and event management systems.
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, warn};
+
+use super::json;
+use super::{FileResults, PreviousReport};
+use crate::errors::{Error, Result};
+
+/// Time after which posting a report fails, if the endpoint did not respond yet.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Time waited before the first retry, which doubles before each following retry.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Posts the JSON report of all analyzed files to `--webhook`, or a report for each file if
+/// `--webhook-per-file` is specified.
+///
+/// Posting per file stops at the first report that could not be posted.
+pub(crate) fn post_results(
+    url: &str,
+    reports: &[(PathBuf, FileResults)],
+    errors: &[(PathBuf, Error)],
+    previous_report: Option<&PreviousReport>,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let kept_files = previous_report.map_or(&[][..], |previous_report| {
+        previous_report.kept_files.as_slice()
+    });
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build();
+
+    if !options.webhook_per_file {
+        let report = json::report(reports, errors, kept_files.to_vec(), options)?;
+        return post(&agent, url, &report, options);
+    }
+
+    for file in kept_files {
+        let report = json::report(&[], &[], vec![file.clone()], options)?;
+        post(&agent, url, &report, options)?;
+    }
+
+    for file_report in reports {
+        let report = json::report(
+            core::slice::from_ref(file_report),
+            &[],
+            Vec::default(),
+            options,
+        )?;
+        post(&agent, url, &report, options)?;
+    }
+
+    for error in errors {
+        let report = json::report(&[], core::slice::from_ref(error), Vec::default(), options)?;
+        post(&agent, url, &report, options)?;
+    }
+    Ok(())
+}
+
+/// Posts a JSON report to `url`, along with the headers given to `--webhook-header`.
+///
+/// Failures that might be transient, i.e., connection failures, server errors and throttling,
+/// are retried up to `--webhook-retries` times, with an exponential backoff.
+fn post(
+    agent: &ureq::Agent,
+    url: &str,
+    report: &json::Report,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let body = serde_json::to_vec(report).map_err(|r| Error::PostReport {
+        message: r.to_string(),
+    })?;
+
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut retries = 0_u32;
+    loop {
+        let mut request = agent.post(url).set("Content-Type", "application/json");
+        for header in &options.webhook_header {
+            request = request.set(&header.name, &header.value);
+        }
+
+        let error = match request.send_bytes(&body) {
+            Ok(response) => {
+                debug!(
+                    "Posted report to '{url}': HTTP status {}.",
+                    response.status()
+                );
+                return Ok(());
+            }
+
+            Err(error) => error,
+        };
+
+        let is_transient = match &error {
+            ureq::Error::Status(status, _response) => *status == 429 || *status >= 500,
+
+            ureq::Error::Transport(transport) => !matches!(
+                transport.kind(),
+                ureq::ErrorKind::InvalidUrl | ureq::ErrorKind::UnknownScheme
+            ),
+        };
+
+        if !is_transient || retries >= options.webhook_retries {
+            return Err(Error::PostReport {
+                message: error.to_string(),
+            });
+        }
+
+        retries += 1;
+        warn!(
+            "Failed to post report to webhook, retrying in {} seconds ({retries}/{}): {error}",
+            delay.as_secs(),
+            options.webhook_retries
+        );
+        thread::sleep(delay);
+        delay = delay.saturating_mul(2);
+    }
+}
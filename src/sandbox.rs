@@ -0,0 +1,585 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Sandbox applied by the tool to itself before parsing files, as files might be crafted to
+//! exploit the parsers.
+
+use log::debug;
+
+use crate::cmdline::{Options, Sandbox};
+use crate::errors::{Error, Result};
+
+/// Applies the sandbox requested by `--sandbox`, which cannot be removed afterwards.
+///
+/// Options needing more than reading files and writing to the standard output and error streams
+/// are refused by the strict sandbox, instead of failing later. When `--isolate` is specified, the
+/// sandbox is applied to worker processes instead, as they parse the files.
+pub(crate) fn apply(options: &Options) -> Result<()> {
+    if options.sandbox == Sandbox::Off {
+        return Ok(());
+    }
+
+    if let Some(option) = conflicting_option(options) {
+        return Err(Error::Sandbox {
+            message: format!("'{option}' is not allowed by '--sandbox strict'"),
+        });
+    }
+
+    // Files are parsed by worker processes, which are sandboxed instead.
+    if options.isolate && options.isolated_worker.is_none() {
+        debug!("Worker processes will be sandboxed.");
+        return Ok(());
+    }
+
+    apply_strict()?;
+    debug!("Applied strict sandbox.");
+    Ok(())
+}
+
+/// Returns the first option needing to write files, access the network, or run programs.
+fn conflicting_option(options: &Options) -> Option<&'static str> {
+    [
+        (options.command.is_some(), "commands"),
+        (options.unpack_upx, "--unpack-upx"),
+        (options.unpack_kernel, "--unpack-kernel"),
+        (options.bundles, "--bundles"),
+        (options.webhook.is_some(), "--webhook"),
+        (options.export_weg.is_some(), "--export-weg"),
+        (options.annotate_elf.is_some(), "--annotate-elf"),
+        (options.stamp_file.is_some(), "--stamp-file"),
+        (options.depfile.is_some(), "--depfile"),
+        (options.badge.is_some(), "--badge"),
+    ]
+    .into_iter()
+    .find_map(|(used, option)| used.then_some(option))
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn apply_strict() -> Result<()> {
+    seccomp::apply()
+}
+
+#[cfg(windows)]
+fn apply_strict() -> Result<()> {
+    job_object::apply()
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )
+)))]
+fn apply_strict() -> Result<()> {
+    Err(Error::Sandbox {
+        message: String::from("sandboxing is not supported on this platform"),
+    })
+}
+
+/// Seccomp filter allowing the system calls needed to read and map files, to run threads, and to
+/// write to the standard output and error streams. Other system calls fail with `EPERM`.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod seccomp {
+    use libc::{c_long, c_uint, sock_filter};
+
+    use crate::errors::{Error, Result};
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xC000_003E;
+
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+    /// Offsets of the fields of `struct seccomp_data`, reading the low 32 bits of arguments.
+    const NR_OFFSET: u32 = 0;
+    const ARCH_OFFSET: u32 = 4;
+    const ARGS_OFFSET: u32 = 16;
+
+    /// System calls with numbers at least this large are x32 system calls on `x86_64`.
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    /// System calls allowed whatever their arguments.
+    const ALLOWED: &[c_long] = &[
+        // Reading files and directories.
+        libc::SYS_read,
+        libc::SYS_pread64,
+        libc::SYS_readv,
+        libc::SYS_preadv,
+        libc::SYS_lseek,
+        libc::SYS_close,
+        libc::SYS_fstat,
+        libc::SYS_newfstatat,
+        libc::SYS_statx,
+        libc::SYS_getdents64,
+        libc::SYS_readlinkat,
+        libc::SYS_faccessat,
+        libc::SYS_faccessat2,
+        libc::SYS_getcwd,
+        libc::SYS_fcntl,
+        libc::SYS_getxattr,
+        libc::SYS_lgetxattr,
+        libc::SYS_fgetxattr,
+        libc::SYS_listxattr,
+        libc::SYS_llistxattr,
+        libc::SYS_flistxattr,
+        // Managing memory.
+        libc::SYS_munmap,
+        libc::SYS_mremap,
+        libc::SYS_madvise,
+        libc::SYS_brk,
+        // Running threads.
+        libc::SYS_futex,
+        libc::SYS_sched_yield,
+        libc::SYS_sched_getaffinity,
+        libc::SYS_set_robust_list,
+        libc::SYS_rseq,
+        libc::SYS_gettid,
+        libc::SYS_getpid,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+        libc::SYS_rt_sigprocmask,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_sigaltstack,
+        // Measuring time, and describing the invocation.
+        libc::SYS_clock_gettime,
+        libc::SYS_clock_nanosleep,
+        libc::SYS_nanosleep,
+        libc::SYS_getrandom,
+        libc::SYS_uname,
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_stat,
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_lstat,
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_access,
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_readlink,
+    ];
+
+    /// Condition on an argument of a system call: `(argument & mask) == value`.
+    struct Condition {
+        argument: u32,
+        mask: u32,
+        value: u32,
+    }
+
+    impl Condition {
+        const fn equals(argument: u32, value: u32) -> Self {
+            Self {
+                argument,
+                mask: u32::MAX,
+                value,
+            }
+        }
+    }
+
+    /// Flags of `open` that allow modifying files.
+    #[allow(clippy::cast_sign_loss)]
+    const WRITING_OPEN_FLAGS: u32 = (libc::O_ACCMODE | libc::O_CREAT | libc::O_TRUNC) as u32;
+
+    /// Returns the system calls allowed if any of their conditions hold.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn conditional_rules() -> Vec<(c_long, Vec<Condition>)> {
+        let standard_streams = || vec![Condition::equals(0, 1), Condition::equals(0, 2)];
+        let not_executable = || {
+            let mask = libc::PROT_EXEC as u32;
+            vec![Condition {
+                argument: 2,
+                mask,
+                value: 0,
+            }]
+        };
+
+        vec![
+            // Only the standard output and error streams are written.
+            (libc::SYS_write, standard_streams()),
+            (libc::SYS_writev, standard_streams()),
+            // Files are only opened for reading.
+            (
+                libc::SYS_openat,
+                vec![Condition {
+                    argument: 2,
+                    mask: WRITING_OPEN_FLAGS,
+                    value: 0,
+                }],
+            ),
+            // Memory is never made executable.
+            (libc::SYS_mmap, not_executable()),
+            (libc::SYS_mprotect, not_executable()),
+            // Only threads are created, not processes.
+            (
+                libc::SYS_clone,
+                vec![Condition {
+                    argument: 0,
+                    mask: libc::CLONE_THREAD as u32,
+                    value: libc::CLONE_THREAD as u32,
+                }],
+            ),
+            // Terminals are detected, and threads are named.
+            (
+                libc::SYS_ioctl,
+                vec![Condition::equals(1, libc::TCGETS as u32)],
+            ),
+            (
+                libc::SYS_prctl,
+                vec![Condition::equals(0, libc::PR_SET_NAME as u32)],
+            ),
+        ]
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn statement(code: u32, k: u32) -> sock_filter {
+        sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn jump(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+        sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    fn errno(value: i32) -> u32 {
+        libc::SECCOMP_RET_ERRNO | (value.unsigned_abs() & libc::SECCOMP_RET_DATA)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn program() -> Vec<sock_filter> {
+        use libc::{
+            BPF_ABS, BPF_ALU, BPF_AND, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W,
+        };
+
+        let load = |offset| statement(BPF_LD | BPF_W | BPF_ABS, offset);
+        let allow = statement(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW);
+        let deny = statement(BPF_RET | BPF_K, errno(libc::EPERM));
+
+        let mut program = vec![
+            // System calls of other architectures kill the process.
+            load(ARCH_OFFSET),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            statement(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+            load(NR_OFFSET),
+            jump(BPF_JMP | BPF_JGE | BPF_K, X32_SYSCALL_BIT, 0, 1),
+            statement(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+            // The C runtime library falls back to `clone` when `clone3` is not implemented,
+            // as the arguments of `clone3` cannot be inspected.
+            jump(BPF_JMP | BPF_JEQ | BPF_K, libc::SYS_clone3 as u32, 0, 1),
+            statement(BPF_RET | BPF_K, errno(libc::ENOSYS)),
+        ];
+
+        for &nr in ALLOWED {
+            program.push(jump(BPF_JMP | BPF_JEQ | BPF_K, nr as u32, 0, 1));
+            program.push(allow);
+        }
+
+        for (nr, conditions) in conditional_rules() {
+            // Each condition is 3 instructions, followed by the denial and the approval.
+            let count = conditions.len() as u8;
+            program.push(jump(BPF_JMP | BPF_JEQ | BPF_K, nr as u32, 0, 3 * count + 2));
+
+            for (index, condition) in (0..count).zip(conditions) {
+                program.push(load(ARGS_OFFSET + 8 * condition.argument));
+                program.push(statement(BPF_ALU | BPF_AND | BPF_K, condition.mask));
+                let to_allow = 3 * (count - 1 - index) + 1;
+                program.push(jump(
+                    BPF_JMP | BPF_JEQ | BPF_K,
+                    condition.value,
+                    to_allow,
+                    0,
+                ));
+            }
+            program.push(deny);
+            program.push(allow);
+        }
+
+        program.push(deny);
+        program
+    }
+
+    pub(super) fn apply() -> Result<()> {
+        let mut program = program();
+        let prog = libc::sock_fprog {
+            len: u16::try_from(program.len()).map_err(|_r| Error::Sandbox {
+                message: String::from("seccomp filter is too large"),
+            })?,
+            filter: program.as_mut_ptr(),
+        };
+
+        // SAFETY: The arguments are the ones documented by `prctl(2)`.
+        let r = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+        if r != 0 {
+            return Err(last_error("failed to set 'no_new_privs'"));
+        }
+
+        // SAFETY: `prog` points to a valid filter, which outlives this call.
+        let r = unsafe {
+            libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER as c_uint,
+                libc::SECCOMP_FILTER_FLAG_TSYNC,
+                &prog,
+            )
+        };
+        if r == 0 {
+            Ok(())
+        } else {
+            Err(last_error("failed to install seccomp filter"))
+        }
+    }
+
+    fn last_error(operation: &str) -> Error {
+        Error::Sandbox {
+            message: format!("{operation}: {}", std::io::Error::last_os_error()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use libc::{c_long, sock_filter};
+
+        use super::{errno, program, ARCH_OFFSET, ARGS_OFFSET, AUDIT_ARCH, NR_OFFSET};
+
+        /// Runs the seccomp filter over a system call, and returns the action it decides.
+        ///
+        /// Only the instructions generated by `program()` are supported.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        fn run(program: &[sock_filter], arch: u32, nr: c_long, args: [u32; 6]) -> u32 {
+            use libc::{
+                BPF_ABS, BPF_ALU, BPF_AND, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W,
+            };
+
+            let load = |offset: u32| match offset {
+                NR_OFFSET => nr as u32,
+                ARCH_OFFSET => arch,
+                _ => args[((offset - ARGS_OFFSET) / 8) as usize],
+            };
+
+            let mut accumulator = 0;
+            let mut pc = 0;
+            loop {
+                let instruction = program[pc];
+                pc += 1;
+                match u32::from(instruction.code) {
+                    code if code == BPF_LD | BPF_W | BPF_ABS => accumulator = load(instruction.k),
+                    code if code == BPF_ALU | BPF_AND | BPF_K => accumulator &= instruction.k,
+                    code if code == BPF_JMP | BPF_JEQ | BPF_K
+                        || code == BPF_JMP | BPF_JGE | BPF_K =>
+                    {
+                        let taken = if code & BPF_JGE == BPF_JGE {
+                            accumulator >= instruction.k
+                        } else {
+                            accumulator == instruction.k
+                        };
+                        let offset = if taken {
+                            instruction.jt
+                        } else {
+                            instruction.jf
+                        };
+                        pc += usize::from(offset);
+                    }
+                    code if code == BPF_RET | BPF_K => return instruction.k,
+                    code => panic!("unexpected instruction code {code:#x}"),
+                }
+            }
+        }
+
+        fn run_native(nr: c_long, args: [u32; 6]) -> u32 {
+            run(&program(), AUDIT_ARCH, nr, args)
+        }
+
+        #[test]
+        fn allows_reading() {
+            assert_eq!(
+                run_native(libc::SYS_read, [3, 0, 0, 0, 0, 0]),
+                libc::SECCOMP_RET_ALLOW
+            );
+            assert_eq!(
+                run_native(libc::SYS_close, [3, 0, 0, 0, 0, 0]),
+                libc::SECCOMP_RET_ALLOW
+            );
+        }
+
+        #[test]
+        fn only_allows_writing_to_standard_streams() {
+            for fd in [1, 2] {
+                let action = run_native(libc::SYS_write, [fd, 0, 0, 0, 0, 0]);
+                assert_eq!(action, libc::SECCOMP_RET_ALLOW);
+            }
+            let action = run_native(libc::SYS_write, [3, 0, 0, 0, 0, 0]);
+            assert_eq!(action, errno(libc::EPERM));
+        }
+
+        #[test]
+        #[allow(clippy::cast_sign_loss)]
+        fn only_allows_opening_files_for_reading() {
+            let read_only = libc::O_RDONLY as u32 | libc::O_CLOEXEC as u32;
+            let action = run_native(libc::SYS_openat, [0, 0, read_only, 0, 0, 0]);
+            assert_eq!(action, libc::SECCOMP_RET_ALLOW);
+
+            for flags in [libc::O_WRONLY, libc::O_RDWR, libc::O_CREAT, libc::O_TRUNC] {
+                let action = run_native(libc::SYS_openat, [0, 0, flags as u32, 0, 0, 0]);
+                assert_eq!(action, errno(libc::EPERM));
+            }
+        }
+
+        #[test]
+        #[allow(clippy::cast_sign_loss)]
+        fn denies_executable_memory() {
+            let read = libc::PROT_READ as u32;
+            let exec = libc::PROT_EXEC as u32;
+            for nr in [libc::SYS_mmap, libc::SYS_mprotect] {
+                assert_eq!(
+                    run_native(nr, [0, 0, read, 0, 0, 0]),
+                    libc::SECCOMP_RET_ALLOW
+                );
+                assert_eq!(
+                    run_native(nr, [0, 0, read | exec, 0, 0, 0]),
+                    errno(libc::EPERM)
+                );
+            }
+        }
+
+        #[test]
+        #[allow(clippy::cast_sign_loss)]
+        fn only_allows_creating_threads() {
+            let thread = libc::CLONE_VM as u32 | libc::CLONE_THREAD as u32;
+            let action = run_native(libc::SYS_clone, [thread, 0, 0, 0, 0, 0]);
+            assert_eq!(action, libc::SECCOMP_RET_ALLOW);
+
+            let action = run_native(libc::SYS_clone, [libc::SIGCHLD as u32, 0, 0, 0, 0, 0]);
+            assert_eq!(action, errno(libc::EPERM));
+
+            let action = run_native(libc::SYS_clone3, [0; 6]);
+            assert_eq!(action, errno(libc::ENOSYS));
+        }
+
+        #[test]
+        fn denies_other_system_calls() {
+            assert_eq!(run_native(libc::SYS_unlinkat, [0; 6]), errno(libc::EPERM));
+            assert_eq!(run_native(libc::SYS_execve, [0; 6]), errno(libc::EPERM));
+        }
+
+        #[test]
+        fn kills_foreign_system_calls() {
+            let action = run(&program(), !AUDIT_ARCH, libc::SYS_read, [0; 6]);
+            assert_eq!(action, libc::SECCOMP_RET_KILL_PROCESS);
+
+            #[cfg(target_arch = "x86_64")]
+            {
+                let action = run_native(0x4000_0000 | libc::SYS_read, [0; 6]);
+                assert_eq!(action, libc::SECCOMP_RET_KILL_PROCESS);
+            }
+        }
+
+        #[test]
+        fn jumps_stay_inside_program() {
+            let program = program();
+            for (index, instruction) in program.iter().enumerate() {
+                // Other instructions continue at the next instruction, or jump further.
+                if u32::from(instruction.code) == libc::BPF_RET | libc::BPF_K {
+                    continue;
+                }
+                let target = index + 1 + usize::from(instruction.jt.max(instruction.jf));
+                assert!(target < program.len(), "instruction {index} jumps outside");
+            }
+        }
+    }
+}
+
+/// Job object preventing the process from creating processes and from accessing the user
+/// interface.
+#[cfg(windows)]
+mod job_object {
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicLimitInformation,
+        JobObjectBasicUIRestrictions, SetInformationJobObject, JOBOBJECT_BASIC_LIMIT_INFORMATION,
+        JOBOBJECT_BASIC_UI_RESTRICTIONS, JOB_OBJECT_LIMIT_ACTIVE_PROCESS,
+        JOB_OBJECT_UILIMIT_DESKTOP, JOB_OBJECT_UILIMIT_DISPLAYSETTINGS,
+        JOB_OBJECT_UILIMIT_EXITWINDOWS, JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES,
+        JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+        JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    use crate::errors::{Error, Result};
+
+    pub(super) fn apply() -> Result<()> {
+        // SAFETY: Null attributes and names are allowed.
+        let job = unsafe { CreateJobObjectW(core::ptr::null(), core::ptr::null()) };
+        if job == 0 {
+            return Err(last_error("failed to create job object"));
+        }
+
+        // The process itself is the only active process of the job.
+        // SAFETY: An all-zero value is valid for this plain data structure.
+        let mut limits: JOBOBJECT_BASIC_LIMIT_INFORMATION = unsafe { core::mem::zeroed() };
+        limits.LimitFlags = JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+        limits.ActiveProcessLimit = 1;
+
+        let ui_restrictions = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+            UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+                | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+                | JOB_OBJECT_UILIMIT_EXITWINDOWS
+                | JOB_OBJECT_UILIMIT_GLOBALATOMS
+                | JOB_OBJECT_UILIMIT_HANDLES
+                | JOB_OBJECT_UILIMIT_READCLIPBOARD
+                | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+                | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+        };
+
+        // SAFETY: The information structures match their classes, and outlive the calls.
+        let r = unsafe {
+            SetInformationJobObject(
+                job,
+                JobObjectBasicLimitInformation,
+                core::ptr::addr_of!(limits).cast(),
+                size_of_u32::<JOBOBJECT_BASIC_LIMIT_INFORMATION>(),
+            ) != 0
+                && SetInformationJobObject(
+                    job,
+                    JobObjectBasicUIRestrictions,
+                    core::ptr::addr_of!(ui_restrictions).cast(),
+                    size_of_u32::<JOBOBJECT_BASIC_UI_RESTRICTIONS>(),
+                ) != 0
+        };
+        if !r {
+            return Err(last_error("failed to set limits of job object"));
+        }
+
+        // SAFETY: Both handles are valid. The job handle is kept open, so that the job lives as
+        // long as the process.
+        if unsafe { AssignProcessToJobObject(job, GetCurrentProcess()) } == 0 {
+            return Err(last_error("failed to assign process to job object"));
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn size_of_u32<T>() -> u32 {
+        core::mem::size_of::<T>() as u32
+    }
+
+    fn last_error(operation: &str) -> Error {
+        Error::Sandbox {
+            message: format!("{operation}: {}", std::io::Error::last_os_error()),
+        }
+    }
+}
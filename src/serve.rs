@@ -0,0 +1,209 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! HTTP server analyzing uploaded binaries, run by the `serve` command, so that upload portals
+//! can analyze binaries without running the tool for each of them.
+
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fs, process, thread};
+
+use log::{debug, warn};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+
+/// Path of the endpoint analyzing uploaded binaries.
+const ANALYZE_PATH: &str = "/analyze";
+
+/// File name under which uploaded binaries are reported, unless the `name` query parameter is
+/// specified.
+const DEFAULT_FILE_NAME: &str = "upload";
+
+/// Listens on `address`, and analyzes the binaries posted to `/analyze` until the process is
+/// terminated.
+///
+/// Requests are handled by as many threads as there are processors. Each uploaded binary is
+/// stored in a temporary file while it is analyzed, then its JSON report is returned, as printed
+/// by `--format json`. The reported path is the `name` query parameter, e.g.,
+/// `/analyze?name=app.exe`. Binaries that cannot be analyzed are reported as errors, with the
+/// status `422 Unprocessable Entity`.
+pub(crate) fn run(
+    address: SocketAddr,
+    max_upload_size: u64,
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let server = tiny_http::Server::http(address).map_err(|source| Error::Listen {
+        address,
+        message: source.to_string(),
+    })?;
+
+    eprintln!("Listening on http://{address}{ANALYZE_PATH}");
+
+    let context = AnalysisContext::new(options);
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    handle_request(request, max_upload_size, &context);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle_request(mut request: Request, max_upload_size: u64, context: &AnalysisContext) {
+    debug!(
+        "Received request '{} {}' from {:?}.",
+        request.method(),
+        request.url(),
+        request.remote_addr()
+    );
+
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+
+    let response = if path != ANALYZE_PATH {
+        error_response(404, "not found")
+    } else if *request.method() != Method::Post {
+        error_response(405, "method not allowed").with_header(header("Allow", "POST"))
+    } else {
+        let name = query_parameter(query, "name")
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_FILE_NAME.into());
+
+        match read_upload(&mut request, max_upload_size) {
+            Ok(bytes) => analyze_upload(&bytes, PathBuf::from(name), context),
+
+            Err(response) => response,
+        }
+    };
+
+    if let Err(r) = request.respond(response) {
+        warn!("Failed to send response: {r}");
+    }
+}
+
+/// Reads the binary uploaded in the body of the request, unless it is larger than
+/// `--max-upload-size`.
+fn read_upload(
+    request: &mut Request,
+    max_upload_size: u64,
+) -> core::result::Result<Vec<u8>, Response<std::io::Cursor<Vec<u8>>>> {
+    let too_large = || error_response(413, "uploaded binary exceeds '--max-upload-size'");
+
+    if request
+        .body_length()
+        .is_some_and(|length| length as u64 > max_upload_size)
+    {
+        return Err(too_large());
+    }
+
+    // The body is read up to one byte past the limit, to detect larger chunked bodies.
+    let mut bytes = Vec::default();
+    request
+        .as_reader()
+        .take(max_upload_size.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(|r| error_response(400, &format!("failed to read uploaded binary: {r}")))?;
+
+    if bytes.len() as u64 > max_upload_size {
+        Err(too_large())
+    } else if bytes.is_empty() {
+        Err(error_response(400, "no binary was uploaded"))
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Analyzes an uploaded binary, reported as `name`, and returns its JSON report.
+fn analyze_upload(
+    bytes: &[u8],
+    name: PathBuf,
+    context: &AnalysisContext,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let path = env::temp_dir().join(format!(
+        "binary-security-check-serve-{}-{}",
+        process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let r = fs::File::create(&path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|r| Error::from_io1(r, "write file", &path))
+        .and_then(|()| crate::analyze_file(&path, context));
+
+    let _ignored = fs::remove_file(&path);
+
+    let (status, document) = match r {
+        Ok(results) => (
+            200,
+            crate::report::json_document(&[(name, results)], &[], context.options),
+        ),
+
+        Err(error) => (
+            422,
+            crate::report::json_document(&[], &[(name, error)], context.options),
+        ),
+    };
+
+    match document {
+        Ok(document) => Response::from_data(document)
+            .with_status_code(status)
+            .with_header(header("Content-Type", "application/json")),
+
+        Err(error) => error_response(500, &crate::format_error(&error)),
+    }
+}
+
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    let document = serde_json::json!({ "error": message }).to_string();
+
+    Response::from_string(document)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("Invalid static header.")
+}
+
+/// Returns the percent-decoded value of the parameter `name` of the query string `query`.
+fn query_parameter(query: &str, name: &str) -> Option<String> {
+    let value = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))?;
+
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = match byte {
+            b'+' => Some((b' ', tail)),
+
+            b'%' => tail
+                .get(..2)
+                .and_then(|hex| core::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .map(|byte| (byte, &tail[2..])),
+
+            _ => None,
+        };
+
+        let (byte, tail) = decoded.unwrap_or((byte, tail));
+        bytes.push(byte);
+        rest = tail;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -0,0 +1,77 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Stamp files and dependency files, written by `--stamp-file` and `--depfile`, so that build
+//! systems (e.g., Make, Ninja and Bazel) track when binaries were last verified, and rerun
+//! the analysis only when they change.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::errors::{Error, Result};
+use crate::report::FileResults;
+
+/// Updates the stamp file at `path` if the analysis succeeded, or removes it otherwise, so that
+/// build systems rerun the analysis until it succeeds.
+pub(crate) fn update_stamp(path: &Path, success: bool) -> Result<()> {
+    if success {
+        return fs::write(path, b"").map_err(|r| Error::from_io1(r, "write file", path));
+    }
+
+    match fs::remove_file(path) {
+        Err(r) if r.kind() != io::ErrorKind::NotFound => {
+            Err(Error::from_io1(r, "remove file", path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Escapes `path` as a word of a Makefile rule.
+fn escape(path: &Path) -> String {
+    let mut r = String::default();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' | '\\' => {
+                r.push('\\');
+                r.push(c);
+            }
+            '$' => r.push_str("$$"),
+            c => r.push(c),
+        }
+    }
+    r
+}
+
+/// Writes to `path` a dependency file in Makefile syntax, declaring that `stamp` depends on the
+/// analyzed files, and on the C runtime libraries given by `--libc`.
+///
+/// Files found inside other files, e.g., members of bundles and embedded images, are represented
+/// by the files containing them.
+pub(crate) fn write_depfile(
+    path: &Path,
+    stamp: &Path,
+    reports: &[(PathBuf, FileResults)],
+    options: &crate::cmdline::Options,
+) -> Result<()> {
+    let mut dependencies: Vec<&Path> = reports
+        .iter()
+        .map(|(path, _results)| path.as_path())
+        .chain(options.libc.iter().map(PathBuf::as_path))
+        .filter(|path| path.is_file())
+        .collect();
+    dependencies.sort_unstable();
+    dependencies.dedup();
+
+    let mut text = format!("{}:", escape(stamp));
+    for dependency in dependencies {
+        text.push_str(" \\\n  ");
+        text.push_str(&escape(dependency));
+    }
+    text.push('\n');
+
+    fs::write(path, text).map_err(|r| Error::from_io1(r, "write file", path))
+}
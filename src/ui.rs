@@ -4,6 +4,8 @@
 // Licensed under the MIT license. This file may not be copied, modified,
 // or distributed except according to those terms.
 
+pub(crate) mod numbers;
+
 use std::sync::Arc;
 
 use crate::cmdline::UseColor;
@@ -0,0 +1,107 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Formatting of numbers reported in the details of security features.
+
+use std::env;
+
+/// Separators used to format numbers.
+///
+/// By default, numbers are formatted without digit grouping and with a `.` decimal separator,
+/// so that reports are the same everywhere and remain easy to parse. When `--locale-numbers` is
+/// specified, the separators of the numeric locale of the environment are used instead.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NumberFormat {
+    grouping_separator: Option<&'static str>,
+    decimal_separator: &'static str,
+}
+
+impl NumberFormat {
+    const PLAIN: Self = Self {
+        grouping_separator: None,
+        decimal_separator: ".",
+    };
+
+    pub(crate) fn new(options: &crate::cmdline::Options) -> Self {
+        if options.locale_numbers {
+            Self::from_environment()
+        } else {
+            Self::PLAIN
+        }
+    }
+
+    /// Returns the separators of the numeric locale specified by `LC_ALL`, `LC_NUMERIC` or `LANG`.
+    fn from_environment() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+
+        // Strip the encoding and the modifier, e.g., `de_CH.UTF-8@euro`.
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let (language, territory) = locale.split_once('_').unwrap_or((locale, ""));
+
+        let (grouping_separator, decimal_separator) = match (language, territory) {
+            ("" | "C" | "POSIX", _) => return Self::PLAIN,
+
+            ("de" | "fr" | "it" | "rm", "CH" | "LI") => ("'", "."),
+
+            (
+                "da" | "de" | "el" | "es" | "hr" | "id" | "is" | "it" | "nl" | "pt" | "ro" | "sl"
+                | "sr" | "tr" | "vi",
+                _,
+            ) => (".", ","),
+
+            (
+                "bg" | "cs" | "et" | "fi" | "fr" | "hu" | "lt" | "lv" | "nb" | "nn" | "no" | "pl"
+                | "ru" | "sk" | "sv" | "uk",
+                _,
+            ) => ("\u{202f}", ","),
+
+            _ => (",", "."),
+        };
+
+        Self {
+            grouping_separator: Some(grouping_separator),
+            decimal_separator,
+        }
+    }
+
+    /// Formats a count or a size in bytes.
+    pub(crate) fn count(self, value: u64) -> String {
+        let digits = value.to_string();
+
+        let Some(separator) = self.grouping_separator else {
+            return digits;
+        };
+
+        let mut result = String::with_capacity(digits.len() + digits.len() / 3 * separator.len());
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                result.push_str(separator);
+            }
+            result.push(digit);
+        }
+        result
+    }
+
+    /// Formats a non-negative decimal number with the given number of fractional digits.
+    pub(crate) fn decimal(self, value: f64, precision: usize) -> String {
+        let text = format!("{value:.precision$}");
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+        let integer = integer
+            .parse::<u64>()
+            .map_or_else(|_| integer.to_owned(), |integer| self.count(integer));
+
+        if fraction.is_empty() {
+            integer
+        } else {
+            format!("{integer}{}{fraction}", self.decimal_separator)
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Executables compressed by UPX, whose payload only appears once they are decompressed.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::debug;
+
+use crate::context::AnalysisContext;
+use crate::errors::{Error, Result};
+use crate::extract::ExtractionLimits;
+use crate::options::status::{CheckResult, InformationalStatus};
+use crate::report::FileResults;
+
+/// Keyword of the result reporting that an executable is packed.
+pub(crate) const PACKED: &str = "PACKED";
+
+/// Marker of the headers written by UPX, in ELF and PE executables.
+const UPX_MAGIC: &[u8] = b"UPX!";
+
+/// Number of bytes at the start of the file where UPX writes its headers.
+const UPX_HEADERS_SIZE: usize = 4096;
+
+/// Names of the sections of PE executables packed by UPX.
+static UPX_SECTION_NAMES: &[&[u8]] = &[b"UPX0", b"UPX1", b"UPX2"];
+
+/// Returns `true` if the executable was compressed by UPX.
+///
+/// UPX writes its own headers, starting with `UPX!`, right after the headers of the executable.
+/// Sections of PE executables are also named `UPX0`, `UPX1`, etc., unless they were renamed.
+pub(crate) fn is_packed(bytes: &[u8], object: &goblin::Object) -> bool {
+    let has_upx_sections = match object {
+        goblin::Object::PE(pe) => pe.sections.iter().any(|section| {
+            let name = section.name.split(|&c| c == 0).next().unwrap_or_default();
+            UPX_SECTION_NAMES.contains(&name)
+        }),
+
+        goblin::Object::Elf(_) => false,
+
+        _ => return false,
+    };
+
+    let headers = &bytes[..bytes.len().min(UPX_HEADERS_SIZE)];
+    let r = has_upx_sections
+        || headers
+            .windows(UPX_MAGIC.len())
+            .any(|window| window == UPX_MAGIC);
+
+    if r {
+        debug!("Executable is packed by UPX.");
+    }
+    r
+}
+
+/// Returns the result reporting that an executable is packed by UPX.
+pub(crate) fn packed_status() -> CheckResult {
+    InformationalStatus::new(PACKED, vec!["upx".into()]).into()
+}
+
+/// Returns the path under which the payload of the packed executable at `path` is reported,
+/// e.g., `app.exe#upx`.
+pub(crate) fn payload_path(path: &Path) -> PathBuf {
+    let mut payload_path = OsString::from(path.as_os_str());
+    payload_path.push("#upx");
+    payload_path.into()
+}
+
+/// Decompresses the executable at `path` by running `upx -d`, then analyzes its payload.
+///
+/// The payload is written to a temporary file, which is removed once it is analyzed. Payloads
+/// larger than allowed by `--extract-max-member-size` are not analyzed.
+pub(crate) fn analyze_payload(path: &Path, context: &AnalysisContext) -> Result<FileResults> {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let unpacked_path = env::temp_dir().join(format!(
+        "binary-security-check-upx-{}-{}",
+        process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let r = decompress(path, &unpacked_path)
+        .and_then(|()| check_payload_size(path, &unpacked_path, context))
+        .and_then(|()| crate::analyze_file(&unpacked_path, context));
+
+    let _ignored = fs::remove_file(&unpacked_path);
+    r
+}
+
+fn check_payload_size(path: &Path, unpacked_path: &Path, context: &AnalysisContext) -> Result<()> {
+    let size = fs::metadata(unpacked_path)
+        .map_err(|r| Error::from_io1(r, "get metadata of file", unpacked_path))?
+        .len();
+
+    if size > ExtractionLimits::new(context.options).member_size {
+        Err(Error::ExtractionLimit {
+            path: payload_path(path),
+            size,
+            option: "--extract-max-member-size",
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn decompress(path: &Path, unpacked_path: &Path) -> Result<()> {
+    debug!(
+        "Decompressing '{}' to '{}'.",
+        path.display(),
+        unpacked_path.display()
+    );
+
+    let output = Command::new("upx")
+        .args(["-d", "-q", "-f", "-o"])
+        .arg(unpacked_path)
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|r| Error::from_io1(r, "run 'upx -d' on", path))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::UnpackUpx {
+            path: path.into(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        })
+    }
+}
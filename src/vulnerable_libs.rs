@@ -0,0 +1,254 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Versions of common libraries embedded in binaries, and the critical vulnerabilities known to
+//! affect them.
+//!
+//! Libraries record their version in read-only data, e.g., `OpenSSL 1.0.1f 6 Jan 2014`, which
+//! remains in the binaries that link them statically, and in the shared libraries themselves.
+//! Distributions often backport fixes without changing the version of a library, so this is
+//! meant to triage firmware images, not to prove that a binary is vulnerable.
+
+use core::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use log::debug;
+use regex::bytes::Regex;
+
+use crate::options::status::{DetailedStatus, SupportLevel};
+use crate::parser::BinaryParser;
+
+/// Keyword of the result reporting the versions of libraries embedded in a binary.
+pub(crate) const VULNERABLE_LIBS: &str = "VULNERABLE-LIBS";
+
+/// Names of libraries, and patterns matching the strings recording their versions, whose first
+/// group is the version.
+static LIBRARY_VERSION_PATTERNS: &[(&str, &str)] = &[
+    // E.g., `OpenSSL 1.0.2k-fips  26 Jan 2017`.
+    (
+        "OpenSSL",
+        r"OpenSSL ([0-9]+\.[0-9]+\.[0-9]+[a-z]{0,2})(?:-[a-z0-9]+)? +[0-9]{1,2} [A-Z][a-z]{2} [0-9]{4}",
+    ),
+    // E.g., ` inflate 1.2.11 Copyright 1995-2017 Mark Adler `.
+    (
+        "zlib",
+        r"(?:de|in)flate ([0-9]+\.[0-9]+(?:\.[0-9]+){0,2}) Copyright [0-9]{4}",
+    ),
+    // E.g., `libcurl/7.68.0`.
+    ("curl", r"libcurl/([0-9]+\.[0-9]+\.[0-9]+)"),
+];
+
+static LIBRARY_VERSION_REGEXES: LazyLock<Vec<(&str, Regex)>> = LazyLock::new(|| {
+    LIBRARY_VERSION_PATTERNS
+        .iter()
+        .map(|&(library, pattern)| {
+            (
+                library,
+                Regex::new(pattern).expect("Invalid static regular expression."),
+            )
+        })
+        .collect()
+});
+
+/// Critical vulnerability affecting a range of versions of a library.
+struct KnownVulnerability {
+    library: &'static str,
+    id: &'static str,
+    /// First affected version.
+    first: &'static str,
+    /// Last affected version.
+    last: &'static str,
+}
+
+/// Critical vulnerabilities of the libraries whose versions are recognized.
+///
+/// New vulnerabilities are added when their severity is critical, or when they are widely
+/// exploited.
+static KNOWN_VULNERABILITIES: &[KnownVulnerability] = &[
+    KnownVulnerability {
+        library: "OpenSSL",
+        id: "CVE-2014-0160",
+        first: "1.0.1",
+        last: "1.0.1f",
+    },
+    KnownVulnerability {
+        library: "OpenSSL",
+        id: "CVE-2016-2108",
+        first: "1.0.1",
+        last: "1.0.1n",
+    },
+    KnownVulnerability {
+        library: "OpenSSL",
+        id: "CVE-2016-2108",
+        first: "1.0.2",
+        last: "1.0.2b",
+    },
+    KnownVulnerability {
+        library: "OpenSSL",
+        id: "CVE-2016-6309",
+        first: "1.1.0a",
+        last: "1.1.0a",
+    },
+    KnownVulnerability {
+        library: "OpenSSL",
+        id: "CVE-2022-3602",
+        first: "3.0.0",
+        last: "3.0.6",
+    },
+    KnownVulnerability {
+        library: "zlib",
+        id: "CVE-2022-37434",
+        first: "1.2.2.1",
+        last: "1.2.12",
+    },
+    KnownVulnerability {
+        library: "curl",
+        id: "CVE-2018-1000120",
+        first: "7.12.3",
+        last: "7.58.0",
+    },
+    KnownVulnerability {
+        library: "curl",
+        id: "CVE-2019-5482",
+        first: "7.19.4",
+        last: "7.65.3",
+    },
+    KnownVulnerability {
+        library: "curl",
+        id: "CVE-2023-38545",
+        first: "7.69.0",
+        last: "8.3.0",
+    },
+];
+
+/// Compares versions made of numbers separated by dots, optionally followed by a letter suffix,
+/// e.g., `1.0.1f`.
+///
+/// Longer suffixes come after shorter ones, e.g., `1.0.2za` comes after `1.0.2z`.
+fn compare_versions(left: &str, right: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u32>, &str) {
+        let suffix_start = version
+            .rfind(|c: char| c.is_ascii_digit())
+            .map_or(0, |i| i + 1);
+        let numbers = version[..suffix_start]
+            .split('.')
+            .map(|n| n.parse().unwrap_or_default())
+            .collect();
+        (numbers, &version[suffix_start..])
+    }
+
+    let (left_numbers, left_suffix) = split(left);
+    let (right_numbers, right_suffix) = split(right);
+    left_numbers
+        .cmp(&right_numbers)
+        .then_with(|| left_suffix.len().cmp(&right_suffix.len()))
+        .then_with(|| left_suffix.cmp(right_suffix))
+}
+
+/// Returns the identifiers of the known vulnerabilities affecting the given library version.
+fn known_vulnerabilities(library: &str, version: &str) -> Vec<&'static str> {
+    let mut ids: Vec<_> = KNOWN_VULNERABILITIES
+        .iter()
+        .filter(|v| {
+            v.library == library
+                && compare_versions(v.first, version).is_le()
+                && compare_versions(version, v.last).is_le()
+        })
+        .map(|v| v.id)
+        .collect();
+    ids.dedup();
+    ids
+}
+
+/// Returns the read-only data of the binary, where libraries record their versions.
+///
+/// All the bytes of the file are returned when read-only data cannot be located, e.g., when
+/// section headers are stripped.
+fn read_only_data<'t>(parser: &'t BinaryParser) -> Vec<&'t [u8]> {
+    let r: Vec<_> = match parser.object() {
+        goblin::Object::Elf(elf) => {
+            use goblin::elf::section_header::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_PROGBITS};
+
+            elf.section_headers
+                .iter()
+                .filter(|s| {
+                    s.sh_type == SHT_PROGBITS
+                        && (s.sh_flags & u64::from(SHF_ALLOC)) != 0
+                        && (s.sh_flags & u64::from(SHF_WRITE | SHF_EXECINSTR)) == 0
+                })
+                .filter_map(|s| crate::elf::section_bytes(parser, s))
+                .collect()
+        }
+
+        goblin::Object::PE(pe) => {
+            use goblin::pe::section_table::{
+                IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_WRITE,
+            };
+
+            pe.sections
+                .iter()
+                .filter(|s| {
+                    (s.characteristics & IMAGE_SCN_CNT_INITIALIZED_DATA) != 0
+                        && (s.characteristics & (IMAGE_SCN_MEM_WRITE | IMAGE_SCN_MEM_EXECUTE)) == 0
+                })
+                .filter_map(|s| {
+                    let start = usize::try_from(s.pointer_to_raw_data).ok()?;
+                    let end = start.checked_add(usize::try_from(s.size_of_raw_data).ok()?)?;
+                    parser.bytes().get(start..end)
+                })
+                .collect()
+        }
+
+        _ => Vec::default(),
+    };
+
+    if r.is_empty() {
+        vec![parser.bytes()]
+    } else {
+        r
+    }
+}
+
+/// Returns the versions of common libraries embedded in the binary, and the critical
+/// vulnerabilities known to affect them.
+///
+/// Each detail names a library version, followed by the identifiers of its known
+/// vulnerabilities, if any, e.g., `OpenSSL-1.0.1f=CVE-2014-0160+CVE-2016-2108`.
+/// The status is unknown if no library versions are found.
+pub(crate) fn vulnerable_libraries(parser: &BinaryParser) -> DetailedStatus {
+    let mut versions = BTreeMap::new();
+    for data in read_only_data(parser) {
+        for (library, regex) in LIBRARY_VERSION_REGEXES.iter() {
+            for captures in regex.captures_iter(data) {
+                let version = String::from_utf8_lossy(&captures[1]).into_owned();
+                let ids = known_vulnerabilities(library, &version);
+                versions.insert((*library, version), ids);
+            }
+        }
+    }
+
+    if versions.is_empty() {
+        return DetailedStatus::unknown(VULNERABLE_LIBS);
+    }
+
+    let mut level = SupportLevel::Supported;
+    let details = versions
+        .into_iter()
+        .map(|((library, version), ids)| {
+            debug!("Found version '{version}' of library '{library}'.");
+
+            if ids.is_empty() {
+                format!("{library}-{version}")
+            } else {
+                level = SupportLevel::Unsupported;
+                format!("{library}-{version}={}", ids.join("+"))
+            }
+        })
+        .collect();
+
+    DetailedStatus::new(VULNERABLE_LIBS, level, details)
+}
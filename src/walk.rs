@@ -0,0 +1,371 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::SyncSender;
+
+use log::{debug, warn};
+use regex::Regex;
+
+use crate::errors::{Error, Result};
+use crate::parser::{extended_length_path, unsupported_file_reason};
+
+/// A file discovered while walking the input paths.
+pub(crate) struct WalkEntry {
+    /// Position of the file in the discovery order.
+    pub(crate) index: usize,
+    pub(crate) path: PathBuf,
+    /// Error that prevented the discovery of files, if any.
+    pub(crate) error: Option<Error>,
+}
+
+/// Name of the files listing the patterns of the files and directories that recursive scans skip,
+/// in the directory containing them and its subdirectories.
+const IGNORE_FILE_NAME: &str = ".bscignore";
+
+/// Glob pattern of the files and directories that recursive scans skip, as given by `--exclude`
+/// or listed in `.bscignore` files.
+///
+/// As in `.gitignore` files, `*` matches any characters except `/`, `?` matches any character
+/// except `/`, `**` matches any characters, and `[...]` matches a character of a class. Patterns
+/// containing `/` match paths relative to the directory where they apply, while other patterns
+/// match the names of files and directories at any depth. Patterns ending with `/` only match
+/// directories.
+#[derive(Debug, Clone)]
+pub(crate) struct IgnorePattern {
+    regex: Regex,
+    /// Whether the pattern matches paths, instead of names.
+    anchored: bool,
+    /// Whether the pattern only matches directories.
+    dirs_only: bool,
+}
+
+impl IgnorePattern {
+    pub(crate) fn parse(pattern: &str) -> core::result::Result<Self, regex::Error> {
+        let (pattern, dirs_only) = match pattern.strip_suffix('/') {
+            Some(pattern) => (pattern, true),
+            None => (pattern, false),
+        };
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+
+        let mut regex = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        regex.push_str("(?:.*/)?");
+                    } else {
+                        regex.push_str(".*");
+                    }
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '[' => {
+                    regex.push('[');
+                    if chars.next_if(|&c| c == '!' || c == '^').is_some() {
+                        regex.push('^');
+                    }
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                        if c == '\\' || c == '[' {
+                            regex.push('\\');
+                        }
+                        regex.push(c);
+                    }
+                    regex.push(']');
+                }
+                c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+        regex.push('$');
+
+        Ok(Self {
+            regex: Regex::new(&regex)?,
+            anchored,
+            dirs_only,
+        })
+    }
+
+    /// Returns `true` if the pattern matches `relative_path`, which is relative to the directory
+    /// where the pattern applies.
+    fn matches(&self, relative_path: &Path, is_dir: bool) -> bool {
+        if self.dirs_only && !is_dir {
+            return false;
+        }
+
+        if self.anchored {
+            let path = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            self.regex.is_match(&path)
+        } else {
+            relative_path
+                .file_name()
+                .is_some_and(|name| self.regex.is_match(&name.to_string_lossy()))
+        }
+    }
+}
+
+/// Pattern of skipped files and directories, and the directory where it applies.
+struct IgnoreRule {
+    base: PathBuf,
+    pattern: IgnorePattern,
+}
+
+/// Returns `rules`, followed by the rules listed in the `.bscignore` file of `dir`, if any.
+fn with_ignore_file(rules: &Rc<[IgnoreRule]>, dir: &Path) -> Rc<[IgnoreRule]> {
+    let path = dir.join(IGNORE_FILE_NAME);
+    let text = match fs::read_to_string(extended_length_path(&path)) {
+        Ok(text) => text,
+
+        Err(r) => {
+            if r.kind() != io::ErrorKind::NotFound {
+                let error = Error::from_io1(r, "read file", &path);
+                warn!("{}", crate::format_error(&error));
+            }
+            return Rc::clone(rules);
+        }
+    };
+
+    debug!("Reading ignored patterns from '{}'.", path.display());
+    let mut new_rules = Vec::default();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match IgnorePattern::parse(line) {
+            Ok(pattern) => new_rules.push(IgnoreRule {
+                base: dir.into(),
+                pattern,
+            }),
+
+            Err(r) => warn!("{}: Ignoring pattern '{line}'. {r}", path.display()),
+        }
+    }
+
+    if new_rules.is_empty() {
+        return Rc::clone(rules);
+    }
+
+    rules
+        .iter()
+        .map(|rule| IgnoreRule {
+            base: rule.base.clone(),
+            pattern: rule.pattern.clone(),
+        })
+        .chain(new_rules)
+        .collect()
+}
+
+/// Returns `true` if a rule matches the file or directory at `path`.
+fn is_ignored(rules: &[IgnoreRule], path: &Path, is_dir: bool) -> bool {
+    rules.iter().any(|rule| {
+        path.strip_prefix(&rule.base)
+            .is_ok_and(|relative_path| rule.pattern.matches(relative_path, is_dir))
+    })
+}
+
+/// Returns the identifier of the device containing the file described by `metadata`.
+#[cfg(unix)]
+fn device_id(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.dev())
+}
+
+/// Device identifiers are not supported on this platform.
+#[cfg(not(unix))]
+fn device_id(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Sends the files to analyze through `sender`, as soon as they are discovered.
+///
+/// If `--recursive` is specified, then input directories are walked recursively, and all
+/// non-empty regular files found inside them are sent. Symbolic links found inside directories are
+/// not followed. Files and directories matching the `--exclude` patterns, which are relative to
+/// input directories, or matching the patterns of `.bscignore` files, are skipped. Files deeper
+/// than `--max-depth`, and directories on other file systems if `--one-file-system` is specified,
+/// are skipped as well. Input files are never skipped.
+///
+/// If `--skip-special-files` is specified, then input files that are not regular files, or that are
+/// empty, are skipped as well. Otherwise, they are sent, and their analysis reports why they are
+/// not supported.
+///
+/// `sender` is expected to be bounded, so that discovery blocks while enough files are pending
+/// analysis. This bounds memory usage even when walking a whole file system.
+pub(crate) fn walk_inputs(
+    inputs: Vec<PathBuf>,
+    options: &crate::cmdline::Options,
+    sender: &SyncSender<WalkEntry>,
+) {
+    let mut walker = Walker {
+        index: 0,
+        sender,
+        max_depth: options.max_depth,
+        one_file_system: options.one_file_system,
+    };
+
+    for path in inputs {
+        let metadata = fs::metadata(extended_length_path(&path));
+        let is_dir = options.recursive && metadata.as_ref().is_ok_and(fs::Metadata::is_dir);
+
+        let keep_going = if is_dir {
+            let device = metadata.as_ref().ok().and_then(device_id);
+            let rules = options
+                .exclude
+                .iter()
+                .map(|pattern| IgnoreRule {
+                    base: path.clone(),
+                    pattern: pattern.clone(),
+                })
+                .collect();
+            walker.walk_directory(path, rules, device)
+        } else if let Some(reason) = metadata
+            .ok()
+            .filter(|_| options.skip_special_files)
+            .and_then(|metadata| unsupported_file_reason(&metadata))
+        {
+            debug!("Skipping '{}', which is {reason}.", path.display());
+            true
+        } else {
+            walker.send(path, None)
+        };
+
+        if !keep_going {
+            break;
+        }
+    }
+}
+
+struct Walker<'t> {
+    index: usize,
+    sender: &'t SyncSender<WalkEntry>,
+    /// Maximum depth of walked files, where the files inside input directories are at depth 1.
+    max_depth: Option<u32>,
+    /// Whether directories on other file systems than input directories are skipped.
+    one_file_system: bool,
+}
+
+impl Walker<'_> {
+    /// Returns `false` if the receiving side stopped listening.
+    fn send(&mut self, path: PathBuf, error: Option<Error>) -> bool {
+        let entry = WalkEntry {
+            index: self.index,
+            path,
+            error,
+        };
+        self.index = self.index.saturating_add(1);
+        self.sender.send(entry).is_ok()
+    }
+
+    /// Walks a directory tree in depth-first order.
+    ///
+    /// Only the directories being walked are kept in memory, not the files they contain, along
+    /// with the rules of skipped files that apply to them. `device` identifies the file system
+    /// containing `root`, if known.
+    /// Returns `false` if the receiving side stopped listening.
+    fn walk_directory(
+        &mut self,
+        root: PathBuf,
+        rules: Rc<[IgnoreRule]>,
+        device: Option<u64>,
+    ) -> bool {
+        debug!("Walking directory '{}'.", root.display());
+
+        let mut pending_dirs = vec![(root, rules, 0_u32)];
+        while let Some((dir, rules, depth)) = pending_dirs.pop() {
+            let depth = depth.saturating_add(1);
+            if self.max_depth.is_some_and(|max_depth| depth > max_depth) {
+                debug!("Skipping '{}', which is too deep.", dir.display());
+                continue;
+            }
+
+            let rules = with_ignore_file(&rules, &dir);
+
+            let entries = match read_directory(&dir) {
+                Ok(entries) => entries,
+
+                Err(err) => {
+                    if !self.send(dir.clone(), Some(err)) {
+                        return false;
+                    }
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                let (path, metadata) = match entry {
+                    Ok(entry) => entry,
+
+                    Err(err) => {
+                        if !self.send(dir.clone(), Some(err)) {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+
+                if is_ignored(&rules, &path, metadata.is_dir())
+                    || path
+                        .file_name()
+                        .is_some_and(|name| name == IGNORE_FILE_NAME)
+                {
+                    debug!("Skipping '{}', which is ignored.", path.display());
+                } else if metadata.is_dir() {
+                    if self.one_file_system
+                        && device.is_some_and(|device| device_id(&metadata) != Some(device))
+                    {
+                        debug!(
+                            "Skipping '{}', which is on another file system.",
+                            path.display()
+                        );
+                    } else {
+                        pending_dirs.push((path, Rc::clone(&rules), depth));
+                    }
+                } else if let Some(reason) = unsupported_file_reason(&metadata) {
+                    debug!("Skipping '{}', which is {reason}.", path.display());
+                } else if !self.send(path, None) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Returns the entries of a directory, with the metadata of the entries themselves, not of the
+/// targets of symbolic links.
+///
+/// The paths of entries start with `dir`, even if the directory is read through its
+/// extended-length path.
+fn read_directory(
+    dir: &Path,
+) -> Result<impl Iterator<Item = Result<(PathBuf, fs::Metadata)>> + '_> {
+    let entries = fs::read_dir(extended_length_path(dir))
+        .map_err(|r| Error::from_io1(r, "read directory", dir))?;
+
+    Ok(entries.map(move |entry| {
+        let entry = entry.map_err(|r| Error::from_io1(r, "read directory", dir))?;
+        let path = dir.join(entry.file_name());
+        let metadata = entry
+            .metadata()
+            .map_err(|r| Error::from_io1(r, "get metadata of file", &path))?;
+        Ok((path, metadata))
+    }))
+}
@@ -0,0 +1,362 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Export of Windows Defender Exploit Guard process mitigation policies.
+//!
+//! The generated file can be deployed by `Set-ProcessMitigation -PolicyFilePath`.
+
+use core::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::errors::{Error, Result};
+use crate::options::status::{CheckResult, SupportLevel};
+use crate::report::{BinaryFormat, FileResults};
+
+/// A process mitigation that can be enabled when an image supports a security feature.
+struct Mitigation {
+    /// Keyword of the security feature required by the mitigation.
+    feature: &'static str,
+    /// Name of the mitigation in the policy.
+    name: &'static str,
+    /// Policy element enabling the mitigation, when the feature is supported.
+    supported: &'static str,
+    /// Policy element enabling the mitigation, when the feature is partially supported.
+    partial: Option<&'static str>,
+}
+
+static MITIGATIONS: &[Mitigation] = &[
+    Mitigation {
+        feature: "DATA-EXEC-PREVENT",
+        name: "DEP",
+        supported: r#"<DEP Enable="true" EmulateAtlThunks="false" />"#,
+        partial: None,
+    },
+    Mitigation {
+        feature: "ASLR",
+        name: "ASLR",
+        supported: r#"<ASLR ForceRelocateImages="false" RequireInfo="false" BottomUp="true" HighEntropy="true" />"#,
+        // High entropy randomization is not supported by the image.
+        partial: Some(
+            r#"<ASLR ForceRelocateImages="false" RequireInfo="false" BottomUp="true" HighEntropy="false" />"#,
+        ),
+    },
+    Mitigation {
+        feature: "CONTROL-FLOW-GUARD",
+        name: "ControlFlowGuard",
+        supported: r#"<ControlFlowGuard Enable="true" SuppressExports="false" />"#,
+        partial: None,
+    },
+    Mitigation {
+        feature: "HEAP-HARDENING",
+        name: "Heap",
+        supported: r#"<Heap TerminateOnError="true" />"#,
+        partial: None,
+    },
+];
+
+/// Writes to `path` a process mitigation policy for every analyzed PE image, enabling the
+/// mitigations supported by that image, and noting those it lacks.
+///
+/// Images sharing the same file name in different directories share a single policy, which only
+/// enables the mitigations supported by all of them.
+pub(crate) fn export_policy(path: &Path, reports: &[(PathBuf, FileResults)]) -> Result<()> {
+    fs::write(path, policy(reports)).map_err(|r| Error::from_io1(r, "write file", path))
+}
+
+fn policy(reports: &[(PathBuf, FileResults)]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<MitigationPolicy>\n");
+
+    for (file_name, images) in images_by_file_name(reports) {
+        if images.len() > 1 {
+            warn!(
+                "{} analyzed images are named '{}'. Their exported policy only enables the \
+                 mitigations supported by all of them.",
+                images.len(),
+                file_name
+            );
+        }
+
+        for (image_path, _results) in &images {
+            let _ignored = writeln!(
+                xml,
+                "  <!-- {} -->",
+                escape_comment(&image_path.to_string_lossy())
+            );
+        }
+        let _ignored = writeln!(
+            xml,
+            "  <AppConfig Executable=\"{}\">",
+            escape_attribute(&file_name)
+        );
+
+        for mitigation in MITIGATIONS {
+            // Mitigations whose security feature was not checked are not mentioned.
+            let Some(level) = mitigation_level(mitigation, &images) else {
+                continue;
+            };
+
+            let element = match level {
+                SupportLevel::Supported => Some(mitigation.supported),
+                SupportLevel::Partial => mitigation.partial,
+                SupportLevel::Unsupported | SupportLevel::Unknown => None,
+            };
+
+            if let Some(element) = element {
+                let _ignored = writeln!(xml, "    {element}");
+            } else {
+                let _ignored = writeln!(
+                    xml,
+                    "    <!-- {} not enabled. {}: {}. -->",
+                    mitigation.name,
+                    mitigation.feature,
+                    level.description()
+                );
+            }
+        }
+
+        xml.push_str("  </AppConfig>\n");
+    }
+
+    xml.push_str("</MitigationPolicy>\n");
+    xml
+}
+
+/// Groups the analyzed PE images by file name, in the order they were first analyzed.
+///
+/// Policies apply to processes by executable file name, regardless of its directory. File names
+/// are compared case-insensitively, as they are on Windows.
+fn images_by_file_name(
+    reports: &[(PathBuf, FileResults)],
+) -> Vec<(String, Vec<(&Path, &FileResults)>)> {
+    let mut groups: Vec<(String, Vec<(&Path, &FileResults)>)> = Vec::new();
+
+    let images = reports
+        .iter()
+        .filter(|(_path, results)| results.format == BinaryFormat::PE);
+
+    for (image_path, results) in images {
+        let Some(file_name) = image_path.file_name() else {
+            continue;
+        };
+        let file_name = file_name.to_string_lossy();
+
+        let image = (image_path.as_path(), results);
+        if let Some((_name, group)) = groups
+            .iter_mut()
+            .find(|(name, _group)| name.eq_ignore_ascii_case(&file_name))
+        {
+            group.push(image);
+        } else {
+            groups.push((file_name.into_owned(), vec![image]));
+        }
+    }
+    groups
+}
+
+/// Returns the level of support, by all `images`, of the security feature required by
+/// `mitigation`, or `None` if it was not checked in some image.
+fn mitigation_level(
+    mitigation: &Mitigation,
+    images: &[(&Path, &FileResults)],
+) -> Option<SupportLevel> {
+    images
+        .iter()
+        .map(|(_path, results)| {
+            results
+                .checks
+                .iter()
+                .find(|r| r.name() == mitigation.feature)
+                .and_then(CheckResult::level)
+        })
+        .try_fold(SupportLevel::Supported, |merged, level| {
+            level.map(|level| weakest_level(merged, level))
+        })
+}
+
+/// Returns the level of support of a security feature by two images.
+fn weakest_level(first: SupportLevel, second: SupportLevel) -> SupportLevel {
+    match (first, second) {
+        (SupportLevel::Supported, level) | (level, SupportLevel::Supported) => level,
+        (SupportLevel::Unsupported, _) | (_, SupportLevel::Unsupported) => {
+            SupportLevel::Unsupported
+        }
+        (SupportLevel::Partial, SupportLevel::Partial) => SupportLevel::Partial,
+        _ => SupportLevel::Unknown,
+    }
+}
+
+fn escape_attribute(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// XML comments cannot contain `--`, nor end with `-`.
+fn escape_comment(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '-' && result.ends_with('-') {
+            result.push(' ');
+        }
+        result.push(c);
+    }
+    if result.ends_with('-') {
+        result.push(' ');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::{escape_attribute, escape_comment, export_policy, policy};
+    use crate::options::status::{DetailedStatus, SupportLevel};
+    use crate::report::{BinaryFormat, FileResults};
+
+    fn image(
+        path: &str,
+        format: BinaryFormat,
+        levels: &[(&'static str, SupportLevel)],
+    ) -> (PathBuf, FileResults) {
+        let checks = levels
+            .iter()
+            .map(|&(name, level)| DetailedStatus::new(name, level, Vec::new()).into())
+            .collect();
+
+        let results = FileResults {
+            format,
+            architecture: None,
+            checks,
+            libc: None,
+            duration: None,
+        };
+        (PathBuf::from(path), results)
+    }
+
+    #[test]
+    fn attributes_are_escaped() {
+        assert_eq!(
+            escape_attribute(r#"a&b<c>d"e'f.exe"#),
+            "a&amp;b&lt;c&gt;d&quot;e&apos;f.exe"
+        );
+        assert_eq!(escape_attribute("tool.exe"), "tool.exe");
+    }
+
+    #[test]
+    fn comments_are_escaped() {
+        assert_eq!(escape_comment("a-b"), "a-b");
+        assert_eq!(escape_comment("a--b"), "a- -b");
+        assert_eq!(escape_comment("a---b"), "a- - -b");
+        assert_eq!(escape_comment("tool-"), "tool- ");
+        assert_eq!(escape_comment("tool--"), "tool- - ");
+
+        for text in ["a---b", "a----", "-", "--", "x-y--z---"] {
+            let escaped = escape_comment(text);
+            assert!(!escaped.contains("--"), "{escaped:?}");
+            assert!(!escaped.ends_with('-'), "{escaped:?}");
+        }
+    }
+
+    #[test]
+    fn supported_mitigations_are_enabled() {
+        let reports = [
+            image(
+                "/opt/app/tool.exe",
+                BinaryFormat::PE,
+                &[
+                    ("DATA-EXEC-PREVENT", SupportLevel::Supported),
+                    ("ASLR", SupportLevel::Partial),
+                    ("CONTROL-FLOW-GUARD", SupportLevel::Unsupported),
+                ],
+            ),
+            image(
+                "/usr/bin/ls",
+                BinaryFormat::ELF,
+                &[("DATA-EXEC-PREVENT", SupportLevel::Supported)],
+            ),
+        ];
+
+        let xml = policy(&reports);
+        assert_eq!(
+            xml,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <MitigationPolicy>\n  \
+             <!-- /opt/app/tool.exe -->\n  \
+             <AppConfig Executable=\"tool.exe\">\n    \
+             <DEP Enable=\"true\" EmulateAtlThunks=\"false\" />\n    \
+             <ASLR ForceRelocateImages=\"false\" RequireInfo=\"false\" BottomUp=\"true\" HighEntropy=\"false\" />\n    \
+             <!-- ControlFlowGuard not enabled. CONTROL-FLOW-GUARD: Unsupported. -->\n  \
+             </AppConfig>\n\
+             </MitigationPolicy>\n"
+        );
+    }
+
+    #[test]
+    fn images_with_the_same_file_name_share_a_policy() {
+        let reports = [
+            image(
+                "/opt/a--/tool.exe",
+                BinaryFormat::PE,
+                &[
+                    ("DATA-EXEC-PREVENT", SupportLevel::Supported),
+                    ("ASLR", SupportLevel::Supported),
+                    ("HEAP-HARDENING", SupportLevel::Supported),
+                ],
+            ),
+            image(
+                "/opt/b-/TOOL.EXE",
+                BinaryFormat::PE,
+                &[
+                    ("DATA-EXEC-PREVENT", SupportLevel::Supported),
+                    ("ASLR", SupportLevel::Unknown),
+                ],
+            ),
+        ];
+
+        let xml = policy(&reports);
+        assert_eq!(xml.matches("<AppConfig ").count(), 1);
+        assert!(xml.contains("<!-- /opt/a- -/tool.exe -->"));
+        assert!(xml.contains("<!-- /opt/b-/TOOL.EXE -->"));
+        assert!(xml.contains("<DEP Enable=\"true\""));
+        assert!(xml.contains("<!-- ASLR not enabled. ASLR: Unknown. -->"));
+        // Heap hardening was not checked in one of the images.
+        assert!(!xml.contains("Heap"));
+    }
+
+    #[test]
+    fn policy_is_exported_to_a_file() {
+        let reports = [image(
+            "C:\\Program Files\\App\\app.exe",
+            BinaryFormat::PE,
+            &[("DATA-EXEC-PREVENT", SupportLevel::Supported)],
+        )];
+
+        let path = std::env::temp_dir().join(format!(
+            "binary-security-check-weg-{}.xml",
+            std::process::id()
+        ));
+        export_policy(&path, &reports).unwrap();
+        let xml = fs::read_to_string(&path);
+        let _ignored = fs::remove_file(&path);
+        assert_eq!(xml.unwrap(), policy(&reports));
+
+        assert!(export_policy(&path.join("missing").join("policy.xml"), &reports).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright 2018-2024 Koutheir Attouchi.
+// See the "LICENSE.txt" file at the top-level directory of this distribution.
+//
+// Licensed under the MIT license. This file may not be copied, modified,
+// or distributed except according to those terms.
+
+//! Security features stored in extended attributes of files, instead of inside binary files.
+
+use std::path::Path;
+
+use crate::options::status::{CheckResult, DetailedStatus};
+#[cfg(unix)]
+use crate::options::status::{InformationalStatus, SupportLevel};
+
+/// Returns the mandatory access control context of a file, as labeled by `SELinux`.
+///
+/// The label is reported as information. Files that are not labeled, or that are labeled
+/// with the `unlabeled_t` type, are reported as unsupported, because no confinement context can
+/// be assigned to processes they execute.
+///
+/// `AppArmor` profiles are attached to paths instead of files, so they are not reported.
+#[cfg(unix)]
+pub(crate) fn mac_context(path: &Path) -> CheckResult {
+    use log::debug;
+
+    let label = match xattr::get(path, "security.selinux") {
+        Ok(label) => label,
+
+        Err(err) => {
+            debug!(
+                "Failed to read SELinux label of '{}': {err}",
+                path.display()
+            );
+            return DetailedStatus::unknown("MAC-CONTEXT").into();
+        }
+    };
+
+    // The label is usually terminated by a null character.
+    let label = label.map(|label| {
+        let label = label.strip_suffix(b"\0").unwrap_or(&label);
+        String::from_utf8_lossy(label).into_owned()
+    });
+
+    match label {
+        None => DetailedStatus::new(
+            "MAC-CONTEXT",
+            SupportLevel::Unsupported,
+            vec!["no-label".into()],
+        )
+        .into(),
+
+        // The type is the third field of `user:role:type:level`.
+        Some(label) if label.split(':').nth(2) == Some("unlabeled_t") => {
+            DetailedStatus::new("MAC-CONTEXT", SupportLevel::Unsupported, vec![label]).into()
+        }
+
+        Some(label) => InformationalStatus::new("MAC-CONTEXT", vec![label]).into(),
+    }
+}
+
+/// Extended attributes are not supported on this platform.
+#[cfg(not(unix))]
+pub(crate) fn mac_context(_path: &Path) -> CheckResult {
+    DetailedStatus::unknown("MAC-CONTEXT").into()
+}
+
+/// Names of Linux capabilities, indexed by their number.
+#[cfg(unix)]
+const CAPABILITY_NAMES: [&str; 41] = [
+    "cap_chown",
+    "cap_dac_override",
+    "cap_dac_read_search",
+    "cap_fowner",
+    "cap_fsetid",
+    "cap_kill",
+    "cap_setgid",
+    "cap_setuid",
+    "cap_setpcap",
+    "cap_linux_immutable",
+    "cap_net_bind_service",
+    "cap_net_broadcast",
+    "cap_net_admin",
+    "cap_net_raw",
+    "cap_ipc_lock",
+    "cap_ipc_owner",
+    "cap_sys_module",
+    "cap_sys_rawio",
+    "cap_sys_chroot",
+    "cap_sys_ptrace",
+    "cap_sys_pacct",
+    "cap_sys_admin",
+    "cap_sys_boot",
+    "cap_sys_nice",
+    "cap_sys_resource",
+    "cap_sys_time",
+    "cap_sys_tty_config",
+    "cap_mknod",
+    "cap_lease",
+    "cap_audit_write",
+    "cap_audit_control",
+    "cap_setfcap",
+    "cap_mac_override",
+    "cap_mac_admin",
+    "cap_syslog",
+    "cap_wake_alarm",
+    "cap_block_suspend",
+    "cap_audit_read",
+    "cap_perfmon",
+    "cap_bpf",
+    "cap_checkpoint_restore",
+];
+
+/// Returns the capabilities granted to processes executing a file, as stored in its
+/// `security.capability` extended attribute by `setcap`.
+///
+/// Each capability is followed by its flags, similarly to `getcap`, e.g., `cap_net_raw=ep`.
+/// `None` is returned if the file has no capabilities, or if they cannot be read.
+#[cfg(unix)]
+pub(crate) fn file_capabilities(path: &Path) -> Option<Vec<String>> {
+    use log::debug;
+
+    let data = match xattr::get(path, "security.capability") {
+        Ok(data) => data?,
+
+        Err(err) => {
+            debug!("Failed to read capabilities of '{}': {err}", path.display());
+            return None;
+        }
+    };
+
+    decode_capabilities(&data)
+}
+
+/// Decodes the capabilities stored in a `security.capability` extended attribute.
+#[cfg(unix)]
+fn decode_capabilities(data: &[u8]) -> Option<Vec<String>> {
+    /// Mask of the revision of the `vfs_cap_data` structure.
+    const VFS_CAP_REVISION_MASK: u32 = 0xff00_0000;
+    const VFS_CAP_REVISION_1: u32 = 0x0100_0000;
+    const VFS_CAP_FLAGS_EFFECTIVE: u32 = 0x0000_0001;
+
+    // `vfs_cap_data` is a sequence of little endian 32-bits words: `magic_etc`, followed by
+    // `permitted` and `inheritable` sets of 32 capabilities. Revisions 2 and 3 have two such
+    // pairs of sets, and revision 3 ends with the owner of the user namespace.
+    let words: Vec<u32> = data
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+
+    let (&magic_etc, sets) = words.split_first()?;
+    let set_count = if (magic_etc & VFS_CAP_REVISION_MASK) == VFS_CAP_REVISION_1 {
+        1
+    } else {
+        2
+    };
+    let sets = sets.get(..set_count * 2)?;
+    let effective = (magic_etc & VFS_CAP_FLAGS_EFFECTIVE) != 0;
+
+    let mut capabilities = Vec::default();
+    for (index, pair) in sets.chunks_exact(2).enumerate() {
+        let (permitted, inheritable) = (pair[0], pair[1]);
+
+        for bit in 0..32_usize {
+            let in_permitted = (permitted & (1 << bit)) != 0;
+            let in_inheritable = (inheritable & (1 << bit)) != 0;
+            if !in_permitted && !in_inheritable {
+                continue;
+            }
+
+            let number = index * 32 + bit;
+            let mut text = CAPABILITY_NAMES
+                .get(number)
+                .map_or_else(|| format!("cap_{number}"), |&name| name.to_owned());
+            text.push('=');
+            if effective {
+                text.push('e');
+            }
+            if in_inheritable {
+                text.push('i');
+            }
+            if in_permitted {
+                text.push('p');
+            }
+            capabilities.push(text);
+        }
+    }
+
+    Some(capabilities).filter(|capabilities| !capabilities.is_empty())
+}
+
+/// Extended attributes are not supported on this platform.
+#[cfg(not(unix))]
+pub(crate) fn file_capabilities(_path: &Path) -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::decode_capabilities;
+
+    /// Encodes a `vfs_cap_data` structure made of little endian 32-bits words.
+    fn vfs_cap_data(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn decodes_revision_2_capabilities() {
+        // `cap_net_raw=ep`, as set by `setcap cap_net_raw=ep`.
+        let data = vfs_cap_data(&[0x0200_0001, 1 << 13, 0, 0, 0]);
+        assert_eq!(
+            decode_capabilities(&data),
+            Some(vec![String::from("cap_net_raw=ep")])
+        );
+    }
+
+    #[test]
+    fn decodes_capabilities_of_both_sets() {
+        // `cap_chown` is inheritable and permitted, `cap_bpf` (39) is only inheritable, and
+        // capability 63 is unnamed.
+        let data = vfs_cap_data(&[0x0200_0000, 1, 1, 1 << 31, (1 << 7) | (1 << 31)]);
+        assert_eq!(
+            decode_capabilities(&data),
+            Some(vec![
+                String::from("cap_chown=ip"),
+                String::from("cap_bpf=i"),
+                String::from("cap_63=ip"),
+            ])
+        );
+    }
+
+    #[test]
+    fn decodes_revision_1_capabilities() {
+        let data = vfs_cap_data(&[0x0100_0000, 1 << 10, 0]);
+        assert_eq!(
+            decode_capabilities(&data),
+            Some(vec![String::from("cap_net_bind_service=p")])
+        );
+    }
+
+    #[test]
+    fn rejects_empty_and_truncated_capabilities() {
+        assert_eq!(
+            decode_capabilities(&vfs_cap_data(&[0x0200_0001, 0, 0, 0, 0])),
+            None
+        );
+        assert_eq!(
+            decode_capabilities(&vfs_cap_data(&[0x0200_0001, 1, 0])),
+            None
+        );
+        assert_eq!(decode_capabilities(&[]), None);
+    }
+}